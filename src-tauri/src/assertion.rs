@@ -0,0 +1,481 @@
+use crate::http_request::HTTPResponse;
+use crate::json_path;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// The assertion of response, it is saved with the setting of api
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Assertion {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub rule: AssertionRule,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "category")]
+pub enum AssertionRule {
+    StatusEquals { value: u16 },
+    StatusInRange { min: u16, max: u16 },
+    HeaderExists { name: String },
+    HeaderMatches { name: String, pattern: String },
+    JsonPathEquals { path: String, value: Value },
+    JsonPathExists { path: String },
+    JsonPathMatches { path: String, pattern: String },
+    BodyContains { value: String },
+    LatencyBelow { value: u32 },
+    JsonSchema { schema: Value },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResult {
+    // Description of the assertion, e.g. `status == 200`
+    pub name: String,
+    pub passed: bool,
+    // The actual value or the reason of failure
+    pub message: String,
+}
+
+struct ResponseData<'a> {
+    resp: &'a HTTPResponse,
+    body: String,
+    json: Option<Value>,
+}
+
+impl ResponseData<'_> {
+    fn header_values(&self, name: &str) -> Option<&Vec<String>> {
+        self.resp.headers.get(&name.to_lowercase())
+    }
+    fn json_values(&self, path: &str) -> Result<Vec<&Value>, String> {
+        match &self.json {
+            Some(json) => Ok(json_path::query(json, path)),
+            None => Err("body is not json".to_string()),
+        }
+    }
+}
+
+fn regex_matches(pattern: &str, values: &[String]) -> Result<Option<String>, String> {
+    let re = Regex::new(pattern).map_err(|err| err.to_string())?;
+    Ok(values.iter().find(|value| re.is_match(value)).cloned())
+}
+
+fn is_json_equal(actual: &Value, expected: &Value) -> bool {
+    // `"1"` is equal to `1` since the expected value is often typed as text
+    actual == expected || json_path::to_string(actual) == json_path::to_string(expected)
+}
+
+fn validate_json_schema(schema: &Value, value: &Value) -> Result<(), String> {
+    let validator = jsonschema::validator_for(schema).map_err(|err| err.to_string())?;
+    let errors: Vec<String> = validator
+        .iter_errors(value)
+        .map(|err| format!("{}: {}", err.instance_path, err))
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+impl AssertionRule {
+    fn name(&self) -> String {
+        match self {
+            AssertionRule::StatusEquals { value } => format!("status == {}", value),
+            AssertionRule::StatusInRange { min, max } => format!("status in {}..{}", min, max),
+            AssertionRule::HeaderExists { name } => format!("header {} exists", name),
+            AssertionRule::HeaderMatches { name, pattern } => {
+                format!("header {} matches {}", name, pattern)
+            }
+            AssertionRule::JsonPathEquals { path, value } => format!("{} == {}", path, value),
+            AssertionRule::JsonPathExists { path } => format!("{} exists", path),
+            AssertionRule::JsonPathMatches { path, pattern } => {
+                format!("{} matches {}", path, pattern)
+            }
+            AssertionRule::BodyContains { value } => format!("body contains {}", value),
+            AssertionRule::LatencyBelow { value } => format!("latency < {}ms", value),
+            AssertionRule::JsonSchema { .. } => "body matches json schema".to_string(),
+        }
+    }
+
+    // Returns the actual value if passed, otherwise the reason of failure
+    fn check(&self, data: &ResponseData) -> Result<String, String> {
+        let status = data.resp.status;
+        match self {
+            AssertionRule::StatusEquals { value } => {
+                if status == *value {
+                    Ok(status.to_string())
+                } else {
+                    Err(format!("status is {}", status))
+                }
+            }
+            AssertionRule::StatusInRange { min, max } => {
+                if status >= *min && status <= *max {
+                    Ok(status.to_string())
+                } else {
+                    Err(format!("status is {}", status))
+                }
+            }
+            AssertionRule::HeaderExists { name } => match data.header_values(name) {
+                Some(values) => Ok(values.join(", ")),
+                None => Err(format!("header {} is not found", name)),
+            },
+            AssertionRule::HeaderMatches { name, pattern } => {
+                let Some(values) = data.header_values(name) else {
+                    return Err(format!("header {} is not found", name));
+                };
+                match regex_matches(pattern, values)? {
+                    Some(value) => Ok(value),
+                    None => Err(format!("header {} is {}", name, values.join(", "))),
+                }
+            }
+            AssertionRule::JsonPathEquals { path, value } => {
+                let values = data.json_values(path)?;
+                let Some(actual) = values.first() else {
+                    return Err(format!("{} is not found", path));
+                };
+                if is_json_equal(actual, value) {
+                    Ok(actual.to_string())
+                } else {
+                    Err(format!("{} is {}", path, actual))
+                }
+            }
+            AssertionRule::JsonPathExists { path } => {
+                let values = data.json_values(path)?;
+                match values.first() {
+                    Some(actual) => Ok(actual.to_string()),
+                    None => Err(format!("{} is not found", path)),
+                }
+            }
+            AssertionRule::JsonPathMatches { path, pattern } => {
+                let values: Vec<String> = data
+                    .json_values(path)?
+                    .into_iter()
+                    .map(json_path::to_string)
+                    .collect();
+                if values.is_empty() {
+                    return Err(format!("{} is not found", path));
+                }
+                match regex_matches(pattern, &values)? {
+                    Some(value) => Ok(value),
+                    None => Err(format!("{} is {}", path, values.join(", "))),
+                }
+            }
+            AssertionRule::BodyContains { value } => {
+                if data.body.contains(value.as_str()) {
+                    Ok(value.clone())
+                } else {
+                    Err(format!("body does not contain {}", value))
+                }
+            }
+            AssertionRule::LatencyBelow { value } => {
+                if data.resp.latency < *value {
+                    Ok(format!("{}ms", data.resp.latency))
+                } else {
+                    Err(format!("latency is {}ms", data.resp.latency))
+                }
+            }
+            AssertionRule::JsonSchema { schema } => {
+                let Some(json) = &data.json else {
+                    return Err("body is not json".to_string());
+                };
+                validate_json_schema(schema, json)?;
+                Ok("valid".to_string())
+            }
+        }
+    }
+}
+
+// Evaluate the enabled assertions against the response
+pub fn evaluate(assertions: &[Assertion], resp: &HTTPResponse) -> Vec<AssertionResult> {
+    let enabled: Vec<&Assertion> = assertions.iter().filter(|item| item.enabled).collect();
+    if enabled.is_empty() {
+        return vec![];
+    }
+    let buf = resp.decode_body().unwrap_or_default();
+    let data = ResponseData {
+        resp,
+        json: serde_json::from_slice(&buf).ok(),
+        body: String::from_utf8_lossy(&buf).to_string(),
+    };
+    enabled
+        .into_iter()
+        .map(|item| {
+            let (passed, message) = match item.rule.check(&data) {
+                Ok(message) => (true, message),
+                Err(message) => (false, message),
+            };
+            AssertionResult {
+                name: item.rule.name(),
+                passed,
+                message,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{Engine as _, engine::general_purpose};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn new_response(body: &str) -> HTTPResponse {
+        HTTPResponse {
+            api: "api".to_string(),
+            latency: 120,
+            status: 201,
+            headers: HashMap::from([(
+                "content-type".to_string(),
+                vec!["application/json; charset=utf-8".to_string()],
+            )]),
+            body: general_purpose::STANDARD.encode(body),
+            stats: Default::default(),
+            body_size: body.len() as u32,
+            assertions: vec![],
+            extractions: vec![],
+        }
+    }
+
+    fn check(rule: Value, body: &str) -> AssertionResult {
+        let assertion: Assertion = serde_json::from_value(rule).unwrap();
+        evaluate(&[assertion], &new_response(body)).remove(0)
+    }
+
+    fn assert_result(result: AssertionResult, name: &str, passed: bool, message: &str) {
+        assert_eq!(name, result.name);
+        assert_eq!(passed, result.passed, "{}", result.message);
+        assert_eq!(message, result.message);
+    }
+
+    const BODY: &str = r#"{"id": 1, "name": "tree", "tags": ["a", "b"], "count": "12"}"#;
+
+    #[test]
+    fn status() {
+        assert_result(
+            check(json!({"category": "statusEquals", "value": 201}), BODY),
+            "status == 201",
+            true,
+            "201",
+        );
+        assert_result(
+            check(json!({"category": "statusEquals", "value": 200}), BODY),
+            "status == 200",
+            false,
+            "status is 201",
+        );
+        assert_result(
+            check(
+                json!({"category": "statusInRange", "min": 200, "max": 299}),
+                BODY,
+            ),
+            "status in 200..299",
+            true,
+            "201",
+        );
+        assert_result(
+            check(
+                json!({"category": "statusInRange", "min": 400, "max": 499}),
+                BODY,
+            ),
+            "status in 400..499",
+            false,
+            "status is 201",
+        );
+    }
+
+    #[test]
+    fn header() {
+        assert_result(
+            check(
+                json!({"category": "headerExists", "name": "Content-Type"}),
+                BODY,
+            ),
+            "header Content-Type exists",
+            true,
+            "application/json; charset=utf-8",
+        );
+        assert_result(
+            check(json!({"category": "headerExists", "name": "etag"}), BODY),
+            "header etag exists",
+            false,
+            "header etag is not found",
+        );
+        assert_result(
+            check(
+                json!({"category": "headerMatches", "name": "content-type", "pattern": "^application/json"}),
+                BODY,
+            ),
+            "header content-type matches ^application/json",
+            true,
+            "application/json; charset=utf-8",
+        );
+        assert_result(
+            check(
+                json!({"category": "headerMatches", "name": "content-type", "pattern": "xml"}),
+                BODY,
+            ),
+            "header content-type matches xml",
+            false,
+            "header content-type is application/json; charset=utf-8",
+        );
+        assert_result(
+            check(
+                json!({"category": "headerMatches", "name": "content-type", "pattern": "("}),
+                BODY,
+            ),
+            "header content-type matches (",
+            false,
+            "regex parse error:\n    (\n    ^\nerror: unclosed group",
+        );
+    }
+
+    #[test]
+    fn json_path() {
+        assert_result(
+            check(
+                json!({"category": "jsonPathEquals", "path": "$.name", "value": "tree"}),
+                BODY,
+            ),
+            "$.name == \"tree\"",
+            true,
+            "\"tree\"",
+        );
+        // The text value is equal to the number
+        assert_result(
+            check(
+                json!({"category": "jsonPathEquals", "path": "$.count", "value": 12}),
+                BODY,
+            ),
+            "$.count == 12",
+            true,
+            "\"12\"",
+        );
+        assert_result(
+            check(
+                json!({"category": "jsonPathEquals", "path": "$.id", "value": 2}),
+                BODY,
+            ),
+            "$.id == 2",
+            false,
+            "$.id is 1",
+        );
+        assert_result(
+            check(
+                json!({"category": "jsonPathEquals", "path": "$.none", "value": 2}),
+                BODY,
+            ),
+            "$.none == 2",
+            false,
+            "$.none is not found",
+        );
+        assert_result(
+            check(
+                json!({"category": "jsonPathExists", "path": "$.tags[1]"}),
+                BODY,
+            ),
+            "$.tags[1] exists",
+            true,
+            "\"b\"",
+        );
+        assert_result(
+            check(
+                json!({"category": "jsonPathMatches", "path": "$.tags[*]", "pattern": "^b$"}),
+                BODY,
+            ),
+            "$.tags[*] matches ^b$",
+            true,
+            "b",
+        );
+        assert_result(
+            check(
+                json!({"category": "jsonPathMatches", "path": "$.tags[*]", "pattern": "^c$"}),
+                BODY,
+            ),
+            "$.tags[*] matches ^c$",
+            false,
+            "$.tags[*] is a, b",
+        );
+        assert_result(
+            check(
+                json!({"category": "jsonPathExists", "path": "$.id"}),
+                "<html>",
+            ),
+            "$.id exists",
+            false,
+            "body is not json",
+        );
+    }
+
+    #[test]
+    fn body_and_latency() {
+        assert_result(
+            check(json!({"category": "bodyContains", "value": "tree"}), BODY),
+            "body contains tree",
+            true,
+            "tree",
+        );
+        assert_result(
+            check(json!({"category": "bodyContains", "value": "leaf"}), BODY),
+            "body contains leaf",
+            false,
+            "body does not contain leaf",
+        );
+        assert_result(
+            check(json!({"category": "latencyBelow", "value": 200}), BODY),
+            "latency < 200ms",
+            true,
+            "120ms",
+        );
+        assert_result(
+            check(json!({"category": "latencyBelow", "value": 100}), BODY),
+            "latency < 100ms",
+            false,
+            "latency is 120ms",
+        );
+    }
+
+    #[test]
+    fn json_schema() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {"id": {"type": "integer"}, "name": {"type": "string"}},
+        });
+        assert_result(
+            check(json!({"category": "jsonSchema", "schema": schema}), BODY),
+            "body matches json schema",
+            true,
+            "valid",
+        );
+        let result = check(
+            json!({"category": "jsonSchema", "schema": schema}),
+            r#"{"id": "1"}"#,
+        );
+        assert!(!result.passed);
+        assert!(result.message.contains("/id: "), "{}", result.message);
+        assert!(result.message.contains("\"name\" is a required property"));
+    }
+
+    #[test]
+    fn evaluate_enabled() {
+        let assertions: Vec<Assertion> = serde_json::from_value(json!([
+            {"category": "statusEquals", "value": 201},
+            {"category": "statusEquals", "value": 500, "enabled": false},
+            {"category": "bodyContains", "value": "tree", "enabled": true},
+        ]))
+        .unwrap();
+        let results = evaluate(&assertions, &new_response(BODY));
+        let names: Vec<&str> = results.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(vec!["status == 201", "body contains tree"], names);
+
+        assert!(evaluate(&[], &new_response(BODY)).is_empty());
+    }
+}
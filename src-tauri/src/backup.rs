@@ -0,0 +1,388 @@
+use crate::error::CyberAPIError;
+use crate::schemas::{self, TablesCount, resolve_db_file};
+use crate::settings;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+const BACKUP_ERROR_CATEGORY: &str = "backup";
+const BACKUP_SETTING_KEY: &str = "backup";
+const BACKUP_DIR: &str = "backups";
+// The automatic backups are named as cyberapi-auto-20240102-150405-startup.zip
+const BACKUP_PREFIX: &str = "cyberapi-auto-";
+const BACKUP_EXT: &str = ".zip";
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+// How often to check whether the scheduled backup is due
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+static BACKUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+const BACKUP_REASON_STARTUP: &str = "startup";
+const BACKUP_REASON_IMPORT: &str = "import";
+const BACKUP_REASON_SCHEDULED: &str = "scheduled";
+
+// The setting of automatic backups, it is set by `backup` of app settings
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupSetting {
+    // The directory of backups, it is the backups beside the database if it is empty
+    pub dir: String,
+    pub on_startup: bool,
+    pub before_import: bool,
+    // 0 means no scheduled backup
+    pub interval_hours: u64,
+    // The latest backups are always kept
+    pub keep_last: usize,
+    // The latest backup of each day or week is kept
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+}
+
+impl Default for BackupSetting {
+    fn default() -> Self {
+        BackupSetting {
+            dir: "".to_string(),
+            on_startup: true,
+            before_import: true,
+            interval_hours: 24,
+            keep_last: 5,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    // startup, import or scheduled
+    pub reason: String,
+    pub created_at: String,
+    #[serde(skip)]
+    time: DateTime<Local>,
+}
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(BACKUP_ERROR_CATEGORY, message)
+}
+
+fn load_setting() -> BackupSetting {
+    settings::load_settings()
+        .ok()
+        .flatten()
+        .and_then(|value| value.get(BACKUP_SETTING_KEY).cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn get_backup_dir(setting: &BackupSetting) -> PathBuf {
+    let dir = setting.dir.trim();
+    if !dir.is_empty() {
+        return PathBuf::from(dir);
+    }
+    resolve_db_file()
+        .parent()
+        .map(|value| value.join(BACKUP_DIR))
+        .unwrap_or_else(|| PathBuf::from(BACKUP_DIR))
+}
+
+// Parse the backup file name, the other files in the directory are ignored
+fn parse_backup_file(path: &Path) -> Option<BackupFile> {
+    let name = path.file_name()?.to_str()?;
+    let value = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(BACKUP_EXT)?;
+    // The time is formatted as 20240102-150405
+    let (time, reason) = value.split_at_checked(15)?;
+    let time = NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok()?;
+    let time = Local.from_local_datetime(&time).earliest()?;
+    let size = fs::metadata(path).ok()?.len();
+    Some(BackupFile {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        size,
+        reason: reason.trim_start_matches('-').to_string(),
+        created_at: time.to_rfc3339(),
+        time,
+    })
+}
+
+fn list_backup_files(dir: &Path) -> Result<Vec<BackupFile>, CyberAPIError> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        if let Some(file) = parse_backup_file(&entry?.path()) {
+            files.push(file);
+        }
+    }
+    // The latest is the first
+    files.sort_by_key(|file| Reverse(file.time));
+    Ok(files)
+}
+
+// The files to be removed, the latest ones and the latest one of each day or week are kept
+fn get_expired_files(files: &[BackupFile], setting: &BackupSetting) -> Vec<PathBuf> {
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    let mut expired = vec![];
+    for (index, file) in files.iter().enumerate() {
+        let mut keep = index < setting.keep_last;
+        let day = file.time.date_naive();
+        if !days.contains(&day) && days.len() < setting.keep_daily {
+            days.insert(day);
+            keep = true;
+        }
+        let week = day.iso_week();
+        if !weeks.contains(&week) && weeks.len() < setting.keep_weekly {
+            weeks.insert(week);
+            keep = true;
+        }
+        if !keep {
+            expired.push(PathBuf::from(&file.path));
+        }
+    }
+    expired
+}
+
+async fn create_backup_with_setting(
+    reason: &str,
+    setting: &BackupSetting,
+) -> Result<Option<BackupFile>, CyberAPIError> {
+    let _guard = BACKUP_LOCK.lock().await;
+    let dir = get_backup_dir(setting);
+    fs::create_dir_all(&dir)?;
+    let name = format!(
+        "{BACKUP_PREFIX}{}-{reason}{BACKUP_EXT}",
+        Local::now().format(BACKUP_TIME_FORMAT)
+    );
+    let file = dir.join(name);
+    // The secrets are still encrypted by the workspace key of the database
+    let count = schemas::export_tables_to_file(&file, None, true).await?;
+    // Nothing to back up for the empty database
+    if count.tables.values().all(|value| *value == 0) {
+        fs::remove_file(&file)?;
+        return Ok(None);
+    }
+    Ok(parse_backup_file(&file))
+}
+
+fn prune_backups(setting: &BackupSetting) -> Result<(), CyberAPIError> {
+    let files = list_backup_files(&get_backup_dir(setting))?;
+    for expired in get_expired_files(&files, setting) {
+        if let Err(err) = fs::remove_file(&expired) {
+            warn!("remove expired backup {:?} fail: {}", expired, err);
+        }
+    }
+    Ok(())
+}
+
+async fn create_backup(reason: &str, setting: &BackupSetting) -> Result<(), CyberAPIError> {
+    if let Some(file) = create_backup_with_setting(reason, setting).await? {
+        info!(file = file.name, "backup is created");
+    }
+    prune_backups(setting)
+}
+
+// Back up the database before the data is replaced, it is skipped if it is disabled
+pub async fn backup_before_import() -> Result<(), CyberAPIError> {
+    let setting = load_setting();
+    if !setting.before_import {
+        return Ok(());
+    }
+    create_backup(BACKUP_REASON_IMPORT, &setting).await
+}
+
+async fn run_scheduled_backup() -> Result<(), CyberAPIError> {
+    let setting = load_setting();
+    if setting.interval_hours == 0 {
+        return Ok(());
+    }
+    let latest = list_backup_files(&get_backup_dir(&setting))?
+        .first()
+        .map(|file| file.time);
+    let interval = chrono::Duration::hours(setting.interval_hours as i64);
+    if latest.is_some_and(|time| Local::now() - time < interval) {
+        return Ok(());
+    }
+    create_backup(BACKUP_REASON_SCHEDULED, &setting).await
+}
+
+// Back up the database on startup and start the scheduled backups,
+// it runs only once after the database is initialized
+pub async fn start() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let setting = load_setting();
+    let result = if setting.on_startup {
+        create_backup(BACKUP_REASON_STARTUP, &setting).await
+    } else {
+        Ok(())
+    };
+    if let Err(err) = result {
+        warn!("create startup backup fail: {}", err);
+    }
+    tokio::spawn(async {
+        loop {
+            if let Err(err) = run_scheduled_backup().await {
+                warn!("create scheduled backup fail: {}", err);
+            }
+            tokio::time::sleep(BACKUP_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+pub fn list_backups() -> Result<Vec<BackupFile>, CyberAPIError> {
+    list_backup_files(&get_backup_dir(&load_setting()))
+}
+
+// Restore the backup by name, the current data is backed up before it is replaced
+pub async fn restore_backup(name: &str) -> Result<TablesCount, CyberAPIError> {
+    let setting = load_setting();
+    let file = list_backup_files(&get_backup_dir(&setting))?
+        .into_iter()
+        .find(|item| item.name == name)
+        .ok_or_else(|| new_error(format!("backup {name} is not found")))?;
+    // The expired backups are removed after restoring, so the restored one is not removed
+    if setting.before_import {
+        create_backup_with_setting(BACKUP_REASON_IMPORT, &setting).await?;
+    }
+    let result = schemas::import_tables(file.path, None).await?;
+    prune_backups(&setting)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_backup_file(value: &str) -> BackupFile {
+        let time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        let time = Local.from_local_datetime(&time).earliest().unwrap();
+        BackupFile {
+            name: value.to_string(),
+            path: value.to_string(),
+            time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn expired_files() {
+        // 2024-01-10 is wednesday of the second week
+        let files: Vec<BackupFile> = [
+            "2024-01-10 12:00",
+            "2024-01-10 08:00",
+            "2024-01-10 06:00",
+            "2024-01-09 20:00",
+            "2024-01-09 10:00",
+            "2024-01-05 10:00",
+            "2024-01-04 10:00",
+            "2023-12-20 10:00",
+        ]
+        .into_iter()
+        .map(new_backup_file)
+        .collect();
+        let setting = BackupSetting {
+            keep_last: 2,
+            keep_daily: 3,
+            keep_weekly: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                PathBuf::from("2024-01-10 06:00"),
+                PathBuf::from("2024-01-09 10:00"),
+                PathBuf::from("2024-01-04 10:00"),
+                PathBuf::from("2023-12-20 10:00"),
+            ],
+            get_expired_files(&files, &setting)
+        );
+
+        // The latest one of each week is kept
+        let setting = BackupSetting {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_weekly: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                PathBuf::from("2024-01-10 08:00"),
+                PathBuf::from("2024-01-10 06:00"),
+                PathBuf::from("2024-01-09 20:00"),
+                PathBuf::from("2024-01-09 10:00"),
+                PathBuf::from("2024-01-04 10:00"),
+            ],
+            get_expired_files(&files, &setting)
+        );
+
+        let setting = BackupSetting {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+            ..Default::default()
+        };
+        assert_eq!(files.len(), get_expired_files(&files, &setting).len());
+        assert!(get_expired_files(&files, &BackupSetting::default()).is_empty());
+    }
+
+    #[test]
+    fn list_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(
+            list_backup_files(&dir.path().join("not-exists"))
+                .unwrap()
+                .is_empty()
+        );
+        for name in [
+            "cyberapi-auto-20240109-201500-import.zip",
+            "cyberapi-auto-20240110-120000-startup.zip",
+            "cyberapi-auto-20240110-080000-scheduled.zip",
+            "cyberapi-auto-2024-01-10-startup.zip",
+            "cyberapi-auto-20240110-120000-startup.zip.enc",
+            "cyberapi-backup-2024-01-10.zip",
+        ] {
+            fs::write(dir.path().join(name), "data").unwrap();
+        }
+        let files = list_backup_files(dir.path()).unwrap();
+        assert_eq!(
+            vec![
+                "cyberapi-auto-20240110-120000-startup.zip",
+                "cyberapi-auto-20240110-080000-scheduled.zip",
+                "cyberapi-auto-20240109-201500-import.zip",
+            ],
+            files
+                .iter()
+                .map(|file| file.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["startup", "scheduled", "import"],
+            files
+                .iter()
+                .map(|file| file.reason.as_str())
+                .collect::<Vec<_>>()
+        );
+        let file = &files[0];
+        assert_eq!(4, file.size);
+        assert!(file.created_at.starts_with("2024-01-10T12:00:00"));
+        assert_eq!(
+            dir.path()
+                .join("cyberapi-auto-20240110-120000-startup.zip")
+                .to_string_lossy(),
+            file.path
+        );
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(app_lib::run_cli());
+}
@@ -0,0 +1,711 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{HTTPClient, HTTPRequest, HTTPRequestKVParam, RequestTimeout};
+use crate::schemas::{self, APISetting, resolve_db_file};
+use base64::{Engine as _, engine::general_purpose};
+use chrono::Local;
+use hyper::{
+    Body, Method, Request, Response, Server, StatusCode,
+    header::{HeaderMap, HeaderValue},
+    server::conn::Http,
+    service::{make_service_fn, service_fn},
+    upgrade::Upgraded,
+};
+use once_cell::sync::OnceCell;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, IsCa, KeyPair, KeyUsagePurpose,
+    SanType,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::fs;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::{self, ServerConfig};
+use tracing::{info, warn};
+use url::Url;
+
+const CAPTURE_ERROR_CATEGORY: &str = "capture";
+const CAPTURE_CA_CERT_FILE: &str = "capture-ca.pem";
+const CAPTURE_CA_KEY_FILE: &str = "capture-ca.key";
+// Only the latest exchanges are kept
+const MAX_CAPTURED_EXCHANGES: usize = 1000;
+// Headers of the connection, they are not forwarded
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "upgrade",
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CaptureOptions {
+    // The listen address, it is 127.0.0.1 if it is empty.
+    // Use 0.0.0.0 to capture the traffic of other devices (e.g. a phone)
+    pub host: String,
+    // A random port is used if it is 0
+    pub port: u16,
+    // Decrypt the https requests with certificates signed by the local CA,
+    // otherwise they are tunneled and only the host is recorded
+    pub decrypt_https: bool,
+    pub timeout: RequestTimeout,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureProxyInfo {
+    pub port: u16,
+    pub address: String,
+    pub decrypt_https: bool,
+    pub started_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedExchange {
+    pub id: String,
+    // Port of the proxy
+    pub port: u16,
+    pub time: String,
+    pub method: String,
+    // Only host and port of the CONNECT tunnel
+    pub url: String,
+    pub request_headers: Vec<HTTPRequestKVParam>,
+    // Base64 encoded
+    pub request_body: String,
+    pub status: Option<u16>,
+    pub response_headers: HashMap<String, Vec<String>>,
+    // Base64 encoded, it is not decompressed
+    pub response_body: String,
+    // Milliseconds
+    pub latency: u32,
+    pub error: Option<String>,
+}
+
+impl CapturedExchange {
+    fn new(port: u16, method: &str, url: &str) -> Self {
+        CapturedExchange {
+            id: ulid::Ulid::new().to_string(),
+            port,
+            time: Local::now().to_rfc3339(),
+            method: method.to_string(),
+            url: url.to_string(),
+            request_headers: vec![],
+            request_body: "".to_string(),
+            status: None,
+            response_headers: HashMap::new(),
+            response_body: "".to_string(),
+            latency: 0,
+            error: None,
+        }
+    }
+}
+
+// The local CA, it signs the certificate of each host
+struct CertificateAuthority {
+    ca: Certificate,
+    configs: Mutex<HashMap<String, Arc<ServerConfig>>>,
+}
+
+impl CertificateAuthority {
+    fn server_config(&self, host: &str) -> Result<Arc<ServerConfig>, CyberAPIError> {
+        if let Some(config) = self
+            .configs
+            .lock()
+            .ok()
+            .and_then(|configs| configs.get(host).cloned())
+        {
+            return Ok(config);
+        }
+        let mut params = CertificateParams::default();
+        params.distinguished_name.push(DnType::CommonName, host);
+        params.subject_alt_names = vec![match host.parse::<IpAddr>() {
+            Ok(ip) => SanType::IpAddress(ip),
+            Err(_) => SanType::DnsName(host.to_string()),
+        }];
+        // Clients reject the certificate which is valid for too long
+        let now = time::OffsetDateTime::now_utc();
+        params.not_before = now - time::Duration::days(1);
+        params.not_after = now + time::Duration::days(365);
+        let cert = Certificate::from_params(params)?;
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::Certificate(
+                    cert.serialize_der_with_signer(&self.ca)?,
+                )],
+                rustls::PrivateKey(cert.serialize_private_key_der()),
+            )?;
+        let config = Arc::new(config);
+        if let Ok(mut configs) = self.configs.lock() {
+            configs.insert(host.to_string(), config.clone());
+        }
+        Ok(config)
+    }
+}
+
+struct CaptureProxy {
+    info: CaptureProxyInfo,
+    timeout: RequestTimeout,
+    ca: Option<Arc<CertificateAuthority>>,
+    on_exchange: Box<dyn Fn(CapturedExchange) + Send + Sync>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+static CAPTURE_PROXIES: OnceCell<Mutex<HashMap<u16, Arc<CaptureProxy>>>> = OnceCell::new();
+static CAPTURED_EXCHANGES: OnceCell<Mutex<VecDeque<CapturedExchange>>> = OnceCell::new();
+static CERTIFICATE_AUTHORITY: OnceCell<Arc<CertificateAuthority>> = OnceCell::new();
+
+fn get_capture_proxies() -> &'static Mutex<HashMap<u16, Arc<CaptureProxy>>> {
+    CAPTURE_PROXIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_captured_exchanges() -> &'static Mutex<VecDeque<CapturedExchange>> {
+    CAPTURED_EXCHANGES.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+// Load the CA from the directory of db, it is created at the first time
+fn load_certificate_authority() -> Result<Arc<CertificateAuthority>, CyberAPIError> {
+    if let Some(ca) = CERTIFICATE_AUTHORITY.get() {
+        return Ok(ca.clone());
+    }
+    let cert_file = resolve_db_file().with_file_name(CAPTURE_CA_CERT_FILE);
+    let key_file = resolve_db_file().with_file_name(CAPTURE_CA_KEY_FILE);
+    let ca = if cert_file.is_file() && key_file.is_file() {
+        let key_pair = KeyPair::from_pem(&fs::read_to_string(&key_file)?)?;
+        let params =
+            CertificateParams::from_ca_cert_pem(&fs::read_to_string(&cert_file)?, key_pair)?;
+        Certificate::from_params(params)?
+    } else {
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "CyberAPI Capture CA");
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "CyberAPI");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_usages = vec![
+            KeyUsagePurpose::KeyCertSign,
+            KeyUsagePurpose::CrlSign,
+            KeyUsagePurpose::DigitalSignature,
+        ];
+        let ca = Certificate::from_params(params)?;
+        fs::write(&cert_file, ca.serialize_pem()?)?;
+        write_private_key(&key_file, &ca.serialize_private_key_pem())?;
+        ca
+    };
+    let ca = Arc::new(CertificateAuthority {
+        ca,
+        configs: Mutex::new(HashMap::new()),
+    });
+    Ok(CERTIFICATE_AUTHORITY.get_or_init(|| ca).clone())
+}
+
+// The private key is only readable by the owner
+fn write_private_key(file: &Path, data: &str) -> Result<(), CyberAPIError> {
+    #[cfg(unix)]
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(file)?;
+    // The mode of open is only applied to the new file
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(data.as_bytes())?;
+    Ok(())
+}
+
+// The certificate of the local CA in PEM format, it should be trusted by the client
+pub fn get_ca_cert() -> Result<String, CyberAPIError> {
+    load_certificate_authority()?;
+    let cert = fs::read_to_string(resolve_db_file().with_file_name(CAPTURE_CA_CERT_FILE))?;
+    Ok(cert)
+}
+
+fn record(proxy: &CaptureProxy, exchange: CapturedExchange) {
+    if let Ok(mut exchanges) = get_captured_exchanges().lock() {
+        if exchanges.len() >= MAX_CAPTURED_EXCHANGES {
+            exchanges.pop_front();
+        }
+        exchanges.push_back(exchange.clone());
+    }
+    (proxy.on_exchange)(exchange);
+}
+
+fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+}
+
+fn error_response(status: StatusCode, message: String) -> Response<Body> {
+    let mut resp = Response::new(Body::from(message));
+    *resp.status_mut() = status;
+    resp.headers_mut().insert(
+        "content-type",
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    resp
+}
+
+async fn send(
+    proxy: &CaptureProxy,
+    req: Request<Body>,
+    url: &str,
+    exchange: &mut CapturedExchange,
+) -> Result<Response<Body>, CyberAPIError> {
+    let (mut parts, body) = req.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+    remove_hop_by_hop_headers(&mut parts.headers);
+    for (name, value) in parts.headers.iter() {
+        exchange.request_headers.push(HTTPRequestKVParam {
+            key: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).to_string(),
+            enabled: true,
+        });
+    }
+    exchange.request_body = general_purpose::STANDARD.encode(&body);
+
+    parts.uri = url.parse()?;
+    let client = HTTPClient::new(&Url::parse(url)?, &proxy.timeout).await?;
+    let resp = client
+        .request(Request::from_parts(parts, Body::from(body)))
+        .await?;
+    let (mut parts, body) = resp.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+    remove_hop_by_hop_headers(&mut parts.headers);
+    exchange.status = Some(parts.status.as_u16());
+    for (name, value) in parts.headers.iter() {
+        exchange
+            .response_headers
+            .entry(name.to_string())
+            .or_default()
+            .push(String::from_utf8_lossy(value.as_bytes()).to_string());
+    }
+    exchange.response_body = general_purpose::STANDARD.encode(&body);
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+// Forward the request to url, the exchange is recorded
+async fn forward(proxy: Arc<CaptureProxy>, req: Request<Body>, url: String) -> Response<Body> {
+    let started = Instant::now();
+    let mut exchange = CapturedExchange::new(proxy.info.port, req.method().as_str(), &url);
+    let resp = match send(&proxy, req, &url, &mut exchange).await {
+        Ok(resp) => resp,
+        Err(err) => {
+            exchange.error = Some(err.to_string());
+            error_response(StatusCode::BAD_GATEWAY, err.to_string())
+        }
+    };
+    exchange.latency = started.elapsed().as_millis() as u32;
+    record(&proxy, exchange);
+    resp
+}
+
+// Copy the data of tunnel, only the host is recorded
+async fn tunnel(
+    proxy: Arc<CaptureProxy>,
+    mut upgraded: Upgraded,
+    authority: String,
+) -> Result<(), CyberAPIError> {
+    let started = Instant::now();
+    let mut exchange = CapturedExchange::new(proxy.info.port, Method::CONNECT.as_str(), &authority);
+    let mut stream = match TcpStream::connect(&authority).await {
+        Ok(stream) => stream,
+        Err(err) => {
+            exchange.error = Some(err.to_string());
+            record(&proxy, exchange);
+            return Err(err.into());
+        }
+    };
+    exchange.status = Some(StatusCode::OK.as_u16());
+    exchange.latency = started.elapsed().as_millis() as u32;
+    record(&proxy, exchange);
+    tokio::io::copy_bidirectional(&mut upgraded, &mut stream).await?;
+    Ok(())
+}
+
+// Terminate the tls of tunnel and serve the decrypted requests
+async fn decrypt(
+    proxy: Arc<CaptureProxy>,
+    ca: Arc<CertificateAuthority>,
+    upgraded: Upgraded,
+    authority: String,
+) -> Result<(), CyberAPIError> {
+    let (host, port) = authority.rsplit_once(':').unwrap_or((&authority, "443"));
+    let host = host.trim_matches(['[', ']']).to_string();
+    let base = if port == "443" {
+        format!("https://{}", host)
+    } else {
+        format!("https://{}:{}", host, port)
+    };
+    let stream = TlsAcceptor::from(ca.server_config(&host)?)
+        .accept(upgraded)
+        .await?;
+    let service = service_fn(move |req: Request<Body>| {
+        let url = format!(
+            "{}{}",
+            base,
+            req.uri()
+                .path_and_query()
+                .map(|value| value.as_str())
+                .unwrap_or("/")
+        );
+        let proxy = proxy.clone();
+        async move { Ok::<_, Infallible>(forward(proxy, req, url).await) }
+    });
+    Http::new()
+        .http1_only(true)
+        .serve_connection(stream, service)
+        .await?;
+    Ok(())
+}
+
+fn connect(proxy: Arc<CaptureProxy>, req: Request<Body>) -> Response<Body> {
+    let Some(authority) = req.uri().authority().map(|value| value.to_string()) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "host of CONNECT is required".to_string(),
+        );
+    };
+    tokio::spawn(async move {
+        let result = match hyper::upgrade::on(req).await {
+            Ok(upgraded) => match proxy.ca.clone() {
+                Some(ca) => decrypt(proxy, ca, upgraded, authority.clone()).await,
+                None => tunnel(proxy, upgraded, authority.clone()).await,
+            },
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            warn!(authority = authority, "capture tunnel error: {}", err);
+        }
+    });
+    Response::new(Body::empty())
+}
+
+async fn handle(
+    proxy: Arc<CaptureProxy>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if req.method() == Method::CONNECT {
+        return Ok(connect(proxy, req));
+    }
+    // The uri of proxy request is absolute
+    if req.uri().scheme().is_none() {
+        return Ok(error_response(
+            StatusCode::BAD_REQUEST,
+            "it is a proxy server, the url of request should be absolute".to_string(),
+        ));
+    }
+    let url = req.uri().to_string();
+    Ok(forward(proxy, req, url).await)
+}
+
+fn get_listen_ip(host: &str) -> Result<IpAddr, CyberAPIError> {
+    let host = host.trim();
+    if host.is_empty() {
+        return Ok(IpAddr::from([127, 0, 0, 1]));
+    }
+    host.trim_matches(['[', ']']).parse().map_err(|_| {
+        CyberAPIError::new(
+            CAPTURE_ERROR_CATEGORY,
+            format!("listen address {} is invalid", host),
+        )
+    })
+}
+
+// Start the capture proxy on 127.0.0.1 (or the host of options),
+// each exchange is also passed to on_exchange
+pub async fn start(
+    options: CaptureOptions,
+    on_exchange: impl Fn(CapturedExchange) + Send + Sync + 'static,
+) -> Result<CaptureProxyInfo, CyberAPIError> {
+    let ca = if options.decrypt_https {
+        Some(load_certificate_authority()?)
+    } else {
+        None
+    };
+    let ip = get_listen_ip(&options.host)?;
+    let listener = std::net::TcpListener::bind(SocketAddr::from((ip, options.port)))?;
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+    let builder = Server::from_tcp(listener)?;
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let proxy = Arc::new(CaptureProxy {
+        info: CaptureProxyInfo {
+            port: addr.port(),
+            address: format!("http://{}", addr),
+            decrypt_https: options.decrypt_https,
+            started_at: Local::now().to_rfc3339(),
+        },
+        timeout: options.timeout,
+        ca,
+        on_exchange: Box::new(on_exchange),
+        shutdown: Mutex::new(Some(tx)),
+    });
+    let state = proxy.clone();
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+    });
+    let http_server = builder.serve(make_service).with_graceful_shutdown(async {
+        rx.await.ok();
+    });
+    let port = addr.port();
+    tokio::spawn(async move {
+        if let Err(err) = http_server.await {
+            warn!(port = port, "capture proxy error: {}", err);
+        }
+        if let Ok(mut proxies) = get_capture_proxies().lock() {
+            proxies.remove(&port);
+        }
+    });
+    info!(port = port, "capture proxy is started");
+    if let Ok(mut proxies) = get_capture_proxies().lock() {
+        proxies.insert(port, proxy.clone());
+    }
+    Ok(proxy.info.clone())
+}
+
+// Stop the capture proxy of port, the captured exchanges are kept
+pub fn stop(port: u16) -> bool {
+    let Some(proxy) = get_capture_proxies()
+        .lock()
+        .ok()
+        .and_then(|mut proxies| proxies.remove(&port))
+    else {
+        return false;
+    };
+    if let Some(tx) = proxy
+        .shutdown
+        .lock()
+        .ok()
+        .and_then(|mut value| value.take())
+    {
+        let _ = tx.send(());
+    }
+    info!(port = port, "capture proxy is stopped");
+    true
+}
+
+pub fn list() -> Vec<CaptureProxyInfo> {
+    let mut result: Vec<CaptureProxyInfo> = get_capture_proxies()
+        .lock()
+        .map(|proxies| proxies.values().map(|item| item.info.clone()).collect())
+        .unwrap_or_default();
+    result.sort_by_key(|item| item.port);
+    result
+}
+
+pub fn list_exchange() -> Vec<CapturedExchange> {
+    get_captured_exchanges()
+        .lock()
+        .map(|exchanges| exchanges.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn clear_exchange() {
+    if let Ok(mut exchanges) = get_captured_exchanges().lock() {
+        exchanges.clear();
+    }
+}
+
+// Save the exchanges of ids as new settings at the end of folder
+pub async fn save_exchanges(
+    ids: Vec<String>,
+    folder: String,
+) -> Result<Vec<APISetting>, CyberAPIError> {
+    let api_folder = schemas::get_api_folder(folder.clone())
+        .await?
+        .ok_or_else(|| {
+            CyberAPIError::new(
+                CAPTURE_ERROR_CATEGORY,
+                format!("folder {} is not found", folder),
+            )
+        })?;
+    let exchanges = list_exchange();
+    let mut result = vec![];
+    for id in ids {
+        let exchange = exchanges.iter().find(|item| item.id == id).ok_or_else(|| {
+            CyberAPIError::new(
+                CAPTURE_ERROR_CATEGORY,
+                format!("exchange {} is not found", id),
+            )
+        })?;
+        // Only the host of the tunnel is known
+        if exchange.method == Method::CONNECT.as_str() {
+            continue;
+        }
+        let req = HTTPRequest::from_raw(
+            &exchange.method,
+            &exchange.url,
+            exchange.request_headers.clone(),
+            &general_purpose::STANDARD.decode(&exchange.request_body)?,
+        )?;
+        let path = Url::parse(&req.uri)?.path().to_string();
+        let setting = schemas::add_api_setting(APISetting {
+            id: ulid::Ulid::new().to_string(),
+            collection: api_folder.collection.clone(),
+            name: Some(format!("{} {}", req.method, path)),
+            category: Some("http".to_string()),
+            setting: Some(serde_json::to_string(&req)?),
+            parent_id: Some(api_folder.id.clone()),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        })
+        .await?;
+        result.push(setting);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_ip() {
+        assert_eq!(IpAddr::from([127, 0, 0, 1]), get_listen_ip("").unwrap());
+        assert_eq!(
+            IpAddr::from([0, 0, 0, 0]),
+            get_listen_ip(" 0.0.0.0 ").unwrap()
+        );
+        assert_eq!(
+            "::1".parse::<IpAddr>().unwrap(),
+            get_listen_ip("[::1]").unwrap()
+        );
+        assert_eq!(
+            "[capture]listen address localhost is invalid",
+            get_listen_ip("localhost").err().unwrap().to_string()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_key_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(CAPTURE_CA_KEY_FILE);
+        // The permissions of the existing file are also fixed
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private_key(&file, "key").unwrap();
+        assert_eq!("key", fs::read_to_string(&file).unwrap());
+        assert_eq!(
+            0o600,
+            fs::metadata(&file).unwrap().permissions().mode() & 0o777
+        );
+    }
+
+    fn new_exchange(method: &str, url: &str, content_type: &str, body: &[u8]) -> CapturedExchange {
+        let mut exchange = CapturedExchange::new(8888, method, url);
+        exchange.request_headers = [
+            ("Host", "example.com"),
+            ("Content-Type", content_type),
+            ("Content-Length", "10"),
+            ("X-Token", "abc"),
+        ]
+        .into_iter()
+        .map(|(key, value)| HTTPRequestKVParam {
+            key: key.to_string(),
+            value: value.to_string(),
+            enabled: true,
+        })
+        .collect();
+        exchange.request_body = general_purpose::STANDARD.encode(body);
+        exchange
+    }
+
+    #[test]
+    fn save_form_and_multipart() {
+        schemas::run_with_database(async {
+            let collection = "capture-save-exchanges";
+            schemas::add_api_folder(schemas::APIFolder {
+                id: "capture-save-f1".to_string(),
+                collection: collection.to_string(),
+                children: None,
+                name: None,
+                parent_id: None,
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+
+            let multipart_type = "multipart/form-data; boundary=X-BOUNDARY";
+            let multipart_body =
+                b"--X-BOUNDARY\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--X-BOUNDARY--\r\n";
+            let exchanges = vec![
+                new_exchange(
+                    "post",
+                    "https://example.com/login?from=app#top",
+                    "application/x-www-form-urlencoded; charset=UTF-8",
+                    b"name=tom&password=a%26b",
+                ),
+                new_exchange(
+                    "POST",
+                    "https://example.com/upload",
+                    multipart_type,
+                    multipart_body,
+                ),
+                new_exchange("CONNECT", "example.com:443", "", b""),
+            ];
+            let ids = exchanges.iter().map(|item| item.id.clone()).collect();
+            get_captured_exchanges().lock().unwrap().extend(exchanges);
+
+            let settings = save_exchanges(ids, "capture-save-f1".to_string())
+                .await
+                .unwrap();
+            // The tunnel is skipped
+            assert_eq!(2, settings.len());
+            let pairs = |params: &[HTTPRequestKVParam]| {
+                params
+                    .iter()
+                    .map(|item| format!("{}={}", item.key, item.value))
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(Some("POST /login".to_string()), settings[0].name);
+            let req: HTTPRequest =
+                serde_json::from_str(settings[0].setting.as_deref().unwrap()).unwrap();
+            assert_eq!("POST", req.method);
+            assert_eq!("https://example.com/login", req.uri);
+            assert_eq!(vec!["from=app"], pairs(&req.query));
+            assert_eq!(vec!["X-Token=abc"], pairs(&req.headers));
+            assert_eq!(crate::http_request::CONTENT_TYPE_FORM, req.content_type);
+            let params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap();
+            assert_eq!(vec!["name=tom", "password=a&b"], pairs(&params));
+
+            assert_eq!(Some("POST /upload".to_string()), settings[1].name);
+            let req: HTTPRequest =
+                serde_json::from_str(settings[1].setting.as_deref().unwrap()).unwrap();
+            assert_eq!("https://example.com/upload", req.uri);
+            assert!(req.query.is_empty());
+            assert_eq!(multipart_type, req.content_type);
+            assert_eq!(
+                multipart_body.to_vec(),
+                general_purpose::STANDARD.decode(&req.body).unwrap()
+            );
+            clear_exchange();
+        });
+    }
+}
@@ -0,0 +1,677 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{HTTPTraceLayer, RequestTimeout};
+use crate::load_test::{self, LoadTestOptions};
+use crate::runner::{self, RunOptions, RunReport, RunResult, RunTarget};
+use crate::schemas::{self, APICollection};
+use crate::util;
+use clap::{Args, Parser, Subcommand};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing_subscriber::prelude::*;
+
+const CLI_ERROR_CATEGORY: &str = "cli";
+
+// Exit codes of the cli
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+#[derive(Parser)]
+#[command(
+    name = "cyberapi-cli",
+    version,
+    about = "Run the saved requests of CyberAPI"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a collection, a folder or a single request
+    Run(RunArgs),
+    /// Send a request repeatedly and report the latency and throughput
+    Load(LoadArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// The db.db of the app or a backup zip exported by the app
+    #[arg(long)]
+    db: PathBuf,
+    /// Passphrase of the encrypted backup
+    #[arg(long)]
+    passphrase: Option<String>,
+    /// Master passphrase to unlock the secret variables
+    #[arg(long)]
+    master_passphrase: Option<String>,
+    /// Id or name of the collection, it can be omitted if there is only one collection
+    #[arg(long)]
+    collection: Option<String>,
+    /// Id or name of the folder to run
+    #[arg(long)]
+    folder: Option<String>,
+    /// Id or name of the request to run
+    #[arg(long)]
+    request: Option<String>,
+    /// Id or name of the environment, the enabled environment is used by default
+    #[arg(long)]
+    env: Option<String>,
+    /// Connect timeout in seconds
+    #[arg(long, default_value_t = 10)]
+    connect_timeout: u64,
+    /// Write timeout in seconds
+    #[arg(long, default_value_t = 120)]
+    write_timeout: u64,
+    /// Read timeout in seconds
+    #[arg(long, default_value_t = 300)]
+    read_timeout: u64,
+    /// Run count, it is the row count of the data file by default
+    #[arg(long)]
+    iterations: Option<u32>,
+    /// CSV or JSON data file, each row binds the variables of an iteration
+    #[arg(long)]
+    data: Option<PathBuf>,
+    /// Delay between requests in milliseconds
+    #[arg(long, default_value_t = 0)]
+    delay: u64,
+    /// Count of iterations run at the same time
+    #[arg(long, default_value_t = 1)]
+    concurrency: u32,
+    /// Stop after the first failed request
+    #[arg(long)]
+    bail: bool,
+    /// Write a JUnit XML report to the file
+    #[arg(long)]
+    junit: Option<PathBuf>,
+    /// Write a JSON report to the file
+    #[arg(long)]
+    json: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct LoadArgs {
+    /// The db.db of the app or a backup zip exported by the app
+    #[arg(long)]
+    db: PathBuf,
+    /// Passphrase of the encrypted backup
+    #[arg(long)]
+    passphrase: Option<String>,
+    /// Master passphrase to unlock the secret variables
+    #[arg(long)]
+    master_passphrase: Option<String>,
+    /// Id or name of the collection, it can be omitted if there is only one collection
+    #[arg(long)]
+    collection: Option<String>,
+    /// Id or name of the request to send
+    #[arg(long)]
+    request: String,
+    /// Id or name of the environment, the enabled environment is used by default
+    #[arg(long)]
+    env: Option<String>,
+    /// Count of requests sent at the same time
+    #[arg(long, default_value_t = 10)]
+    concurrency: u32,
+    /// Total count of requests
+    #[arg(long, default_value_t = 100)]
+    count: u64,
+    /// Duration in seconds, the count is ignored if it is set
+    #[arg(long, default_value_t = 0)]
+    duration: u64,
+    /// Target requests per second, 0 means no limit
+    #[arg(long, default_value_t = 0)]
+    rps: u32,
+    /// Connect timeout in seconds
+    #[arg(long, default_value_t = 10)]
+    connect_timeout: u64,
+    /// Write timeout in seconds
+    #[arg(long, default_value_t = 120)]
+    write_timeout: u64,
+    /// Read timeout in seconds
+    #[arg(long, default_value_t = 300)]
+    read_timeout: u64,
+    /// Write a JSON report to the file
+    #[arg(long)]
+    json: Option<PathBuf>,
+}
+
+fn cli_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(CLI_ERROR_CATEGORY, message)
+}
+
+// The backup zip, or the encrypted backup which ends with .enc
+fn is_backup_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|value| value.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("zip") || ext.eq_ignore_ascii_case("enc"))
+        .unwrap_or(false)
+}
+
+// The db file is copied into a temporary dir, so the migrations and the
+// extracted variables never touch the live database of the app.
+// A backup zip is imported into a temporary db instead.
+// Returns the temporary dir which should be removed after running,
+// it is removed here if the database fails to be opened.
+async fn open_database(file: &Path, passphrase: Option<String>) -> Result<PathBuf, CyberAPIError> {
+    if !file.is_file() {
+        return Err(cli_error(format!("{} is not found", file.display())));
+    }
+    let file = fs::canonicalize(file)?;
+    let dir = std::env::temp_dir().join(format!("cyberapi-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    if let Err(err) = init_database(&file, &dir, passphrase).await {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(err);
+    }
+    Ok(dir)
+}
+
+async fn init_database(
+    file: &Path,
+    dir: &Path,
+    passphrase: Option<String>,
+) -> Result<(), CyberAPIError> {
+    let db_file = dir.join("db.db");
+    util::set_app_dir(dir.to_string_lossy().to_string());
+    util::set_db_path(db_file.to_string_lossy().to_string());
+    if !is_backup_file(file) {
+        fs::copy(file, &db_file)?;
+        // The changes which are not checkpointed are kept in the wal file
+        let wal_file = PathBuf::from(format!("{}-wal", file.display()));
+        if wal_file.is_file() {
+            fs::copy(&wal_file, dir.join("db.db-wal"))?;
+        }
+        let settings_file = file.with_file_name("settings.json");
+        if settings_file.is_file() {
+            fs::copy(&settings_file, dir.join("settings.json"))?;
+        }
+        schemas::init_tables().await?;
+        return Ok(());
+    }
+    schemas::init_tables().await?;
+    schemas::import_tables(file.to_string_lossy().to_string(), passphrase).await?;
+    Ok(())
+}
+
+fn find_by_id_or_name<'a, T>(
+    category: &str,
+    value: &str,
+    items: &'a [T],
+    get: impl Fn(&T) -> (&String, Option<&String>),
+) -> Result<&'a T, CyberAPIError> {
+    if let Some(found) = items.iter().find(|item| get(item).0 == value) {
+        return Ok(found);
+    }
+    let matched: Vec<&T> = items
+        .iter()
+        .filter(|item| get(item).1.map(|name| name == value).unwrap_or(false))
+        .collect();
+    match matched.len() {
+        1 => Ok(matched[0]),
+        0 => Err(cli_error(format!("{} {} is not found", category, value))),
+        _ => Err(cli_error(format!(
+            "{} {} is ambiguous, use the id instead",
+            category, value
+        ))),
+    }
+}
+
+async fn resolve_collection(value: Option<&String>) -> Result<APICollection, CyberAPIError> {
+    let mut collections = schemas::list_api_collection().await?;
+    match value {
+        Some(value) => {
+            let found = find_by_id_or_name("collection", value, &collections, |item| {
+                (&item.id, item.name.as_ref())
+            })?;
+            let id = found.id.clone();
+            let index = collections.iter().position(|item| item.id == id).unwrap();
+            Ok(collections.swap_remove(index))
+        }
+        None if collections.len() == 1 => Ok(collections.remove(0)),
+        None => {
+            let names: Vec<String> = collections
+                .iter()
+                .map(|item| format!("{}({})", item.name.clone().unwrap_or_default(), item.id))
+                .collect();
+            Err(cli_error(format!(
+                "collection is required, available: {}",
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+async fn resolve_target(
+    args: &RunArgs,
+    collection: &APICollection,
+) -> Result<RunTarget, CyberAPIError> {
+    if let Some(value) = &args.request {
+        let settings = schemas::list_api_setting(collection.id.clone()).await?;
+        let found = find_by_id_or_name("request", value, &settings, |item| {
+            (&item.id, item.name.as_ref())
+        })?;
+        return Ok(RunTarget::Setting(found.id.clone()));
+    }
+    if let Some(value) = &args.folder {
+        let folders = schemas::list_api_folder(collection.id.clone()).await?;
+        let found = find_by_id_or_name("folder", value, &folders, |item| {
+            (&item.id, item.name.as_ref())
+        })?;
+        return Ok(RunTarget::Folder(found.id.clone()));
+    }
+    Ok(RunTarget::Collection(collection.id.clone()))
+}
+
+async fn resolve_environment(
+    value: Option<&String>,
+    collection: &APICollection,
+) -> Result<Option<String>, CyberAPIError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let environments = schemas::list_environment(collection.id.clone()).await?;
+    let found = find_by_id_or_name("environment", value, &environments, |item| {
+        (&item.id, item.name.as_ref())
+    })?;
+    Ok(Some(found.id.clone()))
+}
+
+fn print_result(prefix: &str, result: &RunResult) {
+    let flag = if result.passed { "PASS" } else { "FAIL" };
+    let status = if result.status == 0 {
+        "---".to_string()
+    } else {
+        result.status.to_string()
+    };
+    println!(
+        "{}{} {} {:<7} {} {}ms {}",
+        prefix, flag, status, result.method, result.name, result.latency, result.uri
+    );
+    if let Some(err) = &result.error {
+        println!("     {}", err);
+    }
+    for item in result.assertions.iter().filter(|item| !item.passed) {
+        println!("     {}: {}", item.name, item.message);
+    }
+    for item in result.extractions.iter() {
+        if let Some(err) = &item.error {
+            println!("     extract {}: {}", item.variable, err);
+        }
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn to_junit_xml(name: &str, report: &RunReport) -> String {
+    let seconds = |ms: u32| format!("{:.3}", ms as f64 / 1000.0);
+    let mut xml = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        format!(
+            r#"<testsuites name="cyberapi" tests="{}" failures="{}" time="{}">"#,
+            report.total,
+            report.failed,
+            seconds(report.duration)
+        ),
+        format!(
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{}" timestamp="{}">"#,
+            xml_escape(name),
+            report.total,
+            report.failed,
+            seconds(report.duration),
+            xml_escape(&report.started_at)
+        ),
+    ];
+    for result in report.results.iter() {
+        let name = if report.iterations > 1 {
+            format!("{} #{}", result.name, result.iteration)
+        } else {
+            result.name.clone()
+        };
+        let testcase = format!(
+            r#"    <testcase name="{}" classname="{}" time="{}""#,
+            xml_escape(&name),
+            xml_escape(&format!("{} {}", result.method, result.uri)),
+            seconds(result.latency)
+        );
+        if result.passed {
+            xml.push(format!("{} />", testcase));
+            continue;
+        }
+        let failed_assertions: Vec<String> = result
+            .assertions
+            .iter()
+            .filter(|item| !item.passed)
+            .map(|item| format!("{}: {}", item.name, item.message))
+            .collect();
+        let message = match &result.error {
+            Some(err) => err.clone(),
+            None if !failed_assertions.is_empty() => failed_assertions.join("; "),
+            None => format!("status {}", result.status),
+        };
+        xml.push(format!("{}>", testcase));
+        xml.push(format!(
+            r#"      <failure message="{}" />"#,
+            xml_escape(&message)
+        ));
+        xml.push("    </testcase>".to_string());
+    }
+    xml.push("  </testsuite>".to_string());
+    xml.push("</testsuites>".to_string());
+    xml.join("\n") + "\n"
+}
+
+async fn run_collection(args: RunArgs) -> Result<bool, CyberAPIError> {
+    let collection = resolve_collection(args.collection.as_ref()).await?;
+    let target = resolve_target(&args, &collection).await?;
+    let environment = resolve_environment(args.env.as_ref(), &collection).await?;
+    let options = RunOptions {
+        environment,
+        iterations: args.iterations,
+        data_file: args
+            .data
+            .as_ref()
+            .map(|file| file.to_string_lossy().to_string()),
+        delay: args.delay,
+        stop_on_failure: args.bail,
+        concurrency: args.concurrency,
+        timeout: RequestTimeout {
+            connect: args.connect_timeout,
+            write: args.write_timeout,
+            read: args.read_timeout,
+        },
+    };
+
+    let name = collection.name.clone().unwrap_or(collection.id.clone());
+    println!("Running {}", name);
+    let show_iteration = args.iterations.unwrap_or(1) > 1 || args.data.is_some();
+    let report = runner::run_suite("cli".to_string(), &target, options, move |progress| {
+        let prefix = if show_iteration {
+            format!("#{} ", progress.result.iteration)
+        } else {
+            "".to_string()
+        };
+        print_result(&prefix, &progress.result);
+    })
+    .await?;
+    println!(
+        "\n{} requests, {} passed, {} failed, {}ms",
+        report.total, report.passed, report.failed, report.duration
+    );
+
+    if let Some(file) = &args.junit {
+        fs::write(file, to_junit_xml(&name, &report))?;
+    }
+    if let Some(file) = &args.json {
+        fs::write(file, serde_json::to_string_pretty(&report)?)?;
+    }
+    Ok(report.failed == 0)
+}
+
+async fn run_load_test(args: LoadArgs) -> Result<bool, CyberAPIError> {
+    let collection = resolve_collection(args.collection.as_ref()).await?;
+    let settings = schemas::list_api_setting(collection.id.clone()).await?;
+    let setting = find_by_id_or_name("request", &args.request, &settings, |item| {
+        (&item.id, item.name.as_ref())
+    })?;
+    let options = LoadTestOptions {
+        environment: resolve_environment(args.env.as_ref(), &collection).await?,
+        concurrency: args.concurrency,
+        count: args.count,
+        duration: args.duration,
+        rps: args.rps,
+        timeout: RequestTimeout {
+            connect: args.connect_timeout,
+            write: args.write_timeout,
+            read: args.read_timeout,
+        },
+    };
+
+    println!(
+        "Load testing {}",
+        setting.name.clone().unwrap_or(setting.id.clone())
+    );
+    let report = load_test::run("cli".to_string(), &setting.id, options, |progress| {
+        println!(
+            "{:>4}s {} requests, {} errors, p50 {}ms, p99 {}ms",
+            progress.point.second + 1,
+            progress.point.count,
+            progress.point.errors,
+            progress.point.latency.p50,
+            progress.point.latency.p99
+        );
+    })
+    .await?;
+    println!(
+        "\n{} {}\n{} requests in {}ms, {} req/s, {} failed",
+        report.method, report.uri, report.total, report.duration, report.throughput, report.failed
+    );
+    let latency = &report.latency;
+    println!(
+        "latency: min {}ms, mean {}ms, p50 {}ms, p90 {}ms, p99 {}ms, max {}ms",
+        latency.min, latency.mean, latency.p50, latency.p90, latency.p99, latency.max
+    );
+    if !report.status.is_empty() {
+        let status: Vec<String> = report
+            .status
+            .iter()
+            .map(|(status, count)| format!("{}: {}", status, count))
+            .collect();
+        println!("status: {}", status.join(", "));
+    }
+    if !report.errors.is_empty() {
+        let errors: Vec<String> = report
+            .errors
+            .iter()
+            .map(|(category, count)| format!("{}: {}", category, count))
+            .collect();
+        println!("errors: {}", errors.join(", "));
+    }
+
+    if let Some(file) = &args.json {
+        fs::write(file, serde_json::to_string_pretty(&report)?)?;
+    }
+    Ok(report.failed == 0)
+}
+
+// Run the cli and returns the exit code
+pub fn run() -> i32 {
+    let cli = Cli::parse();
+    tracing_subscriber::registry().with(HTTPTraceLayer).init();
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("failed to create runtime: {}", err);
+            return EXIT_ERROR;
+        }
+    };
+    runtime.block_on(async {
+        let (db, passphrase, master_passphrase) = match &cli.command {
+            Command::Run(args) => (
+                args.db.clone(),
+                args.passphrase.clone(),
+                args.master_passphrase.clone(),
+            ),
+            Command::Load(args) => (
+                args.db.clone(),
+                args.passphrase.clone(),
+                args.master_passphrase.clone(),
+            ),
+        };
+        let temp_dir = match open_database(&db, passphrase).await {
+            Ok(dir) => dir,
+            Err(err) => {
+                eprintln!("{}", err);
+                return EXIT_ERROR;
+            }
+        };
+        let unlocked = match master_passphrase {
+            Some(value) => schemas::unlock_secrets(value).await.map(|_| ()),
+            None => Ok(()),
+        };
+        let result = match (unlocked, cli.command) {
+            (Err(err), _) => Err(err),
+            (Ok(_), Command::Run(args)) => run_collection(args).await,
+            (Ok(_), Command::Load(args)) => run_load_test(args).await,
+        };
+        let code = match result {
+            Ok(true) => EXIT_SUCCESS,
+            Ok(false) => EXIT_FAILED,
+            Err(err) => {
+                eprintln!("{}", err);
+                EXIT_ERROR
+            }
+        };
+        let _ = fs::remove_dir_all(temp_dir);
+        code
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertion::AssertionResult;
+
+    fn new_result(name: &str, passed: bool) -> RunResult {
+        RunResult {
+            id: format!("{name}-id"),
+            iteration: 1,
+            name: name.to_string(),
+            method: "GET".to_string(),
+            uri: "https://example.com/users?a=1&b=2".to_string(),
+            status: if passed { 200 } else { 500 },
+            latency: 1250,
+            passed,
+            error: None,
+            assertions: vec![],
+            extractions: vec![],
+        }
+    }
+
+    fn new_report(results: Vec<RunResult>) -> RunReport {
+        let failed = results.iter().filter(|item| !item.passed).count();
+        RunReport {
+            id: "run".to_string(),
+            collection: "collection".to_string(),
+            environment: Some("dev".to_string()),
+            started_at: "2024-01-02T03:04:05+08:00".to_string(),
+            duration: 3500,
+            iterations: 1,
+            total: results.len(),
+            passed: results.len() - failed,
+            failed,
+            results,
+        }
+    }
+
+    #[test]
+    fn junit_xml() {
+        let mut failed = new_result("create <user>", false);
+        failed.assertions = vec![
+            AssertionResult {
+                name: "status == 201".to_string(),
+                passed: false,
+                message: "\"500\"".to_string(),
+            },
+            AssertionResult {
+                name: "header content-type exists".to_string(),
+                passed: true,
+                message: "application/json".to_string(),
+            },
+        ];
+        let mut errored = new_result("delete user", false);
+        errored.status = 0;
+        errored.error = Some("connection refused".to_string());
+        let report = new_report(vec![
+            new_result("list users", true),
+            failed,
+            errored,
+            new_result("get user", false),
+        ]);
+
+        assert_eq!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="cyberapi" tests="4" failures="3" time="3.500">
+  <testsuite name="Demo &amp; Test" tests="4" failures="3" time="3.500" timestamp="2024-01-02T03:04:05+08:00">
+    <testcase name="list users" classname="GET https://example.com/users?a=1&amp;b=2" time="1.250" />
+    <testcase name="create &lt;user&gt;" classname="GET https://example.com/users?a=1&amp;b=2" time="1.250">
+      <failure message="status == 201: &quot;500&quot;" />
+    </testcase>
+    <testcase name="delete user" classname="GET https://example.com/users?a=1&amp;b=2" time="1.250">
+      <failure message="connection refused" />
+    </testcase>
+    <testcase name="get user" classname="GET https://example.com/users?a=1&amp;b=2" time="1.250">
+      <failure message="status 500" />
+    </testcase>
+  </testsuite>
+</testsuites>
+"#,
+            to_junit_xml("Demo & Test", &report)
+        );
+    }
+
+    #[test]
+    fn junit_xml_iterations() {
+        let mut second = new_result("list users", true);
+        second.iteration = 2;
+        let mut report = new_report(vec![new_result("list users", true), second]);
+        report.iterations = 2;
+
+        let xml = to_junit_xml("Demo", &report);
+        assert!(xml.contains(r#"<testcase name="list users #1""#));
+        assert!(xml.contains(r#"<testcase name="list users #2""#));
+    }
+
+    #[test]
+    fn json_report() {
+        let report = new_report(vec![new_result("list users", true)]);
+        let value = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(
+            serde_json::json!({
+                "id": "run",
+                "collection": "collection",
+                "environment": "dev",
+                "startedAt": "2024-01-02T03:04:05+08:00",
+                "duration": 3500,
+                "iterations": 1,
+                "total": 1,
+                "passed": 1,
+                "failed": 0,
+                "results": [{
+                    "id": "list users-id",
+                    "iteration": 1,
+                    "name": "list users",
+                    "method": "GET",
+                    "uri": "https://example.com/users?a=1&b=2",
+                    "status": 200,
+                    "latency": 1250,
+                    "passed": true,
+                    "error": null,
+                    "assertions": [],
+                    "extractions": [],
+                }],
+            }),
+            value
+        );
+    }
+
+    #[test]
+    fn backup_file() {
+        assert!(is_backup_file(Path::new("backup.zip")));
+        assert!(is_backup_file(Path::new("backup.zip.ENC")));
+        assert!(!is_backup_file(Path::new("db.db")));
+        assert!(!is_backup_file(Path::new("db")));
+    }
+}
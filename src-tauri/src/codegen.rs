@@ -0,0 +1,953 @@
+use crate::cookies;
+use crate::error::CyberAPIError;
+use crate::http_request::{
+    CONTENT_TYPE_FORM, CONTENT_TYPE_MULTIPART, HTTPRequest, HTTPRequestKVParam,
+};
+use crate::runner::{self, RunnerContext};
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use url::{Url, form_urlencoded};
+
+const FILE_PROTOCOL: &str = "file://";
+// The boundary of multipart body built by shell commands
+const MULTIPART_BOUNDARY: &str = "----CyberAPISnippetBoundary";
+// The line continuation of shell commands
+const SHELL_NEW_LINE: &str = " \\\n  ";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeLanguage {
+    Curl,
+    Httpie,
+    Wget,
+    Python,
+    Javascript,
+    Go,
+    Rust,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FormValue {
+    Text(String),
+    File(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SnippetBody {
+    Empty,
+    Text(String),
+    Binary(Vec<u8>),
+    Form(Vec<(String, String)>),
+    Multipart(Vec<(String, FormValue)>),
+}
+
+// The fully resolved request, all templates of it are rendered
+#[derive(Debug, Clone)]
+struct Snippet {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: SnippetBody,
+}
+
+// Binary bodies can not be written as text, e.g. the raw multipart body
+fn new_body(buf: Vec<u8>) -> SnippetBody {
+    if buf.is_empty() {
+        return SnippetBody::Empty;
+    }
+    match String::from_utf8(buf) {
+        Ok(value)
+            if !value
+                .chars()
+                .any(|c| c.is_control() && !['\r', '\n', '\t'].contains(&c)) =>
+        {
+            SnippetBody::Text(value)
+        }
+        Ok(value) => SnippetBody::Binary(value.into_bytes()),
+        Err(err) => SnippetBody::Binary(err.into_bytes()),
+    }
+}
+
+// Render the templates of request like it is sent, the form params are kept as params
+async fn resolve(
+    collection: &str,
+    environment: Option<String>,
+    req: HTTPRequest,
+) -> Result<Snippet, CyberAPIError> {
+    let ctx = RunnerContext::load(collection, environment).await?;
+    let is_form =
+        req.content_type == CONTENT_TYPE_FORM || req.content_type == CONTENT_TYPE_MULTIPART;
+    let params: Vec<HTTPRequestKVParam> = if is_form && !req.body.trim().is_empty() {
+        serde_json::from_str(&req.body)?
+    } else {
+        vec![]
+    };
+    let body = if is_form {
+        "".to_string()
+    } else {
+        req.body.clone()
+    };
+    let resolved = runner::resolve_request(&ctx, HTTPRequest { body, ..req })?;
+
+    let mut url = Url::parse(&resolved.uri)?;
+    for item in resolved.query.iter().filter(|item| item.enabled) {
+        url.query_pairs_mut().append_pair(&item.key, &item.value);
+    }
+    let mut headers: Vec<(String, String)> = resolved
+        .headers
+        .iter()
+        .filter(|item| item.enabled)
+        .map(|item| (item.key.clone(), item.value.clone()))
+        .collect();
+    let cookie = cookies::get_cookie_store()
+        .get_request_values(&url)
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ");
+    if !cookie.is_empty() {
+        headers.push(("Cookie".to_string(), cookie));
+    }
+
+    // The content type is empty if the method has no body
+    let body = if is_form && resolved.content_type == CONTENT_TYPE_FORM {
+        let mut pairs = vec![];
+        for item in params.iter().filter(|item| item.enabled) {
+            pairs.push((item.key.clone(), ctx.template.render(&item.value)?));
+        }
+        SnippetBody::Form(pairs)
+    } else if is_form && resolved.content_type == CONTENT_TYPE_MULTIPART {
+        let mut fields = vec![];
+        for item in params
+            .iter()
+            .filter(|item| item.enabled && !item.key.is_empty())
+        {
+            let value = ctx.template.render(&item.value)?;
+            let value = match value.strip_prefix(FILE_PROTOCOL) {
+                Some(file) => FormValue::File(file.to_string()),
+                None => FormValue::Text(value),
+            };
+            fields.push((item.key.clone(), value));
+        }
+        SnippetBody::Multipart(fields)
+    } else if resolved.content_type.starts_with(CONTENT_TYPE_MULTIPART) {
+        // The raw multipart body with boundary is base64 encoded
+        new_body(general_purpose::STANDARD.decode(&resolved.body)?)
+    } else {
+        new_body(resolved.body.into_bytes())
+    };
+    // The content type of form and multipart is set by the tools
+    let has_content_type = headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case("content-type"));
+    if !has_content_type
+        && !resolved.content_type.is_empty()
+        && matches!(body, SnippetBody::Text(_) | SnippetBody::Binary(_))
+    {
+        headers.push(("Content-Type".to_string(), resolved.content_type.clone()));
+    }
+    Ok(Snippet {
+        method: resolved.method,
+        url: url.to_string(),
+        headers,
+        body,
+    })
+}
+
+// Quote the value for posix shell, e.g. `it's` is `'it'\''s'`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// The printf command which outputs the bytes, non printable bytes are octal escaped.
+// The format may start with `-`, so it is after `--`.
+fn shell_printf(buf: &[u8]) -> String {
+    let mut result = String::new();
+    for byte in buf {
+        match byte {
+            b'%' => result.push_str("%%"),
+            b'\\' => result.push_str(r"\\"),
+            b'\'' => result.push_str(r"\047"),
+            0x20..=0x7e => result.push(*byte as char),
+            _ => result.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    format!("printf -- '{}'", result)
+}
+
+// The string literal of python, javascript and go, json string is valid for them
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn rust_string(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+// The content of byte string, e.g. `b"..."` of python and rust
+fn byte_string(buf: &[u8]) -> String {
+    let mut result = String::new();
+    for byte in buf {
+        match byte {
+            b'"' => result.push_str("\\\""),
+            b'\\' => result.push_str(r"\\"),
+            b'\n' => result.push_str(r"\n"),
+            b'\r' => result.push_str(r"\r"),
+            b'\t' => result.push_str(r"\t"),
+            0x20..=0x7e => result.push(*byte as char),
+            _ => result.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    format!("\"{}\"", result)
+}
+
+fn file_name(file: &str) -> String {
+    Path::new(file)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn encode_form(pairs: &[(String, String)]) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+// The header of curl, a header without value is `Name;`
+fn curl_header(key: &str, value: &str) -> String {
+    if value.is_empty() {
+        format!("-H {}", shell_quote(&format!("{};", key)))
+    } else {
+        format!("-H {}", shell_quote(&format!("{}: {}", key, value)))
+    }
+}
+
+// The shell commands which output the multipart body, the files are read by cat
+fn shell_multipart(fields: &[(String, FormValue)]) -> String {
+    let mut commands = vec![];
+    for (key, value) in fields {
+        let head = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            MULTIPART_BOUNDARY,
+            runner::escape_disposition_param(key)
+        );
+        match value {
+            FormValue::Text(value) => commands.push(shell_printf(
+                format!("{}\r\n\r\n{}\r\n", head, value).as_bytes(),
+            )),
+            FormValue::File(file) => {
+                commands.push(shell_printf(
+                    format!(
+                        "{}; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                        head,
+                        runner::escape_disposition_param(&file_name(file))
+                    )
+                    .as_bytes(),
+                ));
+                commands.push(format!("cat {}", shell_quote(file)));
+                commands.push(shell_printf(b"\r\n"));
+            }
+        }
+    }
+    commands.push(shell_printf(
+        format!("--{}--\r\n", MULTIPART_BOUNDARY).as_bytes(),
+    ));
+    format!("{{ {}; }}", commands.join("; "))
+}
+
+fn curl(snippet: &Snippet) -> String {
+    let mut prefix = "".to_string();
+    let mut args = vec!["curl".to_string()];
+    match snippet.method.as_str() {
+        "GET" => {}
+        "HEAD" => args.push("--head".to_string()),
+        method => args.push(format!("-X {}", method)),
+    }
+    args.push(shell_quote(&snippet.url));
+    for (key, value) in snippet.headers.iter() {
+        args.push(curl_header(key, value));
+    }
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => args.push(format!("--data-raw {}", shell_quote(value))),
+        SnippetBody::Binary(buf) => {
+            prefix = format!("{} | ", shell_printf(buf));
+            args.push("--data-binary @-".to_string());
+        }
+        SnippetBody::Form(pairs) => {
+            for (key, value) in pairs {
+                // The name of `--data-urlencode` should be encoded already
+                let key: String = form_urlencoded::byte_serialize(key.as_bytes()).collect();
+                args.push(format!(
+                    "--data-urlencode {}",
+                    shell_quote(&format!("{}={}", key, value))
+                ));
+            }
+        }
+        SnippetBody::Multipart(fields) => {
+            for (key, value) in fields {
+                match value {
+                    // `--form-string` does not treat `@` and `<` as files
+                    FormValue::Text(value) => args.push(format!(
+                        "--form-string {}",
+                        shell_quote(&format!("{}={}", key, value))
+                    )),
+                    FormValue::File(file) => {
+                        let file = file.replace('\\', r"\\").replace('"', "\\\"");
+                        args.push(format!(
+                            "-F {}",
+                            shell_quote(&format!("{}=@\"{}\"", key, file))
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    format!("{}{}", prefix, args.join(SHELL_NEW_LINE))
+}
+
+// The separators of httpie items are escaped, e.g. `a:b` is `a\:b`
+fn httpie_key(key: &str) -> String {
+    let mut result = String::new();
+    for c in key.chars() {
+        if [':', '=', '@', '\\'].contains(&c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+// The first char of value is escaped if it makes a longer separator,
+// e.g. `a=@b` embeds the file b and `a==b` is a query param
+fn httpie_item(key: &str, separator: char, value: &str) -> String {
+    let specials: &[char] = match separator {
+        '=' => &['=', '@'],
+        ':' => &['='],
+        _ => &[],
+    };
+    let escape = if value.starts_with(specials) {
+        "\\"
+    } else {
+        ""
+    };
+    format!("{}{}{}{}", httpie_key(key), separator, escape, value)
+}
+
+fn httpie(snippet: &Snippet) -> String {
+    let mut prefix = "".to_string();
+    let mut args = vec!["http".to_string()];
+    let mut items = vec![];
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => args.push(format!("--raw {}", shell_quote(value))),
+        // The body is read from stdin
+        SnippetBody::Binary(buf) => prefix = format!("{} | ", shell_printf(buf)),
+        SnippetBody::Form(pairs) => {
+            args.push("--form".to_string());
+            for (key, value) in pairs {
+                items.push(shell_quote(&httpie_item(key, '=', value)));
+            }
+        }
+        SnippetBody::Multipart(fields) => {
+            args.push("--multipart".to_string());
+            for (key, value) in fields {
+                let item = match value {
+                    FormValue::Text(value) => httpie_item(key, '=', value),
+                    FormValue::File(file) => httpie_item(key, '@', file),
+                };
+                items.push(shell_quote(&item));
+            }
+        }
+    }
+    args.push(snippet.method.clone());
+    args.push(shell_quote(&snippet.url));
+    for (key, value) in snippet.headers.iter() {
+        let item = if value.is_empty() {
+            format!("{};", httpie_key(key))
+        } else {
+            httpie_item(key, ':', value)
+        };
+        args.push(shell_quote(&item));
+    }
+    args.extend(items);
+    format!("{}{}", prefix, args.join(SHELL_NEW_LINE))
+}
+
+fn wget(snippet: &Snippet) -> String {
+    let mut prefix = "".to_string();
+    let mut args = vec!["wget".to_string(), "--quiet".to_string()];
+    if snippet.method != "GET" {
+        args.push(format!("--method={}", snippet.method));
+    }
+    let mut headers = snippet.headers.clone();
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => args.push(format!("--body-data={}", shell_quote(value))),
+        SnippetBody::Binary(buf) => {
+            prefix = format!("{} > \"$body\"\n", shell_printf(buf));
+            args.push("--body-file=\"$body\"".to_string());
+        }
+        SnippetBody::Form(pairs) => {
+            headers.push(("Content-Type".to_string(), CONTENT_TYPE_FORM.to_string()));
+            args.push(format!("--body-data={}", shell_quote(&encode_form(pairs))));
+        }
+        // wget does not support multipart, the body is built by shell
+        SnippetBody::Multipart(fields) => {
+            prefix = format!("{} > \"$body\"\n", shell_multipart(fields));
+            headers.push((
+                "Content-Type".to_string(),
+                format!(
+                    "{}; boundary={}",
+                    CONTENT_TYPE_MULTIPART, MULTIPART_BOUNDARY
+                ),
+            ));
+            args.push("--body-file=\"$body\"".to_string());
+        }
+    }
+    // The body file of wget should be seekable, so it is written to a temporary file
+    if !prefix.is_empty() {
+        prefix = format!("body=\"$(mktemp)\"\n{}", prefix);
+    }
+    for (key, value) in headers.iter() {
+        args.push(format!(
+            "--header={}",
+            shell_quote(&format!("{}: {}", key, value))
+        ));
+    }
+    args.push("--output-document=-".to_string());
+    args.push(shell_quote(&snippet.url));
+    format!("{}{}", prefix, args.join(SHELL_NEW_LINE))
+}
+
+fn python(snippet: &Snippet) -> String {
+    let mut lines = vec![
+        "import requests".to_string(),
+        "".to_string(),
+        format!("url = {}", json_string(&snippet.url)),
+    ];
+    let mut args = vec![json_string(&snippet.method), "url".to_string()];
+    if !snippet.headers.is_empty() {
+        lines.push("headers = {".to_string());
+        for (key, value) in snippet.headers.iter() {
+            lines.push(format!("    {}: {},", json_string(key), json_string(value)));
+        }
+        lines.push("}".to_string());
+        args.push("headers=headers".to_string());
+    }
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => {
+            lines.push(format!("data = {}", json_string(value)));
+            args.push("data=data".to_string());
+        }
+        SnippetBody::Binary(buf) => {
+            lines.push(format!("data = b{}", byte_string(buf)));
+            args.push("data=data".to_string());
+        }
+        SnippetBody::Form(pairs) => {
+            lines.push("data = [".to_string());
+            for (key, value) in pairs {
+                lines.push(format!(
+                    "    ({}, {}),",
+                    json_string(key),
+                    json_string(value)
+                ));
+            }
+            lines.push("]".to_string());
+            args.push("data=data".to_string());
+        }
+        SnippetBody::Multipart(fields) => {
+            lines.push("files = [".to_string());
+            for (key, value) in fields {
+                let value = match value {
+                    FormValue::Text(value) => format!("(None, {})", json_string(value)),
+                    FormValue::File(file) => format!("open({}, \"rb\")", json_string(file)),
+                };
+                lines.push(format!("    ({}, {}),", json_string(key), value));
+            }
+            lines.push("]".to_string());
+            args.push("files=files".to_string());
+        }
+    }
+    lines.push(format!("response = requests.request({})", args.join(", ")));
+    lines.push("print(response.text)".to_string());
+    lines.join("\n")
+}
+
+fn javascript(snippet: &Snippet) -> String {
+    let mut lines = vec![];
+    let mut body = "".to_string();
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => body = json_string(value),
+        SnippetBody::Binary(buf) => {
+            let bytes: Vec<String> = buf.iter().map(|byte| format!("0x{:02x}", byte)).collect();
+            body = format!("new Uint8Array([{}])", bytes.join(", "));
+        }
+        SnippetBody::Form(pairs) => {
+            let pairs: Vec<String> = pairs
+                .iter()
+                .map(|(key, value)| format!("[{}, {}]", json_string(key), json_string(value)))
+                .collect();
+            body = format!("new URLSearchParams([{}])", pairs.join(", "));
+        }
+        SnippetBody::Multipart(fields) => {
+            // The file is read by node.js
+            if fields
+                .iter()
+                .any(|(_, value)| matches!(value, FormValue::File(_)))
+            {
+                lines.push("import { openAsBlob } from \"node:fs\";".to_string());
+                lines.push("".to_string());
+            }
+            lines.push("const form = new FormData();".to_string());
+            for (key, value) in fields {
+                match value {
+                    FormValue::Text(value) => lines.push(format!(
+                        "form.append({}, {});",
+                        json_string(key),
+                        json_string(value)
+                    )),
+                    FormValue::File(file) => lines.push(format!(
+                        "form.append({}, await openAsBlob({}), {});",
+                        json_string(key),
+                        json_string(file),
+                        json_string(&file_name(file))
+                    )),
+                }
+            }
+            lines.push("".to_string());
+            body = "form".to_string();
+        }
+    }
+    lines.push(format!(
+        "const response = await fetch({}, {{",
+        json_string(&snippet.url)
+    ));
+    lines.push(format!("  method: {},", json_string(&snippet.method)));
+    if !snippet.headers.is_empty() {
+        lines.push("  headers: [".to_string());
+        for (key, value) in snippet.headers.iter() {
+            lines.push(format!(
+                "    [{}, {}],",
+                json_string(key),
+                json_string(value)
+            ));
+        }
+        lines.push("  ],".to_string());
+    }
+    if !body.is_empty() {
+        lines.push(format!("  body: {},", body));
+    }
+    lines.push("});".to_string());
+    lines.push("console.log(await response.text());".to_string());
+    lines.join("\n")
+}
+
+fn go(snippet: &Snippet) -> String {
+    let mut imports = BTreeSet::from(["fmt", "io", "net/http"]);
+    let mut lines = vec![];
+    let mut body = "nil";
+    let mut headers = snippet.headers.clone();
+    let mut add_file_func = false;
+    let check_err = ["\tif err != nil {", "\t\tpanic(err)", "\t}"];
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => {
+            imports.insert("strings");
+            lines.push(format!(
+                "\tbody := strings.NewReader({})",
+                json_string(value)
+            ));
+            body = "body";
+        }
+        SnippetBody::Binary(buf) => {
+            imports.insert("strings");
+            lines.push(format!("\tbody := strings.NewReader({})", byte_string(buf)));
+            body = "body";
+        }
+        SnippetBody::Form(pairs) => {
+            imports.insert("net/url");
+            imports.insert("strings");
+            lines.push("\tform := url.Values{}".to_string());
+            for (key, value) in pairs {
+                lines.push(format!(
+                    "\tform.Add({}, {})",
+                    json_string(key),
+                    json_string(value)
+                ));
+            }
+            lines.push("\tbody := strings.NewReader(form.Encode())".to_string());
+            headers.push(("Content-Type".to_string(), CONTENT_TYPE_FORM.to_string()));
+            body = "body";
+        }
+        SnippetBody::Multipart(fields) => {
+            imports.insert("bytes");
+            imports.insert("mime/multipart");
+            lines.push("\tbody := &bytes.Buffer{}".to_string());
+            lines.push("\twriter := multipart.NewWriter(body)".to_string());
+            for (key, value) in fields {
+                let call = match value {
+                    FormValue::Text(value) => format!(
+                        "writer.WriteField({}, {})",
+                        json_string(key),
+                        json_string(value)
+                    ),
+                    FormValue::File(file) => {
+                        add_file_func = true;
+                        format!(
+                            "addFile(writer, {}, {})",
+                            json_string(key),
+                            json_string(file)
+                        )
+                    }
+                };
+                lines.push(format!("\tif err := {}; err != nil {{", call));
+                lines.push("\t\tpanic(err)".to_string());
+                lines.push("\t}".to_string());
+            }
+            lines.push("\tif err := writer.Close(); err != nil {".to_string());
+            lines.push("\t\tpanic(err)".to_string());
+            lines.push("\t}".to_string());
+            if add_file_func {
+                imports.insert("os");
+                imports.insert("path/filepath");
+            }
+            body = "body";
+        }
+    }
+    lines.push(format!(
+        "\treq, err := http.NewRequest({}, {}, {})",
+        json_string(&snippet.method),
+        json_string(&snippet.url),
+        body
+    ));
+    lines.extend(check_err.iter().map(|line| line.to_string()));
+    for (key, value) in headers.iter() {
+        lines.push(format!(
+            "\treq.Header.Add({}, {})",
+            json_string(key),
+            json_string(value)
+        ));
+    }
+    if matches!(snippet.body, SnippetBody::Multipart(_)) {
+        lines.push("\treq.Header.Set(\"Content-Type\", writer.FormDataContentType())".to_string());
+    }
+    lines.push("\tresp, err := http.DefaultClient.Do(req)".to_string());
+    lines.extend(check_err.iter().map(|line| line.to_string()));
+    lines.push("\tdefer resp.Body.Close()".to_string());
+    lines.push("\tdata, err := io.ReadAll(resp.Body)".to_string());
+    lines.extend(check_err.iter().map(|line| line.to_string()));
+    lines.push("\tfmt.Println(string(data))".to_string());
+
+    let mut result = vec![
+        "package main".to_string(),
+        "".to_string(),
+        "import (".to_string(),
+    ];
+    result.extend(imports.iter().map(|item| format!("\t\"{}\"", item)));
+    result.push(")".to_string());
+    result.push("".to_string());
+    if add_file_func {
+        result.extend(
+            [
+                "func addFile(writer *multipart.Writer, field, file string) error {",
+                "\tf, err := os.Open(file)",
+                "\tif err != nil {",
+                "\t\treturn err",
+                "\t}",
+                "\tdefer f.Close()",
+                "\tpart, err := writer.CreateFormFile(field, filepath.Base(file))",
+                "\tif err != nil {",
+                "\t\treturn err",
+                "\t}",
+                "\t_, err = io.Copy(part, f)",
+                "\treturn err",
+                "}",
+                "",
+            ]
+            .iter()
+            .map(|line| line.to_string()),
+        );
+    }
+    result.push("func main() {".to_string());
+    result.extend(lines);
+    result.push("}".to_string());
+    result.join("\n")
+}
+
+fn rust(snippet: &Snippet) -> String {
+    let mut lines = vec![];
+    let mut dependency = "// reqwest = \"0.12\"";
+    let mut calls = vec![];
+    let method = match snippet.method.as_str() {
+        "GET" | "POST" | "PUT" | "DELETE" | "HEAD" | "OPTIONS" | "CONNECT" | "PATCH" | "TRACE" => {
+            format!("reqwest::Method::{}", snippet.method)
+        }
+        method => format!(
+            "reqwest::Method::from_bytes({})?",
+            byte_string(method.as_bytes())
+        ),
+    };
+    calls.push(format!(
+        ".request({}, {})",
+        method,
+        rust_string(&snippet.url)
+    ));
+    for (key, value) in snippet.headers.iter() {
+        calls.push(format!(
+            ".header({}, {})",
+            rust_string(key),
+            rust_string(value)
+        ));
+    }
+    match &snippet.body {
+        SnippetBody::Empty => {}
+        SnippetBody::Text(value) => calls.push(format!(".body({})", rust_string(value))),
+        SnippetBody::Binary(buf) => calls.push(format!(".body(&b{}[..])", byte_string(buf))),
+        SnippetBody::Form(pairs) => {
+            let pairs: Vec<String> = pairs
+                .iter()
+                .map(|(key, value)| format!("({}, {})", rust_string(key), rust_string(value)))
+                .collect();
+            calls.push(format!(".form(&[{}])", pairs.join(", ")));
+        }
+        SnippetBody::Multipart(fields) => {
+            dependency = "// reqwest = { version = \"0.12\", features = [\"multipart\"] }";
+            lines.push("    let form = reqwest::multipart::Form::new()".to_string());
+            for (key, value) in fields {
+                match value {
+                    FormValue::Text(value) => lines.push(format!(
+                        "        .text({}, {})",
+                        rust_string(key),
+                        rust_string(value)
+                    )),
+                    FormValue::File(file) => lines.push(format!(
+                        "        .part({}, reqwest::multipart::Part::bytes(std::fs::read({})?).file_name({}))",
+                        rust_string(key),
+                        rust_string(file),
+                        rust_string(&file_name(file))
+                    )),
+                }
+            }
+            if let Some(last) = lines.last_mut() {
+                last.push(';');
+            }
+            calls.push(".multipart(form)".to_string());
+        }
+    }
+    calls.push(".send()".to_string());
+    calls.push(".await?;".to_string());
+
+    let mut result = vec![
+        dependency.to_string(),
+        "// tokio = { version = \"1\", features = [\"full\"] }".to_string(),
+        "#[tokio::main]".to_string(),
+        "async fn main() -> Result<(), Box<dyn std::error::Error>> {".to_string(),
+        "    let client = reqwest::Client::new();".to_string(),
+    ];
+    result.extend(lines);
+    result.push("    let response = client".to_string());
+    result.extend(calls.iter().map(|call| format!("        {}", call)));
+    result.push("    println!(\"{}\", response.text().await?);".to_string());
+    result.push("    Ok(())".to_string());
+    result.push("}".to_string());
+    result.join("\n")
+}
+
+// Generate the code snippet of request, the templates are rendered by the environment
+pub async fn generate(
+    collection: &str,
+    environment: Option<String>,
+    req: HTTPRequest,
+    language: CodeLanguage,
+) -> Result<String, CyberAPIError> {
+    let snippet = resolve(collection, environment, req).await?;
+    let code = match language {
+        CodeLanguage::Curl => curl(&snippet),
+        CodeLanguage::Httpie => httpie(&snippet),
+        CodeLanguage::Wget => wget(&snippet),
+        CodeLanguage::Python => python(&snippet),
+        CodeLanguage::Javascript => javascript(&snippet),
+        CodeLanguage::Go => go(&snippet),
+        CodeLanguage::Rust => rust(&snippet),
+    };
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    type Generate = fn(&Snippet) -> String;
+
+    const LANGUAGES: [(&str, Generate); 7] = [
+        ("curl", curl),
+        ("httpie", httpie),
+        ("wget", wget),
+        ("python", python),
+        ("javascript", javascript),
+        ("go", go),
+        ("rust", rust),
+    ];
+
+    fn new_snippet(method: &str, body: SnippetBody) -> Snippet {
+        Snippet {
+            method: method.to_string(),
+            url: "https://api.example.com/users?name=it%27s".to_string(),
+            headers: vec![
+                ("X-Quote".to_string(), "it's \"quoted\"".to_string()),
+                ("X-Empty".to_string(), "".to_string()),
+            ],
+            body,
+        }
+    }
+
+    // The values with quotes, newlines and the special chars of tools
+    fn bodies() -> Vec<(&'static str, Snippet)> {
+        vec![
+            ("empty", new_snippet("GET", SnippetBody::Empty)),
+            (
+                "text",
+                new_snippet(
+                    "POST",
+                    SnippetBody::Text("{\"name\": \"it's\",\n \"rate\": \"100%\\\\\"}".to_string()),
+                ),
+            ),
+            (
+                "form",
+                new_snippet(
+                    "PUT",
+                    SnippetBody::Form(vec![
+                        ("first name".to_string(), "Bob \"B\"".to_string()),
+                        ("a=b&c".to_string(), "it's\nnew line".to_string()),
+                    ]),
+                ),
+            ),
+            (
+                "multipart",
+                new_snippet(
+                    "POST",
+                    SnippetBody::Multipart(vec![
+                        (
+                            "note".to_string(),
+                            FormValue::Text("@/etc/passwd".to_string()),
+                        ),
+                        (
+                            "say \"hi\"\n".to_string(),
+                            FormValue::Text("it's\nok".to_string()),
+                        ),
+                        (
+                            "photo".to_string(),
+                            FormValue::File("/tmp/my \"cat's\".png".to_string()),
+                        ),
+                    ]),
+                ),
+            ),
+            (
+                "binary",
+                new_snippet(
+                    "PATCH",
+                    SnippetBody::Binary(vec![0x00, b'\'', b'%', b'\\', b'"', b'\n', 0x7f, 0xff]),
+                ),
+            ),
+        ]
+    }
+
+    // The snippets are compared with the golden files, `UPDATE_GOLDEN=1` rewrites them
+    #[test]
+    fn golden_snippets() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/codegen");
+        let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|value| value == "1");
+        for (body_name, snippet) in bodies() {
+            for (language, generate) in LANGUAGES {
+                let file = dir.join(format!("{}_{}.txt", language, body_name));
+                let code = format!("{}\n", generate(&snippet));
+                if update {
+                    fs::create_dir_all(&dir).unwrap();
+                    fs::write(&file, &code).unwrap();
+                    continue;
+                }
+                let expected = fs::read_to_string(&file)
+                    .unwrap_or_else(|err| panic!("read {}: {}", file.display(), err));
+                assert_eq!(expected, code, "{} {}", language, body_name);
+            }
+        }
+    }
+
+    #[test]
+    fn shell_escape() {
+        assert_eq!("'it'\\''s'", shell_quote("it's"));
+        assert_eq!("''", shell_quote(""));
+        assert_eq!("'a\nb $HOME `id`'", shell_quote("a\nb $HOME `id`"));
+        assert_eq!(
+            "printf -- '-%%d\\\\\\047\\000\\012\\377'",
+            shell_printf(&[b'-', b'%', b'd', b'\\', b'\'', 0x00, b'\n', 0xff])
+        );
+    }
+
+    #[test]
+    fn curl_form_escape() {
+        let snippet = new_snippet(
+            "POST",
+            SnippetBody::Multipart(vec![
+                ("note".to_string(), FormValue::Text("<@it's".to_string())),
+                (
+                    "photo".to_string(),
+                    FormValue::File("C:\\tmp\\a \"b\".png".to_string()),
+                ),
+            ]),
+        );
+        let code = curl(&snippet);
+        // `@` and `<` of text values are not treated as files
+        assert!(code.contains("--form-string 'note=<@it'\\''s'"), "{}", code);
+        assert!(
+            code.contains("-F 'photo=@\"C:\\\\tmp\\\\a \\\"b\\\".png\"'"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn httpie_escape() {
+        assert_eq!("a\\:b=\\@file", httpie_item("a:b", '=', "@file"));
+        assert_eq!("q=\\=1", httpie_item("q", '=', "=1"));
+        assert_eq!("X-Token:\\=abc", httpie_item("X-Token", ':', "=abc"));
+        assert_eq!("X-Token:a=b", httpie_item("X-Token", ':', "a=b"));
+        assert_eq!(
+            "photo@/tmp/@a.png",
+            httpie_item("photo", '@', "/tmp/@a.png")
+        );
+    }
+
+    #[test]
+    fn shell_multipart_escape() {
+        let code = shell_multipart(&[
+            (
+                "say \"hi\"\r\n".to_string(),
+                FormValue::Text("ok".to_string()),
+            ),
+            (
+                "photo".to_string(),
+                FormValue::File("/tmp/a\"b.png".to_string()),
+            ),
+        ]);
+        assert!(
+            code.contains(
+                "Content-Disposition: form-data; name=\"say %%22hi%%22%%0D%%0A\"\\015\\012"
+            ),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("name=\"photo\"; filename=\"a%%22b.png\"\\015\\012"),
+            "{}",
+            code
+        );
+        assert!(code.contains("cat '/tmp/a\"b.png'"), "{}", code);
+    }
+}
@@ -1,13 +1,26 @@
+use crate::backup;
 use crate::error::CyberAPIError;
 use crate::schemas::{self, APICollection, APIFolder, APISetting, Environment, Proxy, Variable};
 use crate::settings;
 use crate::util;
-use crate::{cookies, http_request};
+use crate::{
+    capture_proxy, codegen, cookies, curl, extractor, har, history, http_request, load_test,
+    mock_server, openapi, postman, runner, template,
+};
 use serde_json::Value;
-use tauri::command;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, command};
+use tracing::warn;
 
 pub type CommandResult<T> = Result<T, CyberAPIError>;
 
+// Events of the collection runner
+const RUNNER_PROGRESS_EVENT: &str = "runner-progress";
+const RUNNER_FINISHED_EVENT: &str = "runner-finished";
+const LOAD_TEST_PROGRESS_EVENT: &str = "load-test-progress";
+const CAPTURE_EXCHANGE_EVENT: &str = "capture-exchange";
+
 // Add API setting
 #[command(async)]
 pub async fn add_api_setting(setting: APISetting) -> CommandResult<APISetting> {
@@ -26,6 +39,7 @@ pub async fn update_api_setting(setting: APISetting) -> CommandResult<APISetting
 #[command(async)]
 pub async fn init_tables() -> CommandResult<()> {
     schemas::init_tables().await?;
+    backup::start().await;
     Ok(())
 }
 
@@ -35,16 +49,185 @@ pub fn set_db_path(path: String) -> CommandResult<()> {
     Ok(())
 }
 
+// The backup is encrypted if the passphrase is not empty
 #[command(async)]
-pub async fn export_tables() -> CommandResult<String> {
-    let filename = schemas::export_tables().await?;
+pub async fn export_tables(
+    passphrase: Option<String>,
+    include_secrets: Option<bool>,
+) -> CommandResult<String> {
+    let filename = schemas::export_tables(passphrase, include_secrets.unwrap_or_default()).await?;
     Ok(filename)
 }
 
 #[command(async)]
-pub async fn import_tables(file: String) -> CommandResult<()> {
-    schemas::import_tables(file).await?;
-    Ok(())
+pub async fn import_tables(
+    file: String,
+    passphrase: Option<String>,
+) -> CommandResult<schemas::TablesCount> {
+    backup::backup_before_import().await?;
+    let result = schemas::import_tables(file, passphrase).await?;
+    Ok(result)
+}
+
+// Export the collections with their dependencies to the file
+#[command(async)]
+pub async fn export_collections(
+    collections: Vec<String>,
+    file: String,
+    passphrase: Option<String>,
+) -> CommandResult<schemas::TablesCount> {
+    let result = schemas::export_collections(collections, file, passphrase).await?;
+    Ok(result)
+}
+
+// Import the collections as new data, the ids are changed to avoid conflicts
+#[command(async)]
+pub async fn import_collections(
+    file: String,
+    passphrase: Option<String>,
+) -> CommandResult<schemas::TablesCount> {
+    let result = schemas::import_collections(file, passphrase).await?;
+    Ok(result)
+}
+
+// Write the collection to the directory as files, which can be committed to git
+#[command(async)]
+pub async fn export_workspace(
+    collection: String,
+    dir: String,
+    format: schemas::WorkspaceFormat,
+) -> CommandResult<schemas::WorkspaceExportResult> {
+    let result = schemas::export_workspace(collection, dir, format).await?;
+    Ok(result)
+}
+
+// Replace the collection with the files of the directory
+#[command(async)]
+pub async fn import_workspace(dir: String) -> CommandResult<schemas::WorkspaceImportResult> {
+    backup::backup_before_import().await?;
+    let result = schemas::import_workspace(dir).await?;
+    Ok(result)
+}
+
+#[command(async)]
+pub async fn list_backups() -> CommandResult<Vec<backup::BackupFile>> {
+    let result = backup::list_backups()?;
+    Ok(result)
+}
+
+// Restore the automatic backup, the current data is backed up first
+#[command(async)]
+pub async fn restore_backup(name: String) -> CommandResult<schemas::TablesCount> {
+    let result = backup::restore_backup(&name).await?;
+    Ok(result)
+}
+
+// Compare the backup file with the database, nothing is changed
+#[command(async)]
+pub async fn diff_tables(
+    file: String,
+    passphrase: Option<String>,
+    policy: schemas::MergePolicy,
+) -> CommandResult<schemas::MergeTablesResult> {
+    let result = schemas::diff_tables(file, passphrase, policy).await?;
+    Ok(result)
+}
+
+#[command(async)]
+pub async fn merge_tables(
+    file: String,
+    passphrase: Option<String>,
+    policy: schemas::MergePolicy,
+) -> CommandResult<schemas::MergeTablesResult> {
+    backup::backup_before_import().await?;
+    let result = schemas::merge_tables(file, passphrase, policy).await?;
+    Ok(result)
+}
+
+// Import the HAR file into collection, the requests are grouped by host or page
+#[command(async)]
+pub async fn import_har(
+    collection: String,
+    file: String,
+    group: har::HarGroup,
+) -> CommandResult<har::HarImportResult> {
+    har::import(&collection, Path::new(&file), group).await
+}
+
+// Export the requests of folder with their latest responses to the HAR file
+#[command(async)]
+pub async fn export_har(
+    folder: String,
+    environment: Option<String>,
+    file: String,
+) -> CommandResult<usize> {
+    har::export(&folder, environment, Path::new(&file)).await
+}
+
+// Import the OpenAPI 3 or Swagger 2 document, content is read from file if it is empty
+#[command(async)]
+pub async fn import_openapi(
+    collection: String,
+    file: String,
+    content: String,
+    options: openapi::OpenAPIImportOptions,
+) -> CommandResult<openapi::OpenAPIImportResult> {
+    openapi::import(&collection, Path::new(&file), &content, options).await
+}
+
+// Export the collection as an OpenAPI 3.1 document, returns the count of operations
+#[command(async)]
+pub async fn export_openapi(
+    collection: String,
+    environment: Option<String>,
+    format: openapi::OpenAPIFormat,
+    file: String,
+) -> CommandResult<usize> {
+    openapi::export(&collection, environment, format, Path::new(&file)).await
+}
+
+// Import the Postman collection or environment, content is read from file if it is empty
+#[command(async)]
+pub async fn import_postman(
+    collection: String,
+    file: String,
+    content: String,
+) -> CommandResult<postman::PostmanImportResult> {
+    let content = if content.trim().is_empty() {
+        std::fs::read_to_string(file)?
+    } else {
+        content
+    };
+    postman::import(&collection, &content).await
+}
+
+// Export the collection and its environments as Postman files
+#[command(async)]
+pub async fn export_postman(
+    collection: String,
+    file: String,
+) -> CommandResult<postman::PostmanExportResult> {
+    postman::export(&collection, Path::new(&file)).await
+}
+
+// Import the curl command as a request, the proxy of `-x` is added too
+#[command(async)]
+pub async fn import_curl(
+    collection: String,
+    content: String,
+) -> CommandResult<curl::CurlImportResult> {
+    curl::import(&collection, &content).await
+}
+
+// Generate the code snippet of request, e.g. curl or python
+#[command(async)]
+pub async fn generate_code_snippet(
+    collection: String,
+    req: http_request::HTTPRequest,
+    language: codegen::CodeLanguage,
+    environment: Option<String>,
+) -> CommandResult<String> {
+    codegen::generate(&collection, environment, req, language).await
 }
 
 // List all API settings
@@ -111,6 +294,20 @@ pub async fn list_api_folder(collection: String) -> CommandResult<Vec<APIFolder>
     Ok(result)
 }
 
+// Move the folder or request into the folder before the item, the empty parent is the top level
+#[command(async)]
+pub async fn move_item(id: String, parent_id: String, before: Option<String>) -> CommandResult<()> {
+    schemas::move_api_item(id, parent_id, before).await?;
+    Ok(())
+}
+
+// Set the order of children of the folder
+#[command(async)]
+pub async fn reorder(collection: String, parent_id: String, ids: Vec<String>) -> CommandResult<()> {
+    schemas::reorder_api_items(collection, parent_id, ids).await?;
+    Ok(())
+}
+
 // Delete all subfolders for the API folder
 #[command(async)]
 pub async fn delete_api_folder(id: String) -> CommandResult<schemas::APIFolderChildren> {
@@ -173,6 +370,41 @@ pub async fn list_variable(collection: String, category: String) -> CommandResul
     Ok(result)
 }
 
+// Get the decrypted value of the secret variable
+#[command(async)]
+pub async fn reveal_variable(id: String) -> CommandResult<String> {
+    let result = schemas::reveal_variable(id).await?;
+    Ok(result)
+}
+
+#[command(async)]
+pub async fn get_secret_status() -> CommandResult<schemas::SecretStatus> {
+    let result = schemas::get_secret_status().await?;
+    Ok(result)
+}
+
+// Unlock the secret variables, the master passphrase is set for the first time
+#[command(async)]
+pub async fn unlock_secrets(passphrase: String) -> CommandResult<schemas::SecretStatus> {
+    let result = schemas::unlock_secrets(passphrase).await?;
+    Ok(result)
+}
+
+#[command(async)]
+pub async fn lock_secrets() -> CommandResult<()> {
+    schemas::lock_secrets();
+    Ok(())
+}
+
+#[command(async)]
+pub async fn change_secret_passphrase(
+    passphrase: String,
+    new_passphrase: String,
+) -> CommandResult<()> {
+    schemas::change_secret_passphrase(passphrase, new_passphrase).await?;
+    Ok(())
+}
+
 // Add environment
 #[command(async)]
 pub async fn add_environment(value: Environment) -> CommandResult<Environment> {
@@ -205,10 +437,235 @@ pub async fn list_environment(collection: String) -> CommandResult<Vec<Environme
 #[command(async)]
 pub async fn do_http_request(
     api: String,
+    collection: Option<String>,
     req: http_request::HTTPRequest,
     timeout: http_request::RequestTimeout,
 ) -> CommandResult<http_request::HTTPResponse> {
-    http_request::request(api, req, timeout).await
+    send_http_request(api, collection.unwrap_or_default(), req, timeout).await
+}
+
+// Send the request and save it into history, the history keeps the request
+// before it is resolved, so the secrets filled by the backend are not saved
+async fn send_http_request(
+    api: String,
+    collection: String,
+    req: http_request::HTTPRequest,
+    timeout: http_request::RequestTimeout,
+) -> Result<http_request::HTTPResponse, CyberAPIError> {
+    let extractors = req.extractors.clone();
+    let ctx = runner::RunnerContext::load(&collection, None).await?;
+    let result = match runner::resolve_app_request(&ctx, req.clone()) {
+        Ok(resolved) => http_request::request(api.clone(), resolved, timeout).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = history::record(&collection, &api, &req, &result).await {
+        warn!("failed to record history: {}", err);
+    }
+    let mut resp = result?;
+    // Save the extracted values into variables of collection
+    if !collection.is_empty() && !extractors.is_empty() {
+        resp.extractions = extractor::apply(&ctx.template, &extractors, &resp).await?;
+    }
+    Ok(resp)
+}
+
+// List the histories of executed requests
+#[command(async)]
+pub async fn list_history(query: schemas::HistoryQuery) -> CommandResult<schemas::HistoryPage> {
+    let result = schemas::list_history(query).await?;
+    Ok(result)
+}
+
+// Get the history with its request and response
+#[command(async)]
+pub async fn get_history(id: String) -> CommandResult<history::HistoryDetail> {
+    history::get(&id).await
+}
+
+// Pin the history, the pinned histories are not pruned
+#[command(async)]
+pub async fn pin_history(id: String, pinned: bool) -> CommandResult<()> {
+    schemas::pin_history(id, pinned).await?;
+    Ok(())
+}
+
+// Delete histories
+#[command(async)]
+pub async fn delete_histories(ids: Vec<String>) -> CommandResult<()> {
+    schemas::delete_histories(ids).await?;
+    Ok(())
+}
+
+// Clear the histories of collection except the pinned ones
+#[command(async)]
+pub async fn clear_history(collection: String) -> CommandResult<u64> {
+    let count = schemas::delete_history_by_collection(collection).await?;
+    Ok(count)
+}
+
+// Search the collections, folders and requests by keyword
+#[command(async)]
+pub async fn search(query: schemas::SearchQuery) -> CommandResult<Vec<schemas::SearchHit>> {
+    let result = schemas::search(query).await?;
+    Ok(result)
+}
+
+// Send the request of history again, a new history is saved
+#[command(async)]
+pub async fn rerun_history(
+    id: String,
+    timeout: http_request::RequestTimeout,
+) -> CommandResult<http_request::HTTPResponse> {
+    let detail = history::get(&id).await?;
+    send_http_request(
+        detail.history.api.unwrap_or_default(),
+        detail.history.collection.unwrap_or_default(),
+        detail.request,
+        timeout,
+    )
+    .await
+}
+
+// Run a collection, a folder or a request as a suite,
+// the result of each request is sent by the progress event
+#[command(async)]
+pub async fn run_suite(
+    app: AppHandle,
+    id: String,
+    target: runner::RunTarget,
+    options: runner::RunOptions,
+) -> CommandResult<runner::RunReport> {
+    let handle = app.clone();
+    let report = runner::run_suite(id, &target, options, move |progress| {
+        let _ = handle.emit(RUNNER_PROGRESS_EVENT, progress);
+    })
+    .await?;
+    let _ = app.emit(RUNNER_FINISHED_EVENT, report.clone());
+    Ok(report)
+}
+
+// Run the load test of api setting, the stats of each second is sent by the progress event
+#[command(async)]
+pub async fn run_load_test(
+    app: AppHandle,
+    id: String,
+    setting: String,
+    options: load_test::LoadTestOptions,
+) -> CommandResult<load_test::LoadTestReport> {
+    load_test::run(id, &setting, options, move |progress| {
+        let _ = app.emit(LOAD_TEST_PROGRESS_EVENT, progress);
+    })
+    .await
+}
+
+// Stop the running load test, it returns false if the load test is not running
+#[command(async)]
+pub async fn stop_load_test(id: String) -> CommandResult<bool> {
+    Ok(load_test::stop_load_test(&id))
+}
+
+// Start the mock server of collection, a random port is used if port is 0
+#[command(async)]
+pub async fn start_mock_server(
+    collection: String,
+    port: u16,
+    environment: Option<String>,
+) -> CommandResult<mock_server::MockServerInfo> {
+    mock_server::start(collection, port, environment).await
+}
+
+// Stop the mock server of port
+#[command(async)]
+pub async fn stop_mock_server(port: u16) -> CommandResult<bool> {
+    Ok(mock_server::stop(port))
+}
+
+// List the running mock servers
+#[command(async)]
+pub async fn list_mock_server() -> CommandResult<Vec<mock_server::MockServerInfo>> {
+    Ok(mock_server::list())
+}
+
+// List the requests received by the mock server of port
+#[command(async)]
+pub async fn list_mock_log(port: u16) -> CommandResult<Vec<mock_server::MockLog>> {
+    Ok(mock_server::list_log(port))
+}
+
+// Start the capture proxy, each recorded exchange is sent by event
+#[command(async)]
+pub async fn start_capture_proxy(
+    app: AppHandle,
+    options: capture_proxy::CaptureOptions,
+) -> CommandResult<capture_proxy::CaptureProxyInfo> {
+    capture_proxy::start(options, move |exchange| {
+        let _ = app.emit(CAPTURE_EXCHANGE_EVENT, exchange);
+    })
+    .await
+}
+
+// Stop the capture proxy of port
+#[command(async)]
+pub async fn stop_capture_proxy(port: u16) -> CommandResult<bool> {
+    Ok(capture_proxy::stop(port))
+}
+
+// List the running capture proxies
+#[command(async)]
+pub async fn list_capture_proxy() -> CommandResult<Vec<capture_proxy::CaptureProxyInfo>> {
+    Ok(capture_proxy::list())
+}
+
+// List the exchanges recorded by the capture proxies
+#[command(async)]
+pub async fn list_captured_exchange() -> CommandResult<Vec<capture_proxy::CapturedExchange>> {
+    Ok(capture_proxy::list_exchange())
+}
+
+// Clear the recorded exchanges
+#[command(async)]
+pub async fn clear_captured_exchange() -> CommandResult<()> {
+    capture_proxy::clear_exchange();
+    Ok(())
+}
+
+// Save the recorded exchanges as api settings of folder
+#[command(async)]
+pub async fn save_captured_exchanges(
+    ids: Vec<String>,
+    folder: String,
+) -> CommandResult<Vec<APISetting>> {
+    capture_proxy::save_exchanges(ids, folder).await
+}
+
+// Get the certificate of the local CA which signs the decrypted https hosts
+#[command(async)]
+pub async fn get_capture_ca_cert() -> CommandResult<String> {
+    capture_proxy::get_ca_cert()
+}
+
+// List the runtime variables extracted from responses
+#[command(async)]
+pub async fn list_runtime_variable(collection: String) -> CommandResult<HashMap<String, String>> {
+    Ok(extractor::list_runtime_variable(&collection))
+}
+
+// Clear the runtime variables of collection
+#[command(async)]
+pub async fn clear_runtime_variable(collection: String) -> CommandResult<()> {
+    extractor::clear_runtime_variable(&collection);
+    Ok(())
+}
+
+// Render the `{{fn(args)}}` placeholders of value
+#[command(async)]
+pub async fn render_template(
+    collection: String,
+    environment: Option<String>,
+    value: String,
+) -> CommandResult<String> {
+    let ctx = template::TemplateContext::load(&collection, environment).await?;
+    ctx.render(&value)
 }
 
 // List all cookies
@@ -269,3 +726,104 @@ pub async fn add_version(version: schemas::Version) -> CommandResult<()> {
     schemas::add_version(version).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{VARIABLE_CATEGORY_ENVIRONMENT, VARIABLE_ENABLED, run_with_database};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+    use std::convert::Infallible;
+
+    // Local server responds the X-Token header of request in json
+    fn start_server() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: hyper::Request<Body>| async move {
+                let token = req
+                    .headers()
+                    .get("X-Token")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                let body = serde_json::json!({"data": {"id": "1", "token": token}});
+                Ok::<_, Infallible>(Response::new(Body::from(body.to_string())))
+            }))
+        });
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_service));
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn send_request_with_locked_secrets() {
+        run_with_database(async {
+            let collection = "commands-locked-secrets";
+            let api = "commands-locked-secrets-api";
+            schemas::unlock_secrets("master".to_string()).await.unwrap();
+            schemas::add_variable(Variable {
+                id: "commands-locked-token".to_string(),
+                category: VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+                collection: collection.to_string(),
+                environment: Some("".to_string()),
+                name: Some("token".to_string()),
+                value: Some("secret-1".to_string()),
+                enabled: Some(VARIABLE_ENABLED.to_string()),
+                secret: Some("1".to_string()),
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+
+            let req: http_request::HTTPRequest = serde_json::from_value(serde_json::json!({
+                "method": "GET",
+                "uri": format!("{}/users", start_server()),
+                "body": "",
+                "contentType": "",
+                "headers": [{"key": "X-Token", "value": "{{token}}", "enabled": true}],
+                "query": [],
+                "auth": [],
+                "assertions": [],
+                "extractors": [
+                    {"variable": "userId", "scope": "runtime", "category": "jsonPath", "path": "$.data.id"},
+                ],
+            }))
+            .unwrap();
+            let send = || {
+                send_http_request(
+                    api.to_string(),
+                    collection.to_string(),
+                    req.clone(),
+                    Default::default(),
+                )
+            };
+            let token = |resp: &http_request::HTTPResponse| {
+                resp.json_body().unwrap()["data"]["token"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            };
+
+            // The locked secret is not filled, but the request is still sent
+            schemas::lock_secrets();
+            let resp = send().await.unwrap();
+            assert_eq!("{{token}}", token(&resp));
+            assert_eq!(Some("1".to_string()), resp.extractions[0].value);
+            assert_eq!(
+                Some("1".to_string()),
+                extractor::get_runtime_variable(collection, "userId")
+            );
+
+            // The secret is filled by the backend, and it is not saved into history
+            schemas::unlock_secrets("master".to_string()).await.unwrap();
+            let resp = send().await.unwrap();
+            assert_eq!("secret-1", token(&resp));
+            let detail = history::get_latest(api).await.unwrap().unwrap();
+            assert_eq!("{{token}}", detail.request.headers[0].value);
+            let request = serde_json::to_string(&detail.request).unwrap();
+            assert!(!request.contains("secret-1"));
+        });
+    }
+}
@@ -0,0 +1,289 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+use crate::error::CyberAPIError;
+
+const CRYPTO_ERROR_CATEGORY: &str = "crypto";
+// The header of encrypted data:
+// magic(8) version(1) kdf(1) m_cost(4) t_cost(4) p_cost(4) salt(16) cipher(1) nonce(24)
+const ENCRYPTED_MAGIC: &[u8; 8] = b"CYBERAPI";
+const ENCRYPTED_VERSION: u8 = 1;
+const KDF_ARGON2ID: u8 = 1;
+const CIPHER_XCHACHA20_POLY1305: u8 = 1;
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+pub const KEY_SIZE: usize = 32;
+const HEADER_SIZE: usize = ENCRYPTED_MAGIC.len() + 2 + 4 * 3 + SALT_SIZE + 1 + NONCE_SIZE;
+// The recommended parameters of argon2id, memory is in KiB
+const ARGON2_M_COST: u32 = 19 * 1024;
+const ARGON2_T_COST: u32 = 2;
+const ARGON2_P_COST: u32 = 1;
+// Avoid using too much memory or time by the header of invalid data
+const ARGON2_MAX_M_COST: u32 = 1024 * 1024;
+const ARGON2_MAX_T_COST: u32 = 16;
+const ARGON2_MAX_P_COST: u32 = 16;
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(CRYPTO_ERROR_CATEGORY, message)
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<[u8; KEY_SIZE], CyberAPIError> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_SIZE))
+        .map_err(|err| new_error(err.to_string()))?;
+    let mut key = [0u8; KEY_SIZE];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| new_error(err.to_string()))?;
+    Ok(key)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_MAGIC)
+}
+
+// Encrypt the data with the key derived from passphrase,
+// the header is authenticated too so it can not be changed
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, CyberAPIError> {
+    let salt: [u8; SALT_SIZE] = rand::random();
+    let nonce: [u8; NONCE_SIZE] = rand::random();
+    let key = derive_key(
+        passphrase,
+        &salt,
+        ARGON2_M_COST,
+        ARGON2_T_COST,
+        ARGON2_P_COST,
+    )?;
+
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(ENCRYPTED_MAGIC);
+    header.push(ENCRYPTED_VERSION);
+    header.push(KDF_ARGON2ID);
+    header.extend_from_slice(&ARGON2_M_COST.to_le_bytes());
+    header.extend_from_slice(&ARGON2_T_COST.to_le_bytes());
+    header.extend_from_slice(&ARGON2_P_COST.to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.push(CIPHER_XCHACHA20_POLY1305);
+    header.extend_from_slice(&nonce);
+
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let encrypted = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: data,
+                aad: &header,
+            },
+        )
+        .map_err(|_| new_error("encrypt data fail".to_string()))?;
+    let mut result = header;
+    result.extend_from_slice(&encrypted);
+    Ok(result)
+}
+
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, CyberAPIError> {
+    if !is_encrypted(data) || data.len() < HEADER_SIZE {
+        return Err(new_error("the encrypted data is corrupted".to_string()));
+    }
+    let mut offset = ENCRYPTED_MAGIC.len();
+    let version = data[offset];
+    if version != ENCRYPTED_VERSION {
+        return Err(new_error(format!(
+            "the version {version} of encrypted data is not supported, please upgrade the app"
+        )));
+    }
+    let kdf = data[offset + 1];
+    if kdf != KDF_ARGON2ID {
+        return Err(new_error(format!("the kdf {kdf} is not supported")));
+    }
+    offset += 2;
+    let m_cost = read_u32(data, offset);
+    let t_cost = read_u32(data, offset + 4);
+    let p_cost = read_u32(data, offset + 8);
+    if m_cost > ARGON2_MAX_M_COST
+        || !(1..=ARGON2_MAX_T_COST).contains(&t_cost)
+        || !(1..=ARGON2_MAX_P_COST).contains(&p_cost)
+    {
+        return Err(new_error("the encrypted data is corrupted".to_string()));
+    }
+    offset += 12;
+    let salt = &data[offset..offset + SALT_SIZE];
+    offset += SALT_SIZE;
+    let cipher_id = data[offset];
+    if cipher_id != CIPHER_XCHACHA20_POLY1305 {
+        return Err(new_error(format!(
+            "the cipher {cipher_id} is not supported"
+        )));
+    }
+    offset += 1;
+    let nonce = &data[offset..offset + NONCE_SIZE];
+
+    let key = derive_key(passphrase, salt, m_cost, t_cost, p_cost)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: &data[HEADER_SIZE..],
+                aad: &data[..HEADER_SIZE],
+            },
+        )
+        .map_err(|_| new_error("the passphrase is wrong or the data is corrupted".to_string()))
+}
+
+pub fn generate_key() -> [u8; KEY_SIZE] {
+    rand::random()
+}
+
+// Encrypt the data with the key directly, it is fast for the small values,
+// the result is nonce(24) and the encrypted data
+pub fn encrypt_with_key(data: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vec<u8>, CyberAPIError> {
+    let nonce: [u8; NONCE_SIZE] = rand::random();
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let encrypted = cipher
+        .encrypt(XNonce::from_slice(&nonce), data)
+        .map_err(|_| new_error("encrypt data fail".to_string()))?;
+    let mut result = nonce.to_vec();
+    result.extend_from_slice(&encrypted);
+    Ok(result)
+}
+
+pub fn decrypt_with_key(data: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vec<u8>, CyberAPIError> {
+    if data.len() < NONCE_SIZE {
+        return Err(new_error("the encrypted data is corrupted".to_string()));
+    }
+    let cipher = XChaCha20Poly1305::new(key.into());
+    cipher
+        .decrypt(XNonce::from_slice(&data[..NONCE_SIZE]), &data[NONCE_SIZE..])
+        .map_err(|_| new_error("the key is wrong or the data is corrupted".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_header() {
+        let data = b"cyberapi backup";
+        let encrypted = encrypt(data, "secret").unwrap();
+        assert!(is_encrypted(&encrypted));
+        // The tag of poly1305 is 16 bytes
+        assert_eq!(HEADER_SIZE + data.len() + 16, encrypted.len());
+        assert_eq!(b"CYBERAPI", &encrypted[..8]);
+        assert_eq!(ENCRYPTED_VERSION, encrypted[8]);
+        assert_eq!(KDF_ARGON2ID, encrypted[9]);
+        assert_eq!(ARGON2_M_COST, read_u32(&encrypted, 10));
+        assert_eq!(ARGON2_T_COST, read_u32(&encrypted, 14));
+        assert_eq!(ARGON2_P_COST, read_u32(&encrypted, 18));
+        assert_eq!(CIPHER_XCHACHA20_POLY1305, encrypted[22 + SALT_SIZE]);
+        assert_eq!(data.to_vec(), decrypt(&encrypted, "secret").unwrap());
+
+        // The salt and nonce are random
+        let other = encrypt(data, "secret").unwrap();
+        assert_ne!(encrypted[22..HEADER_SIZE], other[22..HEADER_SIZE]);
+        assert_ne!(encrypted[HEADER_SIZE..], other[HEADER_SIZE..]);
+    }
+
+    #[test]
+    fn decrypt_invalid() {
+        let encrypted = encrypt(b"cyberapi backup", "secret").unwrap();
+        assert_eq!(
+            "[crypto]the passphrase is wrong or the data is corrupted",
+            decrypt(&encrypted, "Secret").unwrap_err().to_string()
+        );
+
+        // The header is authenticated
+        let mut data = encrypted.clone();
+        data[14] += 1;
+        assert_eq!(
+            "[crypto]the passphrase is wrong or the data is corrupted",
+            decrypt(&data, "secret").unwrap_err().to_string()
+        );
+        let mut data = encrypted.clone();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert_eq!(
+            "[crypto]the passphrase is wrong or the data is corrupted",
+            decrypt(&data, "secret").unwrap_err().to_string()
+        );
+
+        let mut data = encrypted.clone();
+        data[8] = 2;
+        assert_eq!(
+            "[crypto]the version 2 of encrypted data is not supported, please upgrade the app",
+            decrypt(&data, "secret").unwrap_err().to_string()
+        );
+        let mut data = encrypted.clone();
+        data[9] = 2;
+        assert_eq!(
+            "[crypto]the kdf 2 is not supported",
+            decrypt(&data, "secret").unwrap_err().to_string()
+        );
+        let mut data = encrypted.clone();
+        data[10..14].copy_from_slice(&(ARGON2_MAX_M_COST + 1).to_le_bytes());
+        assert_eq!(
+            "[crypto]the encrypted data is corrupted",
+            decrypt(&data, "secret").unwrap_err().to_string()
+        );
+        for (offset, cost) in [
+            (14, 0),
+            (14, ARGON2_MAX_T_COST + 1),
+            (18, 0),
+            (18, ARGON2_MAX_P_COST + 1),
+        ] {
+            let mut data = encrypted.clone();
+            data[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(cost));
+            assert_eq!(
+                "[crypto]the encrypted data is corrupted",
+                decrypt(&data, "secret").unwrap_err().to_string()
+            );
+        }
+        let mut data = encrypted.clone();
+        data[22 + SALT_SIZE] = 2;
+        assert_eq!(
+            "[crypto]the cipher 2 is not supported",
+            decrypt(&data, "secret").unwrap_err().to_string()
+        );
+        for data in [&encrypted[..HEADER_SIZE - 1], b"PK\x03\x04".as_slice()] {
+            assert_eq!(
+                "[crypto]the encrypted data is corrupted",
+                decrypt(data, "secret").unwrap_err().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn encrypt_key() {
+        let key = generate_key();
+        let encrypted = encrypt_with_key(b"token", &key).unwrap();
+        assert_eq!(NONCE_SIZE + 5 + 16, encrypted.len());
+        assert_eq!(
+            b"token".to_vec(),
+            decrypt_with_key(&encrypted, &key).unwrap()
+        );
+        assert_eq!(
+            "[crypto]the key is wrong or the data is corrupted",
+            decrypt_with_key(&encrypted, &generate_key())
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "[crypto]the encrypted data is corrupted",
+            decrypt_with_key(&encrypted[..NONCE_SIZE - 1], &key)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
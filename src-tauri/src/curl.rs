@@ -0,0 +1,916 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{
+    CONTENT_TYPE_FORM, CONTENT_TYPE_MULTIPART, HTTPRequest, HTTPRequestKVParam,
+};
+use crate::schemas::{self, APISetting};
+use serde::Serialize;
+use url::{Url, form_urlencoded};
+
+const CURL_ERROR_CATEGORY: &str = "curl";
+const FILE_PROTOCOL: &str = "file://";
+
+// The options which do not change the request, e.g. the output of curl
+const IGNORED_FLAGS: [&str; 36] = [
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-L",
+    "--location",
+    "--location-trusted",
+    "--compressed",
+    "-#",
+    "--progress-bar",
+    "--no-progress-meter",
+    "-N",
+    "--no-buffer",
+    "-f",
+    "--fail",
+    "--fail-with-body",
+    "-g",
+    "--globoff",
+    "-O",
+    "--remote-name",
+    "-J",
+    "--remote-header-name",
+    "-4",
+    "--ipv4",
+    "-6",
+    "--ipv6",
+    "--http1.1",
+    "--http2",
+    "--http2-prior-knowledge",
+    "-q",
+    "--disable",
+    "--path-as-is",
+];
+
+// The options with a value which are not supported, the value is skipped
+const UNSUPPORTED_VALUE_OPTIONS: [&str; 42] = [
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "--retry-delay",
+    "--retry-max-time",
+    "-w",
+    "--write-out",
+    "--cacert",
+    "--capath",
+    "-E",
+    "--cert",
+    "--cert-type",
+    "--key",
+    "--key-type",
+    "-T",
+    "--upload-file",
+    "-c",
+    "--cookie-jar",
+    "--resolve",
+    "--connect-to",
+    "-U",
+    "--proxy-user",
+    "--proxy-header",
+    "--noproxy",
+    "--limit-rate",
+    "-r",
+    "--range",
+    "--max-redirs",
+    "-D",
+    "--dump-header",
+    "--interface",
+    "--trace",
+    "--trace-ascii",
+    "-K",
+    "--config",
+    "--aws-sigv4",
+    "--unix-socket",
+    "-z",
+    "--time-cond",
+];
+
+// The short options which have a value, e.g. `-XPOST` or `-X POST`
+const SHORT_VALUE_OPTIONS: &str = "XHdFubxAeomwETcUrDKz";
+
+// The long options which have a value, the value may start with `-`, e.g. `--data -1`
+const LONG_VALUE_OPTIONS: [&str; 19] = [
+    "--url",
+    "--request",
+    "--header",
+    "--user-agent",
+    "--referer",
+    "--oauth2-bearer",
+    "--data",
+    "--data-ascii",
+    "--data-binary",
+    "--data-raw",
+    "--data-urlencode",
+    "--json",
+    "--form",
+    "--form-string",
+    "--user",
+    "--cookie",
+    "--proxy",
+    "--socks5",
+    "--socks5-hostname",
+];
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CurlImportResult {
+    pub setting: APISetting,
+    // The proxy of `-x`, it is not saved and only suggested by the warnings
+    pub proxy: Option<String>,
+    // The options which are not supported, e.g. `--cert`
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CurlCommand {
+    pub req: HTTPRequest,
+    pub proxy: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(CURL_ERROR_CATEGORY, message)
+}
+
+// Split the command like posix shell, the line continuations of shell,
+// cmd (`^`) and powershell (`` ` ``) are supported
+fn split_words(command: &str) -> Result<Vec<String>, CyberAPIError> {
+    let mut words = vec![];
+    let mut word = String::new();
+    // The word is started even if it is empty, e.g. `''`
+    let mut started = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '^' | '`' if matches!(chars.peek(), Some('\n') | Some('\r')) => {
+                while matches!(chars.peek(), Some('\n') | Some('\r')) {
+                    chars.next();
+                }
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                    started = true;
+                }
+            }
+            '\'' => {
+                started = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(value) => word.push(value),
+                        None => return Err(new_error("unterminated single quote".to_string())),
+                    }
+                }
+            }
+            '"' => {
+                started = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Only these chars are escaped in double quotes
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(value) if ['$', '`', '"', '\\'].contains(&value) => {
+                                word.push(value)
+                            }
+                            Some(value) => {
+                                word.push('\\');
+                                word.push(value);
+                            }
+                            None => break,
+                        },
+                        Some(value) => word.push(value),
+                        None => return Err(new_error("unterminated double quote".to_string())),
+                    }
+                }
+            }
+            // The ansi c quoting, e.g. `$'a\nb'` which is used by the copy as curl of chrome
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                started = true;
+                let mut buf = vec![];
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => {
+                            let escaped = match chars.next() {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some('r') => '\r',
+                                Some('0') => '\0',
+                                Some('x') => {
+                                    let mut hex = String::new();
+                                    while hex.len() < 2
+                                        && chars.peek().is_some_and(|c| c.is_ascii_hexdigit())
+                                    {
+                                        hex.push(chars.next().unwrap_or_default());
+                                    }
+                                    buf.push(u8::from_str_radix(&hex, 16).unwrap_or_default());
+                                    continue;
+                                }
+                                Some('u') => {
+                                    let mut hex = String::new();
+                                    while hex.len() < 4
+                                        && chars.peek().is_some_and(|c| c.is_ascii_hexdigit())
+                                    {
+                                        hex.push(chars.next().unwrap_or_default());
+                                    }
+                                    u32::from_str_radix(&hex, 16)
+                                        .ok()
+                                        .and_then(char::from_u32)
+                                        .unwrap_or_default()
+                                }
+                                Some(value) => value,
+                                None => {
+                                    return Err(new_error("unterminated ansi c quote".to_string()));
+                                }
+                            };
+                            let mut tmp = [0; 4];
+                            buf.extend_from_slice(escaped.encode_utf8(&mut tmp).as_bytes());
+                        }
+                        Some(value) => {
+                            let mut tmp = [0; 4];
+                            buf.extend_from_slice(value.encode_utf8(&mut tmp).as_bytes());
+                        }
+                        None => return Err(new_error("unterminated ansi c quote".to_string())),
+                    }
+                }
+                word.push_str(&String::from_utf8_lossy(&buf));
+            }
+            c if c.is_whitespace() => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                    started = false;
+                }
+            }
+            c => {
+                word.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+// Split the short options, e.g. `-sSL` is `-s -S -L` and `-XPOST` is `-X POST`
+fn expand_options(words: Vec<String>) -> Vec<String> {
+    let mut result = vec![];
+    let mut is_value = false;
+    for word in words {
+        if is_value || !word.starts_with('-') || word.starts_with("--") || word.len() <= 2 {
+            is_value = !is_value
+                && ((word.len() == 2 && SHORT_VALUE_OPTIONS.contains(&word[1..]))
+                    || LONG_VALUE_OPTIONS.contains(&word.as_str())
+                    || UNSUPPORTED_VALUE_OPTIONS.contains(&word.as_str()));
+            result.push(word);
+            continue;
+        }
+        let mut chars = word[1..].char_indices();
+        is_value = false;
+        for (index, c) in chars.by_ref() {
+            result.push(format!("-{}", c));
+            if SHORT_VALUE_OPTIONS.contains(c) {
+                let value = &word[1 + index + c.len_utf8()..];
+                if value.is_empty() {
+                    is_value = true;
+                } else {
+                    result.push(value.to_string());
+                }
+                break;
+            }
+        }
+    }
+    result
+}
+
+fn add_header(headers: &mut Vec<HTTPRequestKVParam>, key: &str, value: &str) {
+    headers.push(HTTPRequestKVParam {
+        key: key.to_string(),
+        value: value.to_string(),
+        enabled: true,
+    });
+}
+
+fn has_header(headers: &[HTTPRequestKVParam], key: &str) -> bool {
+    headers
+        .iter()
+        .any(|item| item.key.eq_ignore_ascii_case(key))
+}
+
+// The value of `--data-urlencode`, e.g. `name=content` or `=content`
+fn encode_data(value: &str) -> Option<String> {
+    let encode =
+        |value: &str| form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>();
+    match value.find(['=', '@']) {
+        Some(index) if value[index..].starts_with('@') => None,
+        Some(0) => Some(encode(&value[1..])),
+        Some(index) => Some(format!(
+            "{}={}",
+            &value[..index],
+            encode(&value[index + 1..])
+        )),
+        None => Some(encode(value)),
+    }
+}
+
+fn is_json(value: &str) -> bool {
+    let value = value.trim_start();
+    (value.starts_with('{') || value.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(value).is_ok()
+}
+
+// Parse the curl command to the request saved by the app
+pub fn parse(command: &str) -> Result<CurlCommand, CyberAPIError> {
+    let mut words = split_words(command.trim())?;
+    // Remove the prompt of shell, e.g. `$ curl`
+    let Some(index) = words
+        .iter()
+        .position(|value| value == "curl" || value.eq_ignore_ascii_case("curl.exe"))
+    else {
+        return Err(new_error("it is not a curl command".to_string()));
+    };
+    let mut words = expand_options(words.split_off(index + 1)).into_iter();
+
+    let mut result = CurlCommand::default();
+    let mut url = "".to_string();
+    let mut method = "".to_string();
+    let mut headers = vec![];
+    let mut data: Vec<String> = vec![];
+    // The data of file is not imported, but the request is still sent by POST
+    let mut has_data = false;
+    let mut form = vec![];
+    let mut cookies = vec![];
+    let mut is_head = false;
+    let mut is_get = false;
+    let warn = |result: &mut CurlCommand, message: String| {
+        if !result.warnings.contains(&message) {
+            result.warnings.push(message);
+        }
+    };
+    while let Some(word) = words.next() {
+        let option = word.as_str();
+        // The url is not an option
+        if !option.starts_with('-') {
+            if url.is_empty() {
+                url = word;
+            } else {
+                warn(
+                    &mut result,
+                    format!("only the first url is imported, {} is ignored", word),
+                );
+            }
+            continue;
+        }
+        if IGNORED_FLAGS.contains(&option) {
+            continue;
+        }
+        match option {
+            "-I" | "--head" => {
+                is_head = true;
+                continue;
+            }
+            "-G" | "--get" => {
+                is_get = true;
+                continue;
+            }
+            "-k" | "--insecure" => {
+                warn(
+                    &mut result,
+                    format!(
+                        "{} is not supported, the certificate is always verified",
+                        option
+                    ),
+                );
+                continue;
+            }
+            _ => {}
+        }
+        let Some(value) = words.next() else {
+            warn(&mut result, format!("{} is not supported", option));
+            continue;
+        };
+        match option {
+            "--url" => {
+                if url.is_empty() {
+                    url = value;
+                } else {
+                    warn(
+                        &mut result,
+                        format!("only the first url is imported, {} is ignored", value),
+                    );
+                }
+            }
+            "-X" | "--request" => method = value.to_uppercase(),
+            "-H" | "--header" => {
+                if let Some(file) = value.strip_prefix('@') {
+                    warn(
+                        &mut result,
+                        format!("the headers of file {} are not imported", file),
+                    );
+                    continue;
+                }
+                // `Name;` is the header without value, `Name:` removes the header
+                match value.split_once(':') {
+                    Some((key, value)) if !value.trim().is_empty() => {
+                        add_header(&mut headers, key.trim(), value.trim())
+                    }
+                    Some((key, _)) => {
+                        headers.retain(|item| !item.key.eq_ignore_ascii_case(key.trim()))
+                    }
+                    None => add_header(&mut headers, value.trim_end_matches(';').trim(), ""),
+                }
+            }
+            "-A" | "--user-agent" => add_header(&mut headers, "User-Agent", &value),
+            "-e" | "--referer" => add_header(&mut headers, "Referer", &value),
+            "--oauth2-bearer" => {
+                add_header(&mut headers, "Authorization", &format!("Bearer {}", value))
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                has_data = true;
+                if let Some(file) = value.strip_prefix('@') {
+                    warn(
+                        &mut result,
+                        format!("the body of file {} is not imported", file),
+                    );
+                } else if option == "--data-binary" {
+                    data.push(value);
+                } else {
+                    // The new lines are removed by curl except `--data-binary`
+                    data.push(value.replace(['\r', '\n'], ""));
+                }
+            }
+            "--data-raw" => {
+                has_data = true;
+                data.push(value);
+            }
+            "--json" => {
+                has_data = true;
+                if let Some(file) = value.strip_prefix('@') {
+                    warn(
+                        &mut result,
+                        format!("the body of file {} is not imported", file),
+                    );
+                } else {
+                    data.push(value);
+                }
+                if !has_header(&headers, "Content-Type") {
+                    add_header(&mut headers, "Content-Type", "application/json");
+                }
+                if !has_header(&headers, "Accept") {
+                    add_header(&mut headers, "Accept", "application/json");
+                }
+            }
+            "--data-urlencode" => {
+                has_data = true;
+                match encode_data(&value) {
+                    Some(value) => data.push(value),
+                    None => warn(
+                        &mut result,
+                        format!("the body of file in {} is not imported", value),
+                    ),
+                }
+            }
+            "-F" | "--form" | "--form-string" => {
+                let Some((key, field)) = value.split_once('=') else {
+                    warn(&mut result, format!("the form field {} is invalid", value));
+                    continue;
+                };
+                let field = if option == "--form-string" {
+                    field.to_string()
+                } else if let Some(file) = field.strip_prefix('@') {
+                    // The params of file are ignored, e.g. `;type=text/plain`
+                    let file = match file.strip_prefix('"') {
+                        Some(file) => file.split('"').next().unwrap_or_default(),
+                        None => file.split(';').next().unwrap_or_default(),
+                    };
+                    format!("{}{}", FILE_PROTOCOL, file)
+                } else if let Some(file) = field.strip_prefix('<') {
+                    warn(
+                        &mut result,
+                        format!("the content of file {} is not imported", file),
+                    );
+                    continue;
+                } else {
+                    field.to_string()
+                };
+                form.push(HTTPRequestKVParam {
+                    key: key.to_string(),
+                    value: field,
+                    enabled: true,
+                });
+            }
+            "-u" | "--user" => {
+                let (username, password) = value.split_once(':').unwrap_or((&value, ""));
+                result.req.auth = vec![HTTPRequestKVParam {
+                    key: username.to_string(),
+                    value: password.to_string(),
+                    enabled: true,
+                }];
+            }
+            "-b" | "--cookie" => {
+                if value.contains('=') {
+                    cookies.push(value.trim().trim_end_matches(';').to_string());
+                } else {
+                    warn(
+                        &mut result,
+                        format!("the cookies of file {} are not imported", value),
+                    );
+                }
+            }
+            "-x" | "--proxy" => {
+                result.proxy = if value.contains("://") {
+                    Some(value)
+                } else {
+                    Some(format!("http://{}", value))
+                }
+            }
+            "--socks5" | "--socks5-hostname" => result.proxy = Some(format!("socks5://{}", value)),
+            _ if UNSUPPORTED_VALUE_OPTIONS.contains(&option) => {
+                warn(&mut result, format!("{} is not supported", option))
+            }
+            _ => {
+                // The value of unknown option may be the url or another option
+                warn(&mut result, format!("{} is not supported", option));
+                if value.starts_with('-') {
+                    let mut rest = vec![value];
+                    rest.extend(words);
+                    words = rest.into_iter();
+                } else if url.is_empty() && (value.contains("://") || value.contains('.')) {
+                    url = value;
+                }
+            }
+        }
+    }
+    if url.is_empty() {
+        return Err(new_error("the url of curl command is empty".to_string()));
+    }
+    // The default protocol of curl is http
+    if !url.contains("://") {
+        url = format!("http://{}", url);
+    }
+    if !cookies.is_empty() {
+        add_header(&mut headers, "Cookie", &cookies.join("; "));
+    }
+
+    let mut body = data.join("&");
+    // The data is sent as query by `-G`
+    if is_get && !body.is_empty() {
+        let mut value = Url::parse(&url)?;
+        let query = match value.query() {
+            Some(query) if !query.is_empty() => format!("{}&{}", query, body),
+            _ => body.clone(),
+        };
+        value.set_query(Some(&query));
+        url = value.to_string();
+        body = "".to_string();
+    }
+    if !body.is_empty() && !has_header(&headers, "Content-Type") {
+        let content_type = if is_json(&body) {
+            "application/json"
+        } else {
+            CONTENT_TYPE_FORM
+        };
+        add_header(&mut headers, "Content-Type", content_type);
+    }
+    if !form.is_empty() {
+        // The boundary is created when the request is sent
+        headers.retain(|item| !item.key.eq_ignore_ascii_case("content-type"));
+        if !body.is_empty() {
+            warn(
+                &mut result,
+                "the data can not be sent with the form".to_string(),
+            );
+            body = "".to_string();
+        }
+    }
+    let method = if !method.is_empty() {
+        method
+    } else if is_head {
+        "HEAD".to_string()
+    } else if is_get || (!has_data && form.is_empty()) {
+        "GET".to_string()
+    } else {
+        "POST".to_string()
+    };
+
+    let auth = std::mem::take(&mut result.req.auth);
+    let mut req = HTTPRequest::from_raw(&method, &url, headers, body.as_bytes())?;
+    if !form.is_empty() {
+        req.content_type = CONTENT_TYPE_MULTIPART.to_string();
+        req.body = serde_json::to_string(&form)?;
+    }
+    req.auth = auth;
+    result.req = req;
+    Ok(result)
+}
+
+// Import the curl command as a request of collection
+pub async fn import(collection: &str, command: &str) -> Result<CurlImportResult, CyberAPIError> {
+    let CurlCommand {
+        req,
+        proxy,
+        mut warnings,
+    } = parse(command)?;
+    let url = Url::parse(&req.uri)?;
+    let setting = schemas::add_api_setting(APISetting {
+        id: ulid::Ulid::new().to_string(),
+        collection: collection.to_string(),
+        name: Some(format!("{} {}", req.method, url.path())),
+        category: Some("http".to_string()),
+        setting: Some(serde_json::to_string(&req)?),
+        parent_id: None,
+        sort_order: None,
+        created_at: None,
+        updated_at: None,
+    })
+    .await?;
+
+    // The proxies are shared by all requests, so they are only changed by the user
+    if let Some(value) = &proxy {
+        warnings.push(format!(
+            "the proxy {} is not saved, add it in the proxy settings for {} if needed",
+            value,
+            url.host_str().unwrap_or_default()
+        ));
+    }
+    Ok(CurlImportResult {
+        setting,
+        proxy,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn words(command: &str) -> Vec<String> {
+        split_words(command).unwrap()
+    }
+
+    fn kv(key: &str, value: &str) -> Value {
+        json!({"key": key, "value": value, "enabled": true})
+    }
+
+    fn to_json<T: Serialize>(value: &T) -> Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn split_shell_words() {
+        assert_eq!(
+            vec!["curl", "a b", "it's", "", "x\\y", "$HOME", "a\\nb"],
+            words(r#"curl 'a b' it\'s "" "x\\y" "\$HOME" "a\nb""#)
+        );
+        // The line continuations of bash, cmd and powershell
+        assert_eq!(
+            vec!["curl", "-X", "POST", "url"],
+            words("curl \\\n  -X POST ^\r\n  url")
+        );
+        assert_eq!(vec!["curl", "url"], words("curl `\n url"));
+        // The ansi c quoting of chrome
+        assert_eq!(
+            vec!["{\"a\":\n\t\"it's\"}", "\u{e9}", "é"],
+            words(r#"$'{"a":\n\t"it\'s"}' $'\xc3\xa9' $'é'"#)
+        );
+
+        for (command, message) in [
+            ("curl 'a", "[curl]unterminated single quote"),
+            ("curl \"a", "[curl]unterminated double quote"),
+            ("curl $'a", "[curl]unterminated ansi c quote"),
+        ] {
+            assert_eq!(message, split_words(command).unwrap_err().to_string());
+        }
+    }
+
+    #[test]
+    fn expand_short_options() {
+        let expand = |command: &str| expand_options(words(command));
+        assert_eq!(
+            vec!["curl", "-s", "-S", "-L", "-X", "POST", "url"],
+            expand("curl -sSL -XPOST url")
+        );
+        assert_eq!(
+            vec!["curl", "-s", "-H", "-XY: 1", "url"],
+            expand("curl -sH '-XY: 1' url")
+        );
+        assert_eq!(
+            vec!["curl", "--data", "-abc", "-d", "-X", "url"],
+            expand("curl --data -abc -d -X url")
+        );
+    }
+
+    #[test]
+    fn encode_urlencode_data() {
+        assert_eq!(Some("a+b".to_string()), encode_data("a b"));
+        assert_eq!(Some("a%3Db".to_string()), encode_data("=a=b"));
+        assert_eq!(Some("name=a+%26+b".to_string()), encode_data("name=a & b"));
+        assert_eq!(None, encode_data("name@file.txt"));
+        assert_eq!(Some("name=%40me".to_string()), encode_data("name=@me"));
+    }
+
+    #[test]
+    fn parse_chrome_command() {
+        let result = parse(
+            r#"$ curl 'https://api.example.com/users?page=1' \
+  -H 'accept: application/json' \
+  -H 'content-type: application/json' \
+  -b 'sid=abc; theme=dark' \
+  --data-raw $'{"name":"it\'s"}' \
+  --compressed"#,
+        )
+        .unwrap();
+        let req = result.req;
+        assert_eq!("POST", req.method);
+        assert_eq!("https://api.example.com/users", req.uri);
+        assert_eq!("application/json", req.content_type);
+        assert_eq!(r#"{"name":"it's"}"#, req.body);
+        assert_eq!(json!([kv("page", "1")]), to_json(&req.query));
+        assert_eq!(
+            json!([
+                kv("accept", "application/json"),
+                kv("Cookie", "sid=abc; theme=dark")
+            ]),
+            to_json(&req.headers)
+        );
+        assert!(result.warnings.is_empty());
+        assert_eq!(None, result.proxy);
+    }
+
+    #[test]
+    fn parse_data() {
+        // The data is a form by default and the new lines are removed
+        let req = parse("curl -d 'a=1\n' --data-urlencode 'b=x y' example.com")
+            .unwrap()
+            .req;
+        assert_eq!("POST", req.method);
+        assert_eq!("http://example.com/", req.uri);
+        assert_eq!(CONTENT_TYPE_FORM, req.content_type);
+        assert_eq!(
+            json!([kv("a", "1"), kv("b", "x y")]),
+            serde_json::from_str::<Value>(&req.body).unwrap()
+        );
+
+        // The data is the query of `-G`
+        let req = parse("curl -G 'http://localhost/?a=1' -d b=2 -d c=3")
+            .unwrap()
+            .req;
+        assert_eq!("GET", req.method);
+        assert_eq!("", req.body);
+        assert_eq!(
+            json!([kv("a", "1"), kv("b", "2"), kv("c", "3")]),
+            to_json(&req.query)
+        );
+
+        let req = parse(r#"curl --json '{"a": 1}' -X put http://localhost"#)
+            .unwrap()
+            .req;
+        assert_eq!("PUT", req.method);
+        assert_eq!("application/json", req.content_type);
+        assert_eq!(r#"{"a": 1}"#, req.body);
+        assert_eq!(
+            json!([kv("Accept", "application/json")]),
+            to_json(&req.headers)
+        );
+
+        // The data of file is not imported, but the method is still POST
+        let result = parse("curl -d @body.json http://localhost").unwrap();
+        assert_eq!("POST", result.req.method);
+        assert_eq!("", result.req.body);
+        assert_eq!(
+            vec!["the body of file body.json is not imported".to_string()],
+            result.warnings
+        );
+    }
+
+    #[test]
+    fn parse_form() {
+        let result = parse(
+            r#"curl -F 'name=cat' -F 'photo=@"/tmp/my cat.png";type=image/png' -F 'doc=@a.txt;type=text/plain' --form-string 'note=@me' -F 'bio=<bio.txt' -H 'Content-Type: multipart/form-data' http://localhost/upload"#,
+        )
+        .unwrap();
+        let req = result.req;
+        assert_eq!("POST", req.method);
+        assert_eq!(CONTENT_TYPE_MULTIPART, req.content_type);
+        assert!(req.headers.is_empty());
+        assert_eq!(
+            json!([
+                kv("name", "cat"),
+                kv("photo", "file:///tmp/my cat.png"),
+                kv("doc", "file://a.txt"),
+                kv("note", "@me"),
+            ]),
+            serde_json::from_str::<Value>(&req.body).unwrap()
+        );
+        assert_eq!(
+            vec!["the content of file bio.txt is not imported".to_string()],
+            result.warnings
+        );
+    }
+
+    #[test]
+    fn parse_options() {
+        let result = parse(
+            "curl -I -u bob:secret -A agent -e http://ref -H 'X-Empty;' -H 'X-Removed: 1' -H 'x-removed:' --oauth2-bearer t -x 127.0.0.1:8888 -k -k --cert a.pem --unknown -L http://localhost",
+        )
+        .unwrap();
+        let req = result.req;
+        assert_eq!("HEAD", req.method);
+        assert_eq!(json!([kv("bob", "secret")]), to_json(&req.auth));
+        assert_eq!(
+            json!([
+                kv("User-Agent", "agent"),
+                kv("Referer", "http://ref"),
+                kv("X-Empty", ""),
+                kv("Authorization", "Bearer t"),
+            ]),
+            to_json(&req.headers)
+        );
+        assert_eq!(Some("http://127.0.0.1:8888".to_string()), result.proxy);
+        assert_eq!(
+            vec![
+                "-k is not supported, the certificate is always verified".to_string(),
+                "--cert is not supported".to_string(),
+                "--unknown is not supported".to_string(),
+            ],
+            result.warnings
+        );
+
+        // The value of unknown option may be the url
+        let result = parse("curl --unknown example.com/a --socks5 localhost:1080").unwrap();
+        assert_eq!("http://example.com/a", result.req.uri);
+        assert_eq!(Some("socks5://localhost:1080".to_string()), result.proxy);
+
+        let result = parse("curl.exe http://a http://b").unwrap();
+        assert_eq!("http://a/", result.req.uri);
+        assert_eq!(
+            vec!["only the first url is imported, http://b is ignored".to_string()],
+            result.warnings
+        );
+    }
+
+    #[test]
+    fn parse_prompt() {
+        for command in [
+            "$ curl http://localhost/curl",
+            "user@curl-host:~$ curl http://localhost/curl",
+            "PS C:\\> curl.exe http://localhost/curl",
+        ] {
+            let req = parse(command).unwrap().req;
+            assert_eq!("http://localhost/curl", req.uri, "{}", command);
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for (command, message) in [
+            ("wget http://localhost", "[curl]it is not a curl command"),
+            ("curlie http://localhost", "[curl]it is not a curl command"),
+            (
+                "wget http://localhost/curl",
+                "[curl]it is not a curl command",
+            ),
+            ("curl -s", "[curl]the url of curl command is empty"),
+        ] {
+            assert_eq!(message, parse(command).unwrap_err().to_string());
+        }
+    }
+
+    #[test]
+    fn import_proxy_warning() {
+        schemas::run_with_database(async {
+            let proxies = schemas::list_proxy().await.unwrap().len();
+            let result = import(
+                "curl-import-proxy",
+                "curl -x 127.0.0.1:8888 -X POST http://localhost/users",
+            )
+            .await
+            .unwrap();
+            assert_eq!(Some("POST /users".to_string()), result.setting.name);
+            assert_eq!(Some("http://127.0.0.1:8888".to_string()), result.proxy);
+            assert_eq!(
+                vec![
+                    "the proxy http://127.0.0.1:8888 is not saved, add it in the proxy settings for localhost if needed"
+                        .to_string()
+                ],
+                result.warnings
+            );
+            assert_eq!(proxies, schemas::list_proxy().await.unwrap().len());
+        });
+    }
+}
@@ -9,8 +9,9 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: String,
     pub collection: String,
-    pub children: Option<String>,
     pub name: Option<String>,
+    pub parent_id: Option<String>,
+    pub sort_order: Option<i32>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
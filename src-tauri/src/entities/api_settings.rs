@@ -12,6 +12,8 @@ pub struct Model {
     pub name: Option<String>,
     pub category: Option<String>,
     pub setting: Option<String>,
+    pub parent_id: Option<String>,
+    pub sort_order: Option<i32>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -6,6 +6,7 @@ pub mod api_collections;
 pub mod api_folders;
 pub mod api_settings;
 pub mod environments;
+pub mod histories;
 pub mod proxies;
 pub mod variables;
 pub mod versions;
@@ -14,6 +14,7 @@ pub struct Model {
     pub name: Option<String>,
     pub value: Option<String>,
     pub enabled: Option<String>,
+    pub secret: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -7,6 +7,22 @@ pub struct CyberAPIError {
     category: String,
 }
 
+impl CyberAPIError {
+    pub fn new(category: &str, message: String) -> Self {
+        CyberAPIError {
+            message,
+            category: category.to_string(),
+        }
+    }
+}
+
+// Same format as the error message of the frontend
+impl std::fmt::Display for CyberAPIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}]{}", self.category, self.message)
+    }
+}
+
 impl From<sea_orm::DbErr> for CyberAPIError {
     fn from(error: sea_orm::DbErr) -> Self {
         CyberAPIError {
@@ -152,3 +168,43 @@ impl From<cookie::ParseError> for CyberAPIError {
         }
     }
 }
+impl From<csv::Error> for CyberAPIError {
+    fn from(error: csv::Error) -> Self {
+        CyberAPIError {
+            message: error.to_string(),
+            category: "csv".to_string(),
+        }
+    }
+}
+impl From<tokio::task::JoinError> for CyberAPIError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        CyberAPIError {
+            message: error.to_string(),
+            category: "task".to_string(),
+        }
+    }
+}
+impl From<rcgen::RcgenError> for CyberAPIError {
+    fn from(error: rcgen::RcgenError) -> Self {
+        CyberAPIError {
+            message: error.to_string(),
+            category: "certificate".to_string(),
+        }
+    }
+}
+impl From<tokio_rustls::rustls::Error> for CyberAPIError {
+    fn from(error: tokio_rustls::rustls::Error) -> Self {
+        CyberAPIError {
+            message: error.to_string(),
+            category: "tls".to_string(),
+        }
+    }
+}
+impl From<serde_yaml::Error> for CyberAPIError {
+    fn from(error: serde_yaml::Error) -> Self {
+        CyberAPIError {
+            message: error.to_string(),
+            category: "serdeYaml".to_string(),
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use crate::error::CyberAPIError;
+use crate::http_request::HTTPResponse;
+use crate::json_path;
+use crate::schemas::{
+    self, VARIABLE_CATEGORY_ENVIRONMENT, VARIABLE_ENABLED, Variable, list_variable,
+};
+use crate::template::TemplateContext;
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const EXTRACTOR_ERROR_CATEGORY: &str = "extractor";
+
+// Extract a value from the response into a variable, it is saved with the setting of api
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Extractor {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Name of the variable
+    pub variable: String,
+    pub scope: ExtractorScope,
+    #[serde(flatten)]
+    pub source: ExtractorSource,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExtractorScope {
+    // Variable without environment
+    Global,
+    // Variable of the enabled environment
+    Environment,
+    // Only kept in memory until the app exits
+    Runtime,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "category")]
+pub enum ExtractorSource {
+    JsonPath {
+        path: String,
+    },
+    Header {
+        name: String,
+    },
+    // Match the body, the first capture group is used if there is one
+    Regex {
+        pattern: String,
+        group: Option<usize>,
+    },
+    // Cookie set by the response
+    Cookie {
+        name: String,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionResult {
+    pub variable: String,
+    pub scope: ExtractorScope,
+    pub value: Option<String>,
+    pub error: Option<String>,
+}
+
+// Runtime variables of each collection
+static RUNTIME_VARIABLES: OnceCell<Mutex<HashMap<String, HashMap<String, String>>>> =
+    OnceCell::new();
+
+fn get_runtime_variables() -> &'static Mutex<HashMap<String, HashMap<String, String>>> {
+    RUNTIME_VARIABLES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// The concurrent iterations of runner save the same variables,
+// the lookup and insert of variable should not be interleaved
+static SAVE_VARIABLE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+pub fn get_runtime_variable(collection: &str, name: &str) -> Option<String> {
+    let store = get_runtime_variables().lock().ok()?;
+    store.get(collection)?.get(name).cloned()
+}
+
+pub fn set_runtime_variable(collection: &str, name: &str, value: &str) {
+    if let Ok(mut store) = get_runtime_variables().lock() {
+        store
+            .entry(collection.to_string())
+            .or_default()
+            .insert(name.to_string(), value.to_string());
+    }
+}
+
+pub fn list_runtime_variable(collection: &str) -> HashMap<String, String> {
+    get_runtime_variables()
+        .lock()
+        .ok()
+        .and_then(|store| store.get(collection).cloned())
+        .unwrap_or_default()
+}
+
+pub fn clear_runtime_variable(collection: &str) {
+    if let Ok(mut store) = get_runtime_variables().lock() {
+        store.remove(collection);
+    }
+}
+
+fn extract(source: &ExtractorSource, resp: &HTTPResponse) -> Result<String, String> {
+    let not_found = |name: &str| format!("{} is not found", name);
+    match source {
+        ExtractorSource::JsonPath { path } => {
+            let json = resp
+                .json_body()
+                .map_err(|_| "body is not json".to_string())?;
+            json_path::find_first(&json, path)
+                .map(json_path::to_string)
+                .ok_or_else(|| not_found(path))
+        }
+        ExtractorSource::Header { name } => resp
+            .headers
+            .get(&name.to_lowercase())
+            .and_then(|values| values.first())
+            .cloned()
+            .ok_or_else(|| not_found(name)),
+        ExtractorSource::Regex { pattern, group } => {
+            let re = Regex::new(pattern).map_err(|err| err.to_string())?;
+            let buf = resp.decode_body().map_err(|err| err.to_string())?;
+            let body = String::from_utf8_lossy(&buf);
+            let captures = re.captures(&body).ok_or_else(|| not_found(pattern))?;
+            let group = group.unwrap_or(if captures.len() > 1 { 1 } else { 0 });
+            captures
+                .get(group)
+                .map(|value| value.as_str().to_string())
+                .ok_or_else(|| not_found(pattern))
+        }
+        ExtractorSource::Cookie { name } => resp
+            .headers
+            .get("set-cookie")
+            .into_iter()
+            .flatten()
+            .filter_map(|value| cookie::Cookie::parse(value.as_str()).ok())
+            .find(|item| item.name() == name)
+            .map(|item| item.value().to_string())
+            .ok_or_else(|| not_found(name)),
+    }
+}
+
+// Create or update the variable of collection
+async fn save_variable(
+    ctx: &TemplateContext,
+    scope: ExtractorScope,
+    name: &str,
+    value: &str,
+) -> Result<(), CyberAPIError> {
+    let environment = match scope {
+        ExtractorScope::Runtime => {
+            set_runtime_variable(&ctx.collection, name, value);
+            return Ok(());
+        }
+        ExtractorScope::Global => "".to_string(),
+        ExtractorScope::Environment => ctx.environment.clone().ok_or_else(|| {
+            CyberAPIError::new(
+                EXTRACTOR_ERROR_CATEGORY,
+                "there is no enabled environment".to_string(),
+            )
+        })?,
+    };
+    let _guard = SAVE_VARIABLE_LOCK.lock().await;
+    let found = list_variable(
+        ctx.collection.clone(),
+        VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+    )
+    .await?
+    .into_iter()
+    .find(|item| {
+        item.name.as_deref() == Some(name)
+            && item.environment.clone().unwrap_or_default() == environment
+    });
+    match found {
+        Some(mut variable) => {
+            variable.value = Some(value.to_string());
+            variable.updated_at = None;
+            schemas::update_variable(variable).await?;
+        }
+        None => {
+            schemas::add_variable(Variable {
+                id: ulid::Ulid::new().to_string(),
+                category: VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+                collection: ctx.collection.clone(),
+                environment: Some(environment),
+                name: Some(name.to_string()),
+                value: Some(value.to_string()),
+                enabled: Some(VARIABLE_ENABLED.to_string()),
+                secret: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await?;
+        }
+    }
+    ctx.set_variable(scope == ExtractorScope::Environment, name, value);
+    Ok(())
+}
+
+// Apply the enabled extractors, nothing is extracted if the response is not successful
+pub async fn apply(
+    ctx: &TemplateContext,
+    extractors: &[Extractor],
+    resp: &HTTPResponse,
+) -> Result<Vec<ExtractionResult>, CyberAPIError> {
+    let mut results = vec![];
+    if resp.status >= 400 {
+        return Ok(results);
+    }
+    for item in extractors.iter().filter(|item| item.enabled) {
+        let mut result = ExtractionResult {
+            variable: item.variable.clone(),
+            scope: item.scope,
+            value: None,
+            error: None,
+        };
+        match extract(&item.source, resp) {
+            Ok(value) => {
+                save_variable(ctx, item.scope, &item.variable, &value).await?;
+                result.value = Some(value);
+            }
+            Err(err) => result.error = Some(err),
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{Engine as _, engine::general_purpose};
+
+    fn new_response(status: u16, body: &str) -> HTTPResponse {
+        HTTPResponse {
+            api: "api".to_string(),
+            latency: 10,
+            status,
+            headers: HashMap::from([
+                ("x-request-id".to_string(), vec!["abc".to_string()]),
+                (
+                    "set-cookie".to_string(),
+                    vec![
+                        "lang=en; Path=/".to_string(),
+                        "session=s%3A123; Path=/; HttpOnly".to_string(),
+                    ],
+                ),
+            ]),
+            body: general_purpose::STANDARD.encode(body),
+            stats: Default::default(),
+            body_size: body.len() as u32,
+            assertions: vec![],
+            extractions: vec![],
+        }
+    }
+
+    fn new_source(value: serde_json::Value) -> ExtractorSource {
+        serde_json::from_value(value).unwrap()
+    }
+
+    const BODY: &str = r#"{"data": {"token": "t-1", "count": 2}, "items": [{"id": "a"}]}"#;
+
+    #[test]
+    fn extract_sources() {
+        let resp = new_response(200, BODY);
+        let cases = vec![
+            (
+                serde_json::json!({"category": "jsonPath", "path": "$.data.token"}),
+                Ok("t-1"),
+            ),
+            (
+                serde_json::json!({"category": "jsonPath", "path": "$.data.count"}),
+                Ok("2"),
+            ),
+            (
+                serde_json::json!({"category": "jsonPath", "path": "$.items[0].id"}),
+                Ok("a"),
+            ),
+            (
+                serde_json::json!({"category": "jsonPath", "path": "$.none"}),
+                Err("$.none is not found"),
+            ),
+            (
+                serde_json::json!({"category": "header", "name": "X-Request-Id"}),
+                Ok("abc"),
+            ),
+            (
+                serde_json::json!({"category": "header", "name": "etag"}),
+                Err("etag is not found"),
+            ),
+            (
+                serde_json::json!({"category": "regex", "pattern": r#""token": "([^"]+)""#}),
+                Ok("t-1"),
+            ),
+            (
+                serde_json::json!({"category": "regex", "pattern": r#""count": \d+"#}),
+                Ok(r#""count": 2"#),
+            ),
+            (
+                serde_json::json!({"category": "regex", "pattern": r#""(token)": "([^"]+)""#, "group": 2}),
+                Ok("t-1"),
+            ),
+            (
+                serde_json::json!({"category": "regex", "pattern": "token", "group": 1}),
+                Err("token is not found"),
+            ),
+            (
+                serde_json::json!({"category": "cookie", "name": "session"}),
+                Ok("s%3A123"),
+            ),
+            (
+                serde_json::json!({"category": "cookie", "name": "uid"}),
+                Err("uid is not found"),
+            ),
+        ];
+        for (source, expected) in cases {
+            let result = extract(&new_source(source.clone()), &resp);
+            assert_eq!(
+                expected
+                    .map(|value| value.to_string())
+                    .map_err(|err| err.to_string()),
+                result,
+                "{}",
+                source
+            );
+        }
+
+        let html = new_response(200, "<html>");
+        assert_eq!(
+            Err("body is not json".to_string()),
+            extract(
+                &new_source(serde_json::json!({"category": "jsonPath", "path": "$.a"})),
+                &html
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn apply_runtime() {
+        let mut ctx = TemplateContext::default();
+        ctx.collection = "extractor-apply-runtime".to_string();
+        let extractors: Vec<Extractor> = serde_json::from_value(serde_json::json!([
+            {"variable": "token", "scope": "runtime", "category": "jsonPath", "path": "$.data.token"},
+            {"variable": "missing", "scope": "runtime", "category": "header", "name": "etag"},
+            {"variable": "disabled", "scope": "runtime", "category": "header", "name": "x-request-id", "enabled": false},
+        ]))
+        .unwrap();
+
+        // Nothing is extracted from the failed response
+        let results = apply(&ctx, &extractors, &new_response(500, BODY))
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        assert_eq!(None, get_runtime_variable(&ctx.collection, "token"));
+
+        let results = apply(&ctx, &extractors, &new_response(200, BODY))
+            .await
+            .unwrap();
+        assert_eq!(2, results.len());
+        assert_eq!(Some("t-1".to_string()), results[0].value);
+        assert_eq!(None, results[0].error);
+        assert_eq!(None, results[1].value);
+        assert_eq!(Some("etag is not found".to_string()), results[1].error);
+        assert_eq!(
+            HashMap::from([("token".to_string(), "t-1".to_string())]),
+            list_runtime_variable(&ctx.collection)
+        );
+
+        clear_runtime_variable(&ctx.collection);
+        assert!(list_runtime_variable(&ctx.collection).is_empty());
+    }
+
+    #[test]
+    fn apply_concurrent() {
+        schemas::run_with_database(async {
+            let collection = "extractor-apply-concurrent";
+            let mut ctx = TemplateContext::default();
+            ctx.collection = collection.to_string();
+            let ctx = std::sync::Arc::new(ctx);
+            let extractors: Vec<Extractor> = serde_json::from_value(serde_json::json!([
+                {"variable": "token", "scope": "global", "category": "jsonPath", "path": "$.data.token"},
+            ]))
+            .unwrap();
+            let tasks: Vec<_> = (0..8)
+                .map(|_| {
+                    let ctx = ctx.clone();
+                    let extractors = extractors.clone();
+                    tokio::spawn(async move {
+                        apply(&ctx, &extractors, &new_response(200, BODY))
+                            .await
+                            .unwrap()
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+
+            let variables = list_variable(
+                collection.to_string(),
+                VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(1, variables.len());
+            assert_eq!(Some("token".to_string()), variables[0].name);
+            assert_eq!(Some("t-1".to_string()), variables[0].value);
+            assert_eq!(Some("".to_string()), variables[0].environment);
+            assert_eq!(Some("t-1".to_string()), ctx.get_variable("token"));
+        });
+    }
+}
@@ -0,0 +1,900 @@
+use crate::cookies;
+use crate::error::CyberAPIError;
+use crate::history;
+use crate::http_request::{
+    self, CONTENT_TYPE_FORM, CONTENT_TYPE_MULTIPART, HTTPRequest, HTTPRequestKVParam, HTTPResponse,
+    HTTPStats,
+};
+use crate::runner::{self, RunTarget, RunnerContext};
+use crate::schemas::{self, APIFolder, APISetting, History};
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Duration, Local};
+use hyper::StatusCode;
+use sea_orm::TransactionTrait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+use url::{Url, form_urlencoded};
+
+const HAR_VERSION: &str = "1.2";
+const HTTP_VERSION: &str = "HTTP/1.1";
+
+// The format of HAR 1.2, only the fields used by the app are defined,
+// they are optional when importing because the files of tools are not strict
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<HarPage>,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarPage {
+    pub started_date_time: String,
+    pub id: String,
+    pub title: String,
+    pub page_timings: serde_json::Value,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pageref: Option<String>,
+    pub started_date_time: String,
+    // Milliseconds
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: serde_json::Value,
+    pub timings: HarTimings,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarPostParam {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<HarPostParam>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarNameValue>,
+    pub query_string: Vec<HarNameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+// Milliseconds, -1 means it is not available
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HarTimings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    pub ssl: f64,
+}
+
+// How the imported requests are grouped into folders
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum HarGroup {
+    #[default]
+    Host,
+    Page,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HarImportResult {
+    pub folders: usize,
+    pub settings: usize,
+    pub cookies: usize,
+}
+
+fn to_set_cookie(cookie: &HarCookie) -> String {
+    let mut arr = vec![format!("{}={}", cookie.name, cookie.value)];
+    if let Some(path) = cookie.path.as_ref().filter(|value| !value.is_empty()) {
+        arr.push(format!("Path={}", path));
+    }
+    if let Some(domain) = cookie.domain.as_ref().filter(|value| !value.is_empty()) {
+        arr.push(format!("Domain={}", domain));
+    }
+    // The expires of HAR is ISO 8601
+    if let Some(expires) = cookie
+        .expires
+        .as_ref()
+        .and_then(|value| chrono::DateTime::parse_from_rfc3339(value).ok())
+    {
+        arr.push(format!(
+            "Expires={}",
+            expires.to_utc().format("%a, %d %b %Y %H:%M:%S GMT")
+        ));
+    }
+    if cookie.secure.unwrap_or_default() {
+        arr.push("Secure".to_string());
+    }
+    if cookie.http_only.unwrap_or_default() {
+        arr.push("HttpOnly".to_string());
+    }
+    arr.join("; ")
+}
+
+// Save the cookies of entry into the cookie store, returns the count of saved cookies
+fn save_cookies(entry: &HarEntry, url: &Url) -> usize {
+    let mut count = 0;
+    for cookie in entry
+        .request
+        .cookies
+        .iter()
+        .chain(entry.response.cookies.iter())
+    {
+        if cookie.name.is_empty() {
+            continue;
+        }
+        match cookies::save_cookie_store(vec![to_set_cookie(cookie)], url) {
+            Ok(()) => count += 1,
+            Err(err) => warn!("save cookie {} of har fail: {}", cookie.name, err),
+        }
+    }
+    count
+}
+
+fn convert_request(request: &HarRequest) -> Result<HTTPRequest, CyberAPIError> {
+    let headers = request
+        .headers
+        .iter()
+        // Cookies are saved into the cookie store
+        .filter(|item| !item.name.eq_ignore_ascii_case("cookie"))
+        .map(|item| HTTPRequestKVParam {
+            key: item.name.clone(),
+            value: item.value.clone(),
+            enabled: true,
+        })
+        .collect();
+    let post_data = request.post_data.clone().unwrap_or_default();
+    let mut body = post_data.text.clone();
+    // Some tools only export the params of form
+    if body.is_empty() && post_data.mime_type.starts_with(CONTENT_TYPE_FORM) {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        for item in post_data.params.iter() {
+            serializer.append_pair(&item.name, item.value.as_deref().unwrap_or_default());
+        }
+        body = serializer.finish();
+    }
+    let mut req = HTTPRequest::from_raw(&request.method, &request.url, headers, body.as_bytes())?;
+    if req.content_type.is_empty() && !post_data.mime_type.is_empty() {
+        req.content_type = post_data.mime_type.clone();
+    }
+    // The params of multipart are saved as the form of the app
+    if post_data.text.is_empty() && post_data.mime_type.starts_with(CONTENT_TYPE_MULTIPART) {
+        let params: Vec<HTTPRequestKVParam> = post_data
+            .params
+            .iter()
+            .map(|item| HTTPRequestKVParam {
+                key: item.name.clone(),
+                value: item
+                    .value
+                    .clone()
+                    .or(item.file_name.clone())
+                    .unwrap_or_default(),
+                enabled: true,
+            })
+            .collect();
+        req.content_type = CONTENT_TYPE_MULTIPART.to_string();
+        req.body = serde_json::to_string(&params)?;
+    }
+    Ok(req)
+}
+
+// Keep the response of HAR as the latest response of setting
+fn save_response(setting: &str, entry: &HarEntry) {
+    let response = &entry.response;
+    if response.status == 0 {
+        return;
+    }
+    let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+    for item in response.headers.iter() {
+        headers
+            .entry(item.name.to_lowercase())
+            .or_default()
+            .push(item.value.clone());
+    }
+    let text = response.content.text.clone().unwrap_or_default();
+    let body = if response.content.encoding.as_deref() == Some("base64") {
+        text
+    } else {
+        general_purpose::STANDARD.encode(text)
+    };
+    http_request::save_latest_response(&HTTPResponse {
+        api: setting.to_string(),
+        latency: entry.time as u32,
+        status: response.status,
+        headers,
+        body,
+        stats: HTTPStats::default(),
+        body_size: response.content.size.max(0) as u32,
+        assertions: vec![],
+        extractions: vec![],
+    });
+}
+
+// Import the entries of HAR file into collection, each group of entries is a new folder
+pub async fn import(
+    collection: &str,
+    file: &Path,
+    group: HarGroup,
+) -> Result<HarImportResult, CyberAPIError> {
+    let har: Har = serde_json::from_slice(&fs::read(file)?)?;
+    let pages: HashMap<String, String> = har
+        .log
+        .pages
+        .iter()
+        .map(|item| (item.id.clone(), item.title.clone()))
+        .collect();
+
+    let mut result = HarImportResult::default();
+    // Name and id of each folder, in the order of entries
+    let mut folders: Vec<(String, String)> = vec![];
+    // The responses and cookies are saved after the requests are committed
+    let mut imported: Vec<(String, &HarEntry, Url)> = vec![];
+    let db = schemas::get_database().await;
+    let txn = db.begin().await?;
+    for entry in har.log.entries.iter() {
+        let url = Url::parse(&entry.request.url)?;
+        let name = match group {
+            HarGroup::Host => match url.port() {
+                Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                None => url.host_str().unwrap_or_default().to_string(),
+            },
+            HarGroup::Page => entry
+                .pageref
+                .as_ref()
+                .and_then(|id| pages.get(id).cloned().or(Some(id.clone())))
+                .filter(|value| !value.is_empty())
+                .unwrap_or_else(|| "Requests".to_string()),
+        };
+        let req = convert_request(&entry.request)?;
+        let folder = match folders.iter().find(|(value, _)| value == &name) {
+            Some((_, id)) => id.clone(),
+            None => {
+                let folder = schemas::add_api_folder_with(
+                    &txn,
+                    APIFolder {
+                        id: ulid::Ulid::new().to_string(),
+                        collection: collection.to_string(),
+                        children: None,
+                        name: Some(name.clone()),
+                        parent_id: None,
+                        sort_order: None,
+                        created_at: None,
+                        updated_at: None,
+                    },
+                )
+                .await?;
+                result.folders += 1;
+                folders.push((name, folder.id.clone()));
+                folder.id
+            }
+        };
+        let setting = schemas::add_api_setting_with(
+            &txn,
+            APISetting {
+                id: ulid::Ulid::new().to_string(),
+                collection: collection.to_string(),
+                name: Some(format!("{} {}", req.method, url.path())),
+                category: Some("http".to_string()),
+                setting: Some(serde_json::to_string(&req)?),
+                parent_id: Some(folder),
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            },
+        )
+        .await?;
+        result.settings += 1;
+        imported.push((setting.id, entry, url));
+    }
+    txn.commit().await?;
+    for (setting, entry, url) in imported {
+        save_response(&setting, entry);
+        result.cookies += save_cookies(entry, &url);
+    }
+    Ok(result)
+}
+
+fn to_name_values(headers: &hyper::HeaderMap) -> Vec<HarNameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| HarNameValue {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).to_string(),
+        })
+        .collect()
+}
+
+fn to_har_response(resp: &HTTPResponse) -> HarResponse {
+    let mut headers = vec![];
+    let mut cookies = vec![];
+    for (name, values) in resp.headers.iter() {
+        for value in values {
+            if name == "set-cookie" {
+                if let Ok(cookie) = cookie::Cookie::parse(value.as_str()) {
+                    cookies.push(HarCookie {
+                        name: cookie.name().to_string(),
+                        value: cookie.value().to_string(),
+                        path: cookie.path().map(|value| value.to_string()),
+                        domain: cookie.domain().map(|value| value.to_string()),
+                        http_only: cookie.http_only(),
+                        secure: cookie.secure(),
+                        ..Default::default()
+                    });
+                }
+            }
+            headers.push(HarNameValue {
+                name: name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    let header = |name: &str| {
+        resp.headers
+            .get(name)
+            .and_then(|values| values.first())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let buf = resp.decode_body().unwrap_or_default();
+    let (text, encoding) = match String::from_utf8(buf.clone()) {
+        Ok(text) => (text, None),
+        Err(_) => (
+            general_purpose::STANDARD.encode(&buf),
+            Some("base64".to_string()),
+        ),
+    };
+    HarResponse {
+        status: resp.status,
+        status_text: StatusCode::from_u16(resp.status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or_default()
+            .to_string(),
+        http_version: HTTP_VERSION.to_string(),
+        cookies,
+        headers,
+        content: HarContent {
+            size: buf.len() as i64,
+            mime_type: header("content-type"),
+            text: Some(text),
+            encoding,
+        },
+        redirect_url: header("location"),
+        headers_size: -1,
+        body_size: resp.body_size as i64,
+    }
+}
+
+// The history is saved after the response is received, so the latency is subtracted
+fn get_request_time(history: &History) -> Option<String> {
+    let created_at = DateTime::parse_from_rfc3339(history.created_at.as_deref()?).ok()?;
+    let latency = Duration::milliseconds(history.latency.unwrap_or_default());
+    Some((created_at - latency).with_timezone(&Local).to_rfc3339())
+}
+
+fn to_har_entry(
+    req: HTTPRequest,
+    resp: Option<HTTPResponse>,
+    started_date_time: String,
+) -> Result<HarEntry, CyberAPIError> {
+    let content_type = req.content_type.clone();
+    let body = if content_type.starts_with(CONTENT_TYPE_MULTIPART) {
+        String::from_utf8_lossy(&general_purpose::STANDARD.decode(&req.body)?).to_string()
+    } else {
+        req.body.clone()
+    };
+    let (request, url) = http_request::build_request(req)?;
+    let headers = request.headers();
+    let cookies = headers
+        .get_all("cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| cookie::Cookie::split_parse(value.to_string()))
+        .filter_map(|cookie| cookie.ok())
+        .map(|cookie| HarCookie {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            ..Default::default()
+        })
+        .collect();
+    let post_data = if body.is_empty() {
+        None
+    } else {
+        Some(HarPostData {
+            mime_type: content_type,
+            text: body.clone(),
+            params: vec![],
+        })
+    };
+    let stats = resp
+        .as_ref()
+        .map(|item| item.stats.clone())
+        .unwrap_or_default();
+    let optional = |value: u32, is_set: bool| if is_set { value as f64 } else { -1.0 };
+    Ok(HarEntry {
+        pageref: None,
+        started_date_time,
+        time: resp.as_ref().map(|item| item.latency as f64).unwrap_or(0.0),
+        request: HarRequest {
+            method: request.method().to_string(),
+            url: url.to_string(),
+            http_version: HTTP_VERSION.to_string(),
+            cookies,
+            headers: to_name_values(headers),
+            query_string: url
+                .query_pairs()
+                .map(|(name, value)| HarNameValue {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+            post_data,
+            headers_size: -1,
+            body_size: body.len() as i64,
+        },
+        response: match &resp {
+            Some(resp) => to_har_response(resp),
+            // The request is not sent
+            None => HarResponse {
+                http_version: HTTP_VERSION.to_string(),
+                content: HarContent {
+                    mime_type: "x-unknown".to_string(),
+                    ..Default::default()
+                },
+                headers_size: -1,
+                body_size: -1,
+                ..Default::default()
+            },
+        },
+        cache: serde_json::json!({}),
+        timings: HarTimings {
+            blocked: -1.0,
+            dns: optional(stats.dns_lookup, resp.is_some()),
+            connect: optional(stats.tcp, resp.is_some()),
+            ssl: optional(stats.tls, stats.is_https),
+            send: stats.send as f64,
+            wait: stats.server_processing as f64,
+            receive: stats.content_transfer as f64,
+        },
+    })
+}
+
+// Export the requests of folder (including sub folders) into HAR file,
+// the templates are rendered with the environment and the latest responses of history are included
+pub async fn export(
+    folder: &str,
+    environment: Option<String>,
+    file: &Path,
+) -> Result<usize, CyberAPIError> {
+    let (collection, settings) =
+        runner::list_run_settings(&RunTarget::Folder(folder.to_string())).await?;
+    let ctx = RunnerContext::load(&collection, environment).await?;
+    let mut entries = vec![];
+    for setting in settings.iter() {
+        let req = runner::resolve_request(&ctx, runner::parse_setting(setting)?)?;
+        let (resp, started_date_time) = match history::get_latest(&setting.id).await? {
+            Some(detail) => (detail.response, get_request_time(&detail.history)),
+            // The response imported from HAR is only kept as the latest response
+            None => (http_request::get_latest_response(&setting.id), None),
+        };
+        // The request which is never sent uses the time of export
+        let started_date_time = started_date_time.unwrap_or_else(|| Local::now().to_rfc3339());
+        entries.push(to_har_entry(req, resp, started_date_time)?);
+    }
+    let count = entries.len();
+    let har = Har {
+        log: HarLog {
+            version: HAR_VERSION.to_string(),
+            creator: HarCreator {
+                name: "CyberAPI".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            pages: vec![],
+            entries,
+        },
+    };
+    fs::write(file, serde_json::to_vec_pretty(&har)?)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_time() {
+        let history = History {
+            created_at: Some("2024-05-01T10:00:01.500+00:00".to_string()),
+            latency: Some(1500),
+            ..Default::default()
+        };
+        let value = get_request_time(&history).unwrap();
+        assert_eq!(
+            DateTime::parse_from_rfc3339("2024-05-01T10:00:00+00:00").unwrap(),
+            DateTime::parse_from_rfc3339(&value).unwrap()
+        );
+        assert_eq!(None, get_request_time(&History::default()));
+    }
+
+    fn pairs(params: &[HTTPRequestKVParam]) -> Vec<(&str, &str)> {
+        params
+            .iter()
+            .map(|item| (item.key.as_str(), item.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn convert_har_request() {
+        let request: HarRequest = serde_json::from_value(serde_json::json!({
+            "method": "post",
+            "url": "https://example.com/users?page=1#top",
+            "headers": [
+                {"name": "Content-Type", "value": "application/x-www-form-urlencoded"},
+                {"name": "Cookie", "value": "a=1"},
+                {"name": "X-Token", "value": "abc"},
+                {"name": "Content-Length", "value": "14"},
+            ],
+            "postData": {
+                "mimeType": "application/x-www-form-urlencoded",
+                "params": [{"name": "name", "value": "a b"}, {"name": "age", "value": "1"}],
+            },
+        }))
+        .unwrap();
+        let req = convert_request(&request).unwrap();
+        assert_eq!("POST", req.method);
+        assert_eq!("https://example.com/users", req.uri);
+        assert_eq!(CONTENT_TYPE_FORM, req.content_type);
+        assert_eq!(vec![("X-Token", "abc")], pairs(&req.headers));
+        assert_eq!(vec![("page", "1")], pairs(&req.query));
+        let params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(vec![("name", "a b"), ("age", "1")], pairs(&params));
+        assert!(req.auth.is_empty());
+
+        // The params of multipart are converted to the form of app
+        let request: HarRequest = serde_json::from_value(serde_json::json!({
+            "method": "PUT",
+            "url": "https://example.com/upload",
+            "postData": {
+                "mimeType": "multipart/form-data; boundary=X",
+                "params": [{"name": "file", "fileName": "a.png"}, {"name": "id", "value": "1"}],
+            },
+        }))
+        .unwrap();
+        let req = convert_request(&request).unwrap();
+        assert_eq!("PUT", req.method);
+        assert_eq!(CONTENT_TYPE_MULTIPART, req.content_type);
+        assert!(req.headers.is_empty());
+        let params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(vec![("file", "a.png"), ("id", "1")], pairs(&params));
+
+        // The text body is kept as it is
+        let request: HarRequest = serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "url": "https://example.com/users",
+            "postData": {"mimeType": "application/json", "text": "{\"name\":\"tom\"}"},
+        }))
+        .unwrap();
+        let req = convert_request(&request).unwrap();
+        assert_eq!("application/json", req.content_type);
+        assert_eq!(r#"{"name":"tom"}"#, req.body);
+    }
+
+    fn write_har(dir: &Path) -> std::path::PathBuf {
+        let file = dir.join("test.har");
+        let har = serde_json::json!({
+            "log": {
+                "version": "1.2",
+                "creator": {"name": "browser", "version": "1"},
+                "pages": [{"id": "page_1", "title": "Home"}],
+                "entries": [
+                    {
+                        "pageref": "page_1",
+                        "startedDateTime": "2024-05-01T10:00:00.000Z",
+                        "time": 12,
+                        "request": {
+                            "method": "GET",
+                            "url": "https://har-test.example.com/users?page=1",
+                            "cookies": [{"name": "session", "value": "s1", "path": "/"}],
+                        },
+                        "response": {
+                            "status": 200,
+                            "headers": [{"name": "Content-Type", "value": "application/json"}],
+                            "cookies": [{"name": "lang", "value": "en", "path": "/", "httpOnly": true}],
+                            "content": {"size": 11, "mimeType": "application/json", "text": "{\"id\":\"1\"}"},
+                        },
+                    },
+                    {
+                        "pageref": "page_2",
+                        "request": {"method": "DELETE", "url": "https://har-test.example.com:8443/users/1"},
+                    },
+                    {
+                        "request": {"method": "POST", "url": "https://har-test.example.com/login"},
+                    },
+                ],
+            },
+        });
+        fs::write(&file, serde_json::to_vec(&har).unwrap()).unwrap();
+        file
+    }
+
+    // Names of the folders and the names of their settings in order
+    async fn list_groups(collection: &str) -> Vec<(String, Vec<String>)> {
+        let mut result = vec![];
+        for folder in schemas::list_api_folder(collection.to_string())
+            .await
+            .unwrap()
+        {
+            let (_, settings) = runner::list_run_settings(&RunTarget::Folder(folder.id))
+                .await
+                .unwrap();
+            result.push((
+                folder.name.unwrap_or_default(),
+                settings
+                    .into_iter()
+                    .map(|item| item.name.unwrap_or_default())
+                    .collect(),
+            ));
+        }
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn import_groups_and_cookies() {
+        schemas::run_with_database(async {
+            let dir = tempfile::tempdir().unwrap();
+            let file = write_har(dir.path());
+
+            let result = import("har-import-host", &file, HarGroup::Host)
+                .await
+                .unwrap();
+            assert_eq!(2, result.folders);
+            assert_eq!(3, result.settings);
+            assert_eq!(2, result.cookies);
+            assert_eq!(
+                vec![
+                    (
+                        "har-test.example.com".to_string(),
+                        vec!["GET /users".to_string(), "POST /login".to_string()]
+                    ),
+                    (
+                        "har-test.example.com:8443".to_string(),
+                        vec!["DELETE /users/1".to_string()]
+                    ),
+                ],
+                list_groups("har-import-host").await
+            );
+            {
+                let store = cookies::get_cookie_store();
+                for (name, value) in [("session", "s1"), ("lang", "en")] {
+                    let cookie = store.get("har-test.example.com", "/", name).unwrap();
+                    assert_eq!(value, cookie.value());
+                }
+            }
+
+            let result = import("har-import-page", &file, HarGroup::Page)
+                .await
+                .unwrap();
+            assert_eq!(3, result.folders);
+            // The page id is used if the page is not found
+            assert_eq!(
+                vec![
+                    ("Home".to_string(), vec!["GET /users".to_string()]),
+                    ("Requests".to_string(), vec!["POST /login".to_string()]),
+                    ("page_2".to_string(), vec!["DELETE /users/1".to_string()]),
+                ],
+                list_groups("har-import-page").await
+            );
+        });
+    }
+
+    fn assert_keys(value: &serde_json::Value, keys: &[&str]) {
+        for key in keys {
+            assert!(value.get(key).is_some(), "{} is missing: {}", key, value);
+        }
+    }
+
+    #[test]
+    fn export_har_1_2() {
+        schemas::run_with_database(async {
+            let dir = tempfile::tempdir().unwrap();
+            let collection = "har-export";
+            import(collection, &write_har(dir.path()), HarGroup::Host)
+                .await
+                .unwrap();
+            let folder = schemas::list_api_folder(collection.to_string())
+                .await
+                .unwrap()
+                .into_iter()
+                .find(|item| item.name.as_deref() == Some("har-test.example.com"))
+                .unwrap();
+
+            let file = dir.path().join("export.har");
+            assert_eq!(2, export(&folder.id, None, &file).await.unwrap());
+            let har: serde_json::Value = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+            let log = &har["log"];
+            assert_eq!("1.2", log["version"]);
+            assert_eq!("CyberAPI", log["creator"]["name"]);
+            assert!(log["creator"]["version"].is_string());
+            let entries = log["entries"].as_array().unwrap();
+            assert_eq!(2, entries.len());
+            for entry in entries {
+                assert_keys(
+                    entry,
+                    &[
+                        "startedDateTime",
+                        "time",
+                        "request",
+                        "response",
+                        "cache",
+                        "timings",
+                    ],
+                );
+                assert!(
+                    DateTime::parse_from_rfc3339(entry["startedDateTime"].as_str().unwrap())
+                        .is_ok()
+                );
+                assert!(entry["time"].is_number());
+                assert_keys(
+                    &entry["request"],
+                    &[
+                        "method",
+                        "url",
+                        "httpVersion",
+                        "cookies",
+                        "headers",
+                        "queryString",
+                        "headersSize",
+                        "bodySize",
+                    ],
+                );
+                assert_keys(
+                    &entry["response"],
+                    &[
+                        "status",
+                        "statusText",
+                        "httpVersion",
+                        "cookies",
+                        "headers",
+                        "content",
+                        "redirectURL",
+                        "headersSize",
+                        "bodySize",
+                    ],
+                );
+                assert_keys(&entry["response"]["content"], &["size", "mimeType"]);
+                for key in ["send", "wait", "receive"] {
+                    assert!(entry["timings"][key].as_f64().unwrap() >= 0.0);
+                }
+            }
+
+            // The imported response is exported as the latest response
+            let entry = &entries[0];
+            assert_eq!("GET", entry["request"]["method"]);
+            assert_eq!(
+                "https://har-test.example.com/users?page=1",
+                entry["request"]["url"]
+            );
+            assert_eq!(
+                serde_json::json!([{"name": "page", "value": "1"}]),
+                entry["request"]["queryString"]
+            );
+            let cookies: Vec<&str> = entry["request"]["cookies"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["name"].as_str().unwrap())
+                .collect();
+            assert!(cookies.contains(&"session") && cookies.contains(&"lang"));
+            assert_eq!(200, entry["response"]["status"]);
+            assert_eq!("OK", entry["response"]["statusText"]);
+            assert_eq!(r#"{"id":"1"}"#, entry["response"]["content"]["text"]);
+
+            // The request is never sent
+            let entry = &entries[1];
+            assert_eq!("POST", entry["request"]["method"]);
+            assert_eq!(0, entry["response"]["status"]);
+            assert_eq!("x-unknown", entry["response"]["content"]["mimeType"]);
+        });
+    }
+}
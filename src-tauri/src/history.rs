@@ -0,0 +1,420 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{HTTPRequest, HTTPResponse, HTTPStats};
+use crate::schemas::{self, ENVIRONMENT_ENABLED, History, HistoryQuery};
+use crate::settings;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{Duration, Utc};
+use libflate::gzip::{Decoder, Encoder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use tracing::warn;
+use url::Url;
+
+// The response body larger than it is truncated
+const HISTORY_BODY_LIMIT: usize = 1024 * 1024;
+const HISTORY_RETENTION_KEY: &str = "historyRetention";
+
+// The retention of history, it is set by `historyRetention` of app settings
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryRetention {
+    // 0 means no limit
+    pub max_count: u64,
+    pub max_days: u64,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        HistoryRetention {
+            max_count: 1000,
+            max_days: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryDetail {
+    // The request and body of history are converted to the fields below
+    pub history: History,
+    pub request: HTTPRequest,
+    // It is none if the request failed
+    pub response: Option<HTTPResponse>,
+}
+
+fn load_retention() -> HistoryRetention {
+    settings::load_settings()
+        .ok()
+        .flatten()
+        .and_then(|value| value.get(HISTORY_RETENTION_KEY).cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn compress(buf: &[u8]) -> Result<String, CyberAPIError> {
+    let mut encoder = Encoder::new(Vec::new())?;
+    encoder.write_all(buf)?;
+    let data = encoder.finish().into_result()?;
+    Ok(general_purpose::STANDARD.encode(data))
+}
+
+fn decompress(value: &str) -> Result<Vec<u8>, CyberAPIError> {
+    let data = general_purpose::STANDARD.decode(value)?;
+    if data.is_empty() {
+        return Ok(data);
+    }
+    let mut decoder = Decoder::new(&data[..])?;
+    let mut buf = vec![];
+    decoder.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// The url with the enabled query, it is the url which is sent
+fn request_url(req: &HTTPRequest) -> String {
+    let Ok(mut url) = Url::parse(&req.uri) else {
+        return req.uri.clone();
+    };
+    for item in req.query.iter().filter(|item| item.enabled) {
+        url.query_pairs_mut().append_pair(&item.key, &item.value);
+    }
+    url.to_string()
+}
+
+// Save the executed request and its response, the old histories are pruned by the retention
+pub async fn record(
+    collection: &str,
+    api: &str,
+    req: &HTTPRequest,
+    result: &Result<HTTPResponse, CyberAPIError>,
+) -> Result<History, CyberAPIError> {
+    let environment = if collection.is_empty() {
+        None
+    } else {
+        schemas::list_environment(collection.to_string())
+            .await?
+            .into_iter()
+            .find(|item| item.enabled.as_deref() == Some(ENVIRONMENT_ENABLED))
+            .map(|item| item.id)
+    };
+    let mut history = History {
+        id: ulid::Ulid::new().to_string(),
+        collection: Some(collection.to_string()),
+        api: Some(api.to_string()),
+        environment,
+        method: Some(req.method.to_uppercase()),
+        url: Some(request_url(req)),
+        request: Some(serde_json::to_string(req)?),
+        ..Default::default()
+    };
+    match result {
+        Ok(resp) => {
+            let buf = resp.decode_body()?;
+            let truncated = buf.len() > HISTORY_BODY_LIMIT;
+            let buf = &buf[..buf.len().min(HISTORY_BODY_LIMIT)];
+            history.status = Some(resp.status as i32);
+            history.headers = Some(serde_json::to_string(&resp.headers)?);
+            history.body = Some(compress(buf)?);
+            history.body_size = Some(resp.body_size as i64);
+            history.body_truncated = Some(if truncated { "1" } else { "0" }.to_string());
+            history.latency = Some(resp.latency as i64);
+            history.stats = Some(serde_json::to_string(&resp.stats)?);
+        }
+        Err(err) => history.error = Some(err.to_string()),
+    }
+    let history = schemas::add_history(history).await?;
+
+    let retention = load_retention();
+    let before = if retention.max_days == 0 {
+        None
+    } else {
+        Some((Utc::now() - Duration::days(retention.max_days as i64)).to_rfc3339())
+    };
+    if let Err(err) = schemas::prune_history(retention.max_count, before).await {
+        warn!("failed to prune history: {}", err);
+    }
+    Ok(history)
+}
+
+// Get the history with its request and response
+pub async fn get(id: &str) -> Result<HistoryDetail, CyberAPIError> {
+    let Some(mut history) = schemas::get_history(id.to_string()).await? else {
+        return Err(CyberAPIError::new(
+            "history",
+            format!("history {} is not found", id),
+        ));
+    };
+    let request: HTTPRequest =
+        serde_json::from_str(&history.request.take().unwrap_or_default()).unwrap_or_default();
+    let body = history.body.take().unwrap_or_default();
+    let response = if history.error.as_deref().unwrap_or_default().is_empty() {
+        let headers: HashMap<String, Vec<String>> =
+            serde_json::from_str(history.headers.as_deref().unwrap_or_default())
+                .unwrap_or_default();
+        let stats: HTTPStats =
+            serde_json::from_str(history.stats.as_deref().unwrap_or_default()).unwrap_or_default();
+        Some(HTTPResponse {
+            api: history.api.clone().unwrap_or_default(),
+            latency: history.latency.unwrap_or_default() as u32,
+            status: history.status.unwrap_or_default() as u16,
+            headers,
+            body: general_purpose::STANDARD.encode(decompress(&body)?),
+            stats,
+            body_size: history.body_size.unwrap_or_default() as u32,
+            assertions: vec![],
+            extractions: vec![],
+        })
+    } else {
+        None
+    };
+    Ok(HistoryDetail {
+        history,
+        request,
+        response,
+    })
+}
+
+// Get the latest history of api with its request and response
+pub async fn get_latest(api: &str) -> Result<Option<HistoryDetail>, CyberAPIError> {
+    let page = schemas::list_history(HistoryQuery {
+        api: Some(api.to_string()),
+        page_size: 1,
+        ..Default::default()
+    })
+    .await?;
+    match page.items.first() {
+        Some(item) => Ok(Some(get(&item.id).await?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{
+        Environment, HistoryPage, add_environment, add_history, delete_history_by_collection,
+        list_history, pin_history, prune_history, run_with_database,
+    };
+
+    fn new_request(uri: &str) -> HTTPRequest {
+        serde_json::from_value(serde_json::json!({
+            "method": "post",
+            "uri": uri,
+            "body": "{\"name\": \"cat\"}",
+            "contentType": "application/json",
+            "query": [
+                {"key": "page", "value": "1", "enabled": true},
+                {"key": "debug", "value": "1", "enabled": false},
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn new_response(body: &[u8]) -> HTTPResponse {
+        HTTPResponse {
+            api: "history-api".to_string(),
+            latency: 35,
+            status: 201,
+            headers: HashMap::from([("content-type".to_string(), vec!["text/plain".to_string()])]),
+            body: general_purpose::STANDARD.encode(body),
+            stats: HTTPStats {
+                total: 35,
+                ..Default::default()
+            },
+            body_size: body.len() as u32,
+            assertions: vec![],
+            extractions: vec![],
+        }
+    }
+
+    fn ids(page: &HistoryPage) -> Vec<String> {
+        page.items.iter().map(|item| item.id.clone()).collect()
+    }
+
+    #[test]
+    fn compress_body() {
+        let value = compress(b"hello").unwrap();
+        assert_eq!(b"hello".to_vec(), decompress(&value).unwrap());
+        assert!(decompress("").unwrap().is_empty());
+        assert!(decompress("aGVsbG8=").is_err());
+    }
+
+    #[test]
+    fn record_history() {
+        run_with_database(async {
+            let collection = "history-record";
+            let environment = add_environment(Environment {
+                id: "history-record-env".to_string(),
+                collection: collection.to_string(),
+                name: Some("dev".to_string()),
+                enabled: Some(ENVIRONMENT_ENABLED.to_string()),
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+            let req = new_request("http://localhost/users");
+            let body = vec![b'a'; HISTORY_BODY_LIMIT + 1];
+            let history = record(collection, "history-api", &req, &Ok(new_response(&body)))
+                .await
+                .unwrap();
+            assert_eq!(Some(environment.id), history.environment);
+            assert_eq!(Some("POST".to_string()), history.method);
+            assert_eq!(
+                Some("http://localhost/users?page=1".to_string()),
+                history.url
+            );
+            assert_eq!(Some("1".to_string()), history.body_truncated);
+
+            let detail = get(&history.id).await.unwrap();
+            assert_eq!(req.body, detail.request.body);
+            assert!(detail.history.body.is_none());
+            let resp = detail.response.unwrap();
+            assert_eq!(201, resp.status);
+            assert_eq!(35, resp.latency);
+            assert_eq!(35, resp.stats.total);
+            assert_eq!(HISTORY_BODY_LIMIT + 1, resp.body_size as usize);
+            assert_eq!(HISTORY_BODY_LIMIT, resp.decode_body().unwrap().len());
+            assert_eq!(vec!["text/plain".to_string()], resp.headers["content-type"]);
+
+            // The failed request has no response
+            let failed = record(
+                collection,
+                "history-api",
+                &req,
+                &Err(CyberAPIError::new("http", "connection refused".to_string())),
+            )
+            .await
+            .unwrap();
+            let detail = get(&failed.id).await.unwrap();
+            assert_eq!(
+                Some("[http]connection refused".to_string()),
+                detail.history.error
+            );
+            assert!(detail.response.is_none());
+
+            let latest = get_latest("history-api").await.unwrap().unwrap();
+            assert_eq!(failed.id, latest.history.id);
+            assert!(get_latest("history-none").await.unwrap().is_none());
+            assert_eq!(
+                "[history]history history-none is not found",
+                get("history-none").await.unwrap_err().to_string()
+            );
+        });
+    }
+
+    #[test]
+    fn list_and_prune_history() {
+        run_with_database(async {
+            let collection = "history-list";
+            for (index, (method, url, status)) in [
+                ("GET", "http://localhost/users", 200),
+                ("POST", "http://localhost/users", 201),
+                ("GET", "http://localhost/pets", 404),
+                ("DELETE", "http://localhost/users/1", 500),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                add_history(History {
+                    id: format!("history-list-{}", index),
+                    collection: Some(collection.to_string()),
+                    api: Some("history-list-api".to_string()),
+                    method: Some(method.to_string()),
+                    url: Some(url.to_string()),
+                    status: Some(status),
+                    created_at: Some(format!("2000-01-0{}T00:00:00+00:00", index + 1)),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            }
+            let list = |query: HistoryQuery| async move {
+                list_history(HistoryQuery {
+                    collection: Some(collection.to_string()),
+                    ..query
+                })
+                .await
+                .unwrap()
+            };
+
+            // The latest is the first
+            let page = list(HistoryQuery::default()).await;
+            assert_eq!(4, page.total);
+            assert_eq!(
+                vec![
+                    "history-list-3",
+                    "history-list-2",
+                    "history-list-1",
+                    "history-list-0"
+                ],
+                ids(&page)
+            );
+            assert!(page.items.iter().all(|item| item.request.is_none()));
+
+            let page = list(HistoryQuery {
+                url: Some("/users".to_string()),
+                method: Some("get".to_string()),
+                ..Default::default()
+            })
+            .await;
+            assert_eq!(vec!["history-list-0"], ids(&page));
+            let page = list(HistoryQuery {
+                status_min: Some(400),
+                status_max: Some(499),
+                ..Default::default()
+            })
+            .await;
+            assert_eq!(vec!["history-list-2"], ids(&page));
+            let page = list(HistoryQuery {
+                from: Some("2000-01-02T00:00:00+00:00".to_string()),
+                to: Some("2000-01-03T00:00:00+00:00".to_string()),
+                ..Default::default()
+            })
+            .await;
+            assert_eq!(vec!["history-list-2", "history-list-1"], ids(&page));
+            let page = list(HistoryQuery {
+                page: 1,
+                page_size: 3,
+                ..Default::default()
+            })
+            .await;
+            assert_eq!(4, page.total);
+            assert_eq!(vec!["history-list-0"], ids(&page));
+
+            assert_eq!(
+                1,
+                pin_history("history-list-0".to_string(), true)
+                    .await
+                    .unwrap()
+            );
+            let page = list(HistoryQuery {
+                pinned: Some(true),
+                ..Default::default()
+            })
+            .await;
+            assert_eq!(vec!["history-list-0"], ids(&page));
+
+            // The pinned and the latest histories are kept
+            prune_history(0, Some("2000-01-02T00:00:00+00:00".to_string()))
+                .await
+                .unwrap();
+            assert_eq!(4, list(HistoryQuery::default()).await.total);
+            prune_history(0, Some("2000-01-03T00:00:00+00:00".to_string()))
+                .await
+                .unwrap();
+            assert_eq!(
+                vec!["history-list-3", "history-list-2", "history-list-0"],
+                ids(&list(HistoryQuery::default()).await)
+            );
+
+            delete_history_by_collection(collection.to_string())
+                .await
+                .unwrap();
+            assert_eq!(
+                vec!["history-list-0"],
+                ids(&list(HistoryQuery::default()).await)
+            );
+        });
+    }
+}
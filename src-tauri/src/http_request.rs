@@ -1,16 +1,19 @@
+use crate::assertion::{self, Assertion, AssertionResult};
 use crate::cookies;
 use crate::error::CyberAPIError;
+use crate::extractor::{ExtractionResult, Extractor};
+use crate::mock_server::MockResponse;
 use crate::schemas::list_proxy;
 use base64::{Engine as _, engine::general_purpose};
 use hyper::{
-    Body, Client, Method, Request, Uri,
+    Body, Client, Method, Request, Response, Uri,
     body::{Buf, Bytes},
     client::HttpConnector,
     client::connect::HttpInfo,
     header::{HeaderName, HeaderValue},
 };
 use hyper_proxy::{Intercept, Proxy as HyperProxy, ProxyConnector};
-use hyper_rustls::HttpsConnectorBuilder;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use hyper_socks2::SocksConnector;
 use hyper_timeout::TimeoutConnector;
 use libflate::gzip::Decoder;
@@ -24,12 +27,15 @@ use std::{
     io::Read,
     sync::Mutex,
     sync::atomic::{AtomicBool, AtomicU64, Ordering},
-    time::Duration,
+    time::{Duration, Instant},
     vec,
 };
 use url::Url;
 
-#[derive(Deserialize, Serialize, Debug)]
+pub const CONTENT_TYPE_FORM: &str = "application/x-www-form-urlencoded";
+pub const CONTENT_TYPE_MULTIPART: &str = "multipart/form-data";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct HTTPRequestKVParam {
     pub key: String,
@@ -37,8 +43,9 @@ pub struct HTTPRequestKVParam {
     pub enabled: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-#[serde(rename_all = "camelCase")]
+// It is also the format of the setting saved in api settings
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct HTTPRequest {
     pub method: String,
     pub uri: String,
@@ -46,9 +53,82 @@ pub struct HTTPRequest {
     pub content_type: String,
     pub headers: Vec<HTTPRequestKVParam>,
     pub query: Vec<HTTPRequestKVParam>,
+    pub auth: Vec<HTTPRequestKVParam>,
+    pub assertions: Vec<Assertion>,
+    pub extractors: Vec<Extractor>,
+    // Example response served by the mock server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mock: Option<MockResponse>,
+}
+
+impl HTTPRequest {
+    // Convert a raw request (e.g. captured or imported) to the format saved by the app:
+    // the query of url is split into params and the form body is saved as params
+    pub fn from_raw(
+        method: &str,
+        url: &str,
+        headers: Vec<HTTPRequestKVParam>,
+        body: &[u8],
+    ) -> Result<HTTPRequest, CyberAPIError> {
+        let mut url = Url::parse(url)?;
+        let query = url
+            .query_pairs()
+            .map(|(key, value)| HTTPRequestKVParam {
+                key: key.to_string(),
+                value: value.to_string(),
+                enabled: true,
+            })
+            .collect();
+        url.set_query(None);
+        url.set_fragment(None);
+
+        let mut content_type = "".to_string();
+        let mut kept_headers = vec![];
+        for item in headers {
+            match item.key.to_lowercase().as_str() {
+                // They are set when the request is sent
+                "host" | "content-length" | "accept-encoding" => {}
+                "content-type" => content_type = item.value,
+                // Pseudo headers of http2
+                key if key.starts_with(':') => {}
+                _ => kept_headers.push(item),
+            }
+        }
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let body = if essence == CONTENT_TYPE_FORM {
+            content_type = CONTENT_TYPE_FORM.to_string();
+            let params: Vec<HTTPRequestKVParam> = url::form_urlencoded::parse(body)
+                .map(|(key, value)| HTTPRequestKVParam {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    enabled: true,
+                })
+                .collect();
+            serde_json::to_string(&params)?
+        } else if essence == CONTENT_TYPE_MULTIPART {
+            // The content type with boundary is kept, the body is sent as it is
+            general_purpose::STANDARD.encode(body)
+        } else {
+            String::from_utf8_lossy(body).to_string()
+        };
+        Ok(HTTPRequest {
+            method: method.to_uppercase(),
+            uri: url.to_string(),
+            body,
+            content_type,
+            headers: kept_headers,
+            query,
+            ..Default::default()
+        })
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RequestTimeout {
     pub connect: u64,
@@ -56,7 +136,18 @@ pub struct RequestTimeout {
     pub read: u64,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+impl Default for RequestTimeout {
+    // Same as the default timeout of the app
+    fn default() -> Self {
+        RequestTimeout {
+            connect: 10,
+            write: 120,
+            read: 300,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct HTTPStats {
     pub remote_addr: String,
@@ -287,7 +378,7 @@ fn get_http_trace() -> &'static HTTPTrace {
     HTTP_TRACE.get_or_init(HTTPTrace::new)
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct HTTPResponse {
     pub api: String,
@@ -297,6 +388,44 @@ pub struct HTTPResponse {
     pub body: String,
     pub stats: HTTPStats,
     pub body_size: u32,
+    #[serde(default)]
+    pub assertions: Vec<AssertionResult>,
+    #[serde(default)]
+    pub extractions: Vec<ExtractionResult>,
+}
+
+impl HTTPResponse {
+    // The body is base64 encoded for the frontend
+    pub fn decode_body(&self) -> Result<Vec<u8>, CyberAPIError> {
+        let buf = general_purpose::STANDARD.decode(&self.body)?;
+        Ok(buf)
+    }
+    pub fn json_body(&self) -> Result<serde_json::Value, CyberAPIError> {
+        let buf = self.decode_body()?;
+        let value = serde_json::from_slice(&buf)?;
+        Ok(value)
+    }
+}
+
+static LATEST_RESPONSES: OnceCell<Mutex<HashMap<String, HTTPResponse>>> = OnceCell::new();
+
+fn get_latest_responses() -> &'static Mutex<HashMap<String, HTTPResponse>> {
+    LATEST_RESPONSES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Keep the latest response of each api, it is used by `{{get(id, path)}}`
+pub fn save_latest_response(resp: &HTTPResponse) {
+    if resp.api.is_empty() {
+        return;
+    }
+    if let Ok(mut responses) = get_latest_responses().lock() {
+        responses.insert(resp.api.clone(), resp.clone());
+    }
+}
+
+pub fn get_latest_response(api: &str) -> Option<HTTPResponse> {
+    let responses = get_latest_responses().lock().ok()?;
+    responses.get(api).cloned()
 }
 
 struct JsonVisitor<'a>(&'a mut BTreeMap<String, String>);
@@ -446,14 +575,87 @@ async fn resolve_proxy(url: &Url) -> Result<Option<Uri>, CyberAPIError> {
     Ok(None)
 }
 
-pub async fn request(
-    api: String,
-    http_request: HTTPRequest,
-    timeout: RequestTimeout,
-) -> Result<HTTPResponse, CyberAPIError> {
-    let trace = get_http_trace();
-    trace.reset();
+// The client of request, connections are kept in its pool and reused
+pub enum HTTPClient {
+    Direct(Client<TimeoutConnector<HttpsConnector<HttpConnector>>>),
+    Proxy(Client<TimeoutConnector<ProxyConnector<HttpsConnector<HttpConnector>>>>),
+    Socks(Client<TimeoutConnector<SocksConnector<HttpConnector>>>),
+    SocksTls(Client<TimeoutConnector<HttpsConnector<SocksConnector<HttpConnector>>>>),
+}
+
+impl HTTPClient {
+    // Create the client for the url, the matched proxy of settings is used
+    pub async fn new(url: &Url, timeout: &RequestTimeout) -> Result<HTTPClient, CyberAPIError> {
+        let connect_timeout = Duration::from_secs(timeout.connect);
+        let write_timeout = Duration::from_secs(timeout.write);
+        let read_timeout = Duration::from_secs(timeout.read);
+
+        let proxy_uri = resolve_proxy(url).await?;
+        let client = if let Some(proxy_uri) = proxy_uri {
+            let scheme = proxy_uri.scheme_str().unwrap_or("").to_lowercase();
+            if scheme.starts_with("socks") {
+                let mut connector = HttpConnector::new();
+                connector.enforce_http(false);
+                let socks = SocksConnector {
+                    proxy_addr: proxy_uri,
+                    auth: None,
+                    connector,
+                };
+                if url.scheme() == "https" {
+                    let connector = socks.with_tls()?;
+                    let mut connector = TimeoutConnector::new(connector);
+                    connector.set_connect_timeout(Some(connect_timeout));
+                    connector.set_read_timeout(Some(read_timeout));
+                    connector.set_write_timeout(Some(write_timeout));
+                    HTTPClient::SocksTls(Client::builder().build::<_, hyper::Body>(connector))
+                } else {
+                    let mut connector = TimeoutConnector::new(socks);
+                    connector.set_connect_timeout(Some(connect_timeout));
+                    connector.set_read_timeout(Some(read_timeout));
+                    connector.set_write_timeout(Some(write_timeout));
+                    HTTPClient::Socks(Client::builder().build::<_, hyper::Body>(connector))
+                }
+            } else {
+                let base_connector = HttpsConnectorBuilder::new()
+                    .with_native_roots()
+                    .https_or_http()
+                    .enable_http1()
+                    .build();
+                let proxy = HyperProxy::new(Intercept::All, proxy_uri);
+                let connector = ProxyConnector::from_proxy(base_connector, proxy)?;
+                let mut connector = TimeoutConnector::new(connector);
+                connector.set_connect_timeout(Some(connect_timeout));
+                connector.set_read_timeout(Some(read_timeout));
+                connector.set_write_timeout(Some(write_timeout));
+                HTTPClient::Proxy(Client::builder().build::<_, hyper::Body>(connector))
+            }
+        } else {
+            let base_connector = HttpsConnectorBuilder::new()
+                .with_native_roots()
+                .https_or_http()
+                .enable_http1()
+                .build();
+            let mut connector = TimeoutConnector::new(base_connector);
+            connector.set_connect_timeout(Some(connect_timeout));
+            connector.set_read_timeout(Some(read_timeout));
+            connector.set_write_timeout(Some(write_timeout));
+            HTTPClient::Direct(Client::builder().build::<_, hyper::Body>(connector))
+        };
+        Ok(client)
+    }
 
+    pub async fn request(&self, req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        match self {
+            HTTPClient::Direct(client) => client.request(req).await,
+            HTTPClient::Proxy(client) => client.request(req).await,
+            HTTPClient::Socks(client) => client.request(req).await,
+            HTTPClient::SocksTls(client) => client.request(req).await,
+        }
+    }
+}
+
+// Convert to the request of hyper, the cookies of the url are added
+pub fn build_request(http_request: HTTPRequest) -> Result<(Request<Body>, Url), CyberAPIError> {
     let body = if http_request.content_type.starts_with("multipart/form-data") {
         let buf = general_purpose::STANDARD.decode(http_request.body)?;
         Body::from(buf)
@@ -512,93 +714,60 @@ pub async fn request(
         );
     }
 
-    {
-        // Cookie store is not Send; avoid conflicting with the await below
-        // Set Cookie header
-        let cookie_store = cookies::get_cookie_store();
-        let cookie_header = cookie_store
-            .get_request_values(&current_url)
-            .map(|(name, value)| format!("{}={}", name, value))
-            .collect::<Vec<_>>()
-            .join("; ");
-        if !cookie_header.is_empty() {
-            header.insert(
-                "Cookie".parse::<HeaderName>()?,
-                HeaderValue::from_str(cookie_header.as_str())?,
-            );
-        }
+    // Set Cookie header
+    let cookie_store = cookies::get_cookie_store();
+    let cookie_header = cookie_store
+        .get_request_values(&current_url)
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ");
+    if !cookie_header.is_empty() {
+        header.insert(
+            "Cookie".parse::<HeaderName>()?,
+            HeaderValue::from_str(cookie_header.as_str())?,
+        );
     }
-    let connect_timeout = Duration::from_secs(timeout.connect);
-    let write_timeout = Duration::from_secs(timeout.write);
-    let read_timeout = Duration::from_secs(timeout.read);
+    Ok((req, current_url))
+}
 
-    if current_url.scheme() == "https" {
+pub async fn request(
+    api: String,
+    http_request: HTTPRequest,
+    timeout: RequestTimeout,
+) -> Result<HTTPResponse, CyberAPIError> {
+    let trace = get_http_trace();
+    trace.reset();
+    send(api, http_request, timeout, Some(trace)).await
+}
+
+// The trace is shared by the hyper events, so it only works for one request at a time.
+// The concurrent requests (e.g. the collection runner) only measure the latency.
+pub async fn request_without_trace(
+    api: String,
+    http_request: HTTPRequest,
+    timeout: RequestTimeout,
+) -> Result<HTTPResponse, CyberAPIError> {
+    send(api, http_request, timeout, None).await
+}
+
+async fn send(
+    api: String,
+    mut http_request: HTTPRequest,
+    timeout: RequestTimeout,
+    trace: Option<&HTTPTrace>,
+) -> Result<HTTPResponse, CyberAPIError> {
+    let assertions = std::mem::take(&mut http_request.assertions);
+
+    // Cookie store is not Send; the request is built before the await below
+    let (req, current_url) = build_request(http_request)?;
+
+    if let Some(trace) = trace.filter(|_| current_url.scheme() == "https") {
         trace.tls();
     }
 
-    let proxy_uri = resolve_proxy(&current_url).await?;
-    let resp = if let Some(proxy_uri) = proxy_uri {
-        let scheme = proxy_uri.scheme_str().unwrap_or("").to_lowercase();
-        if scheme.starts_with("socks") {
-            let mut connector = HttpConnector::new();
-            connector.enforce_http(false);
-            let socks = SocksConnector {
-                proxy_addr: proxy_uri,
-                auth: None,
-                connector,
-            };
-            if current_url.scheme() == "https" {
-                let connector = socks.with_tls()?;
-                let mut connector = TimeoutConnector::new(connector);
-                connector.set_connect_timeout(Some(connect_timeout));
-                connector.set_read_timeout(Some(read_timeout));
-                connector.set_write_timeout(Some(write_timeout));
-                Client::builder()
-                    .build::<_, hyper::Body>(connector)
-                    .request(req)
-                    .await?
-            } else {
-                let mut connector = TimeoutConnector::new(socks);
-                connector.set_connect_timeout(Some(connect_timeout));
-                connector.set_read_timeout(Some(read_timeout));
-                connector.set_write_timeout(Some(write_timeout));
-                Client::builder()
-                    .build::<_, hyper::Body>(connector)
-                    .request(req)
-                    .await?
-            }
-        } else {
-            let base_connector = HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .https_or_http()
-                .enable_http1()
-                .build();
-            let proxy = HyperProxy::new(Intercept::All, proxy_uri);
-            let connector = ProxyConnector::from_proxy(base_connector, proxy)?;
-            let mut connector = TimeoutConnector::new(connector);
-            connector.set_connect_timeout(Some(connect_timeout));
-            connector.set_read_timeout(Some(read_timeout));
-            connector.set_write_timeout(Some(write_timeout));
-            Client::builder()
-                .build::<_, hyper::Body>(connector)
-                .request(req)
-                .await?
-        }
-    } else {
-        let base_connector = HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .https_or_http()
-            .enable_http1()
-            .build();
-        let mut connector = TimeoutConnector::new(base_connector);
-        connector.set_connect_timeout(Some(connect_timeout));
-        connector.set_read_timeout(Some(read_timeout));
-        connector.set_write_timeout(Some(write_timeout));
-        Client::builder()
-            .build::<_, hyper::Body>(connector)
-            .request(req)
-            .await?
-    };
+    let client = HTTPClient::new(&current_url, &timeout).await?;
+    let started = Instant::now();
+    let resp = client.request(req).await?;
 
     let status = resp.status().as_u16();
     let mut headers = HashMap::new();
@@ -649,7 +818,10 @@ pub async fn request(
     }
     let mut buf = hyper::body::to_bytes(resp).await?;
     // Mark done proactively; decompression time is not included
-    trace.done();
+    let latency = started.elapsed().as_millis() as u32;
+    if let Some(trace) = trace {
+        trace.done();
+    }
     let body_size = buf.len();
     // Decompress gzip
     if is_gzip {
@@ -666,10 +838,17 @@ pub async fn request(
         buf = Bytes::copy_from_slice(&decode_data);
     }
 
-    let mut stats: HTTPStats = trace.into();
+    let mut stats = match trace {
+        Some(trace) => trace.into(),
+        None => HTTPStats {
+            is_https: current_url.scheme() == "https",
+            total: latency,
+            ..Default::default()
+        },
+    };
     stats.remote_addr = remote_addr;
 
-    let resp = HTTPResponse {
+    let mut resp = HTTPResponse {
         api,
         body_size: body_size as u32,
         latency: stats.total,
@@ -677,7 +856,11 @@ pub async fn request(
         headers,
         body: general_purpose::STANDARD.encode(buf),
         stats,
+        assertions: vec![],
+        extractions: vec![],
     };
+    resp.assertions = assertion::evaluate(&assertions, &resp);
+    save_latest_response(&resp);
 
     Ok(resp)
 }
@@ -0,0 +1,136 @@
+use serde_json::Value;
+
+// A small subset of JSONPath: `$`, `.key`, `['key']`, `[0]`, `[-1]`, `[*]`, `.*` and `..key`.
+// Paths without `$` are treated as lodash style path, e.g. `data.items.0.name`.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Descendant(String),
+}
+
+fn parse(path: &str) -> Option<Vec<Token>> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let chars: Vec<char> = path.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let read_name = |start: usize| -> (String, usize) {
+        let mut end = start;
+        while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+            end += 1;
+        }
+        (chars[start..end].iter().collect(), end)
+    };
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                let (name, end) = read_name(i + 2);
+                if name.is_empty() {
+                    return None;
+                }
+                tokens.push(Token::Descendant(name));
+                i = end;
+            }
+            '.' => {
+                let (name, end) = read_name(i + 1);
+                match name.as_str() {
+                    "" => {}
+                    "*" => tokens.push(Token::Wildcard),
+                    _ => tokens.push(Token::Key(name)),
+                }
+                i = end;
+            }
+            '[' => {
+                let end = (i + 1..chars.len()).find(|&index| chars[index] == ']')?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                if inner == "*" {
+                    tokens.push(Token::Wildcard);
+                } else if let Ok(index) = inner.parse::<i64>() {
+                    tokens.push(Token::Index(index));
+                } else {
+                    let key = inner.trim_matches('\'').trim_matches('"');
+                    tokens.push(Token::Key(key.to_string()));
+                }
+                i = end + 1;
+            }
+            _ => {
+                let (name, end) = read_name(i);
+                tokens.push(Token::Key(name));
+                i = end;
+            }
+        }
+    }
+    Some(tokens)
+}
+
+fn get_index(arr: &[Value], index: i64) -> Option<&Value> {
+    let index = if index < 0 {
+        arr.len().checked_sub(index.unsigned_abs() as usize)?
+    } else {
+        index as usize
+    };
+    arr.get(index)
+}
+
+fn collect_descendants<'a>(value: &'a Value, name: &str, result: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(found) = map.get(name) {
+                result.push(found);
+            }
+            for item in map.values() {
+                collect_descendants(item, name, result);
+            }
+        }
+        Value::Array(arr) => {
+            for item in arr {
+                collect_descendants(item, name, result);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Returns all values matched by the path, an invalid path matches nothing
+pub fn query<'a>(value: &'a Value, path: &str) -> Vec<&'a Value> {
+    let Some(tokens) = parse(path) else {
+        return vec![];
+    };
+    let mut current = vec![value];
+    for token in tokens {
+        let mut next = vec![];
+        for item in current {
+            match (&token, item) {
+                (Token::Key(key), Value::Object(map)) => next.extend(map.get(key)),
+                (Token::Key(key), Value::Array(arr)) => {
+                    if let Ok(index) = key.parse::<i64>() {
+                        next.extend(get_index(arr, index));
+                    }
+                }
+                (Token::Index(index), Value::Array(arr)) => next.extend(get_index(arr, *index)),
+                (Token::Wildcard, Value::Object(map)) => next.extend(map.values()),
+                (Token::Wildcard, Value::Array(arr)) => next.extend(arr.iter()),
+                (Token::Descendant(name), _) => collect_descendants(item, name, &mut next),
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+pub fn find_first<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    query(value, path).into_iter().next()
+}
+
+// Strings are returned without quotes, other values are returned as json
+pub fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(str) => str.to_string(),
+        Value::Null => "".to_string(),
+        _ => value.to_string(),
+    }
+}
@@ -6,13 +6,30 @@ use tracing::warn;
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 use tracing_subscriber::prelude::*;
 
+mod assertion;
+mod backup;
+mod capture_proxy;
+mod cli;
+mod codegen;
 mod commands;
 mod cookies;
+mod crypto;
+mod curl;
 mod entities;
 mod error;
+mod extractor;
+mod har;
+mod history;
 mod http_request;
+mod json_path;
+mod load_test;
+mod mock_server;
+mod openapi;
+mod postman;
+mod runner;
 mod schemas;
 mod settings;
+mod template;
 mod util;
 
 fn migrate_from_appdata_to_portable(
@@ -39,6 +56,11 @@ fn migrate_from_appdata_to_portable(
     Ok(())
 }
 
+// Entry of the headless cli, returns the exit code
+pub fn run_cli() -> i32 {
+    cli::run()
+}
+
 pub fn run() {
     tracing_subscriber::registry()
         .with(http_request::HTTPTraceLayer)
@@ -94,6 +116,22 @@ pub fn run() {
             commands::set_db_path,
             commands::export_tables,
             commands::import_tables,
+            commands::export_collections,
+            commands::import_collections,
+            commands::export_workspace,
+            commands::import_workspace,
+            commands::list_backups,
+            commands::restore_backup,
+            commands::diff_tables,
+            commands::merge_tables,
+            commands::import_har,
+            commands::export_har,
+            commands::import_openapi,
+            commands::export_openapi,
+            commands::import_postman,
+            commands::export_postman,
+            commands::generate_code_snippet,
+            commands::import_curl,
             commands::add_api_setting,
             commands::update_api_setting,
             commands::list_api_setting,
@@ -102,11 +140,37 @@ pub fn run() {
             commands::update_api_folder,
             commands::list_api_folder,
             commands::delete_api_folder,
+            commands::move_item,
+            commands::reorder,
             commands::add_api_collection,
             commands::update_api_collection,
             commands::list_api_collection,
             commands::delete_api_collection,
             commands::do_http_request,
+            commands::list_history,
+            commands::get_history,
+            commands::pin_history,
+            commands::delete_histories,
+            commands::clear_history,
+            commands::rerun_history,
+            commands::search,
+            commands::render_template,
+            commands::list_runtime_variable,
+            commands::clear_runtime_variable,
+            commands::run_suite,
+            commands::run_load_test,
+            commands::stop_load_test,
+            commands::start_mock_server,
+            commands::stop_mock_server,
+            commands::list_mock_server,
+            commands::list_mock_log,
+            commands::start_capture_proxy,
+            commands::stop_capture_proxy,
+            commands::list_capture_proxy,
+            commands::list_captured_exchange,
+            commands::clear_captured_exchange,
+            commands::save_captured_exchanges,
+            commands::get_capture_ca_cert,
             commands::list_cookie,
             commands::delete_cookie,
             commands::add_cookie,
@@ -115,6 +179,11 @@ pub fn run() {
             commands::update_variable,
             commands::delete_variable,
             commands::list_variable,
+            commands::reveal_variable,
+            commands::get_secret_status,
+            commands::unlock_secrets,
+            commands::lock_secrets,
+            commands::change_secret_passphrase,
             commands::add_proxy,
             commands::update_proxy,
             commands::delete_proxy,
@@ -0,0 +1,459 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{self, HTTPClient, HTTPRequest, RequestTimeout};
+use crate::runner::{self, RunnerContext};
+use crate::schemas;
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const LOAD_TEST_ERROR_CATEGORY: &str = "loadTest";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoadTestOptions {
+    // The enabled environment is used if it is None
+    pub environment: Option<String>,
+    // Count of requests sent at the same time
+    pub concurrency: u32,
+    // Total count of requests, it is ignored if duration is set
+    pub count: u64,
+    // Duration of the test in seconds
+    pub duration: u64,
+    // Target requests per second, 0 means no limit
+    pub rps: u32,
+    pub timeout: RequestTimeout,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+// Stats of one second, it is used to draw the chart
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestPoint {
+    pub second: u32,
+    pub count: u64,
+    pub errors: u64,
+    pub latency: LatencySummary,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestProgress {
+    pub id: String,
+    pub completed: u64,
+    pub point: LoadTestPoint,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestReport {
+    pub id: String,
+    pub setting: String,
+    pub method: String,
+    pub uri: String,
+    pub started_at: String,
+    // Milliseconds
+    pub duration: u32,
+    pub total: u64,
+    // Requests with status less than 400
+    pub success: u64,
+    pub failed: u64,
+    // Requests per second
+    pub throughput: f64,
+    // Milliseconds
+    pub latency: LatencySummary,
+    pub status: BTreeMap<u16, u64>,
+    // Count of each error category, e.g. timeout, connect
+    pub errors: BTreeMap<String, u64>,
+    pub series: Vec<LoadTestPoint>,
+}
+
+struct Sample {
+    // Milliseconds since the test started, when the request is done
+    done_at: u64,
+    // Milliseconds
+    latency: f64,
+    status: Option<u16>,
+    error: Option<String>,
+}
+
+struct LoadTest {
+    stopped: Arc<AtomicBool>,
+    client: HTTPClient,
+    req: HTTPRequest,
+    options: LoadTestOptions,
+    started: Instant,
+    next: AtomicU64,
+    samples: Mutex<Vec<Sample>>,
+}
+
+// The stop flags of the running load tests
+static RUNNING_LOAD_TESTS: OnceCell<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceCell::new();
+
+fn get_running_load_tests() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    RUNNING_LOAD_TESTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Remove the load test from the running list when the run is finished or failed
+struct RunningGuard(String);
+
+impl RunningGuard {
+    fn new(id: &str) -> Result<(RunningGuard, Arc<AtomicBool>), CyberAPIError> {
+        let mut running = get_running_load_tests()
+            .lock()
+            .map_err(|err| CyberAPIError::new(LOAD_TEST_ERROR_CATEGORY, err.to_string()))?;
+        if running.contains_key(id) {
+            return Err(CyberAPIError::new(
+                LOAD_TEST_ERROR_CATEGORY,
+                format!("load test {} is running", id),
+            ));
+        }
+        let stopped = Arc::new(AtomicBool::new(false));
+        running.insert(id.to_string(), stopped.clone());
+        Ok((RunningGuard(id.to_string()), stopped))
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = get_running_load_tests().lock() {
+            running.remove(&self.0);
+        }
+    }
+}
+
+// Stop the running load test, it returns false if the load test is not running
+pub fn stop_load_test(id: &str) -> bool {
+    let Some(stopped) = get_running_load_tests()
+        .lock()
+        .ok()
+        .and_then(|running| running.get(id).cloned())
+    else {
+        return false;
+    };
+    stopped.store(true, Ordering::SeqCst);
+    true
+}
+
+fn categorize_error(err: &hyper::Error) -> String {
+    let message = err.to_string();
+    let category = if err.is_timeout() || message.contains("timed out") {
+        "timeout"
+    } else if err.is_connect() {
+        "connect"
+    } else if err.is_incomplete_message() || err.is_closed() || err.is_canceled() {
+        "closed"
+    } else if err.is_parse() {
+        "parse"
+    } else {
+        "other"
+    };
+    category.to_string()
+}
+
+// Nearest rank percentile of sorted values
+fn percentile(sorted: &[f64], value: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (value / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn summarize(mut latencies: Vec<f64>) -> LatencySummary {
+    if latencies.is_empty() {
+        return LatencySummary::default();
+    }
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let round = |value: f64| (value * 1000.0).round() / 1000.0;
+    LatencySummary {
+        min: round(latencies[0]),
+        max: round(latencies[latencies.len() - 1]),
+        mean: round(latencies.iter().sum::<f64>() / latencies.len() as f64),
+        p50: round(percentile(&latencies, 50.0)),
+        p90: round(percentile(&latencies, 90.0)),
+        p99: round(percentile(&latencies, 99.0)),
+    }
+}
+
+fn to_point(second: u32, samples: &[&Sample]) -> LoadTestPoint {
+    LoadTestPoint {
+        second,
+        count: samples.len() as u64,
+        errors: samples.iter().filter(|item| item.error.is_some()).count() as u64,
+        latency: summarize(samples.iter().map(|item| item.latency).collect()),
+    }
+}
+
+async fn send(test: &LoadTest) -> Sample {
+    let started = Instant::now();
+    let result = match http_request::build_request(test.req.clone()) {
+        Ok((req, _)) => match test.client.request(req).await {
+            // Read the whole body, then the connection can be reused
+            Ok(resp) => {
+                let status = resp.status().as_u16();
+                hyper::body::to_bytes(resp.into_body())
+                    .await
+                    .map(|_| status)
+                    .map_err(|err| categorize_error(&err))
+            }
+            Err(err) => Err(categorize_error(&err)),
+        },
+        Err(_) => Err("request".to_string()),
+    };
+    let (status, error) = match result {
+        Ok(status) => (Some(status), None),
+        Err(err) => (None, Some(err)),
+    };
+    Sample {
+        done_at: test.started.elapsed().as_millis() as u64,
+        latency: started.elapsed().as_secs_f64() * 1000.0,
+        status,
+        error,
+    }
+}
+
+async fn run_worker(test: Arc<LoadTest>) {
+    let duration = Duration::from_secs(test.options.duration);
+    loop {
+        let index = test.next.fetch_add(1, Ordering::SeqCst);
+        if test.options.duration == 0 && index >= test.options.count {
+            return;
+        }
+        // Wait for the time slot of the request to keep the target rps
+        if test.options.rps != 0 {
+            let at = Duration::from_secs_f64(index as f64 / test.options.rps as f64);
+            if let Some(wait) = at.checked_sub(test.started.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+        if test.options.duration != 0 && test.started.elapsed() >= duration {
+            return;
+        }
+        if test.stopped.load(Ordering::SeqCst) {
+            return;
+        }
+        let sample = send(&test).await;
+        if let Ok(mut samples) = test.samples.lock() {
+            samples.push(sample);
+        }
+    }
+}
+
+// Send the request of setting repeatedly, the stats of each second is reported by progress
+pub async fn run(
+    id: String,
+    setting: &str,
+    options: LoadTestOptions,
+    on_progress: impl Fn(LoadTestProgress),
+) -> Result<LoadTestReport, CyberAPIError> {
+    if options.count == 0 && options.duration == 0 {
+        return Err(CyberAPIError::new(
+            LOAD_TEST_ERROR_CATEGORY,
+            "count or duration is required".to_string(),
+        ));
+    }
+    let (_guard, stopped) = RunningGuard::new(&id)?;
+    let api_setting = schemas::get_api_setting(setting.to_string())
+        .await?
+        .ok_or_else(|| {
+            CyberAPIError::new(
+                LOAD_TEST_ERROR_CATEGORY,
+                format!("request {} is not found", setting),
+            )
+        })?;
+    let ctx = RunnerContext::load(&api_setting.collection, options.environment.clone()).await?;
+    // Templates are rendered once, all requests are the same
+    let req = runner::resolve_request(&ctx, runner::parse_setting(&api_setting)?)?;
+    let (_, url) = http_request::build_request(req.clone())?;
+    let client = HTTPClient::new(&url, &options.timeout).await?;
+    let concurrency = options.concurrency.max(1);
+
+    let started_at = Local::now().to_rfc3339();
+    let test = Arc::new(LoadTest {
+        stopped,
+        client,
+        req,
+        options,
+        started: Instant::now(),
+        next: AtomicU64::new(0),
+        samples: Mutex::new(vec![]),
+    });
+    let tasks: Vec<_> = (0..concurrency)
+        .map(|_| tokio::spawn(run_worker(test.clone())))
+        .collect();
+
+    let mut second = 0;
+    while !tasks.iter().all(|task| task.is_finished()) {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let elapsed = test.started.elapsed().as_secs() as u32;
+        // Report the seconds which are done
+        while second < elapsed {
+            // The lock is released before the callback, the workers should not wait for it
+            let progress = test.samples.lock().ok().map(|samples| {
+                let matched: Vec<&Sample> = samples
+                    .iter()
+                    .filter(|item| item.done_at / 1000 == second as u64)
+                    .collect();
+                LoadTestProgress {
+                    id: id.clone(),
+                    completed: samples.len() as u64,
+                    point: to_point(second, &matched),
+                }
+            });
+            if let Some(progress) = progress {
+                on_progress(progress);
+            }
+            second += 1;
+        }
+    }
+    for task in tasks {
+        task.await?;
+    }
+
+    let duration = test.started.elapsed();
+    let samples = test
+        .samples
+        .lock()
+        .map(|mut samples| std::mem::take(&mut *samples))
+        .unwrap_or_default();
+    let mut status = BTreeMap::new();
+    let mut errors = BTreeMap::new();
+    let mut series: BTreeMap<u32, Vec<&Sample>> = BTreeMap::new();
+    for item in samples.iter() {
+        if let Some(value) = item.status {
+            *status.entry(value).or_insert(0) += 1;
+        }
+        if let Some(err) = &item.error {
+            *errors.entry(err.clone()).or_insert(0) += 1;
+        }
+        series
+            .entry((item.done_at / 1000) as u32)
+            .or_default()
+            .push(item);
+    }
+    let total = samples.len() as u64;
+    let success = samples
+        .iter()
+        .filter(|item| item.status.map(|value| value < 400).unwrap_or(false))
+        .count() as u64;
+    Ok(LoadTestReport {
+        id,
+        setting: setting.to_string(),
+        method: test.req.method.clone(),
+        uri: url.to_string(),
+        started_at,
+        duration: duration.as_millis() as u32,
+        total,
+        success,
+        failed: total - success,
+        throughput: (total as f64 / duration.as_secs_f64().max(0.001) * 100.0).round() / 100.0,
+        latency: summarize(samples.iter().map(|item| item.latency).collect()),
+        status,
+        errors,
+        series: series
+            .into_iter()
+            .map(|(second, items)| to_point(second, &items))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_running_load_test() {
+        let id = "load-test-stop";
+        // The stop request of the load test which is not running is ignored
+        assert!(!stop_load_test(id));
+
+        let (guard, stopped) = RunningGuard::new(id).unwrap();
+        assert_eq!(
+            "[loadTest]load test load-test-stop is running",
+            RunningGuard::new(id).err().unwrap().to_string()
+        );
+        assert!(!stopped.load(Ordering::SeqCst));
+        assert!(stop_load_test(id));
+        assert!(stopped.load(Ordering::SeqCst));
+
+        drop(guard);
+        assert!(!stop_load_test(id));
+        // The new run of the same id is not stopped
+        let (_guard, stopped) = RunningGuard::new(id).unwrap();
+        assert!(!stopped.load(Ordering::SeqCst));
+    }
+
+    fn new_sample(latency: f64, error: Option<&str>) -> Sample {
+        Sample {
+            done_at: 0,
+            latency,
+            status: if error.is_none() { Some(200) } else { None },
+            error: error.map(|value| value.to_string()),
+        }
+    }
+
+    #[test]
+    fn percentile_of_sorted() {
+        assert_eq!(0.0, percentile(&[], 50.0));
+        let sorted: Vec<f64> = (1..=10).map(|value| value as f64).collect();
+        assert_eq!(1.0, percentile(&sorted, 0.0));
+        assert_eq!(1.0, percentile(&sorted, 10.0));
+        assert_eq!(5.0, percentile(&sorted, 50.0));
+        assert_eq!(6.0, percentile(&sorted, 51.0));
+        assert_eq!(9.0, percentile(&sorted, 90.0));
+        assert_eq!(10.0, percentile(&sorted, 99.0));
+        assert_eq!(10.0, percentile(&sorted, 100.0));
+        assert_eq!(3.0, percentile(&[3.0], 99.0));
+    }
+
+    #[test]
+    fn summarize_latencies() {
+        let summary = summarize(vec![]);
+        assert_eq!(0.0, summary.min);
+        assert_eq!(0.0, summary.p99);
+
+        let summary = summarize(vec![4.0, 1.0, 3.0, 2.0, 10.12345]);
+        assert_eq!(1.0, summary.min);
+        assert_eq!(10.123, summary.max);
+        assert_eq!(4.025, summary.mean);
+        assert_eq!(3.0, summary.p50);
+        assert_eq!(10.123, summary.p90);
+        assert_eq!(10.123, summary.p99);
+    }
+
+    #[test]
+    fn point_of_samples() {
+        let samples = [
+            new_sample(10.0, None),
+            new_sample(30.0, Some("timeout")),
+            new_sample(20.0, None),
+        ];
+        let point = to_point(3, &samples.iter().collect::<Vec<_>>());
+        assert_eq!(3, point.second);
+        assert_eq!(3, point.count);
+        assert_eq!(1, point.errors);
+        assert_eq!(10.0, point.latency.min);
+        assert_eq!(30.0, point.latency.max);
+        assert_eq!(20.0, point.latency.mean);
+        assert_eq!(20.0, point.latency.p50);
+
+        let point = to_point(0, &[]);
+        assert_eq!(0, point.count);
+        assert_eq!(0, point.errors);
+        assert_eq!(0.0, point.latency.max);
+    }
+}
@@ -0,0 +1,554 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{self, HTTPRequestKVParam};
+use crate::runner;
+use crate::schemas::{self, APISetting};
+use crate::template::TemplateContext;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::Local;
+use hyper::{
+    Body, Request, Response, Server, StatusCode,
+    header::{HeaderName, HeaderValue},
+    service::{make_service_fn, service_fn},
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+const MOCK_SERVER_ERROR_CATEGORY: &str = "mockServer";
+// Only the latest logs of each server are kept
+const MAX_MOCK_LOGS: usize = 1000;
+
+// Example response of the api, it is saved with the setting of api
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<HTTPRequestKVParam>,
+    pub content_type: String,
+    // Templates of body and headers are rendered, the params of path are also variables
+    pub body: String,
+    // Milliseconds to wait before the response is sent
+    pub delay: u64,
+}
+
+impl Default for MockResponse {
+    fn default() -> Self {
+        MockResponse {
+            status: 200,
+            headers: vec![],
+            content_type: "".to_string(),
+            body: "".to_string(),
+            delay: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MockLog {
+    pub time: String,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    // Id of the matched api setting, None if no route is matched
+    pub setting: Option<String>,
+    // Milliseconds
+    pub latency: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MockServerInfo {
+    pub port: u16,
+    pub address: String,
+    pub collection: String,
+    pub environment: Option<String>,
+    pub started_at: String,
+}
+
+struct MockServer {
+    info: MockServerInfo,
+    logs: Mutex<VecDeque<MockLog>>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+static MOCK_SERVERS: OnceCell<Mutex<HashMap<u16, Arc<MockServer>>>> = OnceCell::new();
+
+fn get_mock_servers() -> &'static Mutex<HashMap<u16, Arc<MockServer>>> {
+    MOCK_SERVERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Static(String),
+    // `:id`, `{id}` or `{{id}}`
+    Param(String),
+    // `*` matches the rest of path, it should have at least one segment
+    Wildcard,
+}
+
+struct Route {
+    method: String,
+    segments: Vec<Segment>,
+    setting: APISetting,
+    mock: Option<MockResponse>,
+}
+
+// Get the path of uri, the host (or the `{{host}}` prefix) and query are removed
+fn get_route_path(uri: &str) -> String {
+    let uri = uri.trim();
+    let uri = uri.split(['?', '#']).next().unwrap_or_default();
+    let path = if uri.starts_with("{{") {
+        uri.split_once("}}")
+            .map(|(_, rest)| rest)
+            .unwrap_or_default()
+    } else if let Some((_, rest)) = uri.split_once("://") {
+        rest.find('/')
+            .map(|index| &rest[index..])
+            .unwrap_or_default()
+    } else {
+        uri
+    };
+    format!("/{}", path.trim_start_matches('/'))
+}
+
+fn parse_segments(path: &str) -> Vec<Segment> {
+    path.split('/')
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            if item == "*" {
+                Segment::Wildcard
+            } else if let Some(name) = item.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            } else if item.starts_with('{') && item.ends_with('}') {
+                Segment::Param(item.trim_matches(['{', '}']).trim().to_string())
+            } else {
+                Segment::Static(item.to_string())
+            }
+        })
+        .collect()
+}
+
+// Match the path, returns the params of path and the count of static segments
+fn match_segments(segments: &[Segment], path: &str) -> Option<(HashMap<String, String>, usize)> {
+    let values: Vec<&str> = path.split('/').filter(|item| !item.is_empty()).collect();
+    let mut params = HashMap::new();
+    let mut score = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Wildcard => return (values.len() > index).then_some((params, score)),
+            Segment::Static(value) => {
+                if values.get(index) != Some(&value.as_str()) {
+                    return None;
+                }
+                score += 1;
+            }
+            Segment::Param(name) => {
+                let value = values.get(index)?;
+                params.insert(name.clone(), value.to_string());
+            }
+        }
+    }
+    if values.len() != segments.len() {
+        return None;
+    }
+    Some((params, score))
+}
+
+// Routes are loaded from the api settings of collection, so changes take effect immediately
+async fn list_routes(collection: &str) -> Result<Vec<Route>, CyberAPIError> {
+    let mut routes = vec![];
+    for setting in schemas::list_api_setting(collection.to_string()).await? {
+        let Ok(req) = runner::parse_setting(&setting) else {
+            continue;
+        };
+        if req.uri.is_empty() {
+            continue;
+        }
+        routes.push(Route {
+            method: req.method.to_uppercase(),
+            segments: parse_segments(&get_route_path(&req.uri)),
+            setting,
+            mock: req.mock,
+        });
+    }
+    Ok(routes)
+}
+
+// The route with the most static segments is used
+fn find_route(
+    routes: Vec<Route>,
+    method: &str,
+    path: &str,
+) -> Option<(Route, HashMap<String, String>)> {
+    let mut found: Option<(Route, HashMap<String, String>, usize)> = None;
+    for route in routes {
+        if route.method != method && !(route.method.is_empty() && method == "GET") {
+            continue;
+        }
+        let Some((params, score)) = match_segments(&route.segments, path) else {
+            continue;
+        };
+        if found.as_ref().map(|item| score > item.2).unwrap_or(true) {
+            found = Some((route, params, score));
+        }
+    }
+    found.map(|(route, params, _)| (route, params))
+}
+
+fn add_cors_headers(resp: &mut Response<Body>) {
+    let headers = resp.headers_mut();
+    headers.insert("access-control-allow-origin", HeaderValue::from_static("*"));
+    headers.insert(
+        "access-control-allow-headers",
+        HeaderValue::from_static("*"),
+    );
+    headers.insert(
+        "access-control-allow-methods",
+        HeaderValue::from_static("GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS"),
+    );
+}
+
+fn text_response(status: StatusCode, message: String) -> Response<Body> {
+    let mut resp = Response::new(Body::from(message));
+    *resp.status_mut() = status;
+    resp.headers_mut().insert(
+        "content-type",
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    resp
+}
+
+// Use the latest response of the api if it has no example response
+fn latest_response(setting: &APISetting) -> Option<Response<Body>> {
+    let latest = http_request::get_latest_response(&setting.id)?;
+    let body = general_purpose::STANDARD.decode(&latest.body).ok()?;
+    let mut resp = Response::new(Body::from(body));
+    *resp.status_mut() = StatusCode::from_u16(latest.status).ok()?;
+    for (key, values) in latest.headers.iter() {
+        // The body is decoded and its size may be changed
+        if ["content-length", "content-encoding", "transfer-encoding"].contains(&key.as_str()) {
+            continue;
+        }
+        let Ok(name) = HeaderName::from_bytes(key.as_bytes()) else {
+            continue;
+        };
+        for value in values {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                resp.headers_mut().append(name.clone(), value);
+            }
+        }
+    }
+    Some(resp)
+}
+
+async fn mock_response(
+    server: &MockServer,
+    setting: &APISetting,
+    mock: &MockResponse,
+    params: HashMap<String, String>,
+) -> Result<Response<Body>, CyberAPIError> {
+    let ctx = TemplateContext::load(&server.info.collection, server.info.environment.clone())
+        .await?
+        .with_data(params);
+    let mut resp = Response::new(Body::from(ctx.render(&mock.body)?));
+    *resp.status_mut() = StatusCode::from_u16(mock.status).map_err(|err| {
+        CyberAPIError::new(
+            MOCK_SERVER_ERROR_CATEGORY,
+            format!("status of {} is invalid: {}", setting.id, err),
+        )
+    })?;
+    let headers = resp.headers_mut();
+    if !mock.content_type.is_empty() {
+        headers.insert("content-type", HeaderValue::from_str(&mock.content_type)?);
+    }
+    for item in mock.headers.iter().filter(|item| item.enabled) {
+        if item.key.is_empty() {
+            continue;
+        }
+        headers.append(
+            HeaderName::from_bytes(ctx.render(&item.key)?.as_bytes())?,
+            HeaderValue::from_str(&ctx.render(&item.value)?)?,
+        );
+    }
+    if mock.delay != 0 {
+        tokio::time::sleep(Duration::from_millis(mock.delay)).await;
+    }
+    Ok(resp)
+}
+
+async fn serve(
+    server: &MockServer,
+    method: &str,
+    path: &str,
+) -> Result<(Response<Body>, Option<String>), CyberAPIError> {
+    let routes = list_routes(&server.info.collection).await?;
+    let Some((route, params)) = find_route(routes, method, path) else {
+        // Preflight request of browser
+        if method == "OPTIONS" {
+            return Ok((text_response(StatusCode::NO_CONTENT, "".to_string()), None));
+        }
+        return Ok((
+            text_response(
+                StatusCode::NOT_FOUND,
+                format!("no route is matched: {} {}", method, path),
+            ),
+            None,
+        ));
+    };
+    let id = Some(route.setting.id.clone());
+    let resp = match &route.mock {
+        Some(mock) => mock_response(server, &route.setting, mock, params).await?,
+        None => latest_response(&route.setting).unwrap_or_else(|| {
+            text_response(
+                StatusCode::NOT_IMPLEMENTED,
+                format!(
+                    "{} has no example response",
+                    route
+                        .setting
+                        .name
+                        .clone()
+                        .unwrap_or(route.setting.id.clone())
+                ),
+            )
+        }),
+    };
+    Ok((resp, id))
+}
+
+async fn handle(server: Arc<MockServer>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let (mut resp, setting) = match serve(&server, &method, &path).await {
+        Ok(result) => result,
+        Err(err) => (
+            text_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            None,
+        ),
+    };
+    add_cors_headers(&mut resp);
+
+    let status = resp.status().as_u16();
+    if setting.is_none() && method != "OPTIONS" {
+        warn!(
+            port = server.info.port,
+            method = method,
+            path = path,
+            "mock route is not matched"
+        );
+    }
+    if let Ok(mut logs) = server.logs.lock() {
+        if logs.len() >= MAX_MOCK_LOGS {
+            logs.pop_front();
+        }
+        logs.push_back(MockLog {
+            time: Local::now().to_rfc3339(),
+            method,
+            path,
+            status,
+            setting,
+            latency: started.elapsed().as_millis() as u32,
+        });
+    }
+    Ok(resp)
+}
+
+// Start the mock server of collection on 127.0.0.1:port, a random port is used if port is 0
+pub async fn start(
+    collection: String,
+    port: u16,
+    environment: Option<String>,
+) -> Result<MockServerInfo, CyberAPIError> {
+    let listener = std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))?;
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+    let builder = Server::from_tcp(listener)?;
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = Arc::new(MockServer {
+        info: MockServerInfo {
+            port: addr.port(),
+            address: format!("http://{}", addr),
+            collection,
+            environment: environment.filter(|value| !value.is_empty()),
+            started_at: Local::now().to_rfc3339(),
+        },
+        logs: Mutex::new(VecDeque::new()),
+        shutdown: Mutex::new(Some(tx)),
+    });
+    let state = server.clone();
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+    });
+    let http_server = builder.serve(make_service).with_graceful_shutdown(async {
+        rx.await.ok();
+    });
+    let port = addr.port();
+    tokio::spawn(async move {
+        if let Err(err) = http_server.await {
+            warn!(port = port, "mock server error: {}", err);
+        }
+        if let Ok(mut servers) = get_mock_servers().lock() {
+            servers.remove(&port);
+        }
+    });
+    info!(port = port, "mock server is started");
+    if let Ok(mut servers) = get_mock_servers().lock() {
+        servers.insert(port, server.clone());
+    }
+    Ok(server.info.clone())
+}
+
+// Stop the mock server of port, it returns false if the server is not running
+pub fn stop(port: u16) -> bool {
+    let Some(server) = get_mock_servers()
+        .lock()
+        .ok()
+        .and_then(|mut servers| servers.remove(&port))
+    else {
+        return false;
+    };
+    if let Some(tx) = server
+        .shutdown
+        .lock()
+        .ok()
+        .and_then(|mut value| value.take())
+    {
+        let _ = tx.send(());
+    }
+    info!(port = port, "mock server is stopped");
+    true
+}
+
+pub fn list() -> Vec<MockServerInfo> {
+    let mut result: Vec<MockServerInfo> = get_mock_servers()
+        .lock()
+        .map(|servers| servers.values().map(|item| item.info.clone()).collect())
+        .unwrap_or_default();
+    result.sort_by_key(|item| item.port);
+    result
+}
+
+// Logs of the mock server, the latest one is the last
+pub fn list_log(port: u16) -> Vec<MockLog> {
+    get_mock_servers()
+        .lock()
+        .ok()
+        .and_then(|servers| servers.get(&port).cloned())
+        .and_then(|server| {
+            server
+                .logs
+                .lock()
+                .ok()
+                .map(|logs| logs.iter().cloned().collect())
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_route(id: &str, method: &str, uri: &str) -> Route {
+        Route {
+            method: method.to_string(),
+            segments: parse_segments(&get_route_path(uri)),
+            setting: APISetting {
+                id: id.to_string(),
+                collection: "collection".to_string(),
+                name: None,
+                category: None,
+                setting: None,
+                parent_id: None,
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            },
+            mock: None,
+        }
+    }
+
+    #[test]
+    fn route_path() {
+        assert_eq!("/users/1", get_route_path("{{host}}/users/1?a=1"));
+        assert_eq!("/users", get_route_path("https://example.com/users#top"));
+        assert_eq!("/", get_route_path("https://example.com"));
+        assert_eq!("/users", get_route_path(" users "));
+        assert_eq!("/", get_route_path("{{host}}"));
+    }
+
+    #[test]
+    fn segments() {
+        assert_eq!(
+            vec![
+                Segment::Static("users".to_string()),
+                Segment::Param("id".to_string()),
+                Segment::Param("name".to_string()),
+                Segment::Param("tag".to_string()),
+                Segment::Wildcard,
+            ],
+            parse_segments("/users/:id/{name}/{{ tag }}/*")
+        );
+
+        let segments = parse_segments("/users/:id/books");
+        let (params, score) = match_segments(&segments, "/users/1/books").unwrap();
+        assert_eq!(HashMap::from([("id".to_string(), "1".to_string())]), params);
+        assert_eq!(2, score);
+        assert!(match_segments(&segments, "/users/1").is_none());
+        assert!(match_segments(&segments, "/users/1/books/2").is_none());
+        assert!(match_segments(&segments, "/users/1/cars").is_none());
+
+        let segments = parse_segments("/files/*");
+        assert!(match_segments(&segments, "/files/a/b/c").is_some());
+        assert!(match_segments(&segments, "/files/a").is_some());
+        assert!(match_segments(&segments, "/files").is_none());
+        assert!(match_segments(&segments, "/files/").is_none());
+        assert!(match_segments(&segments, "/images/a").is_none());
+    }
+
+    #[test]
+    fn find_most_static_route() {
+        let routes = || {
+            vec![
+                new_route("param", "GET", "{{host}}/users/:id"),
+                new_route("static", "GET", "{{host}}/users/me"),
+                new_route("post", "POST", "{{host}}/users"),
+                new_route("default", "", "{{host}}/books"),
+            ]
+        };
+        let find = |method: &str, path: &str| {
+            find_route(routes(), method, path).map(|(route, params)| (route.setting.id, params))
+        };
+
+        assert_eq!(
+            Some(("static".to_string(), HashMap::new())),
+            find("GET", "/users/me")
+        );
+        assert_eq!(
+            Some((
+                "param".to_string(),
+                HashMap::from([("id".to_string(), "1".to_string())])
+            )),
+            find("GET", "/users/1")
+        );
+        assert_eq!(
+            Some(("post".to_string(), HashMap::new())),
+            find("POST", "/users")
+        );
+        assert_eq!(None, find("GET", "/users"));
+        // The method of route is GET if it is not set
+        assert_eq!(
+            Some(("default".to_string(), HashMap::new())),
+            find("GET", "/books")
+        );
+        assert_eq!(None, find("DELETE", "/books"));
+    }
+}
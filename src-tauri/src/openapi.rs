@@ -0,0 +1,2138 @@
+use crate::error::CyberAPIError;
+use crate::history;
+use crate::http_request::{
+    self, CONTENT_TYPE_FORM, CONTENT_TYPE_MULTIPART, HTTPRequest, HTTPRequestKVParam, HTTPResponse,
+};
+use crate::json_path;
+use crate::runner::{self, RunTarget};
+use crate::schemas::{
+    self, APIFolder, APISetting, ENVIRONMENT_ENABLED, Environment, VARIABLE_CATEGORY_ENVIRONMENT,
+    VARIABLE_ENABLED, Variable,
+};
+use crate::template::TemplateContext;
+use hyper::StatusCode;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use url::form_urlencoded;
+
+const OPENAPI_ERROR_CATEGORY: &str = "openapi";
+const CONTENT_TYPE_JSON: &str = "application/json";
+// The variable of server url, it is the prefix of all imported requests
+const HOST_VARIABLE: &str = "host";
+const ENVIRONMENT_DISABLED: &str = "0";
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+// Avoid endless recursion of circular schemas
+const MAX_REF_DEPTH: usize = 32;
+const MAX_RESOLVED_VALUES: usize = 1_000_000;
+const MAX_EXAMPLE_DEPTH: usize = 8;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OpenAPIImportOptions {
+    // Update the requests with the same method and url instead of creating new ones
+    pub update: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenAPIImportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub folders: usize,
+    pub environments: usize,
+    // Ids of the new folders and requests which are not in any folder
+    pub top: Vec<String>,
+}
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(OPENAPI_ERROR_CATEGORY, message)
+}
+
+// Parse the document of json or yaml
+fn parse_document(content: &str) -> Result<Value, CyberAPIError> {
+    if content.trim_start().starts_with('{') {
+        return Ok(serde_json::from_str(content)?);
+    }
+    // The keys of yaml may be numbers, e.g. the status of responses
+    let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+    Ok(serde_json::to_value(value)?)
+}
+
+// Resolve `$ref` of the documents, external files are relative to the file of the ref
+struct RefResolver {
+    documents: HashMap<PathBuf, Value>,
+    // The resolved refs and the count of their values, a ref is only resolved once
+    // unless it is inside a circular ref
+    resolved: HashMap<String, (Value, usize)>,
+    // The lowest index of stack which is cut as circular, the refs above it can not be reused
+    cut: usize,
+    // Count of the resolved values, the refs are not inlined any more if it is too large
+    count: usize,
+}
+
+impl RefResolver {
+    fn new(file: &Path, doc: Value) -> Self {
+        RefResolver {
+            documents: HashMap::from([(file.to_path_buf(), doc)]),
+            resolved: HashMap::new(),
+            cut: usize::MAX,
+            count: 0,
+        }
+    }
+
+    fn load(&mut self, file: &Path) -> Result<&Value, CyberAPIError> {
+        if !self.documents.contains_key(file) {
+            let content = fs::read_to_string(file)?;
+            self.documents
+                .insert(file.to_path_buf(), parse_document(&content)?);
+        }
+        Ok(&self.documents[file])
+    }
+
+    fn resolve(
+        &mut self,
+        value: &Value,
+        file: &Path,
+        stack: &mut Vec<String>,
+    ) -> Result<Value, CyberAPIError> {
+        self.count += 1;
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    return self.resolve_ref(map, reference, file, stack);
+                }
+                let mut result = Map::new();
+                for (key, item) in map.iter() {
+                    result.insert(key.clone(), self.resolve(item, file, stack)?);
+                }
+                Ok(Value::Object(result))
+            }
+            Value::Array(items) => items
+                .iter()
+                .map(|item| self.resolve(item, file, stack))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            _ => Ok(value.clone()),
+        }
+    }
+
+    fn resolve_target(
+        &mut self,
+        reference: &str,
+        key: String,
+        target_file: PathBuf,
+        pointer: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<Value, CyberAPIError> {
+        if let Some((value, count)) = self.resolved.get(&key) {
+            self.count += count;
+            return Ok(value.clone());
+        }
+        let found = self
+            .load(&target_file)?
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| new_error(format!("ref {} is not found", reference)))?;
+        let index = stack.len();
+        let cut = std::mem::replace(&mut self.cut, usize::MAX);
+        let count = self.count;
+        stack.push(key.clone());
+        let resolved = self.resolve(&found, &target_file, stack)?;
+        stack.pop();
+        // The ref is the same wherever it is used if all circular refs are inside it
+        if self.cut >= index {
+            self.resolved
+                .insert(key, (resolved.clone(), self.count - count));
+        }
+        self.cut = self.cut.min(cut);
+        Ok(resolved)
+    }
+
+    fn resolve_ref(
+        &mut self,
+        map: &Map<String, Value>,
+        reference: &str,
+        file: &Path,
+        stack: &mut Vec<String>,
+    ) -> Result<Value, CyberAPIError> {
+        let (target, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        if target.starts_with("http://") || target.starts_with("https://") {
+            warn!("remote ref {} is not supported", reference);
+            return Ok(Value::Object(Map::new()));
+        }
+        let target_file = if target.is_empty() {
+            file.to_path_buf()
+        } else {
+            file.parent().unwrap_or(Path::new("")).join(target)
+        };
+        let key = format!("{}#{}", target_file.display(), pointer);
+        // The circular schema is replaced by an empty one
+        if let Some(index) = stack.iter().position(|item| item == &key) {
+            self.cut = self.cut.min(index);
+            return Ok(Value::Object(Map::new()));
+        }
+        if stack.len() >= MAX_REF_DEPTH {
+            self.cut = 0;
+            return Ok(Value::Object(Map::new()));
+        }
+        // The refs which are used many times may make the document too large
+        if self.count >= MAX_RESOLVED_VALUES {
+            warn!(
+                "ref {} is not inlined, the document is too large",
+                reference
+            );
+            self.cut = 0;
+            return Ok(Value::Object(Map::new()));
+        }
+        let resolved = self.resolve_target(reference, key, target_file, pointer, stack)?;
+        // The siblings of `$ref` are allowed since 3.1, e.g. description
+        match resolved {
+            Value::Object(mut resolved) => {
+                for (key, item) in map.iter().filter(|(key, _)| *key != "$ref") {
+                    resolved.insert(key.clone(), self.resolve(item, file, stack)?);
+                }
+                Ok(Value::Object(resolved))
+            }
+            _ => Ok(resolved),
+        }
+    }
+}
+
+fn get_str<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn get_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.as_slice())
+        .unwrap_or_default()
+}
+
+// The type of 3.1 may be an array, e.g. ["string", "null"]
+fn schema_type(schema: &Value) -> String {
+    let value = match schema.get("type") {
+        Some(Value::String(value)) => value.as_str(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|item| *item != "null")
+            .unwrap_or_default(),
+        _ => "",
+    };
+    if !value.is_empty() {
+        return value.to_string();
+    }
+    if schema.get("properties").is_some() {
+        "object".to_string()
+    } else if schema.get("items").is_some() {
+        "array".to_string()
+    } else {
+        "".to_string()
+    }
+}
+
+// Generate the example of schema, the example, default and enum of schema are used first
+fn schema_example(schema: &Value, depth: usize) -> Value {
+    if depth > MAX_EXAMPLE_DEPTH {
+        return Value::Null;
+    }
+    for key in ["example", "default", "const"] {
+        if let Some(value) = schema.get(key) {
+            return value.clone();
+        }
+    }
+    for key in ["examples", "enum"] {
+        if let Some(value) = get_array(schema, key).first() {
+            return value.clone();
+        }
+    }
+    if let Some(items) = schema.get("allOf").and_then(Value::as_array) {
+        let mut result = Map::new();
+        for item in items {
+            if let Value::Object(value) = schema_example(item, depth + 1) {
+                result.extend(value);
+            }
+        }
+        return Value::Object(result);
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(item) = get_array(schema, key).first() {
+            return schema_example(item, depth + 1);
+        }
+    }
+    match schema_type(schema).as_str() {
+        "object" => {
+            let mut result = Map::new();
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (key, item) in properties.iter() {
+                    result.insert(key.clone(), schema_example(item, depth + 1));
+                }
+            }
+            Value::Object(result)
+        }
+        "array" => match schema.get("items") {
+            Some(items) => Value::Array(vec![schema_example(items, depth + 1)]),
+            None => Value::Array(vec![]),
+        },
+        "string" => {
+            let value = match get_str(schema, "format") {
+                "date-time" => "2024-01-01T00:00:00Z",
+                "date" => "2024-01-01",
+                "time" => "00:00:00",
+                "email" => "user@example.com",
+                "uuid" => "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+                "uri" | "url" => "https://example.com",
+                "ipv4" => "127.0.0.1",
+                "binary" | "byte" => "",
+                _ => "string",
+            };
+            Value::String(value.to_string())
+        }
+        "integer" | "number" => schema.get("minimum").cloned().unwrap_or(Value::from(0)),
+        "boolean" => Value::Bool(true),
+        _ => Value::Null,
+    }
+}
+
+// Example of parameter or media type, the examples of it are used before the schema
+fn value_example(value: &Value) -> Value {
+    if let Some(example) = value.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = value
+        .get("examples")
+        .and_then(Value::as_object)
+        .and_then(|examples| examples.values().next())
+        .and_then(|item| item.get("value"))
+    {
+        return example.clone();
+    }
+    // The schema of swagger 2 parameter is itself
+    let schema = value.get("schema").unwrap_or(value);
+    schema_example(schema, 0)
+}
+
+fn example_string(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        _ => json_path::to_string(value),
+    }
+}
+
+fn is_json_media_type(value: &str) -> bool {
+    let value = value.split(';').next().unwrap_or_default().trim();
+    value == CONTENT_TYPE_JSON || value.ends_with("+json")
+}
+
+// Convert the properties of schema to the params of form
+fn form_params(schema: &Value, example: &Value) -> Vec<HTTPRequestKVParam> {
+    let mut params = vec![];
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return params;
+    };
+    for (key, item) in properties.iter() {
+        let is_file = ["binary", "base64"].contains(&get_str(item, "format"));
+        let value = match example.get(key) {
+            _ if is_file => "file://".to_string(),
+            Some(value) => example_string(value),
+            None => example_string(&schema_example(item, 1)),
+        };
+        params.push(HTTPRequestKVParam {
+            key: key.clone(),
+            value,
+            enabled: true,
+        });
+    }
+    params
+}
+
+// Set the body of request by the request body of OpenAPI 3
+fn set_request_body(req: &mut HTTPRequest, request_body: &Value) -> Result<(), CyberAPIError> {
+    let Some(content) = request_body.get("content").and_then(Value::as_object) else {
+        return Ok(());
+    };
+    let media_type = content
+        .keys()
+        .find(|key| is_json_media_type(key))
+        .or_else(|| {
+            content
+                .keys()
+                .find(|key| key.starts_with(CONTENT_TYPE_FORM))
+        })
+        .or_else(|| {
+            content
+                .keys()
+                .find(|key| key.starts_with(CONTENT_TYPE_MULTIPART))
+        })
+        .or_else(|| content.keys().next());
+    let Some(media_type) = media_type else {
+        return Ok(());
+    };
+    let media = &content[media_type];
+    let example = value_example(media);
+    let schema = media.get("schema").cloned().unwrap_or_default();
+    if media_type.starts_with(CONTENT_TYPE_FORM) || media_type.starts_with(CONTENT_TYPE_MULTIPART) {
+        req.content_type = if media_type.starts_with(CONTENT_TYPE_FORM) {
+            CONTENT_TYPE_FORM.to_string()
+        } else {
+            CONTENT_TYPE_MULTIPART.to_string()
+        };
+        req.body = serde_json::to_string(&form_params(&schema, &example))?;
+    } else if is_json_media_type(media_type) {
+        req.content_type = CONTENT_TYPE_JSON.to_string();
+        req.body = serde_json::to_string_pretty(&example)?;
+    } else {
+        req.content_type = media_type.clone();
+        req.body = example_string(&example);
+    }
+    Ok(())
+}
+
+// The parameters of path item and operation, the ones of operation have higher priority
+fn merge_parameters(path_item: &Value, operation: &Value) -> Vec<Value> {
+    let mut result: Vec<Value> = get_array(path_item, "parameters").to_vec();
+    for item in get_array(operation, "parameters") {
+        let key = (get_str(item, "name"), get_str(item, "in"));
+        result.retain(|exist| (get_str(exist, "name"), get_str(exist, "in")) != key);
+        result.push(item.clone());
+    }
+    result
+}
+
+// Set the auth of request by the security schemes, returns the names of used variables
+fn apply_security(
+    req: &mut HTTPRequest,
+    doc: &Value,
+    operation: &Value,
+    variables: &mut BTreeSet<String>,
+) {
+    let requirements = operation
+        .get("security")
+        .or_else(|| doc.get("security"))
+        .and_then(Value::as_array);
+    let Some(requirement) = requirements.and_then(|items| items.first()) else {
+        return;
+    };
+    let schemes = doc
+        .pointer("/components/securitySchemes")
+        .or_else(|| doc.get("securityDefinitions"));
+    let Some(names) = requirement.as_object() else {
+        return;
+    };
+    for name in names.keys() {
+        let Some(scheme) = schemes.and_then(|value| value.get(name)) else {
+            continue;
+        };
+        let add_header = |req: &mut HTTPRequest, key: &str, value: String| {
+            req.headers.push(HTTPRequestKVParam {
+                key: key.to_string(),
+                value,
+                enabled: true,
+            })
+        };
+        let scheme_type = get_str(scheme, "type");
+        let http_scheme = get_str(scheme, "scheme").to_lowercase();
+        if scheme_type == "basic" || (scheme_type == "http" && http_scheme == "basic") {
+            req.auth = vec![HTTPRequestKVParam {
+                key: "{{username}}".to_string(),
+                value: "{{password}}".to_string(),
+                enabled: true,
+            }];
+            variables.insert("username".to_string());
+            variables.insert("password".to_string());
+        } else if scheme_type == "apiKey" {
+            let key = get_str(scheme, "name");
+            let value = format!("{{{{{}}}}}", name);
+            variables.insert(name.clone());
+            match get_str(scheme, "in") {
+                "query" => req.query.push(HTTPRequestKVParam {
+                    key: key.to_string(),
+                    value,
+                    enabled: true,
+                }),
+                "cookie" => add_header(req, "Cookie", format!("{}={}", key, value)),
+                _ => add_header(req, key, value),
+            }
+        } else if ["http", "oauth2", "openIdConnect"].contains(&scheme_type) {
+            add_header(req, "Authorization", "Bearer {{token}}".to_string());
+            variables.insert("token".to_string());
+        }
+    }
+}
+
+// Convert the operation to request, the path params are converted to `{{name}}`
+fn convert_operation(
+    doc: &Value,
+    path: &str,
+    method: &str,
+    path_item: &Value,
+    operation: &Value,
+    variables: &mut BTreeSet<String>,
+) -> Result<HTTPRequest, CyberAPIError> {
+    let uri = path.replace('{', "{{").replace('}', "}}");
+    let mut req = HTTPRequest {
+        method: method.to_uppercase(),
+        uri: format!("{{{{{}}}}}{}", HOST_VARIABLE, uri),
+        ..Default::default()
+    };
+    let mut cookies = vec![];
+    let mut form = vec![];
+    for param in merge_parameters(path_item, operation) {
+        let name = get_str(&param, "name").to_string();
+        let required = param
+            .get("required")
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+        let example = value_example(&param);
+        let item = HTTPRequestKVParam {
+            key: name.clone(),
+            value: example_string(&example),
+            // The optional params without example are disabled
+            enabled: required || !example.is_null(),
+        };
+        match get_str(&param, "in") {
+            "query" => req.query.push(item),
+            "header" => req.headers.push(item),
+            "cookie" => cookies.push(format!("{}={}", item.key, item.value)),
+            // Swagger 2
+            "body" => {
+                req.content_type = CONTENT_TYPE_JSON.to_string();
+                req.body = serde_json::to_string_pretty(&example)?;
+            }
+            "formData" => form.push(HTTPRequestKVParam {
+                value: if get_str(&param, "type") == "file" {
+                    "file://".to_string()
+                } else {
+                    item.value
+                },
+                ..item
+            }),
+            _ => {}
+        }
+    }
+    if !cookies.is_empty() {
+        req.headers.push(HTTPRequestKVParam {
+            key: "Cookie".to_string(),
+            value: cookies.join("; "),
+            enabled: true,
+        });
+    }
+    if !form.is_empty() {
+        let consumes = get_array(operation, "consumes");
+        let consumes = if consumes.is_empty() {
+            get_array(doc, "consumes")
+        } else {
+            consumes
+        };
+        let is_multipart = consumes
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|item| item.starts_with(CONTENT_TYPE_MULTIPART))
+            || form.iter().any(|item| item.value == "file://");
+        req.content_type = if is_multipart {
+            CONTENT_TYPE_MULTIPART.to_string()
+        } else {
+            CONTENT_TYPE_FORM.to_string()
+        };
+        req.body = serde_json::to_string(&form)?;
+    }
+    if let Some(request_body) = operation.get("requestBody") {
+        set_request_body(&mut req, request_body)?;
+    }
+    apply_security(&mut req, doc, operation, variables);
+    Ok(req)
+}
+
+struct Server {
+    name: String,
+    url: String,
+    variables: Vec<(String, String)>,
+}
+
+// The servers of document, the variables of url are replaced by their default values
+fn list_servers(doc: &Value) -> Vec<Server> {
+    // Swagger 2
+    if let Some(host) = doc.get("host").and_then(Value::as_str) {
+        let scheme = get_array(doc, "schemes")
+            .first()
+            .and_then(Value::as_str)
+            .unwrap_or("https");
+        let url = format!("{}://{}{}", scheme, host, get_str(doc, "basePath"));
+        return vec![Server {
+            name: url.clone(),
+            url: url.trim_end_matches('/').to_string(),
+            variables: vec![],
+        }];
+    }
+    let mut result = vec![];
+    for server in get_array(doc, "servers") {
+        let mut url = get_str(server, "url").to_string();
+        let mut variables = vec![];
+        if let Some(items) = server.get("variables").and_then(Value::as_object) {
+            for (name, item) in items.iter() {
+                let value = example_string(item.get("default").unwrap_or(&Value::Null));
+                url = url.replace(&format!("{{{}}}", name), &value);
+                variables.push((name.clone(), value));
+            }
+        }
+        let name = match get_str(server, "description") {
+            "" => url.clone(),
+            description => description.to_string(),
+        };
+        result.push(Server {
+            name,
+            url: url.trim_end_matches('/').to_string(),
+            variables,
+        });
+    }
+    result
+}
+
+// Create or update the variable of environment, the existing value is kept if overwrite is false
+async fn save_variable<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+    environment: &str,
+    name: &str,
+    value: &str,
+    overwrite: bool,
+) -> Result<(), CyberAPIError> {
+    let found = schemas::list_variable_with(
+        db,
+        collection.to_string(),
+        VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+    )
+    .await?
+    .into_iter()
+    .find(|item| {
+        item.name.as_deref() == Some(name)
+            && item.environment.clone().unwrap_or_default() == environment
+    });
+    match found {
+        Some(mut variable) => {
+            if overwrite {
+                variable.value = Some(value.to_string());
+                variable.updated_at = None;
+                schemas::update_variable_with(db, variable).await?;
+            }
+        }
+        None => {
+            schemas::add_variable_with(
+                db,
+                Variable {
+                    id: ulid::Ulid::new().to_string(),
+                    category: VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+                    collection: collection.to_string(),
+                    environment: Some(environment.to_string()),
+                    name: Some(name.to_string()),
+                    value: Some(value.to_string()),
+                    enabled: Some(VARIABLE_ENABLED.to_string()),
+                    secret: None,
+                    created_at: None,
+                    updated_at: None,
+                },
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// Each server is an environment with the `host` variable, returns the count of new environments
+async fn save_environments<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+    doc: &Value,
+) -> Result<usize, CyberAPIError> {
+    let mut environments = schemas::list_environment_with(db, collection.to_string()).await?;
+    let mut has_enabled = environments
+        .iter()
+        .any(|item| item.enabled.as_deref() == Some(ENVIRONMENT_ENABLED));
+    let mut count = 0;
+    for Server {
+        name,
+        url,
+        variables,
+    } in list_servers(doc)
+    {
+        let id = match environments
+            .iter()
+            .find(|item| item.name.as_deref() == Some(name.as_str()))
+        {
+            Some(environment) => environment.id.clone(),
+            None => {
+                let environment = schemas::add_environment_with(
+                    db,
+                    Environment {
+                        id: ulid::Ulid::new().to_string(),
+                        collection: collection.to_string(),
+                        name: Some(name),
+                        enabled: Some(
+                            if has_enabled {
+                                ENVIRONMENT_DISABLED
+                            } else {
+                                ENVIRONMENT_ENABLED
+                            }
+                            .to_string(),
+                        ),
+                        created_at: None,
+                        updated_at: None,
+                    },
+                )
+                .await?;
+                has_enabled = true;
+                count += 1;
+                environments.push(environment);
+                environments[environments.len() - 1].id.clone()
+            }
+        };
+        save_variable(db, collection, &id, HOST_VARIABLE, &url, true).await?;
+        for (name, value) in variables {
+            save_variable(db, collection, &id, &name, &value, true).await?;
+        }
+    }
+    Ok(count)
+}
+
+// Keep the values edited by user when the request is imported again
+fn merge_request(exist: HTTPRequest, req: HTTPRequest) -> HTTPRequest {
+    let merge_params = |exist: &[HTTPRequestKVParam], params: Vec<HTTPRequestKVParam>| {
+        params
+            .into_iter()
+            .map(
+                |item| match exist.iter().find(|value| value.key == item.key) {
+                    Some(value) => value.clone(),
+                    None => item,
+                },
+            )
+            .collect::<Vec<_>>()
+    };
+    let body = if exist.content_type == req.content_type && !exist.body.is_empty() {
+        exist.body.clone()
+    } else {
+        req.body
+    };
+    HTTPRequest {
+        query: merge_params(&exist.query, req.query),
+        headers: merge_params(&exist.headers, req.headers),
+        auth: if req.auth.is_empty() {
+            exist.auth.clone()
+        } else {
+            merge_params(&exist.auth, req.auth)
+        },
+        body,
+        content_type: req.content_type,
+        method: req.method,
+        uri: req.uri,
+        ..exist
+    }
+}
+
+// Import the OpenAPI 3 or Swagger 2 document into collection,
+// the external refs are relative to file, content is read from file if it is empty
+pub async fn import(
+    collection: &str,
+    file: &Path,
+    content: &str,
+    options: OpenAPIImportOptions,
+) -> Result<OpenAPIImportResult, CyberAPIError> {
+    let content = if content.trim().is_empty() {
+        fs::read_to_string(file)?
+    } else {
+        content.to_string()
+    };
+    let raw = parse_document(&content)?;
+    if raw.get("openapi").is_none() && raw.get("swagger").is_none() {
+        return Err(new_error(
+            "it is not an OpenAPI or Swagger document".to_string(),
+        ));
+    }
+    let mut resolver = RefResolver::new(file, raw.clone());
+    let doc = resolver.resolve(&raw, file, &mut vec![])?;
+
+    // Existing requests of collection, keyed by method and url
+    let mut exist_settings: HashMap<(String, String), (APISetting, HTTPRequest)> = HashMap::new();
+    if options.update {
+        for setting in schemas::list_api_setting(collection.to_string()).await? {
+            if let Ok(req) = runner::parse_setting(&setting) {
+                exist_settings.insert((req.method.to_uppercase(), req.uri.clone()), (setting, req));
+            }
+        }
+    }
+    let mut folders: Vec<APIFolder> = if options.update {
+        schemas::list_api_folder(collection.to_string()).await?
+    } else {
+        vec![]
+    };
+    let mut variables = BTreeSet::new();
+
+    // Nothing is saved if any request of document fails
+    let db = schemas::get_database().await;
+    let txn = db.begin().await?;
+    let mut result = OpenAPIImportResult {
+        environments: save_environments(&txn, collection, &doc).await?,
+        ..Default::default()
+    };
+
+    let empty = Map::new();
+    let paths = doc
+        .get("paths")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    for (path, path_item) in paths.iter() {
+        for method in METHODS {
+            let Some(operation) = path_item.get(method) else {
+                continue;
+            };
+            let req = convert_operation(&doc, path, method, path_item, operation, &mut variables)?;
+            let name = [
+                get_str(operation, "summary"),
+                get_str(operation, "operationId"),
+            ]
+            .into_iter()
+            .find(|value| !value.is_empty())
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| format!("{} {}", req.method, path));
+
+            if let Some((mut setting, exist)) =
+                exist_settings.remove(&(req.method.clone(), req.uri.clone()))
+            {
+                setting.name = Some(name);
+                setting.setting = Some(serde_json::to_string(&merge_request(exist, req))?);
+                setting.updated_at = None;
+                schemas::update_api_setting_with(&txn, setting).await?;
+                result.updated += 1;
+                continue;
+            }
+            let parent_id = match get_array(operation, "tags").first().and_then(Value::as_str) {
+                Some(tag) => match folders
+                    .iter()
+                    .find(|item| item.name.as_deref() == Some(tag))
+                {
+                    Some(folder) => Some(folder.id.clone()),
+                    None => {
+                        let folder = schemas::add_api_folder_with(
+                            &txn,
+                            APIFolder {
+                                id: ulid::Ulid::new().to_string(),
+                                collection: collection.to_string(),
+                                children: None,
+                                name: Some(tag.to_string()),
+                                parent_id: None,
+                                sort_order: None,
+                                created_at: None,
+                                updated_at: None,
+                            },
+                        )
+                        .await?;
+                        result.top.push(folder.id.clone());
+                        result.folders += 1;
+                        let id = folder.id.clone();
+                        folders.push(folder);
+                        Some(id)
+                    }
+                },
+                None => None,
+            };
+            let setting = schemas::add_api_setting_with(
+                &txn,
+                APISetting {
+                    id: ulid::Ulid::new().to_string(),
+                    collection: collection.to_string(),
+                    name: Some(name),
+                    category: Some("http".to_string()),
+                    setting: Some(serde_json::to_string(&req)?),
+                    parent_id: parent_id.clone(),
+                    sort_order: None,
+                    created_at: None,
+                    updated_at: None,
+                },
+            )
+            .await?;
+            result.created += 1;
+            if parent_id.is_none() {
+                result.top.push(setting.id);
+            }
+        }
+    }
+    // The variables of auth are created as global variables
+    for name in variables {
+        save_variable(&txn, collection, "", &name, "", false).await?;
+    }
+    txn.commit().await?;
+    Ok(result)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAPIFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+struct ExportInfo {
+    title: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    version: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+struct ExportServer {
+    url: String,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    variables: Map<String, Value>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct ExportTag {
+    name: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct ExportOperation {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    summary: String,
+    operation_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    servers: Vec<ExportServer>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parameters: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_body: Option<Value>,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    responses: Map<String, Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    security: Vec<Value>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct ExportComponents {
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    security_schemes: Map<String, Value>,
+}
+
+// The fields are serialized in the order of OpenAPI document
+#[derive(Serialize, Debug, Clone, Default)]
+struct ExportDocument {
+    openapi: String,
+    info: ExportInfo,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    servers: Vec<ExportServer>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<ExportTag>,
+    paths: BTreeMap<String, BTreeMap<String, ExportOperation>>,
+    #[serde(skip_serializing_if = "ExportComponents::is_empty")]
+    components: ExportComponents,
+}
+
+impl ExportComponents {
+    fn is_empty(&self) -> bool {
+        self.security_schemes.is_empty()
+    }
+}
+
+// Infer the schema of the example value
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "type": "null" }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(number) if number.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => match items.first() {
+            Some(item) => json!({ "type": "array", "items": infer_schema(item) }),
+            None => json!({ "type": "array", "items": {} }),
+        },
+        Value::Object(map) => {
+            let properties: Map<String, Value> = map
+                .iter()
+                .map(|(key, item)| (key.clone(), infer_schema(item)))
+                .collect();
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+// The name of template variable, e.g. `{{ userId }}` is `userId`
+fn variable_name(template: &str) -> String {
+    let name: String = template
+        .trim_start_matches("{{")
+        .trim_end_matches("}}")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+    if name.is_empty() {
+        "param".to_string()
+    } else {
+        name
+    }
+}
+
+// Replace the `{{name}}` templates with `{name}`, returns the names of them
+fn convert_templates(value: &str) -> (String, Vec<String>) {
+    let mut result = String::new();
+    let mut names = vec![];
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = variable_name(&rest[start..start + end + 2]);
+        result.push_str(&rest[..start]);
+        result.push_str(&format!("{{{}}}", name));
+        names.push(name);
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    (result, names)
+}
+
+// The numbers and uuids of path are treated as path params
+fn is_id_segment(segment: &str) -> bool {
+    (!segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()))
+        || (segment.len() == 36
+            && segment.chars().filter(|c| *c == '-').count() == 4
+            && segment.chars().all(|c| c.is_ascii_hexdigit() || c == '-'))
+}
+
+// Split the uri into server, path, path params and query
+fn split_uri(
+    ctx: &TemplateContext,
+    uri: &str,
+) -> (ExportServer, String, Vec<Value>, Vec<(String, String)>) {
+    let (uri, query) = uri.split_once('?').unwrap_or((uri, ""));
+    let query = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    // The server is the template prefix, e.g. `{{host}}`, or the scheme and host of url
+    let split_at = if uri.starts_with("{{") {
+        uri.find("}}").map(|index| index + 2).unwrap_or(uri.len())
+    } else if let Some(index) = uri.find("://") {
+        uri[index + 3..]
+            .find('/')
+            .map(|value| value + index + 3)
+            .unwrap_or(uri.len())
+    } else {
+        0
+    };
+    let (origin, path) = uri.split_at(split_at);
+    let (url, names) = convert_templates(origin);
+    let mut server = ExportServer {
+        url,
+        ..Default::default()
+    };
+    for name in names {
+        let value = ctx.get_variable(&name).unwrap_or_default();
+        server
+            .variables
+            .insert(name, json!({ "default": value.trim_end_matches('/') }));
+    }
+
+    let mut params = vec![];
+    let mut segments = vec![];
+    for segment in path.split('/').filter(|item| !item.is_empty()) {
+        let (segment, names) = if is_id_segment(segment) {
+            let name = match params.len() {
+                0 => "id".to_string(),
+                count => format!("id{}", count + 1),
+            };
+            let schema = if segment.len() == 36 {
+                json!({ "type": "string", "format": "uuid" })
+            } else {
+                json!({ "type": "integer" })
+            };
+            params.push(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": schema,
+                "example": segment,
+            }));
+            (format!("{{{}}}", name), vec![])
+        } else {
+            convert_templates(segment)
+        };
+        for name in names {
+            params.push(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            }));
+        }
+        segments.push(segment);
+    }
+    (server, format!("/{}", segments.join("/")), params, query)
+}
+
+fn kv_parameter(location: &str, key: &str, value: &str) -> Value {
+    json!({
+        "name": key,
+        "in": location,
+        "required": location == "path",
+        "schema": { "type": "string" },
+        "example": value,
+    })
+}
+
+// The request body of saved body, the form bodies are converted to objects
+fn export_request_body(req: &HTTPRequest) -> Option<Value> {
+    if req.body.trim().is_empty() {
+        return None;
+    }
+    let content_type = match req.content_type.as_str() {
+        "" => "text/plain",
+        value => value,
+    };
+    let media = if content_type == CONTENT_TYPE_FORM || content_type == CONTENT_TYPE_MULTIPART {
+        let params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap_or_default();
+        let mut properties = Map::new();
+        let mut example = Map::new();
+        for param in params.into_iter().filter(|item| item.enabled) {
+            if param.value.starts_with("file://") {
+                properties.insert(param.key, json!({ "type": "string", "format": "binary" }));
+                continue;
+            }
+            properties.insert(param.key.clone(), json!({ "type": "string" }));
+            example.insert(param.key, Value::String(param.value));
+        }
+        json!({
+            "schema": { "type": "object", "properties": properties },
+            "example": example,
+        })
+    } else if content_type.starts_with(CONTENT_TYPE_MULTIPART) {
+        // The raw multipart body with boundary
+        json!({ "schema": { "type": "string", "format": "binary" } })
+    } else if is_json_media_type(content_type) {
+        match serde_json::from_str::<Value>(&req.body) {
+            Ok(example) => json!({ "schema": infer_schema(&example), "example": example }),
+            // The body with templates may be not valid json, e.g. `{"n": {{n}}}`
+            Err(_) => json!({ "schema": {}, "example": req.body }),
+        }
+    } else {
+        json!({ "schema": { "type": "string" }, "example": req.body })
+    };
+    let content_type = content_type.split(';').next().unwrap_or_default().trim();
+    Some(json!({ "content": { content_type: media } }))
+}
+
+// The response of the latest recorded response
+fn export_response(resp: &HTTPResponse) -> Value {
+    let description = StatusCode::from_u16(resp.status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Response");
+    let content_type = resp
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .and_then(|(_, values)| values.first())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default();
+    let body = resp.decode_body().unwrap_or_default();
+    if content_type.is_empty() || body.is_empty() {
+        return json!({ "description": description });
+    }
+    let media = if is_json_media_type(&content_type) {
+        match serde_json::from_slice::<Value>(&body) {
+            Ok(example) => json!({ "schema": infer_schema(&example), "example": example }),
+            Err(_) => json!({ "schema": {} }),
+        }
+    } else {
+        match String::from_utf8(body) {
+            Ok(example) => json!({ "schema": { "type": "string" }, "example": example }),
+            Err(_) => json!({ "schema": { "type": "string", "format": "binary" } }),
+        }
+    };
+    json!({
+        "description": description,
+        "content": { content_type: media },
+    })
+}
+
+// The unique id of operation from the name of request, e.g. `Get pet` is `getPet`
+fn operation_id(name: &str, used: &mut BTreeSet<String>) -> String {
+    let mut id = String::new();
+    for (index, word) in name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|item| !item.is_empty())
+        .enumerate()
+    {
+        if index == 0 && word.chars().all(|c| !c.is_ascii_lowercase()) {
+            id.push_str(&word.to_ascii_lowercase());
+            continue;
+        }
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            if index == 0 {
+                id.push(first.to_ascii_lowercase());
+            } else {
+                id.push(first.to_ascii_uppercase());
+            }
+            id.push_str(chars.as_str());
+        }
+    }
+    if id.is_empty() {
+        id = "operation".to_string();
+    }
+    let mut result = id.clone();
+    let mut index = 2;
+    while used.contains(&result) {
+        result = format!("{}{}", id, index);
+        index += 1;
+    }
+    used.insert(result.clone());
+    result
+}
+
+// Convert the request to operation, the security schemes of it are added to components
+fn export_operation(
+    ctx: &TemplateContext,
+    name: &str,
+    req: &HTTPRequest,
+    components: &mut ExportComponents,
+) -> (ExportServer, String, ExportOperation) {
+    let (server, path, mut parameters, query) = split_uri(ctx, &req.uri);
+    let mut operation = ExportOperation {
+        summary: name.to_string(),
+        ..Default::default()
+    };
+    let mut add_security = |name: &str, scheme: Value| {
+        components.security_schemes.insert(name.to_string(), scheme);
+        operation.security.push(json!({ name: [] }));
+    };
+    for (key, value) in query {
+        parameters.push(kv_parameter("query", &key, &value));
+    }
+    for param in req.query.iter().filter(|item| item.enabled) {
+        parameters.push(kv_parameter("query", &param.key, &param.value));
+    }
+    for param in req.headers.iter().filter(|item| item.enabled) {
+        let key = param.key.to_lowercase();
+        if key == "content-type" {
+            continue;
+        }
+        if key == "authorization" {
+            let scheme = param
+                .value
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            if scheme == "bearer" || scheme == "basic" {
+                add_security(
+                    &format!("{}Auth", scheme),
+                    json!({ "type": "http", "scheme": scheme }),
+                );
+                continue;
+            }
+        }
+        if key == "cookie" {
+            for (key, value) in param
+                .value
+                .split(';')
+                .filter_map(|item| item.trim().split_once('='))
+            {
+                parameters.push(kv_parameter("cookie", key, value));
+            }
+            continue;
+        }
+        parameters.push(kv_parameter("header", &param.key, &param.value));
+    }
+    if req.auth.iter().any(|item| item.enabled) {
+        add_security("basicAuth", json!({ "type": "http", "scheme": "basic" }));
+    }
+    operation.parameters = parameters;
+    operation.request_body = export_request_body(req);
+    (server, path, operation)
+}
+
+// Export the folders and requests of collection as an OpenAPI 3.1 document,
+// the templates of server are resolved by the environment, it returns the count of operations
+pub async fn export(
+    collection: &str,
+    environment: Option<String>,
+    format: OpenAPIFormat,
+    file: &Path,
+) -> Result<usize, CyberAPIError> {
+    let (collection, settings) =
+        runner::list_run_settings(&RunTarget::Collection(collection.to_string())).await?;
+    let ctx = TemplateContext::load(&collection, environment).await?;
+    let info = schemas::list_api_collection()
+        .await?
+        .into_iter()
+        .find(|item| item.id == collection)
+        .map(|item| ExportInfo {
+            title: item.name.unwrap_or_default(),
+            description: item.description.unwrap_or_default(),
+            version: "1.0.0".to_string(),
+        })
+        .ok_or_else(|| new_error(format!("collection {} is not found", collection)))?;
+
+    // The name of the folder which contains the request is its tag
+    let folder_names: HashMap<String, String> = schemas::list_api_folder(collection.clone())
+        .await?
+        .into_iter()
+        .map(|item| (item.id, item.name.unwrap_or_default()))
+        .collect();
+
+    let mut doc = ExportDocument {
+        openapi: "3.1.0".to_string(),
+        info,
+        ..Default::default()
+    };
+    let mut operation_servers = vec![];
+    let mut used_ids = BTreeSet::new();
+    let mut count = 0;
+    for setting in settings.iter() {
+        let req = runner::parse_setting(setting)?;
+        let name = setting.name.clone().unwrap_or_default();
+        let (server, path, mut operation) =
+            export_operation(&ctx, &name, &req, &mut doc.components);
+        if let Some(tag) = setting
+            .parent_id
+            .as_ref()
+            .and_then(|id| folder_names.get(id))
+            .filter(|tag| !tag.is_empty())
+        {
+            operation.tags = vec![tag.clone()];
+            if !doc.tags.iter().any(|item| &item.name == tag) {
+                doc.tags.push(ExportTag { name: tag.clone() });
+            }
+        }
+        let method = req.method.to_lowercase();
+        let response = match history::get_latest(&setting.id).await? {
+            Some(detail) => detail.response,
+            // The response imported from HAR is only kept as the latest response
+            None => http_request::get_latest_response(&setting.id),
+        };
+        let operations = doc.paths.entry(path.clone()).or_default();
+        // The requests of the same path and method are merged, only their responses are added
+        if let Some(exist) = operations.get_mut(&method) {
+            if let Some(resp) = response {
+                exist
+                    .responses
+                    .entry(resp.status.to_string())
+                    .or_insert_with(|| export_response(&resp));
+            }
+            continue;
+        }
+        operation.operation_id = operation_id(&name, &mut used_ids);
+        if let Some(resp) = response {
+            operation
+                .responses
+                .insert(resp.status.to_string(), export_response(&resp));
+        }
+        if !server.url.is_empty() {
+            if !doc.servers.iter().any(|item| item.url == server.url) {
+                doc.servers.push(server.clone());
+            }
+            operation_servers.push((path, method.clone(), server));
+        }
+        operations.insert(method, operation);
+        count += 1;
+    }
+    // The operations of other servers than the first one
+    if doc.servers.len() > 1 {
+        for (path, method, server) in operation_servers {
+            if server.url == doc.servers[0].url {
+                continue;
+            }
+            if let Some(operation) = doc
+                .paths
+                .get_mut(&path)
+                .and_then(|operations| operations.get_mut(&method))
+            {
+                operation.servers = vec![server];
+            }
+        }
+    }
+
+    let buf = match format {
+        OpenAPIFormat::Json => serde_json::to_vec_pretty(&doc)?,
+        OpenAPIFormat::Yaml => serde_yaml::to_string(&doc)?.into_bytes(),
+    };
+    fs::write(file, buf)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{Engine as _, engine::general_purpose};
+
+    fn resolve(doc: Value) -> (Value, RefResolver) {
+        let file = Path::new("openapi.json");
+        let mut resolver = RefResolver::new(file, doc.clone());
+        let value = resolver.resolve(&doc, file, &mut vec![]).unwrap();
+        (value, resolver)
+    }
+
+    // Every schema refers to the next one twice
+    fn diamond(depth: usize) -> Value {
+        let mut schemas = serde_json::Map::new();
+        for i in 0..depth {
+            let next = json!({
+                "$ref": format!("#/components/schemas/S{}", i + 1),
+            });
+            schemas.insert(
+                format!("S{i}"),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "a": next,
+                        "b": next,
+                    },
+                }),
+            );
+        }
+        schemas.insert(format!("S{depth}"), json!({"type": "string"}));
+        json!({
+            "paths": {
+                "/": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": {"$ref": "#/components/schemas/S0"},
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+            "components": {"schemas": schemas},
+        })
+    }
+
+    #[test]
+    fn resolve_ref_once() {
+        let (value, resolver) = resolve(diamond(8));
+        let schema = value
+            .pointer("/paths/~1/get/responses/200/content/application~1json/schema")
+            .unwrap();
+        let leaf = (0..8).fold(schema, |schema, i| {
+            let key = if i % 2 == 0 { "a" } else { "b" };
+            &schema["properties"][key]
+        });
+        assert_eq!(&json!({"type": "string"}), leaf);
+        assert_eq!(9, resolver.resolved.len());
+        assert_eq!(
+            schema["properties"]["b"],
+            value["components"]["schemas"]["S1"]
+        );
+    }
+
+    #[test]
+    fn resolve_ref_limit() {
+        let (value, resolver) = resolve(diamond(40));
+        assert!(resolver.count < 2 * MAX_RESOLVED_VALUES);
+        let schema = value
+            .pointer("/paths/~1/get/responses/200/content/application~1json/schema")
+            .unwrap();
+        let leaf = (0..40).try_fold(schema, |schema, _| schema["properties"].get("a"));
+        assert_eq!(None, leaf);
+    }
+
+    #[test]
+    fn resolve_circular_ref() {
+        let (value, _) = resolve(json!({
+            "components": {
+                "schemas": {
+                    "Node": {
+                        "properties": {
+                            "next": {"$ref": "#/components/schemas/Node"},
+                        },
+                    },
+                    "A": {
+                        "properties": {
+                            "b": {"$ref": "#/components/schemas/B"},
+                        },
+                    },
+                    "B": {
+                        "properties": {
+                            "a": {"$ref": "#/components/schemas/A"},
+                        },
+                    },
+                    "C": {"$ref": "#/components/schemas/A"},
+                    "D": {"$ref": "#/components/schemas/B"},
+                },
+            },
+        }));
+        let schemas = &value["components"]["schemas"];
+        assert_eq!(
+            json!({"properties": {"next": {"properties": {"next": {}}}}}),
+            schemas["Node"]
+        );
+        assert_eq!(
+            json!({"properties": {"b": {"properties": {"a": {"properties": {"b": {}}}}}}}),
+            schemas["A"]
+        );
+        // The circular refs end with an empty schema within two rounds
+        for (name, keys) in [("C", ["b", "a"]), ("D", ["a", "b"])] {
+            let mut schema = &schemas[name];
+            let mut depth = 0;
+            while let Some(next) = schema["properties"].get(keys[depth % 2]) {
+                schema = next;
+                depth += 1;
+            }
+            assert_eq!(&json!({}), schema);
+            assert!((2..=4).contains(&depth));
+        }
+    }
+
+    fn kv(key: &str, value: &str) -> HTTPRequestKVParam {
+        HTTPRequestKVParam {
+            key: key.to_string(),
+            value: value.to_string(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn export_operation_parameters() {
+        let ctx = TemplateContext::default();
+        ctx.set_variable(false, "host", "https://api.example.com/");
+        let req = HTTPRequest {
+            method: "GET".to_string(),
+            uri: "{{host}}/users/42/posts/{{postId}}?page=1".to_string(),
+            query: vec![kv("size", "10")],
+            headers: vec![
+                kv("Authorization", "Bearer {{token}}"),
+                kv("Cookie", "a=1; b=2"),
+                kv("Content-Type", "application/json"),
+                kv("X-Trace", "t"),
+                HTTPRequestKVParam {
+                    enabled: false,
+                    ..kv("X-Disabled", "1")
+                },
+            ],
+            ..Default::default()
+        };
+        let mut components = ExportComponents::default();
+        let (server, path, operation) = export_operation(&ctx, "Get post", &req, &mut components);
+
+        assert_eq!("{host}", server.url);
+        assert_eq!(
+            json!({"host": {"default": "https://api.example.com"}}),
+            Value::Object(server.variables)
+        );
+        assert_eq!("/users/{id}/posts/{postId}", path);
+        assert_eq!("Get post", operation.summary);
+        assert_eq!(
+            json!([
+                {"name": "id", "in": "path", "required": true, "schema": {"type": "integer"}, "example": "42"},
+                {"name": "postId", "in": "path", "required": true, "schema": {"type": "string"}},
+                {"name": "page", "in": "query", "required": false, "schema": {"type": "string"}, "example": "1"},
+                {"name": "size", "in": "query", "required": false, "schema": {"type": "string"}, "example": "10"},
+                {"name": "a", "in": "cookie", "required": false, "schema": {"type": "string"}, "example": "1"},
+                {"name": "b", "in": "cookie", "required": false, "schema": {"type": "string"}, "example": "2"},
+                {"name": "X-Trace", "in": "header", "required": false, "schema": {"type": "string"}, "example": "t"},
+            ]),
+            Value::Array(operation.parameters)
+        );
+        assert_eq!(None, operation.request_body);
+        assert_eq!(vec![json!({"bearerAuth": []})], operation.security);
+        assert_eq!(
+            json!({"bearerAuth": {"type": "http", "scheme": "bearer"}}),
+            Value::Object(components.security_schemes)
+        );
+    }
+
+    #[test]
+    fn export_split_uri() {
+        let ctx = TemplateContext::default();
+        let uuid = "0b8e9a3c-4f6d-4c5e-9a1b-2c3d4e5f6a7b";
+        let (server, path, params, query) = split_uri(
+            &ctx,
+            &format!("http://localhost:3000/pets/{uuid}/toys/7?q=a%20b"),
+        );
+        assert_eq!("http://localhost:3000", server.url);
+        assert!(server.variables.is_empty());
+        assert_eq!("/pets/{id}/toys/{id2}", path);
+        assert_eq!(
+            json!({"type": "string", "format": "uuid"}),
+            params[0]["schema"]
+        );
+        assert_eq!(json!("id2"), params[1]["name"]);
+        assert_eq!(vec![("q".to_string(), "a b".to_string())], query);
+
+        let (server, path, params, _) = split_uri(&ctx, "/health");
+        assert_eq!("", server.url);
+        assert_eq!("/health", path);
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn export_body() {
+        let new_request = |content_type: &str, body: &str| HTTPRequest {
+            method: "POST".to_string(),
+            content_type: content_type.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(None, export_request_body(&new_request("", " ")));
+        assert_eq!(
+            Some(json!({"content": {"application/json": {
+                "schema": {"type": "object", "properties": {
+                    "id": {"type": "integer"},
+                    "price": {"type": "number"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                }},
+                "example": {"id": 1, "price": 1.5, "tags": ["a"]},
+            }}})),
+            export_request_body(&new_request(
+                "application/json; charset=utf-8",
+                r#"{"id": 1, "price": 1.5, "tags": ["a"]}"#
+            ))
+        );
+        // The body with templates is kept as it is
+        assert_eq!(
+            Some(json!({"content": {"application/json": {
+                "schema": {},
+                "example": r#"{"n": {{n}}}"#,
+            }}})),
+            export_request_body(&new_request("application/json", r#"{"n": {{n}}}"#))
+        );
+        let params = serde_json::to_string(&vec![
+            kv("name", "cat"),
+            kv("photo", "file:///tmp/cat.png"),
+            HTTPRequestKVParam {
+                enabled: false,
+                ..kv("age", "2")
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            Some(json!({"content": {"multipart/form-data": {
+                "schema": {"type": "object", "properties": {
+                    "name": {"type": "string"},
+                    "photo": {"type": "string", "format": "binary"},
+                }},
+                "example": {"name": "cat"},
+            }}})),
+            export_request_body(&new_request(CONTENT_TYPE_MULTIPART, &params))
+        );
+        assert_eq!(
+            Some(json!({"content": {"text/plain": {
+                "schema": {"type": "string"},
+                "example": "hello",
+            }}})),
+            export_request_body(&new_request("", "hello"))
+        );
+    }
+
+    #[test]
+    fn export_latest_response() {
+        let new_response = |status: u16, content_type: &str, body: &[u8]| HTTPResponse {
+            api: "api".to_string(),
+            latency: 10,
+            status,
+            headers: HashMap::from([("Content-Type".to_string(), vec![content_type.to_string()])]),
+            body: general_purpose::STANDARD.encode(body),
+            stats: Default::default(),
+            body_size: body.len() as u32,
+            assertions: vec![],
+            extractions: vec![],
+        };
+        assert_eq!(
+            json!({
+                "description": "Created",
+                "content": {"application/json": {
+                    "schema": {"type": "object", "properties": {"ok": {"type": "boolean"}}},
+                    "example": {"ok": true},
+                }},
+            }),
+            export_response(&new_response(
+                201,
+                "application/json; charset=utf-8",
+                br#"{"ok": true}"#
+            ))
+        );
+        assert_eq!(
+            json!({
+                "description": "OK",
+                "content": {"image/png": {"schema": {"type": "string", "format": "binary"}}},
+            }),
+            export_response(&new_response(200, "image/png", &[0x89, 0xff, 0x00]))
+        );
+        assert_eq!(
+            json!({"description": "No Content"}),
+            export_response(&new_response(204, "text/plain", b""))
+        );
+    }
+
+    #[test]
+    fn export_operation_id() {
+        let mut used = BTreeSet::new();
+        assert_eq!("getPet", operation_id("Get pet", &mut used));
+        assert_eq!("getPet2", operation_id("Get pet", &mut used));
+        assert_eq!("getUserById", operation_id("GET user by-id", &mut used));
+        assert_eq!("operation", operation_id("中文", &mut used));
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/openapi")
+            .join(name)
+    }
+
+    fn pairs(params: &[HTTPRequestKVParam]) -> Vec<(&str, &str, bool)> {
+        params
+            .iter()
+            .map(|item| (item.key.as_str(), item.value.as_str(), item.enabled))
+            .collect()
+    }
+
+    // The imported requests of collection by name
+    async fn list_requests(collection: &str) -> HashMap<String, (APISetting, HTTPRequest)> {
+        schemas::list_api_setting(collection.to_string())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|setting| {
+                let req = runner::parse_setting(&setting).unwrap();
+                (setting.name.clone().unwrap_or_default(), (setting, req))
+            })
+            .collect()
+    }
+
+    // The variables of environment (empty for global ones) as name and value
+    async fn list_variables(collection: &str, environment: &str) -> Vec<(String, String)> {
+        let mut result: Vec<(String, String)> = schemas::list_variable(
+            collection.to_string(),
+            VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|item| item.environment.as_deref().unwrap_or_default() == environment)
+        .map(|item| {
+            (
+                item.name.unwrap_or_default(),
+                item.value.unwrap_or_default(),
+            )
+        })
+        .collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn schema_examples() {
+        let cases = [
+            (
+                json!({"type": "string", "example": "a", "default": "b"}),
+                json!("a"),
+            ),
+            (json!({"type": "string", "default": "b"}), json!("b")),
+            (json!({"type": "string", "enum": ["x", "y"]}), json!("x")),
+            (json!({"type": ["null", "integer"], "minimum": 5}), json!(5)),
+            (json!({"type": "number"}), json!(0)),
+            (json!({"type": "boolean"}), json!(true)),
+            (
+                json!({"type": "string", "format": "date-time"}),
+                json!("2024-01-01T00:00:00Z"),
+            ),
+            (json!({"type": "string", "format": "binary"}), json!("")),
+            (json!({"items": {"type": "string"}}), json!(["string"])),
+            (json!({"type": "array"}), json!([])),
+            (
+                json!({"properties": {"id": {"type": "integer"}, "name": {"type": "string"}}}),
+                json!({"id": 0, "name": "string"}),
+            ),
+            (
+                json!({"allOf": [
+                    {"properties": {"id": {"type": "integer"}}},
+                    {"properties": {"name": {"const": "tom"}}},
+                ]}),
+                json!({"id": 0, "name": "tom"}),
+            ),
+            (
+                json!({"anyOf": [{"type": "boolean"}, {"type": "string"}]}),
+                json!(true),
+            ),
+            (json!({}), Value::Null),
+        ];
+        for (schema, expected) in cases {
+            assert_eq!(expected, schema_example(&schema, 0), "{}", schema);
+        }
+
+        // The deep schema is cut
+        let mut schema = json!({"type": "string"});
+        for _ in 0..=MAX_EXAMPLE_DEPTH {
+            schema = json!({"type": "array", "items": schema});
+        }
+        let mut example = schema_example(&schema, 0);
+        let mut depth = 0;
+        while let Value::Array(mut items) = example {
+            example = items.remove(0);
+            depth += 1;
+        }
+        assert_eq!(MAX_EXAMPLE_DEPTH + 1, depth);
+        assert_eq!(Value::Null, example);
+    }
+
+    #[test]
+    fn import_openapi_fixture() {
+        schemas::run_with_database(async {
+            let collection = "openapi-import-petstore";
+            let file = fixture("petstore.yaml");
+            let result = import(collection, &file, "", OpenAPIImportOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(4, result.created);
+            assert_eq!(0, result.updated);
+            assert_eq!(2, result.folders);
+            assert_eq!(2, result.environments);
+            // Two folders of tags and the request without tag
+            assert_eq!(3, result.top.len());
+
+            let folders: HashMap<String, String> = schemas::list_api_folder(collection.to_string())
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|item| (item.id, item.name.unwrap_or_default()))
+                .collect();
+            let requests = list_requests(collection).await;
+            let parent = |name: &str| {
+                let parent_id = requests[name].0.parent_id.clone().unwrap_or_default();
+                folders.get(&parent_id).cloned().unwrap_or_default()
+            };
+
+            let (_, req) = &requests["List pets"];
+            assert_eq!("pets", parent("List pets"));
+            assert_eq!("GET", req.method);
+            assert_eq!("{{host}}/pets", req.uri);
+            // The params of operation override the ones of path item
+            assert_eq!(
+                vec![
+                    ("limit", "1", true),
+                    ("status", "available", true),
+                    ("sort", "string", true),
+                    ("debug", "", false),
+                ],
+                pairs(&req.query)
+            );
+            assert_eq!(
+                vec![
+                    ("X-Request-Id", "3fa85f64-5717-4562-b3fc-2c963f66afa6", true),
+                    ("Cookie", "session=s1", true),
+                    ("Authorization", "Bearer {{token}}", true),
+                ],
+                pairs(&req.headers)
+            );
+            assert!(req.body.is_empty());
+
+            // The schema of body is in the external file
+            let (_, req) = &requests["createPet"];
+            assert_eq!("pets", parent("createPet"));
+            assert_eq!("POST", req.method);
+            assert_eq!(CONTENT_TYPE_JSON, req.content_type);
+            assert_eq!(
+                json!({
+                    "id": 0,
+                    "name": "doggie",
+                    "birthday": "2024-01-01",
+                    "tags": [{"name": "string"}],
+                    "owner": {"name": "tom", "email": "user@example.com"},
+                    "kind": "dog",
+                }),
+                serde_json::from_str::<Value>(&req.body).unwrap()
+            );
+
+            let name = "PUT /pets/{petId}/photo";
+            let (_, req) = &requests[name];
+            assert_eq!("photos", parent(name));
+            assert_eq!("{{host}}/pets/{{petId}}/photo", req.uri);
+            assert_eq!(CONTENT_TYPE_MULTIPART, req.content_type);
+            let mut params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap();
+            params.sort_by(|a, b| a.key.cmp(&b.key));
+            assert_eq!(
+                vec![("caption", "cute", true), ("file", "file://", true)],
+                pairs(&params)
+            );
+            // The security of operation overrides the one of document
+            assert_eq!(vec![("api_key", "{{apiKey}}", true)], pairs(&req.query));
+            assert!(req.headers.is_empty());
+
+            let (setting, req) = &requests["GET /health"];
+            assert_eq!(None, setting.parent_id.clone().filter(|id| !id.is_empty()));
+            assert!(req.headers.is_empty() && req.query.is_empty() && req.auth.is_empty());
+
+            // Each server is an environment, the first one is enabled
+            let mut environments: Vec<(String, String)> =
+                schemas::list_environment(collection.to_string())
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|item| {
+                        (
+                            item.name.unwrap_or_default(),
+                            item.enabled.unwrap_or_default(),
+                        )
+                    })
+                    .collect();
+            environments.sort();
+            assert_eq!(
+                vec![
+                    ("Production".to_string(), ENVIRONMENT_ENABLED.to_string()),
+                    (
+                        "http://localhost:3000".to_string(),
+                        ENVIRONMENT_DISABLED.to_string()
+                    ),
+                ],
+                environments
+            );
+            let environment = |name: &str| {
+                let name = name.to_string();
+                async move {
+                    schemas::list_environment(collection.to_string())
+                        .await
+                        .unwrap()
+                        .into_iter()
+                        .find(|item| item.name.as_deref() == Some(name.as_str()))
+                        .unwrap()
+                        .id
+                }
+            };
+            assert_eq!(
+                vec![
+                    (
+                        "host".to_string(),
+                        "https://us.petstore.example.com/v1".to_string()
+                    ),
+                    ("region".to_string(), "us".to_string()),
+                ],
+                list_variables(collection, &environment("Production").await).await
+            );
+            assert_eq!(
+                vec![("host".to_string(), "http://localhost:3000".to_string())],
+                list_variables(collection, &environment("http://localhost:3000").await).await
+            );
+            // The variables of auth are global
+            assert_eq!(
+                vec![
+                    ("apiKey".to_string(), "".to_string()),
+                    ("token".to_string(), "".to_string()),
+                ],
+                list_variables(collection, "").await
+            );
+        });
+    }
+
+    #[test]
+    fn import_swagger_fixture() {
+        schemas::run_with_database(async {
+            let collection = "openapi-import-swagger";
+            let file = fixture("swagger.json");
+            let result = import(collection, &file, "", OpenAPIImportOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(3, result.created);
+            assert_eq!(0, result.folders);
+            assert_eq!(1, result.environments);
+            let requests = list_requests(collection).await;
+
+            let (_, req) = &requests["Create order"];
+            assert_eq!("{{host}}/orders", req.uri);
+            assert_eq!(CONTENT_TYPE_JSON, req.content_type);
+            assert_eq!(
+                json!({"id": 1, "paid": true, "items": ["string"]}),
+                serde_json::from_str::<Value>(&req.body).unwrap()
+            );
+            assert_eq!(
+                vec![("{{username}}", "{{password}}", true)],
+                pairs(&req.auth)
+            );
+
+            // The form of document consumes
+            let (_, req) = &requests["Login"];
+            assert_eq!(CONTENT_TYPE_FORM, req.content_type);
+            let params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap();
+            assert_eq!(
+                vec![("username", "string", true), ("remember", "false", true)],
+                pairs(&params)
+            );
+
+            // The form with file is multipart
+            let (_, req) = &requests["Upload avatar"];
+            assert_eq!(CONTENT_TYPE_MULTIPART, req.content_type);
+            let params: Vec<HTTPRequestKVParam> = serde_json::from_str(&req.body).unwrap();
+            assert_eq!(
+                vec![("file", "file://", true), ("name", "me", true)],
+                pairs(&params)
+            );
+
+            let environment = schemas::list_environment(collection.to_string())
+                .await
+                .unwrap()
+                .remove(0);
+            assert_eq!(
+                Some("http://store.example.com/api/".to_string()),
+                environment.name
+            );
+            assert_eq!(
+                vec![(
+                    "host".to_string(),
+                    "http://store.example.com/api".to_string()
+                )],
+                list_variables(collection, &environment.id).await
+            );
+            assert_eq!(
+                vec![
+                    ("password".to_string(), "".to_string()),
+                    ("username".to_string(), "".to_string()),
+                ],
+                list_variables(collection, "").await
+            );
+        });
+    }
+
+    #[test]
+    fn import_update_merge() {
+        schemas::run_with_database(async {
+            let collection = "openapi-import-update";
+            let file = fixture("petstore.yaml");
+            import(collection, &file, "", OpenAPIImportOptions::default())
+                .await
+                .unwrap();
+
+            // The values edited by user
+            let mut requests = list_requests(collection).await;
+            let (mut setting, mut req) = requests.remove("List pets").unwrap();
+            req.query[0].value = "20".to_string();
+            req.headers.retain(|item| item.key != "Authorization");
+            req.assertions = serde_json::from_value(json!([
+                {"category": "statusEquals", "value": 200},
+            ]))
+            .unwrap();
+            setting.setting = Some(serde_json::to_string(&req).unwrap());
+            schemas::update_api_setting(setting).await.unwrap();
+            let (mut setting, mut req) = requests.remove("createPet").unwrap();
+            req.body = r#"{"name": "cat"}"#.to_string();
+            setting.setting = Some(serde_json::to_string(&req).unwrap());
+            schemas::update_api_setting(setting).await.unwrap();
+
+            let result = import(collection, &file, "", OpenAPIImportOptions { update: true })
+                .await
+                .unwrap();
+            assert_eq!(0, result.created);
+            assert_eq!(4, result.updated);
+            assert_eq!(0, result.folders);
+            assert_eq!(0, result.environments);
+            assert!(result.top.is_empty());
+
+            let requests = list_requests(collection).await;
+            assert_eq!(4, requests.len());
+            let (_, req) = &requests["List pets"];
+            assert_eq!(("limit", "20", true), pairs(&req.query)[0]);
+            assert_eq!(1, req.assertions.len());
+            // The removed header of document is added again
+            assert_eq!(
+                Some("Bearer {{token}}"),
+                req.headers
+                    .iter()
+                    .find(|item| item.key == "Authorization")
+                    .map(|item| item.value.as_str())
+            );
+            let (_, req) = &requests["createPet"];
+            assert_eq!(r#"{"name": "cat"}"#, req.body);
+            assert_eq!(
+                2,
+                schemas::list_api_folder(collection.to_string())
+                    .await
+                    .unwrap()
+                    .len()
+            );
+        });
+    }
+
+    #[test]
+    fn export_history_response() {
+        schemas::run_with_database(async {
+            let collection = "openapi-export-history";
+            schemas::add_api_collection(schemas::APICollection {
+                id: collection.to_string(),
+                name: Some("History".to_string()),
+                description: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+            let req = HTTPRequest {
+                method: "POST".to_string(),
+                uri: "http://localhost/users".to_string(),
+                ..Default::default()
+            };
+            let setting = schemas::add_api_setting(APISetting {
+                id: "openapi-export-history-s1".to_string(),
+                collection: collection.to_string(),
+                name: Some("Create user".to_string()),
+                category: Some("http".to_string()),
+                setting: Some(serde_json::to_string(&req).unwrap()),
+                parent_id: None,
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+            let body = br#"{"id": 1}"#;
+            let resp = HTTPResponse {
+                api: setting.id.clone(),
+                latency: 10,
+                status: 201,
+                headers: HashMap::from([(
+                    "content-type".to_string(),
+                    vec![CONTENT_TYPE_JSON.to_string()],
+                )]),
+                body: general_purpose::STANDARD.encode(body),
+                stats: Default::default(),
+                body_size: body.len() as u32,
+                assertions: vec![],
+                extractions: vec![],
+            };
+            history::record(collection, &setting.id, &req, &Ok(resp))
+                .await
+                .unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join("openapi.json");
+            let count = export(collection, None, OpenAPIFormat::Json, &file)
+                .await
+                .unwrap();
+            assert_eq!(1, count);
+            let doc: Value = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+            assert_eq!(
+                json!({"id": 1}),
+                doc["paths"]["/users"]["post"]["responses"]["201"]["content"][CONTENT_TYPE_JSON]["example"]
+            );
+        });
+    }
+}
@@ -0,0 +1,1575 @@
+use crate::error::CyberAPIError;
+use crate::http_request::{
+    CONTENT_TYPE_FORM, CONTENT_TYPE_MULTIPART, HTTPRequest, HTTPRequestKVParam,
+};
+use crate::mock_server::MockResponse;
+use crate::runner;
+use crate::schemas::{
+    self, API_TREE_ROOT, APIFolder, APISetting, ENVIRONMENT_ENABLED, Environment,
+    VARIABLE_CATEGORY_ENVIRONMENT, VARIABLE_ENABLED, Variable,
+};
+use hyper::StatusCode;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+const POSTMAN_ERROR_CATEGORY: &str = "postman";
+const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+const CONTENT_TYPE_JSON: &str = "application/json";
+const FILE_PROTOCOL: &str = "file://";
+const VARIABLE_DISABLED: &str = "0";
+const ENVIRONMENT_DISABLED: &str = "0";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanKV {
+    key: String,
+    // The value may be null or a number
+    #[serde(skip_serializing_if = "Value::is_null")]
+    value: Value,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disabled: bool,
+    #[serde(rename = "type", skip_serializing_if = "String::is_empty")]
+    kv_type: String,
+    // The file of form data
+    #[serde(skip_serializing_if = "Value::is_null")]
+    src: Value,
+}
+
+impl PostmanKV {
+    fn new(key: &str, value: &str) -> PostmanKV {
+        PostmanKV {
+            key: key.to_string(),
+            value: Value::String(value.to_string()),
+            ..Default::default()
+        }
+    }
+    fn value_string(&self) -> String {
+        value_string(&self.value)
+    }
+    fn to_param(&self) -> HTTPRequestKVParam {
+        HTTPRequestKVParam {
+            key: self.key.clone(),
+            value: self.value_string(),
+            enabled: !self.disabled,
+        }
+    }
+}
+
+impl From<&HTTPRequestKVParam> for PostmanKV {
+    fn from(param: &HTTPRequestKVParam) -> Self {
+        PostmanKV {
+            disabled: !param.enabled,
+            ..PostmanKV::new(&param.key, &param.value)
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanAuth {
+    #[serde(rename = "type")]
+    auth_type: String,
+    // The params of auth type, e.g. `"basic": [{"key": "username", "value": "bob"}]`
+    #[serde(flatten)]
+    params: Map<String, Value>,
+}
+
+impl PostmanAuth {
+    fn new(auth_type: &str, params: Vec<PostmanKV>) -> PostmanAuth {
+        let mut map = Map::new();
+        map.insert(auth_type.to_string(), json!(params));
+        PostmanAuth {
+            auth_type: auth_type.to_string(),
+            params: map,
+        }
+    }
+    // The params are a list of v2.1 or an object of v2.0
+    fn get(&self, key: &str) -> String {
+        match self.params.get(&self.auth_type) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .find(|item| item.get("key").and_then(Value::as_str) == Some(key))
+                .and_then(|item| item.get("value"))
+                .map(value_string)
+                .unwrap_or_default(),
+            Some(Value::Object(map)) => map.get(key).map(value_string).unwrap_or_default(),
+            _ => "".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanUrlObject {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    raw: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    protocol: String,
+    // The host and path may be a string or a list
+    #[serde(skip_serializing_if = "Value::is_null")]
+    host: Value,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    port: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    path: Value,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    query: Vec<PostmanKV>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    variable: Vec<PostmanKV>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+enum PostmanUrl {
+    Raw(String),
+    Object(PostmanUrlObject),
+}
+
+impl Default for PostmanUrl {
+    fn default() -> Self {
+        PostmanUrl::Raw("".to_string())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanBody {
+    mode: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    raw: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    urlencoded: Vec<PostmanKV>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    formdata: Vec<PostmanKV>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    graphql: Value,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    options: Value,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disabled: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanRequest {
+    method: String,
+    header: Vec<PostmanKV>,
+    url: PostmanUrl,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<PostmanBody>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<PostmanAuth>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanResponse {
+    name: String,
+    status: String,
+    code: u16,
+    // The header may be null or a string
+    #[serde(skip_serializing_if = "Value::is_null")]
+    header: Value,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    body: Value,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanEvent {
+    listen: String,
+    script: Value,
+}
+
+impl PostmanEvent {
+    fn has_script(&self) -> bool {
+        match self.script.get("exec") {
+            Some(Value::String(value)) => !value.trim().is_empty(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .any(|item| !value_string(item).trim().is_empty()),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanItem {
+    name: String,
+    // The item is a folder if it has sub items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<Vec<PostmanItem>>,
+    // The request may be a url string
+    #[serde(skip_serializing_if = "Value::is_null")]
+    request: Value,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    response: Vec<PostmanResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<PostmanAuth>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    event: Vec<PostmanEvent>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanInfo {
+    #[serde(rename = "_postman_id", skip_serializing_if = "String::is_empty")]
+    postman_id: String,
+    name: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    description: Value,
+    schema: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanCollection {
+    info: PostmanInfo,
+    item: Vec<PostmanItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<PostmanAuth>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    event: Vec<PostmanEvent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    variable: Vec<PostmanKV>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+struct PostmanEnvironmentValue {
+    key: String,
+    value: Value,
+    enabled: bool,
+    #[serde(rename = "type", skip_serializing_if = "String::is_empty")]
+    value_type: String,
+}
+
+impl Default for PostmanEnvironmentValue {
+    fn default() -> Self {
+        PostmanEnvironmentValue {
+            key: "".to_string(),
+            value: Value::Null,
+            enabled: true,
+            value_type: "".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PostmanEnvironment {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    id: String,
+    name: String,
+    values: Vec<PostmanEnvironmentValue>,
+    #[serde(rename = "_postman_variable_scope")]
+    scope: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PostmanImportResult {
+    pub folders: usize,
+    pub settings: usize,
+    pub variables: usize,
+    pub environments: usize,
+    // Ids of the new folders and requests which are not in any folder
+    pub top: Vec<String>,
+    // The parts of Postman which are not supported, e.g. scripts
+    pub unsupported: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PostmanExportResult {
+    pub settings: usize,
+    pub environments: usize,
+    // The parts of the app which can not be exported, e.g. assertions
+    pub unsupported: Vec<String>,
+}
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(POSTMAN_ERROR_CATEGORY, message)
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(value) => value.clone(),
+        _ => value.to_string(),
+    }
+}
+
+// The parts of host or path, e.g. `["api", "example", "com"]`
+fn url_parts(value: &Value, separator: &str) -> String {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                // The segment of path may be an object of path variable
+                Value::Object(map) => map.get("value").map(value_string).unwrap_or_default(),
+                _ => value_string(item),
+            })
+            .collect::<Vec<_>>()
+            .join(separator),
+        _ => value_string(value),
+    }
+}
+
+// The path variables, e.g. `:id`, are replaced by their values or `{{id}}`
+fn replace_path_variables(url: &str, variables: &[PostmanKV]) -> String {
+    let (url, query) = match url.split_once('?') {
+        Some((url, query)) => (url, format!("?{}", query)),
+        None => (url, "".to_string()),
+    };
+    let path = url
+        .split('/')
+        .map(|segment| {
+            let Some(name) = segment.strip_prefix(':').filter(|name| !name.is_empty()) else {
+                return segment.to_string();
+            };
+            match variables.iter().find(|item| item.key == name) {
+                Some(item) if !item.value_string().is_empty() => item.value_string(),
+                _ => format!("{{{{{}}}}}", name),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{}{}", path, query)
+}
+
+// The uri without query and the params of query
+fn convert_url(url: &PostmanUrl) -> (String, Vec<HTTPRequestKVParam>) {
+    let url = match url {
+        PostmanUrl::Raw(raw) => PostmanUrlObject {
+            raw: raw.clone(),
+            ..Default::default()
+        },
+        PostmanUrl::Object(url) => url.clone(),
+    };
+    let mut raw = url.raw.clone();
+    if raw.is_empty() {
+        if !url.protocol.is_empty() {
+            raw = format!("{}://", url.protocol);
+        }
+        raw.push_str(&url_parts(&url.host, "."));
+        if !url.port.is_empty() {
+            raw.push_str(&format!(":{}", url.port));
+        }
+        let path = url_parts(&url.path, "/");
+        if !path.is_empty() {
+            raw.push_str(&format!("/{}", path));
+        }
+    }
+    let raw = replace_path_variables(&raw, &url.variable);
+    let (uri, raw_query) = raw.split_once('?').unwrap_or((&raw, ""));
+    let query = if url.query.is_empty() {
+        raw_query
+            .split('&')
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                let (key, value) = item.split_once('=').unwrap_or((item, ""));
+                HTTPRequestKVParam {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    enabled: true,
+                }
+            })
+            .collect()
+    } else {
+        url.query.iter().map(PostmanKV::to_param).collect()
+    };
+    (uri.to_string(), query)
+}
+
+// The content type of raw body by the language option
+fn raw_content_type(body: &PostmanBody) -> String {
+    let language = body
+        .options
+        .pointer("/raw/language")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    match language {
+        "json" => CONTENT_TYPE_JSON,
+        "xml" => "application/xml",
+        "html" => "text/html",
+        "javascript" => "application/javascript",
+        _ => "text/plain",
+    }
+    .to_string()
+}
+
+fn set_body(req: &mut HTTPRequest, body: &PostmanBody, name: &str, unsupported: &mut Vec<String>) {
+    if body.disabled {
+        return;
+    }
+    match body.mode.as_str() {
+        "raw" => {
+            if req.content_type.is_empty() {
+                req.content_type = raw_content_type(body);
+            }
+            req.body = body.raw.clone();
+        }
+        "urlencoded" => {
+            req.content_type = CONTENT_TYPE_FORM.to_string();
+            let params: Vec<HTTPRequestKVParam> =
+                body.urlencoded.iter().map(PostmanKV::to_param).collect();
+            req.body = serde_json::to_string(&params).unwrap_or_default();
+        }
+        "formdata" => {
+            req.content_type = CONTENT_TYPE_MULTIPART.to_string();
+            let params: Vec<HTTPRequestKVParam> = body
+                .formdata
+                .iter()
+                .map(|item| {
+                    let mut param = item.to_param();
+                    if item.kv_type == "file" {
+                        let src = match &item.src {
+                            Value::Array(items) => {
+                                items.first().map(value_string).unwrap_or_default()
+                            }
+                            value => value_string(value),
+                        };
+                        param.value = format!("{}{}", FILE_PROTOCOL, src);
+                    }
+                    param
+                })
+                .collect();
+            req.body = serde_json::to_string(&params).unwrap_or_default();
+        }
+        "graphql" => {
+            let variables = body
+                .graphql
+                .get("variables")
+                .map(value_string)
+                .unwrap_or_default();
+            let variables: Value = serde_json::from_str(&variables).unwrap_or(Value::Null);
+            req.content_type = CONTENT_TYPE_JSON.to_string();
+            req.body = serde_json::to_string_pretty(&json!({
+                "query": body.graphql.get("query").map(value_string).unwrap_or_default(),
+                "variables": variables,
+            }))
+            .unwrap_or_default();
+        }
+        "" => {}
+        mode => unsupported.push(format!("{} body of {}", mode, name)),
+    }
+}
+
+// Basic auth is the auth of request, the others are converted to headers or query
+fn set_auth(req: &mut HTTPRequest, auth: &PostmanAuth, name: &str, unsupported: &mut Vec<String>) {
+    let add_header = |req: &mut HTTPRequest, key: &str, value: String| {
+        req.headers.push(HTTPRequestKVParam {
+            key: key.to_string(),
+            value,
+            enabled: true,
+        });
+    };
+    match auth.auth_type.as_str() {
+        "noauth" | "" => {}
+        "basic" => {
+            req.auth = vec![HTTPRequestKVParam {
+                key: auth.get("username"),
+                value: auth.get("password"),
+                enabled: true,
+            }];
+        }
+        "bearer" => add_header(
+            req,
+            "Authorization",
+            format!("Bearer {}", auth.get("token")),
+        ),
+        "apikey" => {
+            let key = match auth.get("key") {
+                value if value.is_empty() => "X-API-Key".to_string(),
+                value => value,
+            };
+            if auth.get("in") == "query" {
+                req.query.push(HTTPRequestKVParam {
+                    key,
+                    value: auth.get("value"),
+                    enabled: true,
+                });
+            } else {
+                add_header(req, &key, auth.get("value"));
+            }
+        }
+        // The access token is sent as bearer token, it is not refreshed
+        "oauth2" if !auth.get("accessToken").is_empty() => add_header(
+            req,
+            "Authorization",
+            format!("Bearer {}", auth.get("accessToken")),
+        ),
+        auth_type => unsupported.push(format!("{} auth of {}", auth_type, name)),
+    }
+}
+
+fn report_scripts(events: &[PostmanEvent], name: &str, unsupported: &mut Vec<String>) {
+    for event in events.iter().filter(|item| item.has_script()) {
+        unsupported.push(format!("{} script of {}", event.listen, name));
+    }
+}
+
+fn convert_request(
+    item: &PostmanItem,
+    inherited_auth: Option<&PostmanAuth>,
+    unsupported: &mut Vec<String>,
+) -> Result<HTTPRequest, CyberAPIError> {
+    let request: PostmanRequest = match &item.request {
+        Value::String(url) => PostmanRequest {
+            url: PostmanUrl::Raw(url.clone()),
+            ..Default::default()
+        },
+        value => serde_json::from_value(value.clone())?,
+    };
+    let (uri, query) = convert_url(&request.url);
+    let mut req = HTTPRequest {
+        method: match request.method.to_uppercase() {
+            method if method.is_empty() => "GET".to_string(),
+            method => method,
+        },
+        uri,
+        query,
+        ..Default::default()
+    };
+    for header in request.header.iter() {
+        // The content type is saved as the field of request
+        if header.key.eq_ignore_ascii_case("content-type") && !header.disabled {
+            req.content_type = header.value_string();
+            continue;
+        }
+        req.headers.push(header.to_param());
+    }
+    if let Some(body) = &request.body {
+        set_body(&mut req, body, &item.name, unsupported);
+    }
+    // The request without auth inherits the auth of its parent
+    if let Some(auth) = request.auth.as_ref().or(inherited_auth) {
+        set_auth(&mut req, auth, &item.name, unsupported);
+    }
+    // The first example response is served by the mock server
+    if let Some(example) = item.response.first() {
+        let mut mock = MockResponse {
+            status: if example.code == 0 { 200 } else { example.code },
+            body: value_string(&example.body),
+            ..Default::default()
+        };
+        if let Value::Array(headers) = &example.header {
+            for header in headers {
+                let header: PostmanKV = serde_json::from_value(header.clone()).unwrap_or_default();
+                if header.key.eq_ignore_ascii_case("content-type") {
+                    mock.content_type = header.value_string();
+                } else if !header.key.is_empty() {
+                    mock.headers.push(header.to_param());
+                }
+            }
+        }
+        req.mock = Some(mock);
+        if item.response.len() > 1 {
+            unsupported.push(format!("examples except the first one of {}", item.name));
+        }
+    }
+    Ok(req)
+}
+
+struct ImportContext {
+    collection: String,
+    result: PostmanImportResult,
+}
+
+// Create the folders and requests of items in the parent folder, returns the ids of them
+async fn import_items<C: ConnectionTrait>(
+    db: &C,
+    ctx: &mut ImportContext,
+    items: &[PostmanItem],
+    parent_id: Option<String>,
+    inherited_auth: Option<&PostmanAuth>,
+) -> Result<Vec<String>, CyberAPIError> {
+    let mut ids = vec![];
+    for item in items {
+        report_scripts(&item.event, &item.name, &mut ctx.result.unsupported);
+        if let Some(sub_items) = &item.item {
+            let folder = schemas::add_api_folder_with(
+                db,
+                APIFolder {
+                    id: ulid::Ulid::new().to_string(),
+                    collection: ctx.collection.clone(),
+                    children: None,
+                    name: Some(item.name.clone()),
+                    parent_id: parent_id.clone(),
+                    sort_order: None,
+                    created_at: None,
+                    updated_at: None,
+                },
+            )
+            .await?;
+            ctx.result.folders += 1;
+            let auth = item.auth.as_ref().or(inherited_auth);
+            Box::pin(import_items(
+                db,
+                ctx,
+                sub_items,
+                Some(folder.id.clone()),
+                auth,
+            ))
+            .await?;
+            ids.push(folder.id);
+            continue;
+        }
+        let req = convert_request(item, inherited_auth, &mut ctx.result.unsupported)?;
+        let setting = schemas::add_api_setting_with(
+            db,
+            APISetting {
+                id: ulid::Ulid::new().to_string(),
+                collection: ctx.collection.clone(),
+                name: Some(item.name.clone()),
+                category: Some("http".to_string()),
+                setting: Some(serde_json::to_string(&req)?),
+                parent_id: parent_id.clone(),
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            },
+        )
+        .await?;
+        ctx.result.settings += 1;
+        ids.push(setting.id);
+    }
+    Ok(ids)
+}
+
+// Create or update the variables of environment, the global variables' environment is empty
+async fn save_variables<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+    environment: &str,
+    values: Vec<(String, String, bool)>,
+) -> Result<usize, CyberAPIError> {
+    let mut exists: HashMap<String, Variable> = schemas::list_variable_with(
+        db,
+        collection.to_string(),
+        VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+    )
+    .await?
+    .into_iter()
+    .filter(|item| item.environment.as_deref().unwrap_or_default() == environment)
+    .filter_map(|item| Some((item.name.clone()?, item)))
+    .collect();
+    let mut count = 0;
+    for (name, value, enabled) in values {
+        let enabled = if enabled {
+            VARIABLE_ENABLED
+        } else {
+            VARIABLE_DISABLED
+        }
+        .to_string();
+        match exists.remove(&name) {
+            Some(variable) => {
+                schemas::update_variable_with(
+                    db,
+                    Variable {
+                        value: Some(value),
+                        enabled: Some(enabled),
+                        updated_at: None,
+                        ..variable
+                    },
+                )
+                .await?;
+            }
+            None => {
+                schemas::add_variable_with(
+                    db,
+                    Variable {
+                        id: ulid::Ulid::new().to_string(),
+                        category: VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+                        collection: collection.to_string(),
+                        environment: Some(environment.to_string()),
+                        name: Some(name),
+                        value: Some(value),
+                        enabled: Some(enabled),
+                        secret: None,
+                        created_at: None,
+                        updated_at: None,
+                    },
+                )
+                .await?;
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+async fn import_environment<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+    data: PostmanEnvironment,
+) -> Result<PostmanImportResult, CyberAPIError> {
+    let environments = schemas::list_environment_with(db, collection.to_string()).await?;
+    let id = match environments
+        .iter()
+        .find(|item| item.name.as_deref() == Some(data.name.as_str()))
+    {
+        Some(environment) => environment.id.clone(),
+        None => {
+            let has_enabled = environments
+                .iter()
+                .any(|item| item.enabled.as_deref() == Some(ENVIRONMENT_ENABLED));
+            let environment = schemas::add_environment_with(
+                db,
+                Environment {
+                    id: ulid::Ulid::new().to_string(),
+                    collection: collection.to_string(),
+                    name: Some(data.name.clone()),
+                    enabled: Some(
+                        if has_enabled {
+                            ENVIRONMENT_DISABLED
+                        } else {
+                            ENVIRONMENT_ENABLED
+                        }
+                        .to_string(),
+                    ),
+                    created_at: None,
+                    updated_at: None,
+                },
+            )
+            .await?;
+            environment.id
+        }
+    };
+    let values = data
+        .values
+        .iter()
+        .map(|item| (item.key.clone(), value_string(&item.value), item.enabled))
+        .collect();
+    Ok(PostmanImportResult {
+        environments: 1,
+        variables: save_variables(db, collection, &id, values).await?,
+        ..Default::default()
+    })
+}
+
+// Import the Postman collection or environment of v2.1, the collection variables are global variables.
+// Nothing is saved if any item fails.
+pub async fn import(collection: &str, content: &str) -> Result<PostmanImportResult, CyberAPIError> {
+    let value: Value = serde_json::from_str(content)?;
+    let is_environment = value.get("values").is_some() && value.get("item").is_none();
+    if !is_environment && value.get("item").is_none() {
+        return Err(new_error(
+            "it is not a Postman collection or environment".to_string(),
+        ));
+    }
+    let db = schemas::get_database().await;
+    let txn = db.begin().await?;
+    if is_environment {
+        let result = import_environment(&txn, collection, serde_json::from_value(value)?).await?;
+        txn.commit().await?;
+        return Ok(result);
+    }
+    let data: PostmanCollection = serde_json::from_value(value)?;
+    let mut ctx = ImportContext {
+        collection: collection.to_string(),
+        result: PostmanImportResult::default(),
+    };
+    report_scripts(&data.event, &data.info.name, &mut ctx.result.unsupported);
+    ctx.result.top = import_items(&txn, &mut ctx, &data.item, None, data.auth.as_ref()).await?;
+    let values = data
+        .variable
+        .iter()
+        .filter(|item| !item.key.is_empty())
+        .map(|item| (item.key.clone(), item.value_string(), !item.disabled))
+        .collect();
+    ctx.result.variables = save_variables(&txn, collection, "", values).await?;
+    txn.commit().await?;
+    Ok(ctx.result)
+}
+
+fn export_url(req: &HTTPRequest) -> PostmanUrl {
+    let query: Vec<PostmanKV> = req.query.iter().map(PostmanKV::from).collect();
+    let mut raw = req.uri.clone();
+    let enabled_query: Vec<String> = req
+        .query
+        .iter()
+        .filter(|item| item.enabled)
+        .map(|item| format!("{}={}", item.key, item.value))
+        .collect();
+    if !enabled_query.is_empty() {
+        raw.push(if raw.contains('?') { '&' } else { '?' });
+        raw.push_str(&enabled_query.join("&"));
+    }
+    let (protocol, rest) = match req.uri.split_once("://") {
+        Some((protocol, rest)) => (protocol.to_string(), rest),
+        None => ("".to_string(), req.uri.as_str()),
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (host, port) = match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => (host, port.to_string()),
+        _ => (host, "".to_string()),
+    };
+    let path: Vec<&str> = path.split('/').filter(|item| !item.is_empty()).collect();
+    PostmanUrl::Object(PostmanUrlObject {
+        raw,
+        protocol,
+        host: json!(host.split('.').collect::<Vec<_>>()),
+        port,
+        path: json!(path),
+        query,
+        variable: vec![],
+    })
+}
+
+fn export_body(req: &HTTPRequest, header: &mut Vec<PostmanKV>) -> Option<PostmanBody> {
+    if req.body.is_empty() {
+        return None;
+    }
+    let params =
+        || -> Vec<HTTPRequestKVParam> { serde_json::from_str(&req.body).unwrap_or_default() };
+    let body = match req.content_type.as_str() {
+        CONTENT_TYPE_FORM => PostmanBody {
+            mode: "urlencoded".to_string(),
+            urlencoded: params().iter().map(PostmanKV::from).collect(),
+            ..Default::default()
+        },
+        CONTENT_TYPE_MULTIPART => PostmanBody {
+            mode: "formdata".to_string(),
+            formdata: params()
+                .iter()
+                .map(|item| match item.value.strip_prefix(FILE_PROTOCOL) {
+                    Some(src) => PostmanKV {
+                        key: item.key.clone(),
+                        disabled: !item.enabled,
+                        kv_type: "file".to_string(),
+                        src: Value::String(src.to_string()),
+                        ..Default::default()
+                    },
+                    None => PostmanKV {
+                        kv_type: "text".to_string(),
+                        ..PostmanKV::from(item)
+                    },
+                })
+                .collect(),
+            ..Default::default()
+        },
+        content_type => {
+            let language = match content_type.split(';').next().unwrap_or_default().trim() {
+                CONTENT_TYPE_JSON => "json",
+                "application/xml" | "text/xml" => "xml",
+                "text/html" => "html",
+                "application/javascript" => "javascript",
+                _ => "text",
+            };
+            // The content type of json is set by the language option
+            if language != "json" && !content_type.is_empty() {
+                header.push(PostmanKV::new("Content-Type", content_type));
+            }
+            PostmanBody {
+                mode: "raw".to_string(),
+                raw: req.body.clone(),
+                options: json!({ "raw": { "language": language } }),
+                ..Default::default()
+            }
+        }
+    };
+    Some(body)
+}
+
+fn export_request(
+    name: &str,
+    req: &HTTPRequest,
+    unsupported: &mut Vec<String>,
+) -> Result<PostmanItem, CyberAPIError> {
+    let mut header = vec![];
+    let mut auth = req.auth.iter().find(|item| item.enabled).map(|basic| {
+        PostmanAuth::new(
+            "basic",
+            vec![
+                PostmanKV {
+                    kv_type: "string".to_string(),
+                    ..PostmanKV::new("username", &basic.key)
+                },
+                PostmanKV {
+                    kv_type: "string".to_string(),
+                    ..PostmanKV::new("password", &basic.value)
+                },
+            ],
+        )
+    });
+    for param in req.headers.iter() {
+        // The bearer token is the auth of Postman, it is kept as header if basic auth is set
+        if param.enabled && param.key.eq_ignore_ascii_case("authorization") && auth.is_none() {
+            if let Some(token) = param.value.strip_prefix("Bearer ") {
+                auth = Some(PostmanAuth::new(
+                    "bearer",
+                    vec![PostmanKV {
+                        kv_type: "string".to_string(),
+                        ..PostmanKV::new("token", token.trim())
+                    }],
+                ));
+                continue;
+            }
+        }
+        header.push(PostmanKV::from(param));
+    }
+    let body = export_body(req, &mut header);
+    if !req.assertions.is_empty() {
+        unsupported.push(format!("assertions of {}", name));
+    }
+    if !req.extractors.is_empty() {
+        unsupported.push(format!("extractors of {}", name));
+    }
+    let response = match &req.mock {
+        Some(mock) => {
+            let mut header: Vec<PostmanKV> = mock.headers.iter().map(PostmanKV::from).collect();
+            if !mock.content_type.is_empty() {
+                header.push(PostmanKV::new("Content-Type", &mock.content_type));
+            }
+            vec![PostmanResponse {
+                name: name.to_string(),
+                status: StatusCode::from_u16(mock.status)
+                    .ok()
+                    .and_then(|status| status.canonical_reason())
+                    .unwrap_or_default()
+                    .to_string(),
+                code: mock.status,
+                header: json!(header),
+                body: Value::String(mock.body.clone()),
+            }]
+        }
+        None => vec![],
+    };
+    let request = PostmanRequest {
+        method: req.method.clone(),
+        header,
+        url: export_url(req),
+        body,
+        auth,
+    };
+    Ok(PostmanItem {
+        name: name.to_string(),
+        request: serde_json::to_value(request)?,
+        response,
+        ..Default::default()
+    })
+}
+
+struct ExportTree {
+    folders: HashMap<String, APIFolder>,
+    settings: HashMap<String, APISetting>,
+    children: HashMap<String, Vec<String>>,
+}
+
+fn export_items(
+    ids: &[String],
+    tree: &ExportTree,
+    visited: &mut HashSet<String>,
+    result: &mut PostmanExportResult,
+) -> Result<Vec<PostmanItem>, CyberAPIError> {
+    let mut items = vec![];
+    for id in ids {
+        // Avoid endless loop of invalid children
+        if !visited.insert(id.clone()) {
+            continue;
+        }
+        if let Some(folder) = tree.folders.get(id) {
+            let children = tree.children.get(id).map(Vec::as_slice).unwrap_or_default();
+            items.push(PostmanItem {
+                name: folder.name.clone().unwrap_or_default(),
+                item: Some(export_items(children, tree, visited, result)?),
+                ..Default::default()
+            });
+        } else if let Some(setting) = tree.settings.get(id) {
+            let name = setting.name.clone().unwrap_or_default();
+            let req = runner::parse_setting(setting)?;
+            items.push(export_request(&name, &req, &mut result.unsupported)?);
+            result.settings += 1;
+        }
+    }
+    Ok(items)
+}
+
+fn export_values(variables: &[Variable], environment: &str) -> Vec<(String, String, bool)> {
+    variables
+        .iter()
+        .filter(|item| item.environment.as_deref().unwrap_or_default() == environment)
+        .filter_map(|item| {
+            Some((
+                item.name.clone().filter(|name| !name.is_empty())?,
+                // The secret values are not exported
+                item.value
+                    .clone()
+                    .filter(|_| !item.is_secret())
+                    .unwrap_or_default(),
+                item.enabled.as_deref() == Some(VARIABLE_ENABLED),
+            ))
+        })
+        .collect()
+}
+
+// Export the collection as a Postman collection of v2.1, the environments are exported
+// beside it as `<name>.<environment>.postman_environment.json`
+pub async fn export(collection: &str, file: &Path) -> Result<PostmanExportResult, CyberAPIError> {
+    let info = schemas::list_api_collection()
+        .await?
+        .into_iter()
+        .find(|item| item.id == collection)
+        .ok_or_else(|| new_error(format!("collection {} is not found", collection)))?;
+    let tree = ExportTree {
+        folders: schemas::list_api_folder(collection.to_string())
+            .await?
+            .into_iter()
+            .map(|item| (item.id.clone(), item))
+            .collect(),
+        settings: schemas::list_api_setting(collection.to_string())
+            .await?
+            .into_iter()
+            .map(|item| (item.id.clone(), item))
+            .collect(),
+        children: schemas::list_api_children(collection.to_string()).await?,
+    };
+    let mut result = PostmanExportResult::default();
+    let top = tree
+        .children
+        .get(API_TREE_ROOT)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let items = export_items(top, &tree, &mut HashSet::new(), &mut result)?;
+
+    let variables = schemas::list_variable(
+        collection.to_string(),
+        VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+    )
+    .await?;
+    let data = PostmanCollection {
+        info: PostmanInfo {
+            postman_id: info.id.clone(),
+            name: info.name.clone().unwrap_or_default(),
+            description: info
+                .description
+                .clone()
+                .filter(|value| !value.is_empty())
+                .map(Value::String)
+                .unwrap_or_default(),
+            schema: POSTMAN_SCHEMA.to_string(),
+        },
+        item: items,
+        variable: export_values(&variables, "")
+            .into_iter()
+            .map(|(key, value, enabled)| PostmanKV {
+                disabled: !enabled,
+                ..PostmanKV::new(&key, &value)
+            })
+            .collect(),
+        ..Default::default()
+    };
+    fs::write(file, serde_json::to_vec_pretty(&data)?)?;
+
+    let stem = file
+        .file_stem()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = stem.trim_end_matches(".postman_collection");
+    for environment in schemas::list_environment(collection.to_string()).await? {
+        let name = environment.name.clone().unwrap_or_default();
+        let data = PostmanEnvironment {
+            id: environment.id.clone(),
+            name: name.clone(),
+            values: export_values(&variables, &environment.id)
+                .into_iter()
+                .map(|(key, value, enabled)| PostmanEnvironmentValue {
+                    key,
+                    value: Value::String(value),
+                    enabled,
+                    value_type: "default".to_string(),
+                })
+                .collect(),
+            scope: "environment".to_string(),
+        };
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        fs::write(
+            file.with_file_name(format!("{}.{}.postman_environment.json", stem, name)),
+            serde_json::to_vec_pretty(&data)?,
+        )?;
+        result.environments += 1;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(item: Value) -> (Value, Vec<String>) {
+        let item: PostmanItem = serde_json::from_value(item).unwrap();
+        let mut unsupported = vec![];
+        let req = convert_request(&item, None, &mut unsupported).unwrap();
+        (serde_json::to_value(req).unwrap(), unsupported)
+    }
+
+    fn kv(key: &str, value: &str) -> Value {
+        json!({"key": key, "value": value, "enabled": true})
+    }
+
+    #[test]
+    fn convert_postman_url() {
+        let (uri, query) = convert_url(&PostmanUrl::Raw(
+            "{{host}}/users/:id/posts/:postId?page=1&q".to_string(),
+        ));
+        assert_eq!("{{host}}/users/{{id}}/posts/{{postId}}", uri);
+        assert_eq!(
+            json!([kv("page", "1"), kv("q", "")]),
+            serde_json::to_value(query).unwrap()
+        );
+
+        let url: PostmanUrl = serde_json::from_value(json!({
+            "protocol": "https",
+            "host": ["api", "example", "com"],
+            "port": "8443",
+            "path": ["users", ":id"],
+            "query": [
+                {"key": "size", "value": 10},
+                {"key": "debug", "value": null, "disabled": true},
+            ],
+            "variable": [{"key": "id", "value": "42"}],
+        }))
+        .unwrap();
+        let (uri, query) = convert_url(&url);
+        assert_eq!("https://api.example.com:8443/users/42", uri);
+        assert_eq!(
+            json!([
+                kv("size", "10"),
+                {"key": "debug", "value": "", "enabled": false},
+            ]),
+            serde_json::to_value(query).unwrap()
+        );
+    }
+
+    #[test]
+    fn convert_postman_body() {
+        let (req, _) = convert(json!({
+            "name": "raw",
+            "request": {
+                "method": "post",
+                "url": "http://localhost/users",
+                "body": {
+                    "mode": "raw",
+                    "raw": "{\"name\": \"cat\"}",
+                    "options": {"raw": {"language": "json"}},
+                },
+            },
+        }));
+        assert_eq!("POST", req["method"]);
+        assert_eq!("application/json", req["contentType"]);
+        assert_eq!("{\"name\": \"cat\"}", req["body"]);
+
+        // The content type of header takes precedence over the language
+        let (req, _) = convert(json!({
+            "name": "xml",
+            "request": {
+                "method": "PUT",
+                "url": "http://localhost/users",
+                "header": [{"key": "Content-Type", "value": "text/xml"}],
+                "body": {"mode": "raw", "raw": "<a/>", "options": {"raw": {"language": "xml"}}},
+            },
+        }));
+        assert_eq!("text/xml", req["contentType"]);
+        assert_eq!(json!([]), req["headers"]);
+
+        let (req, _) = convert(json!({
+            "name": "form",
+            "request": {
+                "method": "POST",
+                "url": "http://localhost/upload",
+                "body": {
+                    "mode": "formdata",
+                    "formdata": [
+                        {"key": "name", "value": "cat", "type": "text"},
+                        {"key": "photo", "src": ["/tmp/cat.png"], "type": "file"},
+                        {"key": "age", "value": "2", "type": "text", "disabled": true},
+                    ],
+                },
+            },
+        }));
+        assert_eq!(CONTENT_TYPE_MULTIPART, req["contentType"]);
+        assert_eq!(
+            json!([
+                kv("name", "cat"),
+                kv("photo", "file:///tmp/cat.png"),
+                {"key": "age", "value": "2", "enabled": false},
+            ]),
+            serde_json::from_str::<Value>(req["body"].as_str().unwrap()).unwrap()
+        );
+
+        let (req, _) = convert(json!({
+            "name": "urlencoded",
+            "request": {
+                "method": "POST",
+                "url": "http://localhost/login",
+                "body": {"mode": "urlencoded", "urlencoded": [{"key": "user", "value": "bob"}]},
+            },
+        }));
+        assert_eq!(CONTENT_TYPE_FORM, req["contentType"]);
+        assert_eq!(
+            json!([kv("user", "bob")]),
+            serde_json::from_str::<Value>(req["body"].as_str().unwrap()).unwrap()
+        );
+
+        let (req, unsupported) = convert(json!({
+            "name": "graphql",
+            "request": {
+                "method": "POST",
+                "url": "http://localhost/graphql",
+                "body": {
+                    "mode": "graphql",
+                    "graphql": {"query": "{ me { id } }", "variables": "{\"a\": 1}"},
+                },
+            },
+        }));
+        assert_eq!("application/json", req["contentType"]);
+        assert_eq!(
+            json!({"query": "{ me { id } }", "variables": {"a": 1}}),
+            serde_json::from_str::<Value>(req["body"].as_str().unwrap()).unwrap()
+        );
+        assert!(unsupported.is_empty());
+
+        let (_, unsupported) = convert(json!({
+            "name": "file",
+            "request": {"url": "http://localhost", "body": {"mode": "file", "file": {}}},
+        }));
+        assert_eq!(vec!["file body of file".to_string()], unsupported);
+    }
+
+    #[test]
+    fn convert_postman_auth() {
+        let check = |auth: Value, headers: Value, query: Value, auth_params: Value| {
+            let auth: PostmanAuth = serde_json::from_value(auth).unwrap();
+            let mut req = HTTPRequest::default();
+            let mut unsupported = vec![];
+            set_auth(&mut req, &auth, "api", &mut unsupported);
+            assert!(unsupported.is_empty());
+            assert_eq!(headers, serde_json::to_value(&req.headers).unwrap());
+            assert_eq!(query, serde_json::to_value(&req.query).unwrap());
+            assert_eq!(auth_params, serde_json::to_value(&req.auth).unwrap());
+        };
+        check(
+            json!({"type": "basic", "basic": [
+                {"key": "username", "value": "bob"},
+                {"key": "password", "value": "secret"},
+            ]}),
+            json!([]),
+            json!([]),
+            json!([kv("bob", "secret")]),
+        );
+        // The params of v2.0 are an object
+        check(
+            json!({"type": "bearer", "bearer": {"token": "{{token}}"}}),
+            json!([kv("Authorization", "Bearer {{token}}")]),
+            json!([]),
+            json!([]),
+        );
+        check(
+            json!({"type": "apikey", "apikey": [
+                {"key": "key", "value": "api_key"},
+                {"key": "value", "value": "abc"},
+                {"key": "in", "value": "query"},
+            ]}),
+            json!([]),
+            json!([kv("api_key", "abc")]),
+            json!([]),
+        );
+        check(
+            json!({"type": "apikey", "apikey": [{"key": "value", "value": "abc"}]}),
+            json!([kv("X-API-Key", "abc")]),
+            json!([]),
+            json!([]),
+        );
+        check(
+            json!({"type": "oauth2", "oauth2": [{"key": "accessToken", "value": "t"}]}),
+            json!([kv("Authorization", "Bearer t")]),
+            json!([]),
+            json!([]),
+        );
+
+        let auth: PostmanAuth =
+            serde_json::from_value(json!({"type": "digest", "digest": []})).unwrap();
+        let mut unsupported = vec![];
+        set_auth(&mut HTTPRequest::default(), &auth, "api", &mut unsupported);
+        assert_eq!(vec!["digest auth of api".to_string()], unsupported);
+    }
+
+    #[test]
+    fn convert_postman_request() {
+        let item: PostmanItem = serde_json::from_value(json!({
+            "name": "Get user",
+            "request": {
+                "method": "GET",
+                "url": "http://localhost/users/1",
+                "header": [{"key": "X-Trace", "value": "t", "disabled": true}],
+            },
+            "response": [
+                {
+                    "name": "ok",
+                    "code": 201,
+                    "header": [
+                        {"key": "Content-Type", "value": "application/json"},
+                        {"key": "X-Id", "value": "1"},
+                    ],
+                    "body": "{\"id\": 1}",
+                },
+                {"name": "not found", "code": 404},
+            ],
+            "event": [{"listen": "test", "script": {"exec": ["pm.test()"]}}],
+        }))
+        .unwrap();
+        let inherited = PostmanAuth::new("bearer", vec![PostmanKV::new("token", "t")]);
+        let mut unsupported = vec![];
+        report_scripts(&item.event, &item.name, &mut unsupported);
+        let req = convert_request(&item, Some(&inherited), &mut unsupported).unwrap();
+        assert_eq!(
+            json!([
+                {"key": "X-Trace", "value": "t", "enabled": false},
+                kv("Authorization", "Bearer t"),
+            ]),
+            serde_json::to_value(&req.headers).unwrap()
+        );
+        let mock = req.mock.unwrap();
+        assert_eq!(201, mock.status);
+        assert_eq!("application/json", mock.content_type);
+        assert_eq!("{\"id\": 1}", mock.body);
+        assert_eq!(
+            json!([kv("X-Id", "1")]),
+            serde_json::to_value(&mock.headers).unwrap()
+        );
+        assert_eq!(
+            vec![
+                "test script of Get user".to_string(),
+                "examples except the first one of Get user".to_string(),
+            ],
+            unsupported
+        );
+
+        // The url string is a get request
+        let (req, _) = convert(json!({"name": "url", "request": "http://localhost/?a=1"}));
+        assert_eq!("GET", req["method"]);
+        assert_eq!("http://localhost/", req["uri"]);
+        assert_eq!(json!([kv("a", "1")]), req["query"]);
+    }
+
+    #[test]
+    fn export_postman_request() {
+        let req: HTTPRequest = serde_json::from_value(json!({
+            "method": "POST",
+            "uri": "https://api.example.com:8443/users",
+            "contentType": "application/xml",
+            "body": "<a/>",
+            "headers": [kv("Authorization", "Bearer abc"), kv("X-Trace", "t")],
+            "query": [kv("page", "1"), {"key": "debug", "value": "1", "enabled": false}],
+            "auth": [kv("bob", "secret")],
+            "assertions": [{"category": "statusEquals", "value": 200}],
+            "mock": {"status": 404, "contentType": "text/plain", "body": "not found"},
+        }))
+        .unwrap();
+        let mut unsupported = vec![];
+        let item = export_request("Create user", &req, &mut unsupported).unwrap();
+        assert_eq!(vec!["assertions of Create user".to_string()], unsupported);
+        assert_eq!(
+            json!({
+                "method": "POST",
+                "header": [
+                    {"key": "Authorization", "value": "Bearer abc"},
+                    {"key": "X-Trace", "value": "t"},
+                    {"key": "Content-Type", "value": "application/xml"},
+                ],
+                "url": {
+                    "raw": "https://api.example.com:8443/users?page=1",
+                    "protocol": "https",
+                    "host": ["api", "example", "com"],
+                    "port": "8443",
+                    "path": ["users"],
+                    "query": [
+                        {"key": "page", "value": "1"},
+                        {"key": "debug", "value": "1", "disabled": true},
+                    ],
+                },
+                "body": {"mode": "raw", "raw": "<a/>", "options": {"raw": {"language": "xml"}}},
+                // The basic auth takes precedence over the bearer token, which is kept as header
+                "auth": {"type": "basic", "basic": [
+                    {"key": "username", "value": "bob", "type": "string"},
+                    {"key": "password", "value": "secret", "type": "string"},
+                ]},
+            }),
+            item.request
+        );
+        assert_eq!(
+            json!([{
+                "name": "Create user",
+                "status": "Not Found",
+                "code": 404,
+                "header": [{"key": "Content-Type", "value": "text/plain"}],
+                "body": "not found",
+            }]),
+            serde_json::to_value(&item.response).unwrap()
+        );
+
+        // The bearer token is the auth if there is no basic auth
+        let req: HTTPRequest = serde_json::from_value(json!({
+            "method": "GET",
+            "uri": "https://api.example.com/users",
+            "headers": [kv("Authorization", "Bearer abc"), kv("X-Trace", "t")],
+        }))
+        .unwrap();
+        let item = export_request("List users", &req, &mut vec![]).unwrap();
+        assert_eq!(
+            json!([{"key": "X-Trace", "value": "t"}]),
+            item.request["header"]
+        );
+        assert_eq!(
+            json!({"type": "bearer", "bearer": [{"key": "token", "value": "abc", "type": "string"}]}),
+            item.request["auth"]
+        );
+    }
+
+    #[test]
+    fn import_postman_atomic() {
+        schemas::run_with_database(async {
+            let collection = "postman-import-atomic";
+            let data = |request: Value| {
+                json!({
+                    "info": {"name": "Atomic", "schema": POSTMAN_SCHEMA},
+                    "item": [{
+                        "name": "Users",
+                        "item": [
+                            {"name": "List users", "request": "https://example.com/users"},
+                            {"name": "Create user", "request": request},
+                        ],
+                    }],
+                    "variable": [{"key": "host", "value": "https://example.com"}],
+                })
+                .to_string()
+            };
+            let count = || async {
+                let folders = schemas::list_api_folder(collection.to_string())
+                    .await
+                    .unwrap();
+                let settings = schemas::list_api_setting(collection.to_string())
+                    .await
+                    .unwrap();
+                let variables = schemas::list_variable(
+                    collection.to_string(),
+                    VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+                )
+                .await
+                .unwrap();
+                (folders.len(), settings.len(), variables.len())
+            };
+
+            // The invalid request fails the import, nothing is saved
+            let result = import(collection, &data(json!({"method": "POST", "header": "x"}))).await;
+            assert!(result.is_err());
+            assert_eq!((0, 0, 0), count().await);
+
+            let result = import(
+                collection,
+                &data(json!({"method": "POST", "url": "https://example.com/users"})),
+            )
+            .await
+            .unwrap();
+            assert_eq!(1, result.folders);
+            assert_eq!(2, result.settings);
+            assert_eq!(1, result.variables);
+            assert_eq!((1, 2, 1), count().await);
+
+            let environment = json!({
+                "name": "dev",
+                "values": [{"key": "token", "value": "t", "enabled": true}],
+            });
+            let result = import(collection, &environment.to_string()).await.unwrap();
+            assert_eq!(1, result.environments);
+            assert_eq!(1, result.variables);
+            assert_eq!((1, 2, 2), count().await);
+        });
+    }
+
+    #[test]
+    fn export_postman_form() {
+        let body = serde_json::to_string(&json!([
+            kv("name", "cat"),
+            kv("photo", "file:///tmp/cat.png"),
+        ]))
+        .unwrap();
+        let req = HTTPRequest {
+            method: "POST".to_string(),
+            uri: "http://localhost/upload".to_string(),
+            content_type: CONTENT_TYPE_MULTIPART.to_string(),
+            body,
+            ..Default::default()
+        };
+        let mut unsupported = vec![];
+        let item = export_request("Upload", &req, &mut unsupported).unwrap();
+        assert_eq!(
+            json!({
+                "mode": "formdata",
+                "formdata": [
+                    {"key": "name", "value": "cat", "type": "text"},
+                    {"key": "photo", "type": "file", "src": "/tmp/cat.png"},
+                ],
+            }),
+            item.request["body"]
+        );
+        // The exported request is imported as the same one
+        let mut unsupported = vec![];
+        let imported = convert_request(&item, None, &mut unsupported).unwrap();
+        assert_eq!(req.uri, imported.uri);
+        assert_eq!(req.content_type, imported.content_type);
+        assert_eq!(
+            serde_json::from_str::<Value>(&req.body).unwrap(),
+            serde_json::from_str::<Value>(&imported.body).unwrap()
+        );
+    }
+
+    #[test]
+    fn export_postman_values() {
+        let new_variable = |environment: &str, name: &str, secret: Option<&str>| Variable {
+            id: name.to_string(),
+            category: VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+            collection: "collection".to_string(),
+            environment: Some(environment.to_string()),
+            name: Some(name.to_string()),
+            value: Some(format!("{name}-value")),
+            enabled: Some(VARIABLE_ENABLED.to_string()),
+            secret: secret.map(|value| value.to_string()),
+            created_at: None,
+            updated_at: None,
+        };
+        let variables = vec![
+            new_variable("", "host", None),
+            new_variable("", "token", Some("1")),
+            new_variable("", "", None),
+            new_variable("dev", "user", None),
+        ];
+        assert_eq!(
+            vec![
+                ("host".to_string(), "host-value".to_string(), true),
+                ("token".to_string(), "".to_string(), true),
+            ],
+            export_values(&variables, "")
+        );
+        assert_eq!(
+            vec![("user".to_string(), "user-value".to_string(), true)],
+            export_values(&variables, "dev")
+        );
+    }
+}
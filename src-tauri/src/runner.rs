@@ -0,0 +1,1108 @@
+use crate::assertion::AssertionResult;
+use crate::error::CyberAPIError;
+use crate::extractor::{self, ExtractionResult};
+use crate::http_request::{
+    self, CONTENT_TYPE_FORM, CONTENT_TYPE_MULTIPART, HTTPRequest, HTTPRequestKVParam,
+    RequestTimeout,
+};
+use crate::json_path;
+use crate::schemas::{
+    self, API_TREE_ROOT, APISetting, VARIABLE_CATEGORY_GLOBAL_REQ_HEADERS, VARIABLE_ENABLED,
+};
+use crate::template::TemplateContext;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::form_urlencoded;
+
+const RUNNER_ERROR_CATEGORY: &str = "runner";
+const FILE_PROTOCOL: &str = "file://";
+
+// What to run: a whole collection, a folder (including sub folders) or a single request
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "category", content = "id")]
+pub enum RunTarget {
+    Collection(String),
+    Folder(String),
+    Setting(String),
+}
+
+// The variables and headers shared by all requests of a run
+pub struct RunnerContext {
+    pub collection: String,
+    pub template: TemplateContext,
+    global_headers: Vec<HTTPRequestKVParam>,
+}
+
+impl RunnerContext {
+    pub async fn load(
+        collection: &str,
+        environment: Option<String>,
+    ) -> Result<RunnerContext, CyberAPIError> {
+        let template = TemplateContext::load(collection, environment).await?;
+        let global_headers = schemas::list_decrypted_variable(
+            collection.to_string(),
+            VARIABLE_CATEGORY_GLOBAL_REQ_HEADERS.to_string(),
+        )
+        .await?
+        .into_iter()
+        .filter(|item| item.enabled.as_deref() == Some(VARIABLE_ENABLED))
+        .filter_map(|item| {
+            let key = item.name.filter(|value| !value.is_empty())?;
+            Some(HTTPRequestKVParam {
+                key,
+                value: item.value.unwrap_or_default(),
+                enabled: true,
+            })
+        })
+        .collect();
+        Ok(RunnerContext {
+            collection: collection.to_string(),
+            template,
+            global_headers,
+        })
+    }
+
+    fn with_data(&self, data: HashMap<String, String>) -> RunnerContext {
+        RunnerContext {
+            collection: self.collection.clone(),
+            template: self.template.with_data(data),
+            global_headers: self.global_headers.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunResult {
+    pub id: String,
+    // Starts from 1
+    pub iteration: u32,
+    pub name: String,
+    pub method: String,
+    pub uri: String,
+    pub status: u16,
+    pub latency: u32,
+    pub passed: bool,
+    pub error: Option<String>,
+    pub assertions: Vec<AssertionResult>,
+    pub extractions: Vec<ExtractionResult>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReport {
+    // Id of the run, it is also sent with the progress events
+    pub id: String,
+    pub collection: String,
+    pub environment: Option<String>,
+    pub started_at: String,
+    pub duration: u32,
+    pub iterations: u32,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<RunResult>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunProgress {
+    pub id: String,
+    pub completed: usize,
+    pub total: usize,
+    pub result: RunResult,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunOptions {
+    // The enabled environment is used if it is None
+    pub environment: Option<String>,
+    // It is the row count of data file by default, otherwise 1
+    pub iterations: Option<u32>,
+    // CSV with header or JSON array of objects, each row binds the variables of an iteration
+    pub data_file: Option<String>,
+    // Delay between requests in milliseconds
+    pub delay: u64,
+    pub stop_on_failure: bool,
+    // Count of iterations run at the same time, the requests of an iteration are always run in order
+    pub concurrency: u32,
+    pub timeout: RequestTimeout,
+}
+
+impl RunReport {
+    fn new(
+        id: String,
+        ctx: &RunnerContext,
+        started_at: String,
+        duration: u32,
+        iterations: u32,
+        results: Vec<RunResult>,
+    ) -> Self {
+        let passed = results.iter().filter(|item| item.passed).count();
+        RunReport {
+            id,
+            collection: ctx.collection.clone(),
+            environment: ctx.template.environment.clone(),
+            started_at,
+            duration,
+            iterations,
+            total: results.len(),
+            passed,
+            failed: results.len() - passed,
+            results,
+        }
+    }
+}
+
+// Append the settings of children in order, sub folders are expanded in place
+fn flatten_children(
+    children: &[String],
+    tree: &HashMap<String, Vec<String>>,
+    settings: &mut HashMap<String, APISetting>,
+    visited: &mut HashSet<String>,
+    result: &mut Vec<APISetting>,
+) {
+    for id in children {
+        // Avoid endless loop of invalid children
+        if !visited.insert(id.clone()) {
+            continue;
+        }
+        if let Some(setting) = settings.remove(id) {
+            result.push(setting);
+        } else if let Some(sub_children) = tree.get(id) {
+            flatten_children(sub_children, tree, settings, visited, result);
+        }
+    }
+}
+
+// List the settings of target in the order of the api tree, returns the collection and settings
+pub async fn list_run_settings(
+    target: &RunTarget,
+) -> Result<(String, Vec<APISetting>), CyberAPIError> {
+    let not_found = |category: &str, id: &str| {
+        CyberAPIError::new(
+            RUNNER_ERROR_CATEGORY,
+            format!("{} {} is not found", category, id),
+        )
+    };
+    let collection = match target {
+        RunTarget::Collection(id) => id.clone(),
+        RunTarget::Folder(id) => {
+            schemas::get_api_folder(id.clone())
+                .await?
+                .ok_or_else(|| not_found("folder", id))?
+                .collection
+        }
+        RunTarget::Setting(id) => {
+            let setting = schemas::get_api_setting(id.clone())
+                .await?
+                .ok_or_else(|| not_found("request", id))?;
+            return Ok((setting.collection.clone(), vec![setting]));
+        }
+    };
+
+    let tree = schemas::list_api_children(collection.clone()).await?;
+    let mut settings: HashMap<String, APISetting> = schemas::list_api_setting(collection.clone())
+        .await?
+        .into_iter()
+        .map(|item| (item.id.clone(), item))
+        .collect();
+
+    let children = match target {
+        RunTarget::Folder(id) => vec![id.clone()],
+        _ => tree.get(API_TREE_ROOT).cloned().unwrap_or_default(),
+    };
+    let mut result = vec![];
+    flatten_children(
+        &children,
+        &tree,
+        &mut settings,
+        &mut HashSet::new(),
+        &mut result,
+    );
+    Ok((collection, result))
+}
+
+fn is_json(value: &str) -> bool {
+    let value = value.trim();
+    (value.starts_with('{') && value.ends_with('}'))
+        || (value.starts_with('[') && value.ends_with(']'))
+}
+
+fn encode_form(ctx: &TemplateContext, body: &str) -> Result<String, CyberAPIError> {
+    let params: Vec<HTTPRequestKVParam> = serde_json::from_str(body)?;
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for item in params.iter().filter(|item| item.enabled) {
+        serializer.append_pair(&item.key, &ctx.render(&item.value)?);
+    }
+    Ok(serializer.finish())
+}
+
+// The quote, CR and LF of the field and file names are percent-encoded like browsers do,
+// so they can not break the Content-Disposition header
+pub(crate) fn escape_disposition_param(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+// Encode the multipart form, `file://` values are read from local files.
+// Returns the content type with boundary and the base64 encoded body.
+fn encode_multipart(ctx: &TemplateContext, body: &str) -> Result<(String, String), CyberAPIError> {
+    let params: Vec<HTTPRequestKVParam> = serde_json::from_str(body)?;
+    let boundary = format!("----CyberAPIFormBoundary{:016x}", rand::random::<u64>());
+    let mut buf = Vec::new();
+    for item in params
+        .iter()
+        .filter(|item| item.enabled && !item.key.is_empty())
+    {
+        buf.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        let value = ctx.render(&item.value)?;
+        if let Some(file) = value.strip_prefix(FILE_PROTOCOL) {
+            let filename = Path::new(file)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            buf.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                    escape_disposition_param(&item.key),
+                    escape_disposition_param(&filename)
+                )
+                .as_bytes(),
+            );
+            buf.extend_from_slice(&fs::read(file)?);
+        } else {
+            buf.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"\r\n\r\n{}",
+                    escape_disposition_param(&item.key),
+                    value
+                )
+                .as_bytes(),
+            );
+        }
+        buf.extend_from_slice(b"\r\n");
+    }
+    buf.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok((
+        format!("{}; boundary={}", CONTENT_TYPE_MULTIPART, boundary),
+        general_purpose::STANDARD.encode(buf),
+    ))
+}
+
+fn render_params(
+    ctx: &TemplateContext,
+    params: Vec<HTTPRequestKVParam>,
+) -> Result<Vec<HTTPRequestKVParam>, CyberAPIError> {
+    params
+        .into_iter()
+        .filter(|item| item.enabled)
+        .map(|item| {
+            Ok(HTTPRequestKVParam {
+                value: ctx.render(&item.value)?,
+                ..item
+            })
+        })
+        .collect()
+}
+
+// The global headers are added unless the request has an enabled header of the same key
+fn add_global_headers(ctx: &RunnerContext, headers: &mut Vec<HTTPRequestKVParam>) {
+    for item in ctx.global_headers.iter() {
+        if !headers
+            .iter()
+            .any(|header| header.enabled && header.key == item.key)
+        {
+            headers.push(item.clone());
+        }
+    }
+}
+
+// The first enabled auth is sent as the basic authorization
+fn basic_auth_header(
+    ctx: &TemplateContext,
+    auth: Vec<HTTPRequestKVParam>,
+) -> Result<Option<HTTPRequestKVParam>, CyberAPIError> {
+    let Some(auth) = auth.into_iter().find(|item| item.enabled) else {
+        return Ok(None);
+    };
+    let value = format!("{}:{}", ctx.render(&auth.key)?, ctx.render(&auth.value)?);
+    Ok(Some(HTTPRequestKVParam {
+        key: "Authorization".to_string(),
+        value: format!("Basic {}", general_purpose::STANDARD.encode(value)),
+        enabled: true,
+    }))
+}
+
+// Render the values of form encoded by the app, it is not changed if there is no placeholder
+fn render_encoded_form(ctx: &TemplateContext, body: &str) -> Result<String, CyberAPIError> {
+    let mut changed = false;
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for (key, value) in form_urlencoded::parse(body.as_bytes()) {
+        let rendered = ctx.render(&value)?;
+        changed |= rendered != value;
+        serializer.append_pair(&key, &rendered);
+    }
+    if !changed {
+        return Ok(body.to_string());
+    }
+    Ok(serializer.finish())
+}
+
+fn user_agent() -> String {
+    format!(
+        "CyberAPI/{} ({}; {})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+// Convert the saved setting to the request sent by `http_request::request`,
+// it does the same as `doHTTPRequest` of the app.
+pub fn resolve_request(
+    ctx: &RunnerContext,
+    req: HTTPRequest,
+) -> Result<HTTPRequest, CyberAPIError> {
+    let template = &ctx.template;
+    let method = if req.method.is_empty() {
+        "GET".to_string()
+    } else {
+        req.method.to_uppercase()
+    };
+    let (mut body, mut content_type) = if ["POST", "PATCH", "PUT"].contains(&method.as_str()) {
+        (req.body.clone(), req.content_type.clone())
+    } else {
+        ("".to_string(), "".to_string())
+    };
+    // Remove the comments of json
+    if is_json(&body) {
+        body = body
+            .lines()
+            .filter(|line| !line.trim().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    if !body.is_empty() {
+        if content_type == CONTENT_TYPE_FORM {
+            body = encode_form(template, &body)?;
+        } else if content_type == CONTENT_TYPE_MULTIPART {
+            (content_type, body) = encode_multipart(template, &body)?;
+        } else {
+            body = template.render(&body)?;
+        }
+    }
+
+    let mut headers = req.headers;
+    add_global_headers(ctx, &mut headers);
+    let mut headers = render_params(template, headers)?;
+    headers.push(HTTPRequestKVParam {
+        key: "User-Agent".to_string(),
+        value: user_agent(),
+        enabled: true,
+    });
+    headers.extend(basic_auth_header(template, req.auth)?);
+
+    Ok(HTTPRequest {
+        method,
+        uri: template.render(&req.uri)?,
+        body,
+        content_type,
+        headers,
+        query: render_params(template, req.query)?,
+        assertions: req.assertions,
+        extractors: req.extractors,
+        ..Default::default()
+    })
+}
+
+// Resolve the request sent by the app, the placeholders left by the app are rendered here.
+// The app does not fill the secret variables and the global headers of secrets,
+// so they are only decrypted by the backend. The multipart body is encoded by the app,
+// so it is sent as it is.
+pub fn resolve_app_request(
+    ctx: &RunnerContext,
+    req: HTTPRequest,
+) -> Result<HTTPRequest, CyberAPIError> {
+    let template = &ctx.template;
+    let body = if req.content_type == CONTENT_TYPE_FORM {
+        render_encoded_form(template, &req.body)?
+    } else if req.content_type.starts_with(CONTENT_TYPE_MULTIPART) {
+        req.body
+    } else {
+        template.render(&req.body)?
+    };
+    let mut headers = req.headers;
+    add_global_headers(ctx, &mut headers);
+    let mut headers = render_params(template, headers)?;
+    headers.extend(basic_auth_header(template, req.auth)?);
+    Ok(HTTPRequest {
+        uri: template.render(&req.uri)?,
+        body,
+        headers,
+        query: render_params(template, req.query)?,
+        auth: vec![],
+        ..req
+    })
+}
+
+pub fn parse_setting(setting: &APISetting) -> Result<HTTPRequest, CyberAPIError> {
+    let value = setting.setting.as_deref().unwrap_or_default();
+    if value.trim().is_empty() {
+        return Ok(HTTPRequest::default());
+    }
+    let req = serde_json::from_str(value)?;
+    Ok(req)
+}
+
+// Send the request of setting, a request with assertions is passed if all assertions are passed,
+// otherwise it is passed if its status is less than 400
+pub async fn run_setting(
+    ctx: &RunnerContext,
+    setting: &APISetting,
+    timeout: RequestTimeout,
+) -> RunResult {
+    let mut result = RunResult {
+        id: setting.id.clone(),
+        iteration: 1,
+        name: setting.name.clone().unwrap_or_default(),
+        method: "".to_string(),
+        uri: "".to_string(),
+        status: 0,
+        latency: 0,
+        passed: false,
+        error: None,
+        assertions: vec![],
+        extractions: vec![],
+    };
+    let started_at = Instant::now();
+    let req = match parse_setting(setting).and_then(|req| resolve_request(ctx, req)) {
+        Ok(req) => req,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return result;
+        }
+    };
+    result.method = req.method.clone();
+    result.uri = req.uri.clone();
+    let extractors = req.extractors.clone();
+    match http_request::request_without_trace(setting.id.clone(), req, timeout).await {
+        Ok(resp) => {
+            // The extracted values are used by the following requests
+            match extractor::apply(&ctx.template, &extractors, &resp).await {
+                Ok(extractions) => result.extractions = extractions,
+                Err(err) => result.error = Some(err.to_string()),
+            }
+            result.status = resp.status;
+            result.latency = resp.latency;
+            let passed = if resp.assertions.is_empty() {
+                resp.status < 400
+            } else {
+                resp.assertions.iter().all(|item| item.passed)
+            };
+            result.passed = passed && result.error.is_none();
+            result.assertions = resp.assertions;
+        }
+        Err(err) => {
+            result.error = Some(err.to_string());
+            result.latency = started_at.elapsed().as_millis() as u32;
+        }
+    }
+    result
+}
+
+// Read the rows of data file, values of json are converted to text
+pub fn load_data_file(file: &Path) -> Result<Vec<HashMap<String, String>>, CyberAPIError> {
+    let is_json = file
+        .extension()
+        .and_then(|value| value.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    if is_json {
+        let rows: Vec<serde_json::Map<String, serde_json::Value>> =
+            serde_json::from_slice(&fs::read(file)?)?;
+        return Ok(rows
+            .into_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(key, value)| (key.clone(), json_path::to_string(value)))
+                    .collect()
+            })
+            .collect());
+    }
+    let mut reader = csv::Reader::from_path(file)?;
+    let headers = reader.headers()?.clone();
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record?;
+        rows.push(
+            headers
+                .iter()
+                .zip(record.iter())
+                .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+                .collect(),
+        );
+    }
+    Ok(rows)
+}
+
+struct Suite {
+    id: String,
+    ctx: RunnerContext,
+    settings: Vec<APISetting>,
+    rows: Vec<HashMap<String, String>>,
+    options: RunOptions,
+    iterations: u32,
+    total: usize,
+    next_iteration: AtomicU32,
+    stopped: AtomicBool,
+    // Results with the iteration and index of setting
+    results: Mutex<Vec<(u32, usize, RunResult)>>,
+    on_progress: Box<dyn Fn(RunProgress) + Send + Sync>,
+}
+
+async fn run_iterations(suite: Arc<Suite>) {
+    loop {
+        let iteration = suite.next_iteration.fetch_add(1, Ordering::SeqCst);
+        if iteration >= suite.iterations || suite.stopped.load(Ordering::SeqCst) {
+            return;
+        }
+        let data = if suite.rows.is_empty() {
+            HashMap::new()
+        } else {
+            suite.rows[iteration as usize % suite.rows.len()].clone()
+        };
+        let ctx = suite.ctx.with_data(data);
+        for (index, setting) in suite.settings.iter().enumerate() {
+            if suite.stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            if index != 0 && suite.options.delay != 0 {
+                tokio::time::sleep(Duration::from_millis(suite.options.delay)).await;
+            }
+            let mut result = run_setting(&ctx, setting, suite.options.timeout.clone()).await;
+            result.iteration = iteration + 1;
+            if !result.passed && suite.options.stop_on_failure {
+                suite.stopped.store(true, Ordering::SeqCst);
+            }
+            let completed = match suite.results.lock() {
+                Ok(mut results) => {
+                    results.push((iteration, index, result.clone()));
+                    results.len()
+                }
+                Err(_) => 0,
+            };
+            (suite.on_progress)(RunProgress {
+                id: suite.id.clone(),
+                completed,
+                total: suite.total,
+                result,
+            });
+        }
+    }
+}
+
+// Run the settings of target as a suite, the progress is reported after each request
+pub async fn run_suite(
+    id: String,
+    target: &RunTarget,
+    options: RunOptions,
+    on_progress: impl Fn(RunProgress) + Send + Sync + 'static,
+) -> Result<RunReport, CyberAPIError> {
+    let (collection, settings) = list_run_settings(target).await?;
+    let ctx = RunnerContext::load(&collection, options.environment.clone()).await?;
+    let rows = match options.data_file.as_deref() {
+        Some(file) if !file.is_empty() => load_data_file(Path::new(file))?,
+        _ => vec![],
+    };
+    let iterations = options
+        .iterations
+        .filter(|value| *value != 0)
+        .unwrap_or(rows.len().max(1) as u32);
+    let concurrency = options.concurrency.clamp(1, iterations);
+
+    let started_at = Local::now().to_rfc3339();
+    let started = Instant::now();
+    let suite = Arc::new(Suite {
+        id: id.clone(),
+        total: iterations as usize * settings.len(),
+        ctx,
+        settings,
+        rows,
+        options,
+        iterations,
+        next_iteration: AtomicU32::new(0),
+        stopped: AtomicBool::new(false),
+        results: Mutex::new(vec![]),
+        on_progress: Box::new(on_progress),
+    });
+    let tasks: Vec<_> = (0..concurrency)
+        .map(|_| tokio::spawn(run_iterations(suite.clone())))
+        .collect();
+    for task in tasks {
+        task.await?;
+    }
+
+    let mut results = suite
+        .results
+        .lock()
+        .map(|mut results| std::mem::take(&mut *results))
+        .unwrap_or_default();
+    results.sort_by_key(|(iteration, index, _)| (*iteration, *index));
+    Ok(RunReport::new(
+        id,
+        &suite.ctx,
+        started_at,
+        started.elapsed().as_millis() as u32,
+        iterations,
+        results.into_iter().map(|(_, _, result)| result).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{APIFolder, add_api_folder, add_api_setting, run_with_database};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+    use std::convert::Infallible;
+    use std::io::Write;
+
+    fn new_setting(collection: &str, id: &str, parent_id: &str, uri: &str) -> APISetting {
+        let req = HTTPRequest {
+            method: "GET".to_string(),
+            uri: uri.to_string(),
+            ..Default::default()
+        };
+        APISetting {
+            id: id.to_string(),
+            collection: collection.to_string(),
+            name: Some(id.to_string()),
+            category: Some("http".to_string()),
+            setting: Some(serde_json::to_string(&req).unwrap()),
+            parent_id: Some(parent_id.to_string()),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    // Local server responds 500 for `/fail` and 200 for others after the delay,
+    // it returns the address and the max count of requests handled at the same time
+    fn start_server(delay: u64) -> (String, Arc<AtomicU32>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let running = Arc::new(AtomicU32::new(0));
+        let max_running = Arc::new(AtomicU32::new(0));
+        let state = (running, max_running.clone());
+        let make_service = make_service_fn(move |_| {
+            let (running, max_running) = state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| {
+                    let running = running.clone();
+                    let max_running = max_running.clone();
+                    async move {
+                        let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_running.fetch_max(count, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        let status = if req.uri().path() == "/fail" {
+                            500
+                        } else {
+                            200
+                        };
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .body(Body::from(req.uri().to_string()))
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+        let server = Server::from_tcp(listener).unwrap().serve(make_service);
+        tokio::spawn(server);
+        (format!("http://{}", addr), max_running)
+    }
+
+    #[test]
+    fn flatten_children_order() {
+        let tree = HashMap::from([
+            (
+                API_TREE_ROOT.to_string(),
+                vec!["s1".to_string(), "f1".to_string(), "s4".to_string()],
+            ),
+            (
+                "f1".to_string(),
+                vec!["s2".to_string(), "f2".to_string(), "f1".to_string()],
+            ),
+            (
+                "f2".to_string(),
+                vec!["s3".to_string(), "missing".to_string()],
+            ),
+        ]);
+        let mut settings: HashMap<String, APISetting> = ["s4", "s3", "s2", "s1"]
+            .into_iter()
+            .map(|id| (id.to_string(), new_setting("collection", id, "", "")))
+            .collect();
+        let mut result = vec![];
+        flatten_children(
+            &tree[API_TREE_ROOT],
+            &tree,
+            &mut settings,
+            &mut HashSet::new(),
+            &mut result,
+        );
+        assert_eq!(
+            vec!["s1", "s2", "s3", "s4"],
+            result
+                .iter()
+                .map(|item| item.id.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(settings.is_empty());
+    }
+
+    #[test]
+    fn load_data_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_file = dir.path().join("data.csv");
+        fs::write(&csv_file, "name , age\ntom,18\njerry, 8\n").unwrap();
+        assert_eq!(
+            vec![
+                HashMap::from([
+                    ("name".to_string(), "tom".to_string()),
+                    ("age".to_string(), "18".to_string()),
+                ]),
+                HashMap::from([
+                    ("name".to_string(), "jerry".to_string()),
+                    ("age".to_string(), " 8".to_string()),
+                ]),
+            ],
+            load_data_file(&csv_file).unwrap()
+        );
+
+        let json_file = dir.path().join("data.JSON");
+        fs::write(
+            &json_file,
+            r#"[{"name": "tom", "age": 18, "admin": true}, {"tags": ["a"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                HashMap::from([
+                    ("name".to_string(), "tom".to_string()),
+                    ("age".to_string(), "18".to_string()),
+                    ("admin".to_string(), "true".to_string()),
+                ]),
+                HashMap::from([("tags".to_string(), r#"["a"]"#.to_string())]),
+            ],
+            load_data_file(&json_file).unwrap()
+        );
+
+        let invalid_file = dir.path().join("invalid.json");
+        fs::write(&invalid_file, r#"{"name": "tom"}"#).unwrap();
+        assert!(load_data_file(&invalid_file).is_err());
+    }
+
+    #[test]
+    fn run_suite_iterations() {
+        run_with_database(async {
+            let collection = "runner-iterations";
+            let (address, _) = start_server(0);
+            add_api_folder(APIFolder {
+                id: "runner-iterations-f1".to_string(),
+                collection: collection.to_string(),
+                children: None,
+                name: None,
+                parent_id: None,
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+            for (id, parent_id) in [
+                ("runner-iterations-s1", "runner-iterations-f1"),
+                ("runner-iterations-s2", ""),
+            ] {
+                let uri = format!("{address}/{id}?name={{{{name}}}}");
+                add_api_setting(new_setting(collection, id, parent_id, &uri))
+                    .await
+                    .unwrap();
+            }
+            let dir = tempfile::tempdir().unwrap();
+            let data_file = dir.path().join("data.csv");
+            fs::write(&data_file, "name\ntom\njerry\n").unwrap();
+
+            let progress = Arc::new(AtomicU32::new(0));
+            let count = progress.clone();
+            let report = run_suite(
+                "run-iterations".to_string(),
+                &RunTarget::Collection(collection.to_string()),
+                RunOptions {
+                    iterations: Some(3),
+                    data_file: Some(data_file.to_string_lossy().to_string()),
+                    concurrency: 3,
+                    ..Default::default()
+                },
+                move |_| {
+                    count.fetch_add(1, Ordering::SeqCst);
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(3, report.iterations);
+            assert_eq!(6, report.total);
+            assert_eq!(6, report.passed);
+            assert_eq!(6, progress.load(Ordering::SeqCst));
+            assert_eq!(
+                vec![
+                    (1, format!("{address}/runner-iterations-s1?name=tom")),
+                    (1, format!("{address}/runner-iterations-s2?name=tom")),
+                    (2, format!("{address}/runner-iterations-s1?name=jerry")),
+                    (2, format!("{address}/runner-iterations-s2?name=jerry")),
+                    (3, format!("{address}/runner-iterations-s1?name=tom")),
+                    (3, format!("{address}/runner-iterations-s2?name=tom")),
+                ],
+                report
+                    .results
+                    .iter()
+                    .map(|item| (item.iteration, item.uri.clone()))
+                    .collect::<Vec<_>>()
+            );
+
+            // The iterations default to the row count of data file
+            let report = run_suite(
+                "run-folder".to_string(),
+                &RunTarget::Folder("runner-iterations-f1".to_string()),
+                RunOptions {
+                    data_file: Some(data_file.to_string_lossy().to_string()),
+                    ..Default::default()
+                },
+                |_| {},
+            )
+            .await
+            .unwrap();
+            assert_eq!(2, report.iterations);
+            assert_eq!(2, report.total);
+        });
+    }
+
+    #[test]
+    fn run_suite_stop_on_failure() {
+        run_with_database(async {
+            let collection = "runner-stop-on-failure";
+            let (address, _) = start_server(0);
+            for (id, path) in [
+                ("runner-stop-on-failure-s1", "ok"),
+                ("runner-stop-on-failure-s2", "fail"),
+                ("runner-stop-on-failure-s3", "ok"),
+            ] {
+                add_api_setting(new_setting(
+                    collection,
+                    id,
+                    "",
+                    &format!("{address}/{path}"),
+                ))
+                .await
+                .unwrap();
+            }
+            let target = RunTarget::Collection(collection.to_string());
+
+            let report = run_suite(
+                "run-continue".to_string(),
+                &target,
+                RunOptions {
+                    iterations: Some(2),
+                    ..Default::default()
+                },
+                |_| {},
+            )
+            .await
+            .unwrap();
+            assert_eq!(6, report.total);
+            assert_eq!(4, report.passed);
+            assert_eq!(2, report.failed);
+
+            let report = run_suite(
+                "run-stop".to_string(),
+                &target,
+                RunOptions {
+                    iterations: Some(2),
+                    stop_on_failure: true,
+                    ..Default::default()
+                },
+                |_| {},
+            )
+            .await
+            .unwrap();
+            assert_eq!(2, report.total);
+            assert_eq!(
+                vec![(true, 200), (false, 500)],
+                report
+                    .results
+                    .iter()
+                    .map(|item| (item.passed, item.status))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(1, report.failed);
+        });
+    }
+
+    #[test]
+    fn run_suite_concurrency() {
+        run_with_database(async {
+            let collection = "runner-concurrency";
+            let (address, max_running) = start_server(100);
+            add_api_setting(new_setting(
+                collection,
+                "runner-concurrency-s1",
+                "",
+                &format!("{address}/ok"),
+            ))
+            .await
+            .unwrap();
+            let report = run_suite(
+                "run-concurrency".to_string(),
+                &RunTarget::Collection(collection.to_string()),
+                RunOptions {
+                    iterations: Some(6),
+                    concurrency: 2,
+                    ..Default::default()
+                },
+                |_| {},
+            )
+            .await
+            .unwrap();
+            assert_eq!(6, report.passed);
+            assert_eq!(2, max_running.load(Ordering::SeqCst));
+        });
+    }
+
+    #[test]
+    fn resolve_app_request_placeholders() {
+        let ctx = RunnerContext {
+            collection: "".to_string(),
+            template: TemplateContext::default(),
+            global_headers: vec![
+                HTTPRequestKVParam {
+                    key: "X-Token".to_string(),
+                    value: "{{token}}".to_string(),
+                    enabled: true,
+                },
+                HTTPRequestKVParam {
+                    key: "Accept".to_string(),
+                    value: "*/*".to_string(),
+                    enabled: true,
+                },
+            ],
+        };
+        ctx.template.set_variable(false, "token", "secret 1");
+        let param = |key: &str, value: &str| HTTPRequestKVParam {
+            key: key.to_string(),
+            value: value.to_string(),
+            enabled: true,
+        };
+        let req = HTTPRequest {
+            method: "POST".to_string(),
+            uri: "http://localhost/{{token}}".to_string(),
+            body: "a=%7B%7Btoken%7D%7D&b=1".to_string(),
+            content_type: CONTENT_TYPE_FORM.to_string(),
+            headers: vec![param("Accept", "application/json")],
+            query: vec![param("q", "{{token}}")],
+            auth: vec![param("admin", "{{token}}")],
+            ..Default::default()
+        };
+        let req = resolve_app_request(&ctx, req).unwrap();
+        assert_eq!("http://localhost/secret 1", req.uri);
+        assert_eq!("a=secret+1&b=1", req.body);
+        assert_eq!("secret 1", req.query[0].value);
+        assert!(req.auth.is_empty());
+        let headers: Vec<(&str, &str)> = req
+            .headers
+            .iter()
+            .map(|item| (item.key.as_str(), item.value.as_str()))
+            .collect();
+        let auth = format!(
+            "Basic {}",
+            general_purpose::STANDARD.encode("admin:secret 1")
+        );
+        assert_eq!(
+            vec![
+                ("Accept", "application/json"),
+                ("X-Token", "secret 1"),
+                ("Authorization", auth.as_str()),
+            ],
+            headers
+        );
+
+        // The form without placeholders is sent as the app encodes it
+        let req = HTTPRequest {
+            body: "a=b%20c".to_string(),
+            content_type: CONTENT_TYPE_FORM.to_string(),
+            ..Default::default()
+        };
+        assert_eq!("a=b%20c", resolve_app_request(&ctx, req).unwrap().body);
+    }
+
+    #[test]
+    fn multipart_escape_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a\"b.txt");
+        fs::File::create(&file)
+            .unwrap()
+            .write_all(b"file content")
+            .unwrap();
+        let params = vec![
+            HTTPRequestKVParam {
+                key: "na\"me\r\nX-Injected: 1".to_string(),
+                value: "value".to_string(),
+                enabled: true,
+            },
+            HTTPRequestKVParam {
+                key: "upload".to_string(),
+                value: format!("{FILE_PROTOCOL}{}", file.display()),
+                enabled: true,
+            },
+            HTTPRequestKVParam {
+                key: "disabled".to_string(),
+                value: "value".to_string(),
+                enabled: false,
+            },
+        ];
+        let ctx = TemplateContext::default();
+        let (content_type, body) =
+            encode_multipart(&ctx, &serde_json::to_string(&params).unwrap()).unwrap();
+        let boundary = content_type
+            .strip_prefix(&format!("{CONTENT_TYPE_MULTIPART}; boundary="))
+            .unwrap();
+        let body = String::from_utf8(general_purpose::STANDARD.decode(body).unwrap()).unwrap();
+
+        assert_eq!(
+            format!(
+                "--{boundary}\r\n\
+                Content-Disposition: form-data; name=\"na%22me%0D%0AX-Injected: 1\"\r\n\r\n\
+                value\r\n\
+                --{boundary}\r\n\
+                Content-Disposition: form-data; name=\"upload\"; filename=\"a%22b.txt\"\r\n\
+                Content-Type: application/octet-stream\r\n\r\n\
+                file content\r\n\
+                --{boundary}--\r\n"
+            ),
+            body
+        );
+    }
+}
@@ -3,10 +3,12 @@ use crate::{
     error::CyberAPIError,
 };
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
 use serde::{Deserialize, Serialize};
 
-use super::database::{ExportData, get_database};
+use super::database::{ExportData, IMPORT_BATCH_SIZE, get_database};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -87,29 +89,36 @@ pub fn get_table_name_api_collection() -> String {
     "api_collections".to_string()
 }
 
-pub async fn delete_all_api_collection() -> Result<(), DbErr> {
-    let db = get_database().await;
-    ApiCollections::delete_many().exec(&db).await?;
+pub async fn delete_all_api_collection<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    ApiCollections::delete_many().exec(db).await?;
     Ok(())
 }
 
-pub async fn export_api_collection() -> Result<ExportData, DbErr> {
+pub async fn export_api_collection(collections: Option<Vec<String>>) -> Result<ExportData, DbErr> {
     let db = get_database().await;
-    let data = ApiCollections::find().into_json().all(&db).await?;
+    let mut query = ApiCollections::find();
+    if let Some(collections) = collections {
+        query = query.filter(api_collections::Column::Id.is_in(collections));
+    }
+    let data = query.into_json().all(&db).await?;
     Ok(ExportData {
         name: get_table_name_api_collection(),
         data,
     })
 }
 
-pub async fn import_api_collection(data: Vec<serde_json::Value>) -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
+pub async fn import_api_collection<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+) -> Result<usize, CyberAPIError> {
     let mut records = Vec::new();
     for ele in data {
         let model = api_collections::ActiveModel::from_json(ele)?;
         records.push(model);
     }
-    ApiCollections::insert_many(records).exec(&db).await?;
-    Ok(())
+    let count = records.len();
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        ApiCollections::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(count)
 }
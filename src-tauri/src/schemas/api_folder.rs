@@ -3,19 +3,26 @@ use crate::{
     error::CyberAPIError,
 };
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, NotSet, QueryFilter, Set,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
-use super::database::{ExportData, get_database};
+use super::api_tree::{check_api_parent, list_api_children, next_sort_order};
+use super::database::{ExportData, IMPORT_BATCH_SIZE, get_database};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct APIFolder {
     pub id: String,
     pub collection: String,
+    // The ids of children in order, it is generated by the tree when listing
+    // and ignored when saving, use `move_api_item` to change it
     pub children: Option<String>,
     pub name: Option<String>,
+    // The parent folder, it is empty for the top level
+    pub parent_id: Option<String>,
+    pub sort_order: Option<i32>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -25,8 +32,10 @@ impl From<api_folders::Model> for APIFolder {
         APIFolder {
             id: model.id,
             collection: model.collection,
-            children: model.children,
+            children: None,
             name: model.name,
+            parent_id: model.parent_id,
+            sort_order: model.sort_order,
             created_at: model.created_at,
             updated_at: model.updated_at,
         }
@@ -39,21 +48,17 @@ impl APIFolder {
         api_folders::ActiveModel {
             id: Set(self.id),
             collection: Set(self.collection),
-            children: Set(self.children),
             name: Set(self.name),
+            // The position in tree is only changed by moving
+            parent_id: NotSet,
+            sort_order: NotSet,
             created_at: Set(created_at),
             updated_at: Set(updated_at),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct APIFolderChildren {
-    pub folders: Vec<String>,
-    pub settings: Vec<String>,
-}
-
+// It is the schema of the first migration, the changes after it are in the migrations
 pub fn get_api_folders_create_sql() -> String {
     "CREATE TABLE IF NOT EXISTS api_folders (
             id TEXT PRIMARY KEY NOT NULL check (id != ''),
@@ -66,10 +71,26 @@ pub fn get_api_folders_create_sql() -> String {
     .to_string()
 }
 
-pub async fn add_api_folder(folder: APIFolder) -> Result<APIFolder, DbErr> {
-    let model = folder.into_active_model();
+pub async fn add_api_folder(folder: APIFolder) -> Result<APIFolder, CyberAPIError> {
     let db = get_database().await;
-    let result = model.insert(&db).await?;
+    add_api_folder_with(&db, folder).await
+}
+
+// The `_with` functions run on the connection of caller, e.g. the transaction of import
+pub(crate) async fn add_api_folder_with<C: ConnectionTrait>(
+    db: &C,
+    folder: APIFolder,
+) -> Result<APIFolder, CyberAPIError> {
+    let parent_id = folder.parent_id.clone().unwrap_or_default();
+    check_api_parent(db, &folder.collection, &parent_id).await?;
+    let sort_order = match folder.sort_order {
+        Some(value) => value,
+        None => next_sort_order(db, &folder.collection, &parent_id).await?,
+    };
+    let mut model = folder.into_active_model();
+    model.parent_id = Set(Some(parent_id));
+    model.sort_order = Set(Some(sort_order));
+    let result = model.insert(db).await?;
     Ok(result.into())
 }
 
@@ -80,13 +101,27 @@ pub async fn update_api_folder(folder: APIFolder) -> Result<APIFolder, DbErr> {
     Ok(result.into())
 }
 
+pub async fn get_api_folder(id: String) -> Result<Option<APIFolder>, DbErr> {
+    let db = get_database().await;
+    let result = ApiFolders::find_by_id(id).one(&db).await?;
+    Ok(result.map(APIFolder::from))
+}
+
 pub async fn list_api_folder(collection: String) -> Result<Vec<APIFolder>, DbErr> {
     let db = get_database().await;
     let result = ApiFolders::find()
-        .filter(api_folders::Column::Collection.eq(collection))
+        .filter(api_folders::Column::Collection.eq(collection.clone()))
         .all(&db)
         .await?;
-    Ok(result.into_iter().map(APIFolder::from).collect())
+    let mut children = list_api_children(collection).await?;
+    Ok(result
+        .into_iter()
+        .map(|model| {
+            let mut folder = APIFolder::from(model);
+            folder.children = children.remove(&folder.id).map(|ids| ids.join(","));
+            folder
+        })
+        .collect())
 }
 
 pub async fn delete_api_folder_by_collection(collection: String) -> Result<u64, DbErr> {
@@ -110,87 +145,40 @@ pub async fn delete_api_folders(ids: Vec<String>) -> Result<u64, DbErr> {
     Ok(result.rows_affected)
 }
 
-// Get all children for this folder (including subfolders and their children)
-pub async fn list_api_folder_all_children(id: String) -> Result<APIFolderChildren, DbErr> {
-    // Shortcut: fetch all API folders first and then filter
-    let mut folder_children = HashMap::new();
-    let mut folders = Vec::new();
-    let mut settings = Vec::new();
-    let mut children = "".to_string();
-
-    let db = get_database().await;
-    let current_folder = ApiFolders::find()
-        .filter(api_folders::Column::Id.eq(id.clone()))
-        .one(&db)
-        .await?;
-    if let Some(folder) = current_folder {
-        // Track each folder and its subfolders
-        for ele in list_api_folder(folder.collection).await? {
-            if ele.id == id {
-                if let Some(data) = ele.children.clone() {
-                    children = data
-                }
-            }
-            folder_children.insert(ele.id, ele.children.clone());
-        }
-    }
-    while !children.is_empty() {
-        let arr = children.split(',');
-        let mut current_children = Vec::new();
-
-        for ele in arr {
-            let id = ele.trim();
-            // Is a folder
-            match folder_children.get(id) {
-                // Folder
-                Some(str) => {
-                    folders.push(id.to_string());
-                    if let Some(value) = str {
-                        // Record child entries
-                        if !value.is_empty() {
-                            current_children.push(value.as_str());
-                        }
-                    }
-                }
-                None => {
-                    // api settings
-                    settings.push(id.to_string())
-                }
-            }
-        }
-        // Store the updated children
-        children = current_children.join(",")
-    }
-    Ok(APIFolderChildren { folders, settings })
-}
-
 pub fn get_table_name_api_folder() -> String {
     "api_folders".to_string()
 }
 
-pub async fn delete_all_api_folder() -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-    ApiFolders::delete_many().exec(&db).await?;
+pub async fn delete_all_api_folder<C: ConnectionTrait>(db: &C) -> Result<(), CyberAPIError> {
+    ApiFolders::delete_many().exec(db).await?;
     Ok(())
 }
 
-pub async fn export_api_folder() -> Result<ExportData, DbErr> {
+pub async fn export_api_folder(collections: Option<Vec<String>>) -> Result<ExportData, DbErr> {
     let db = get_database().await;
-    let data = ApiFolders::find().into_json().all(&db).await?;
+    let mut query = ApiFolders::find();
+    if let Some(collections) = collections {
+        query = query.filter(api_folders::Column::Collection.is_in(collections));
+    }
+    let data = query.into_json().all(&db).await?;
     Ok(ExportData {
         name: get_table_name_api_folder(),
         data,
     })
 }
 
-pub async fn import_api_folder(data: Vec<serde_json::Value>) -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
+pub async fn import_api_folder<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+) -> Result<usize, CyberAPIError> {
     let mut records = Vec::new();
     for ele in data {
         let model = api_folders::ActiveModel::from_json(ele)?;
         records.push(model);
     }
-    ApiFolders::insert_many(records).exec(&db).await?;
-    Ok(())
+    let count = records.len();
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        ApiFolders::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(count)
 }
@@ -3,10 +3,13 @@ use crate::{
     error::CyberAPIError,
 };
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, NotSet, QueryFilter, Set,
+};
 use serde::{Deserialize, Serialize};
 
-use super::database::{ExportData, get_database};
+use super::api_tree::{check_api_parent, next_sort_order};
+use super::database::{ExportData, IMPORT_BATCH_SIZE, get_database};
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct APISetting {
@@ -15,6 +18,9 @@ pub struct APISetting {
     pub name: Option<String>,
     pub category: Option<String>,
     pub setting: Option<String>,
+    // The parent folder, it is empty for the top level
+    pub parent_id: Option<String>,
+    pub sort_order: Option<i32>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -27,6 +33,8 @@ impl From<api_settings::Model> for APISetting {
             name: model.name,
             category: model.category,
             setting: model.setting,
+            parent_id: model.parent_id,
+            sort_order: model.sort_order,
             created_at: model.created_at,
             updated_at: model.updated_at,
         }
@@ -42,12 +50,16 @@ impl APISetting {
             name: Set(self.name),
             category: Set(self.category),
             setting: Set(self.setting),
+            // The position in tree is only changed by moving
+            parent_id: NotSet,
+            sort_order: NotSet,
             created_at: Set(created_at),
             updated_at: Set(updated_at),
         }
     }
 }
 
+// It is the schema of the first migration, the changes after it are in the migrations
 pub fn get_api_settings_create_sql() -> String {
     "CREATE TABLE IF NOT EXISTS api_settings (
         id TEXT PRIMARY KEY NOT NULL check (id != ''),
@@ -61,19 +73,47 @@ pub fn get_api_settings_create_sql() -> String {
     .to_string()
 }
 
-pub async fn add_api_setting(setting: APISetting) -> Result<APISetting, DbErr> {
-    let model = setting.into_active_model();
+pub async fn add_api_setting(setting: APISetting) -> Result<APISetting, CyberAPIError> {
     let db = get_database().await;
-    let result = model.insert(&db).await?;
+    add_api_setting_with(&db, setting).await
+}
+
+pub(crate) async fn add_api_setting_with<C: ConnectionTrait>(
+    db: &C,
+    setting: APISetting,
+) -> Result<APISetting, CyberAPIError> {
+    let parent_id = setting.parent_id.clone().unwrap_or_default();
+    check_api_parent(db, &setting.collection, &parent_id).await?;
+    let sort_order = match setting.sort_order {
+        Some(value) => value,
+        None => next_sort_order(db, &setting.collection, &parent_id).await?,
+    };
+    let mut model = setting.into_active_model();
+    model.parent_id = Set(Some(parent_id));
+    model.sort_order = Set(Some(sort_order));
+    let result = model.insert(db).await?;
     Ok(result.into())
 }
 pub async fn update_api_setting(setting: APISetting) -> Result<APISetting, DbErr> {
-    let model = setting.into_active_model();
     let db = get_database().await;
-    let result = model.update(&db).await?;
+    update_api_setting_with(&db, setting).await
+}
+
+pub(crate) async fn update_api_setting_with<C: ConnectionTrait>(
+    db: &C,
+    setting: APISetting,
+) -> Result<APISetting, DbErr> {
+    let model = setting.into_active_model();
+    let result = model.update(db).await?;
     Ok(result.into())
 }
 
+pub async fn get_api_setting(id: String) -> Result<Option<APISetting>, DbErr> {
+    let db = get_database().await;
+    let result = ApiSettings::find_by_id(id).one(&db).await?;
+    Ok(result.map(APISetting::from))
+}
+
 pub async fn list_api_setting(collection: String) -> Result<Vec<APISetting>, DbErr> {
     let db = get_database().await;
     let result = ApiSettings::find()
@@ -107,30 +147,36 @@ pub fn get_table_name_api_setting() -> String {
     "api_settings".to_string()
 }
 
-pub async fn delete_all_api_setting() -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
-    ApiSettings::delete_many().exec(&db).await?;
+pub async fn delete_all_api_setting<C: ConnectionTrait>(db: &C) -> Result<(), CyberAPIError> {
+    ApiSettings::delete_many().exec(db).await?;
     Ok(())
 }
 
-pub async fn export_api_setting() -> Result<ExportData, DbErr> {
+pub async fn export_api_setting(collections: Option<Vec<String>>) -> Result<ExportData, DbErr> {
     let db = get_database().await;
-    let data = ApiSettings::find().into_json().all(&db).await?;
+    let mut query = ApiSettings::find();
+    if let Some(collections) = collections {
+        query = query.filter(api_settings::Column::Collection.is_in(collections));
+    }
+    let data = query.into_json().all(&db).await?;
     Ok(ExportData {
         name: get_table_name_api_setting(),
         data,
     })
 }
 
-pub async fn import_api_setting(data: Vec<serde_json::Value>) -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
+pub async fn import_api_setting<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+) -> Result<usize, CyberAPIError> {
     let mut records = Vec::new();
     for ele in data {
         let model = api_settings::ActiveModel::from_json(ele)?;
         records.push(model);
     }
-    ApiSettings::insert_many(records).exec(&db).await?;
-    Ok(())
+    let count = records.len();
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        ApiSettings::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(count)
 }
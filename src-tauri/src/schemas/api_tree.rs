@@ -0,0 +1,727 @@
+use crate::entities::{api_folders, api_settings, prelude::*};
+use crate::error::CyberAPIError;
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, Statement,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::database::get_database;
+
+// The parent of top level items
+pub const API_TREE_ROOT: &str = "";
+const API_TREE_ERROR_CATEGORY: &str = "apiTree";
+
+// The folders and settings of a folder, the sub folders are included
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct APIFolderChildren {
+    pub folders: Vec<String>,
+    pub settings: Vec<String>,
+}
+
+struct APITreeItem {
+    id: String,
+    parent_id: String,
+    sort_order: i32,
+    is_folder: bool,
+}
+
+// Convert the comma-separated children of folders to the parent and order of items,
+// the item in more than one folder belongs to the first one,
+// the children column is kept for the old versions but not used any more
+pub fn get_api_tree_migrate_sql() -> Vec<String> {
+    let update_parent = |table: &str| {
+        let find = format!(
+            "FROM api_tree_children c WHERE c.child = {table}.id AND c.collection = {table}.collection \
+            ORDER BY c.parent_rowid, c.position LIMIT 1"
+        );
+        format!(
+            "UPDATE {table} SET parent_id = IFNULL((SELECT c.parent {find}), ''), \
+            sort_order = IFNULL((SELECT c.position {find}), 0)"
+        )
+    };
+    let mut sql_list = vec![
+        "CREATE TEMP TABLE api_tree_children AS
+        WITH RECURSIVE split(parent_rowid, parent, collection, rest, child, position) AS (
+            SELECT rowid, id, collection, IFNULL(children, '') || ',', '', -1 FROM api_folders
+            UNION ALL
+            SELECT parent_rowid, parent, collection, substr(rest, instr(rest, ',') + 1),
+                trim(substr(rest, 1, instr(rest, ',') - 1)), position + 1
+            FROM split WHERE rest != ''
+        )
+        SELECT parent_rowid, parent, collection, child, position FROM split
+        WHERE child != '' AND child != parent"
+            .to_string(),
+        update_parent("api_folders"),
+        update_parent("api_settings"),
+    ];
+    sql_list.extend(get_api_tree_repair_sql());
+    let sub_folders = "WITH RECURSIVE sub_folders(id) AS (
+                    SELECT OLD.id
+                    UNION
+                    SELECT f.id FROM api_folders f JOIN sub_folders ON f.parent_id = sub_folders.id
+                ) SELECT id FROM sub_folders";
+    // The top items are sorted by created time as before
+    sql_list.push(
+        "CREATE TEMP TABLE api_tree_top AS
+        SELECT id, ROW_NUMBER() OVER (PARTITION BY collection ORDER BY created_at, id) - 1 AS position
+        FROM (
+            SELECT id, collection, created_at FROM api_folders WHERE parent_id = ''
+            UNION ALL
+            SELECT id, collection, created_at FROM api_settings WHERE parent_id = ''
+        )"
+        .to_string(),
+    );
+    for table in ["api_folders", "api_settings"] {
+        sql_list.push(format!(
+            "UPDATE {table} SET sort_order = (SELECT position FROM api_tree_top WHERE api_tree_top.id = {table}.id) \
+            WHERE parent_id = ''"
+        ));
+    }
+    sql_list.extend([
+        "DROP TABLE api_tree_children".to_string(),
+        "DROP TABLE api_tree_top".to_string(),
+        "CREATE INDEX IF NOT EXISTS idx_api_folders_parent ON api_folders (collection, parent_id)"
+            .to_string(),
+        "CREATE INDEX IF NOT EXISTS idx_api_settings_parent ON api_settings (collection, parent_id)"
+            .to_string(),
+        // The sub folders and settings of deleted folder are deleted too,
+        // the trigger is not recursive so they are found by the tree
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS api_folders_tree_delete AFTER DELETE ON api_folders BEGIN
+                DELETE FROM api_settings WHERE parent_id IN ({sub_folders});
+                DELETE FROM api_folders WHERE id IN ({sub_folders});
+            END"
+        ),
+    ]);
+    sql_list
+}
+
+// Move the items whose parent is not found or in a loop to the top level
+pub fn get_api_tree_repair_sql() -> Vec<String> {
+    let mut sql_list = vec![];
+    for table in ["api_folders", "api_settings"] {
+        sql_list.push(format!(
+            "UPDATE {table} SET parent_id = '' WHERE parent_id IS NULL OR (parent_id != '' AND NOT EXISTS (\
+            SELECT 1 FROM api_folders p WHERE p.id = {table}.parent_id AND p.collection = {table}.collection))"
+        ));
+        sql_list.push(format!(
+            "UPDATE {table} SET sort_order = 0 WHERE sort_order IS NULL"
+        ));
+    }
+    sql_list.push(
+        "UPDATE api_folders SET parent_id = '' WHERE id IN (
+            WITH RECURSIVE ancestors(id, parent) AS (
+                SELECT id, parent_id FROM api_folders WHERE parent_id != ''
+                UNION
+                SELECT ancestors.id, f.parent_id FROM ancestors
+                JOIN api_folders f ON f.id = ancestors.parent WHERE f.parent_id != ''
+            )
+            SELECT id FROM ancestors WHERE parent = id
+        )"
+        .to_string(),
+    );
+    sql_list
+}
+
+// Apply the comma-separated children of folders exported by the old versions,
+// then repair the tree of imported items
+pub async fn repair_api_tree<C: ConnectionTrait>(
+    db: &C,
+    legacy_children: Vec<(String, String)>,
+) -> Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    for (parent_id, children) in legacy_children {
+        let children = children
+            .split(',')
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty() && *id != parent_id);
+        for (index, id) in children.enumerate() {
+            for table in ["api_folders", "api_settings"] {
+                let sql = format!(
+                    "UPDATE {table} SET parent_id = ?, sort_order = ? WHERE id = ? AND IFNULL(parent_id, '') = ''"
+                );
+                db.execute(Statement::from_sql_and_values(
+                    backend,
+                    &sql,
+                    [parent_id.clone().into(), (index as i32).into(), id.into()],
+                ))
+                .await?;
+            }
+        }
+    }
+    for sql in get_api_tree_repair_sql() {
+        db.execute(Statement::from_string(backend, sql)).await?;
+    }
+    Ok(())
+}
+
+async fn list_items<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+) -> Result<Vec<APITreeItem>, DbErr> {
+    let folders: Vec<(String, Option<String>, Option<i32>)> = ApiFolders::find()
+        .select_only()
+        .column(api_folders::Column::Id)
+        .column(api_folders::Column::ParentId)
+        .column(api_folders::Column::SortOrder)
+        .filter(api_folders::Column::Collection.eq(collection))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let settings: Vec<(String, Option<String>, Option<i32>)> = ApiSettings::find()
+        .select_only()
+        .column(api_settings::Column::Id)
+        .column(api_settings::Column::ParentId)
+        .column(api_settings::Column::SortOrder)
+        .filter(api_settings::Column::Collection.eq(collection))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let to_item = |is_folder: bool| {
+        move |(id, parent_id, sort_order): (String, Option<String>, Option<i32>)| APITreeItem {
+            id,
+            parent_id: parent_id.unwrap_or_default(),
+            sort_order: sort_order.unwrap_or_default(),
+            is_folder,
+        }
+    };
+    let mut items: Vec<APITreeItem> = folders
+        .into_iter()
+        .map(to_item(true))
+        .chain(settings.into_iter().map(to_item(false)))
+        .collect();
+    items.sort_by(|a, b| {
+        a.parent_id
+            .cmp(&b.parent_id)
+            .then(a.sort_order.cmp(&b.sort_order))
+            .then(a.id.cmp(&b.id))
+    });
+    Ok(items)
+}
+
+// The ids of children in order for each folder, the top level items are in `API_TREE_ROOT`
+pub async fn list_api_children(collection: String) -> Result<HashMap<String, Vec<String>>, DbErr> {
+    let db = get_database().await;
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for item in list_items(&db, &collection).await? {
+        children.entry(item.parent_id).or_default().push(item.id);
+    }
+    Ok(children)
+}
+
+// The parent folder should be in the same collection
+pub(crate) async fn check_api_parent<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+    parent_id: &str,
+) -> Result<(), CyberAPIError> {
+    if parent_id == API_TREE_ROOT {
+        return Ok(());
+    }
+    let found = ApiFolders::find_by_id(parent_id)
+        .filter(api_folders::Column::Collection.eq(collection))
+        .one(db)
+        .await?;
+    if found.is_none() {
+        return Err(CyberAPIError::new(
+            API_TREE_ERROR_CATEGORY,
+            format!("parent folder {} is not found", parent_id),
+        ));
+    }
+    Ok(())
+}
+
+// The order after the last child of parent
+pub(crate) async fn next_sort_order<C: ConnectionTrait>(
+    db: &C,
+    collection: &str,
+    parent_id: &str,
+) -> Result<i32, DbErr> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT MAX(sort_order) AS sort_order FROM (
+                SELECT sort_order FROM api_folders WHERE collection = ? AND parent_id = ?
+                UNION ALL
+                SELECT sort_order FROM api_settings WHERE collection = ? AND parent_id = ?
+            )",
+            [
+                collection.into(),
+                parent_id.into(),
+                collection.into(),
+                parent_id.into(),
+            ],
+        ))
+        .await?;
+    let max: Option<i32> = match row {
+        Some(row) => row.try_get("", "sort_order")?,
+        None => None,
+    };
+    Ok(max.map(|value| value + 1).unwrap_or_default())
+}
+
+// The sub folders of folder, the folder itself is included
+async fn list_sub_folders<C: ConnectionTrait>(db: &C, id: &str) -> Result<Vec<String>, DbErr> {
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "WITH RECURSIVE sub_folders(id) AS (
+                SELECT ?
+                UNION
+                SELECT f.id FROM api_folders f JOIN sub_folders ON f.parent_id = sub_folders.id
+            )
+            SELECT id FROM sub_folders",
+            [id.into()],
+        ))
+        .await?;
+    let mut folders = vec![];
+    for row in rows {
+        folders.push(row.try_get("", "id")?);
+    }
+    Ok(folders)
+}
+
+// Get all children for this folder (including subfolders and their children)
+pub async fn list_api_folder_all_children(id: String) -> Result<APIFolderChildren, DbErr> {
+    let db = get_database().await;
+    let mut folders = list_sub_folders(&db, &id).await?;
+    folders.retain(|item| item != &id);
+    let settings = ApiSettings::find()
+        .select_only()
+        .column(api_settings::Column::Id)
+        .filter(api_settings::Column::ParentId.is_in(folders.iter().chain(std::iter::once(&id))))
+        .into_tuple()
+        .all(&db)
+        .await?;
+    Ok(APIFolderChildren { folders, settings })
+}
+
+async fn update_position<C: ConnectionTrait>(
+    db: &C,
+    item: &APITreeItem,
+    touched: bool,
+) -> Result<(), DbErr> {
+    if item.is_folder {
+        let mut update = ApiFolders::update_many()
+            .col_expr(api_folders::Column::ParentId, Expr::value(&item.parent_id))
+            .col_expr(api_folders::Column::SortOrder, Expr::value(item.sort_order));
+        if touched {
+            update = update.col_expr(
+                api_folders::Column::UpdatedAt,
+                Expr::value(Utc::now().to_rfc3339()),
+            );
+        }
+        update
+            .filter(api_folders::Column::Id.eq(&item.id))
+            .exec(db)
+            .await?;
+    } else {
+        let mut update = ApiSettings::update_many()
+            .col_expr(api_settings::Column::ParentId, Expr::value(&item.parent_id))
+            .col_expr(
+                api_settings::Column::SortOrder,
+                Expr::value(item.sort_order),
+            );
+        if touched {
+            update = update.col_expr(
+                api_settings::Column::UpdatedAt,
+                Expr::value(Utc::now().to_rfc3339()),
+            );
+        }
+        update
+            .filter(api_settings::Column::Id.eq(&item.id))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+// Save the order of children, only the changed ones are updated
+async fn save_children<C: ConnectionTrait>(
+    db: &C,
+    parent_id: &str,
+    children: Vec<APITreeItem>,
+    moved: &str,
+) -> Result<(), DbErr> {
+    for (index, mut item) in children.into_iter().enumerate() {
+        let sort_order = index as i32;
+        let touched = item.id == moved;
+        if !touched && item.parent_id == parent_id && item.sort_order == sort_order {
+            continue;
+        }
+        item.parent_id = parent_id.to_string();
+        item.sort_order = sort_order;
+        update_position(db, &item, touched).await?;
+    }
+    Ok(())
+}
+
+// Move the folder or setting into the parent folder before the item,
+// it is appended if the item is empty or not found
+pub async fn move_api_item(
+    id: String,
+    parent_id: String,
+    before: Option<String>,
+) -> Result<(), CyberAPIError> {
+    let db = get_database().await;
+    let txn = db.begin().await?;
+    let collection = if let Some(folder) = ApiFolders::find_by_id(&id).one(&txn).await? {
+        folder.collection
+    } else if let Some(setting) = ApiSettings::find_by_id(&id).one(&txn).await? {
+        setting.collection
+    } else {
+        return Err(CyberAPIError::new(
+            API_TREE_ERROR_CATEGORY,
+            format!("item {} is not found", id),
+        ));
+    };
+    check_api_parent(&txn, &collection, &parent_id).await?;
+    if parent_id != API_TREE_ROOT && list_sub_folders(&txn, &id).await?.contains(&parent_id) {
+        return Err(CyberAPIError::new(
+            API_TREE_ERROR_CATEGORY,
+            "folder can not be moved into itself or its sub folder".to_string(),
+        ));
+    }
+
+    let mut item = None;
+    let mut children = vec![];
+    for value in list_items(&txn, &collection).await? {
+        if value.id == id {
+            item = Some(value);
+        } else if value.parent_id == parent_id {
+            children.push(value);
+        }
+    }
+    let Some(item) = item else {
+        return Ok(());
+    };
+    let index = before
+        .and_then(|before| children.iter().position(|value| value.id == before))
+        .unwrap_or(children.len());
+    children.insert(index, item);
+    save_children(&txn, &parent_id, children, &id).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// Set the order of children of the parent folder,
+// the children not in the list are kept after them in the current order
+pub async fn reorder_api_items(
+    collection: String,
+    parent_id: String,
+    ids: Vec<String>,
+) -> Result<(), CyberAPIError> {
+    let db = get_database().await;
+    let txn = db.begin().await?;
+    check_api_parent(&txn, &collection, &parent_id).await?;
+    let mut children: Vec<APITreeItem> = list_items(&txn, &collection)
+        .await?
+        .into_iter()
+        .filter(|item| item.parent_id == parent_id)
+        .collect();
+    if let Some(id) = ids
+        .iter()
+        .find(|id| !children.iter().any(|item| &item.id == *id))
+    {
+        return Err(CyberAPIError::new(
+            API_TREE_ERROR_CATEGORY,
+            format!("item {} is not the child of folder", id),
+        ));
+    }
+    children.sort_by_key(|item| {
+        ids.iter()
+            .position(|id| id == &item.id)
+            .unwrap_or(ids.len())
+    });
+    save_children(&txn, &parent_id, children, "").await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{
+        APIFolder, APISetting, add_api_folder, add_api_setting, delete_api_folders,
+        run_with_database,
+    };
+    use sea_orm::{Database, DatabaseConnection};
+
+    async fn execute<C: ConnectionTrait>(db: &C, sql: &str) {
+        db.execute(Statement::from_string(
+            db.get_database_backend(),
+            sql.to_string(),
+        ))
+        .await
+        .unwrap();
+    }
+
+    async fn list_positions(db: &DatabaseConnection, table: &str) -> Vec<(String, String, i32)> {
+        let rows = db
+            .query_all(Statement::from_string(
+                db.get_database_backend(),
+                format!("SELECT id, parent_id, sort_order FROM {table} ORDER BY id"),
+            ))
+            .await
+            .unwrap();
+        rows.iter()
+            .map(|row| {
+                (
+                    row.try_get("", "id").unwrap(),
+                    row.try_get("", "parent_id").unwrap(),
+                    row.try_get("", "sort_order").unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn position(id: &str, parent_id: &str, sort_order: i32) -> (String, String, i32) {
+        (id.to_string(), parent_id.to_string(), sort_order)
+    }
+
+    fn new_folder(collection: &str, id: &str, parent_id: &str) -> APIFolder {
+        APIFolder {
+            id: id.to_string(),
+            collection: collection.to_string(),
+            children: None,
+            name: Some(id.to_string()),
+            parent_id: Some(parent_id.to_string()),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn new_setting(collection: &str, id: &str, parent_id: &str) -> APISetting {
+        APISetting {
+            id: id.to_string(),
+            collection: collection.to_string(),
+            name: Some(id.to_string()),
+            category: Some("http".to_string()),
+            setting: None,
+            parent_id: Some(parent_id.to_string()),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn migrate_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("api_tree.db");
+        let db = Database::connect(format!("sqlite://{}?mode=rwc", file.display()))
+            .await
+            .unwrap();
+        execute(
+            &db,
+            "CREATE TABLE api_folders (id TEXT PRIMARY KEY, collection TEXT, name TEXT, \
+            children TEXT, created_at TEXT, parent_id TEXT DEFAULT '', sort_order INTEGER DEFAULT 0)",
+        )
+        .await;
+        execute(
+            &db,
+            "CREATE TABLE api_settings (id TEXT PRIMARY KEY, collection TEXT, name TEXT, \
+            created_at TEXT, parent_id TEXT DEFAULT '', sort_order INTEGER DEFAULT 0)",
+        )
+        .await;
+        // The setting s1 is in two folders, the folders f3 and f4 are in a loop
+        // and f5 is in the folder of other collection
+        execute(
+            &db,
+            "INSERT INTO api_folders (id, collection, children, created_at) VALUES
+            ('f1', 'c1', ' s2, f2 ,s1,missing,f1', '2023-01-01'),
+            ('f2', 'c1', 's3,s1', '2023-01-02'),
+            ('f3', 'c1', 'f4', '2023-01-03'),
+            ('f4', 'c1', 'f3', '2023-01-04'),
+            ('f5', 'c2', '', '2023-01-01'),
+            ('f6', 'c2', 'f5,s4', '2023-01-02')",
+        )
+        .await;
+        execute(
+            &db,
+            "INSERT INTO api_settings (id, collection, created_at) VALUES
+            ('s1', 'c1', '2023-01-05'),
+            ('s2', 'c1', '2023-01-06'),
+            ('s3', 'c1', '2023-01-07'),
+            ('s4', 'c1', '2022-12-31'),
+            ('s5', 'c1', '2022-12-30')",
+        )
+        .await;
+
+        let txn = db.begin().await.unwrap();
+        for sql in get_api_tree_migrate_sql() {
+            execute(&txn, &sql).await;
+        }
+        txn.commit().await.unwrap();
+
+        assert_eq!(
+            vec![
+                position("f1", "", 2),
+                position("f2", "f1", 1),
+                position("f3", "", 3),
+                position("f4", "", 4),
+                position("f5", "f6", 0),
+                position("f6", "", 0),
+            ],
+            list_positions(&db, "api_folders").await
+        );
+        assert_eq!(
+            vec![
+                position("s1", "f1", 2),
+                position("s2", "f1", 0),
+                position("s3", "f2", 0),
+                position("s4", "", 1),
+                position("s5", "", 0),
+            ],
+            list_positions(&db, "api_settings").await
+        );
+
+        // The sub folders and settings are deleted with the folder
+        execute(&db, "DELETE FROM api_folders WHERE id = 'f1'").await;
+        assert_eq!(
+            vec!["f3", "f4", "f5", "f6"],
+            list_positions(&db, "api_folders")
+                .await
+                .into_iter()
+                .map(|(id, _, _)| id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["s4", "s5"],
+            list_positions(&db, "api_settings")
+                .await
+                .into_iter()
+                .map(|(id, _, _)| id)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn move_item() {
+        run_with_database(async {
+            let collection = "api-tree-move";
+            for (id, parent_id) in [
+                ("tree-move-f1", ""),
+                ("tree-move-f2", "tree-move-f1"),
+                ("tree-move-f3", "tree-move-f2"),
+                ("tree-move-f4", ""),
+            ] {
+                add_api_folder(new_folder(collection, id, parent_id))
+                    .await
+                    .unwrap();
+            }
+            add_api_setting(new_setting(collection, "tree-move-s1", "tree-move-f3"))
+                .await
+                .unwrap();
+
+            for parent_id in ["tree-move-f1", "tree-move-f3"] {
+                let err = move_api_item("tree-move-f1".to_string(), parent_id.to_string(), None)
+                    .await
+                    .unwrap_err();
+                assert_eq!(
+                    "[apiTree]folder can not be moved into itself or its sub folder",
+                    err.to_string()
+                );
+            }
+            let err = move_api_item("tree-move-f1".to_string(), "tree-move-s1".to_string(), None)
+                .await
+                .unwrap_err();
+            assert_eq!(
+                "[apiTree]parent folder tree-move-s1 is not found",
+                err.to_string()
+            );
+
+            move_api_item(
+                "tree-move-f2".to_string(),
+                API_TREE_ROOT.to_string(),
+                Some("tree-move-f1".to_string()),
+            )
+            .await
+            .unwrap();
+            move_api_item("tree-move-f1".to_string(), "tree-move-f3".to_string(), None)
+                .await
+                .unwrap();
+            let children = list_api_children(collection.to_string()).await.unwrap();
+            assert_eq!(
+                Some(&vec![
+                    "tree-move-f2".to_string(),
+                    "tree-move-f4".to_string()
+                ]),
+                children.get(API_TREE_ROOT)
+            );
+            assert_eq!(
+                Some(&vec![
+                    "tree-move-s1".to_string(),
+                    "tree-move-f1".to_string()
+                ]),
+                children.get("tree-move-f3")
+            );
+        });
+    }
+
+    #[test]
+    fn folder_all_children() {
+        run_with_database(async {
+            let collection = "api-tree-children";
+            for (id, parent_id) in [
+                ("tree-children-f1", ""),
+                ("tree-children-f2", "tree-children-f1"),
+                ("tree-children-f3", "tree-children-f2"),
+                ("tree-children-f4", ""),
+            ] {
+                add_api_folder(new_folder(collection, id, parent_id))
+                    .await
+                    .unwrap();
+            }
+            for (id, parent_id) in [
+                ("tree-children-s1", "tree-children-f1"),
+                ("tree-children-s2", "tree-children-f3"),
+                ("tree-children-s3", "tree-children-f4"),
+                ("tree-children-s4", ""),
+            ] {
+                add_api_setting(new_setting(collection, id, parent_id))
+                    .await
+                    .unwrap();
+            }
+
+            let mut children = list_api_folder_all_children("tree-children-f1".to_string())
+                .await
+                .unwrap();
+            children.folders.sort();
+            children.settings.sort();
+            assert_eq!(
+                vec!["tree-children-f2", "tree-children-f3"],
+                children.folders
+            );
+            assert_eq!(
+                vec!["tree-children-s1", "tree-children-s2"],
+                children.settings
+            );
+
+            let children = list_api_folder_all_children("tree-children-f3".to_string())
+                .await
+                .unwrap();
+            assert!(children.folders.is_empty());
+            assert_eq!(vec!["tree-children-s2"], children.settings);
+
+            delete_api_folders(vec!["tree-children-f1".to_string()])
+                .await
+                .unwrap();
+            let children = list_api_children(collection.to_string()).await.unwrap();
+            assert_eq!(
+                Some(&vec![
+                    "tree-children-f4".to_string(),
+                    "tree-children-s4".to_string()
+                ]),
+                children.get(API_TREE_ROOT)
+            );
+            assert_eq!(None, children.get("tree-children-f3"));
+        });
+    }
+}
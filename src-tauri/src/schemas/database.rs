@@ -1,21 +1,27 @@
+use crate::entities::{
+    api_collections, api_folders, api_settings, environments, proxies, variables,
+};
 use crate::error::CyberAPIError;
-use chrono::Local;
+use chrono::{Local, Utc};
 use sea_orm::{
     ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbErr, QueryResult, Statement,
+    TransactionTrait,
 };
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::OpenOptions;
-use std::io::{Read, Write};
+use std::io::{Cursor, Read, Write};
 use std::time::Duration;
 use std::vec;
 use std::{
     env, fs,
-    fs::File,
     path::{Path, PathBuf},
 };
-use tokio::sync::OnceCell;
+use tokio::sync::{Mutex, OnceCell};
 use zip::write::FileOptions;
 
-use crate::util;
+use crate::{crypto, util};
 
 use super::api_collection::{
     delete_all_api_collection, export_api_collection, get_api_collections_create_sql,
@@ -29,22 +35,35 @@ use super::api_setting::{
     delete_all_api_setting, export_api_setting, get_api_settings_create_sql,
     get_table_name_api_setting, import_api_setting,
 };
+use super::api_tree::{get_api_tree_migrate_sql, repair_api_tree};
 use super::environment::{
     delete_all_environment, export_environment, get_environments_create_sql,
     get_table_name_environment, import_environment,
 };
+use super::history::{get_histories_create_sql, get_histories_index_sql};
 use super::proxy::{
     delete_all_proxy, export_proxy, get_proxies_create_sql, get_table_name_proxy, import_proxy,
 };
+use super::search::{get_search_index_create_sql, get_search_index_rebuild_sql};
+use super::secret::{
+    SecretKey, export_secret_key, get_secret_keys_create_sql, get_table_name_secret_key,
+    import_secret_key, lock_secrets,
+};
 use super::variable::{
-    delete_all_variable, export_variable, get_table_name_variable, get_variables_create_sql,
-    import_variable,
+    delete_all_variable, export_variable, fill_secret_values, get_table_name_variable,
+    get_variables_create_sql, import_variable,
 };
 use super::version::get_versions_table_create_sql;
 use tracing::warn;
 
 static DB: OnceCell<DatabaseConnection> = OnceCell::const_new();
+static MIGRATION_LOCK: Mutex<()> = Mutex::const_new(());
 const DB_FILENAME: &str = "db.db";
+const IMPORT_ERROR_CATEGORY: &str = "importTables";
+const EXPORT_ERROR_CATEGORY: &str = "exportTables";
+const ENCRYPTED_BACKUP_EXT: &str = ".enc";
+// The rows of each insert statement, it keeps the variables of sql under the limit
+pub const IMPORT_BATCH_SIZE: usize = 100;
 
 pub struct ExportData {
     pub name: String,
@@ -212,50 +231,214 @@ pub async fn get_database() -> DatabaseConnection {
     db.to_owned()
 }
 
-pub async fn init_tables() -> Result<(), DbErr> {
-    let db = get_database().await;
-    let init_sql_list = vec![
-        get_versions_table_create_sql(),
-        get_api_collections_create_sql(),
-        get_api_folders_create_sql(),
-        get_api_settings_create_sql(),
-        get_environments_create_sql(),
-        get_proxies_create_sql(),
-        get_variables_create_sql(),
-    ];
-    for sql in init_sql_list {
-        db.execute(Statement::from_string(db.get_database_backend(), sql))
-            .await?;
-    }
-    // Lightweight migrations for existing installs
-    ensure_column(
-        &db,
-        &get_table_name_proxy(),
-        "enabled",
-        "enabled TEXT DEFAULT '1'",
-    )
-    .await?;
-    Ok(())
+// The schema migrations are applied in order of version and each one only once,
+// so an applied migration must not be changed and the schema changes need new ones
+struct Migration {
+    version: i64,
+    name: &'static str,
+    steps: Vec<MigrationStep>,
 }
 
-pub async fn export_tables() -> Result<String, CyberAPIError> {
-    let download = resolve_db_file().parent().unwrap().to_path_buf();
+enum MigrationStep {
+    Sql(String),
+    // The column may have been added by the versions before migrations
+    AddColumn {
+        table: String,
+        column: &'static str,
+        column_def: &'static str,
+    },
+}
 
-    let local = Local::now();
+fn sql_steps(sql_list: Vec<String>) -> Vec<MigrationStep> {
+    sql_list.into_iter().map(MigrationStep::Sql).collect()
+}
 
-    let filename = format!("cyberapi-backup-{}.zip", local.format("%Y-%m-%d"));
+fn get_migrations() -> Vec<Migration> {
+    let mut search_index_sql = get_search_index_create_sql();
+    search_index_sql.extend(get_search_index_rebuild_sql());
+    vec![
+        Migration {
+            version: 1,
+            name: "create tables",
+            steps: sql_steps(vec![
+                get_versions_table_create_sql(),
+                get_api_collections_create_sql(),
+                get_api_folders_create_sql(),
+                get_api_settings_create_sql(),
+                get_environments_create_sql(),
+                get_proxies_create_sql(),
+                get_variables_create_sql(),
+            ]),
+        },
+        Migration {
+            version: 2,
+            name: "add enabled of proxies",
+            steps: vec![MigrationStep::AddColumn {
+                table: get_table_name_proxy(),
+                column: "enabled",
+                column_def: "enabled TEXT DEFAULT '1'",
+            }],
+        },
+        Migration {
+            version: 3,
+            name: "create histories",
+            steps: sql_steps(vec![get_histories_create_sql(), get_histories_index_sql()]),
+        },
+        Migration {
+            version: 4,
+            name: "create search index",
+            steps: sql_steps(search_index_sql),
+        },
+        Migration {
+            version: 5,
+            name: "add parent and order of api items",
+            steps: [
+                (
+                    get_table_name_api_folder(),
+                    "parent_id",
+                    "parent_id TEXT DEFAULT ''",
+                ),
+                (
+                    get_table_name_api_folder(),
+                    "sort_order",
+                    "sort_order INTEGER DEFAULT 0",
+                ),
+                (
+                    get_table_name_api_setting(),
+                    "parent_id",
+                    "parent_id TEXT DEFAULT ''",
+                ),
+                (
+                    get_table_name_api_setting(),
+                    "sort_order",
+                    "sort_order INTEGER DEFAULT 0",
+                ),
+            ]
+            .into_iter()
+            .map(|(table, column, column_def)| MigrationStep::AddColumn {
+                table,
+                column,
+                column_def,
+            })
+            .chain(sql_steps(get_api_tree_migrate_sql()))
+            .collect(),
+        },
+        Migration {
+            version: 6,
+            name: "add secret variables",
+            steps: vec![
+                MigrationStep::AddColumn {
+                    table: get_table_name_variable(),
+                    column: "secret",
+                    column_def: "secret TEXT DEFAULT ''",
+                },
+                MigrationStep::Sql(get_secret_keys_create_sql()),
+            ],
+        },
+    ]
+}
 
-    let file = File::create(&download.join(filename.clone()))?;
-    let mut w = zip::ZipWriter::new(file);
+fn get_schema_migrations_create_sql() -> String {
+    "CREATE TABLE IF NOT EXISTS schema_migrations (
+        version INTEGER PRIMARY KEY NOT NULL,
+        name TEXT DEFAULT '',
+        applied_at TEXT DEFAULT ''
+    )"
+    .to_string()
+}
 
-    let table_data_list = vec![
-        export_api_collection().await?,
-        export_api_folder().await?,
-        export_api_setting().await?,
-        export_environment().await?,
-        export_proxy().await?,
-        export_variable().await?,
-    ];
+async fn list_applied_migration(db: &DatabaseConnection) -> Result<Vec<i64>, DbErr> {
+    let rows = db
+        .query_all(Statement::from_string(
+            db.get_database_backend(),
+            "SELECT version FROM schema_migrations".to_string(),
+        ))
+        .await?;
+    let mut versions = vec![];
+    for row in rows {
+        versions.push(row.try_get("", "version")?);
+    }
+    Ok(versions)
+}
+
+// Apply the migration in a transaction, it is rolled back if any step fails
+async fn apply_migration(db: &DatabaseConnection, migration: &Migration) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    let backend = txn.get_database_backend();
+    for step in migration.steps.iter() {
+        match step {
+            MigrationStep::Sql(sql) => {
+                txn.execute(Statement::from_string(backend, sql.clone()))
+                    .await?;
+            }
+            MigrationStep::AddColumn {
+                table,
+                column,
+                column_def,
+            } => ensure_column(&txn, table, column, column_def).await?,
+        }
+    }
+    txn.execute(Statement::from_sql_and_values(
+        backend,
+        "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, ?)",
+        [
+            migration.version.into(),
+            migration.name.into(),
+            Utc::now().to_rfc3339().into(),
+        ],
+    ))
+    .await?;
+    txn.commit().await
+}
+
+// Apply the migrations which are not applied yet in order of version
+async fn migrate(db: &DatabaseConnection, migrations: &[Migration]) -> Result<(), DbErr> {
+    db.execute(Statement::from_string(
+        db.get_database_backend(),
+        get_schema_migrations_create_sql(),
+    ))
+    .await?;
+    let applied = list_applied_migration(db).await?;
+    let latest = migrations.iter().map(|item| item.version).max();
+    if let Some(version) = applied
+        .iter()
+        .max()
+        .filter(|version| Some(**version) > latest)
+    {
+        warn!(
+            "the schema version {} of database is newer than the app",
+            version
+        );
+    }
+    for migration in migrations.iter() {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+        apply_migration(db, migration).await.map_err(|err| {
+            DbErr::Migration(format!(
+                "apply migration {}({}) fail: {}",
+                migration.version, migration.name, err
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+pub async fn init_tables() -> Result<(), DbErr> {
+    // Avoid applying the same migration by concurrent calls
+    let _guard = MIGRATION_LOCK.lock().await;
+    let db = get_database().await;
+    migrate(&db, &get_migrations()).await
+}
+
+// Write the data of tables to zip file, each table is saved as a json file,
+// the zip is encrypted if the passphrase is not empty
+fn write_tables(
+    file: &Path,
+    table_data_list: Vec<ExportData>,
+    passphrase: Option<&str>,
+) -> Result<(), CyberAPIError> {
+    let mut w = zip::ZipWriter::new(Cursor::new(Vec::new()));
     let options = FileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated)
         .unix_permissions(0o755);
@@ -272,20 +455,156 @@ pub async fn export_tables() -> Result<String, CyberAPIError> {
         let file_data = format!("[{}]", json.join(","));
         w.write_all(file_data.as_bytes())?;
     }
-    w.finish()?;
+    let mut data = w.finish()?.into_inner();
+    if let Some(passphrase) = passphrase.filter(|value| !value.is_empty()) {
+        data = crypto::encrypt(&data, passphrase)?;
+    }
+    fs::write(file, data)?;
+    Ok(())
+}
+
+// The secret values are left out unless include_secrets is true
+pub async fn export_tables(
+    passphrase: Option<String>,
+    include_secrets: bool,
+) -> Result<String, CyberAPIError> {
+    let download = resolve_db_file().parent().unwrap().to_path_buf();
+
+    let local = Local::now();
+
+    let mut filename = format!("cyberapi-backup-{}.zip", local.format("%Y-%m-%d"));
+    if passphrase.as_ref().is_some_and(|value| !value.is_empty()) {
+        filename.push_str(ENCRYPTED_BACKUP_EXT);
+    }
+
+    export_tables_to_file(
+        &download.join(&filename),
+        passphrase.as_deref(),
+        include_secrets,
+    )
+    .await?;
 
     Ok(filename)
 }
 
-pub async fn import_tables(filename: String) -> Result<(), CyberAPIError> {
-    let mut r = zip::ZipArchive::new(File::open(filename)?)?;
+fn count_tables(table_data_list: &[ExportData]) -> TablesCount {
+    let mut result = TablesCount::default();
+    for table_data in table_data_list.iter() {
+        result
+            .tables
+            .insert(table_data.name.clone(), table_data.data.len());
+    }
+    result
+}
+
+// Export all the tables to the file, it is the same as export_tables,
+// the workspace key is exported with the secrets so they can be decrypted after importing
+pub async fn export_tables_to_file(
+    file: &Path,
+    passphrase: Option<&str>,
+    include_secrets: bool,
+) -> Result<TablesCount, CyberAPIError> {
+    let mut table_data_list = vec![
+        export_api_collection(None).await?,
+        export_api_folder(None).await?,
+        export_api_setting(None).await?,
+        export_environment(None).await?,
+        export_proxy().await?,
+        export_variable(None, include_secrets).await?,
+    ];
+    if include_secrets {
+        table_data_list.push(export_secret_key().await?);
+    }
+    let result = count_tables(&table_data_list);
+    write_tables(file, table_data_list, passphrase)?;
+    Ok(result)
+}
+
+// Export the collections with their folders, settings, environments and variables,
+// the file can be imported by import_collections
+pub async fn export_collections(
+    collections: Vec<String>,
+    filename: String,
+    passphrase: Option<String>,
+) -> Result<TablesCount, CyberAPIError> {
+    let collection_data = export_api_collection(Some(collections.clone())).await?;
+    for id in collections.iter() {
+        let found = collection_data
+            .data
+            .iter()
+            .any(|item| item.get("id").and_then(|value| value.as_str()) == Some(id.as_str()));
+        if !found {
+            return Err(CyberAPIError::new(
+                EXPORT_ERROR_CATEGORY,
+                format!("collection {id} is not found"),
+            ));
+        }
+    }
+    let table_data_list = vec![
+        collection_data,
+        export_api_folder(Some(collections.clone())).await?,
+        export_api_setting(Some(collections.clone())).await?,
+        export_environment(Some(collections.clone())).await?,
+        export_variable(Some(collections), false).await?,
+    ];
+    let result = count_tables(&table_data_list);
+    write_tables(Path::new(&filename), table_data_list, passphrase.as_deref())?;
+    Ok(result)
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TablesCount {
+    // The count of imported or exported rows of each table
+    pub tables: BTreeMap<String, usize>,
+}
 
-    delete_all_api_collection().await?;
-    delete_all_api_folder().await?;
-    delete_all_api_setting().await?;
-    delete_all_environment().await?;
-    delete_all_proxy().await?;
-    delete_all_variable().await?;
+fn new_import_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(IMPORT_ERROR_CATEGORY, message)
+}
+
+// Check the rows can be converted to the model of table and the ids are unique
+fn validate_rows<M: DeserializeOwned>(
+    name: &str,
+    data: &[serde_json::Value],
+) -> Result<(), CyberAPIError> {
+    let mut ids = HashSet::new();
+    for (index, item) in data.iter().enumerate() {
+        if let Err(err) = serde_json::from_value::<M>(item.clone()) {
+            return Err(new_import_error(format!(
+                "row {} of {name} is invalid: {err}",
+                index + 1
+            )));
+        }
+        let id = item
+            .get("id")
+            .and_then(|id| id.as_str())
+            .unwrap_or_default();
+        if !ids.insert(id) {
+            return Err(new_import_error(format!(
+                "row {} of {name} has duplicate id: {id}",
+                index + 1
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Read and validate the tables of backup file, the missing tables are skipped
+pub(crate) fn read_backup_tables(
+    filename: &str,
+    passphrase: Option<&str>,
+) -> Result<Vec<(String, Vec<serde_json::Value>)>, CyberAPIError> {
+    let mut data = fs::read(filename)?;
+    if crypto::is_encrypted(&data) {
+        let Some(passphrase) = passphrase.filter(|value| !value.is_empty()) else {
+            return Err(new_import_error(
+                "the backup is encrypted, the passphrase is required".to_string(),
+            ));
+        };
+        data = crypto::decrypt(&data, passphrase)?;
+    }
+    let mut r = zip::ZipArchive::new(Cursor::new(data))?;
 
     let names = vec![
         get_table_name_api_collection(),
@@ -294,7 +613,9 @@ pub async fn import_tables(filename: String) -> Result<(), CyberAPIError> {
         get_table_name_environment(),
         get_table_name_proxy(),
         get_table_name_variable(),
+        get_table_name_secret_key(),
     ];
+    let mut tables = vec![];
     for name in names {
         let mut buf = Vec::new();
         let file_name = format!("{}.json", name);
@@ -306,22 +627,95 @@ pub async fn import_tables(filename: String) -> Result<(), CyberAPIError> {
             let mut file = file?;
             file.read_to_end(&mut buf)?;
         }
-        let data: Vec<serde_json::Value> = serde_json::from_slice(&buf)?;
+        let data: Vec<serde_json::Value> = serde_json::from_slice(&buf)
+            .map_err(|err| new_import_error(format!("{file_name} is invalid: {err}")))?;
         match name.as_str() {
-            n if n == get_table_name_api_collection() => import_api_collection(data).await?,
-            n if n == get_table_name_api_folder() => import_api_folder(data).await?,
-            n if n == get_table_name_api_setting() => import_api_setting(data).await?,
-            n if n == get_table_name_environment() => import_environment(data).await?,
-            n if n == get_table_name_proxy() => import_proxy(data).await?,
-            n if n == get_table_name_variable() => import_variable(data).await?,
+            n if n == get_table_name_api_collection() => {
+                validate_rows::<api_collections::Model>(n, &data)?
+            }
+            n if n == get_table_name_api_folder() => validate_rows::<api_folders::Model>(n, &data)?,
+            n if n == get_table_name_api_setting() => {
+                validate_rows::<api_settings::Model>(n, &data)?
+            }
+            n if n == get_table_name_environment() => {
+                validate_rows::<environments::Model>(n, &data)?
+            }
+            n if n == get_table_name_proxy() => validate_rows::<proxies::Model>(n, &data)?,
+            n if n == get_table_name_variable() => validate_rows::<variables::Model>(n, &data)?,
+            n if n == get_table_name_secret_key() => validate_rows::<SecretKey>(n, &data)?,
             _ => (),
         }
+        tables.push((name, data));
     }
 
-    Ok(())
+    Ok(tables)
 }
 
-async fn column_exists(db: &DatabaseConnection, table: &str, column: &str) -> Result<bool, DbErr> {
+// All the data is validated before writing, and it is imported in one transaction,
+// so the database is unchanged if any table fails.
+// The secrets left out of the backup are kept, and so is the workspace key if it is not in the backup
+pub async fn import_tables(
+    filename: String,
+    passphrase: Option<String>,
+) -> Result<TablesCount, CyberAPIError> {
+    let mut tables = read_backup_tables(&filename, passphrase.as_deref())?;
+
+    let db = get_database().await;
+    let txn = db.begin().await?;
+    for (name, data) in tables.iter_mut() {
+        if *name == get_table_name_variable() {
+            fill_secret_values(&txn, data).await?;
+        }
+    }
+    delete_all_api_collection(&txn).await?;
+    delete_all_api_folder(&txn).await?;
+    delete_all_api_setting(&txn).await?;
+    delete_all_environment(&txn).await?;
+    delete_all_proxy(&txn).await?;
+    delete_all_variable(&txn).await?;
+
+    let mut result = TablesCount::default();
+    // The tree of folders is saved as children by the old versions
+    let mut legacy_children = vec![];
+    for (name, data) in tables {
+        let count = match name.as_str() {
+            n if n == get_table_name_api_collection() => import_api_collection(&txn, data).await?,
+            n if n == get_table_name_api_folder() => {
+                legacy_children.extend(data.iter().filter_map(|item| {
+                    let id = item.get("id")?.as_str()?;
+                    let children = item.get("children")?.as_str()?;
+                    Some((id.to_string(), children.to_string()))
+                }));
+                import_api_folder(&txn, data).await?
+            }
+            n if n == get_table_name_api_setting() => import_api_setting(&txn, data).await?,
+            n if n == get_table_name_environment() => import_environment(&txn, data).await?,
+            n if n == get_table_name_proxy() => import_proxy(&txn, data).await?,
+            n if n == get_table_name_variable() => import_variable(&txn, data).await?,
+            n if n == get_table_name_secret_key() => import_secret_key(&txn, data, true).await?,
+            _ => 0,
+        };
+        result.tables.insert(name, count);
+    }
+    repair_api_tree(&txn, legacy_children).await?;
+    // The transaction is rolled back when it is dropped without commit
+    txn.commit().await?;
+    if result
+        .tables
+        .get(&get_table_name_secret_key())
+        .is_some_and(|count| *count != 0)
+    {
+        lock_secrets();
+    }
+
+    Ok(result)
+}
+
+async fn column_exists<C: ConnectionTrait>(
+    db: &C,
+    table: &str,
+    column: &str,
+) -> Result<bool, DbErr> {
     let sql = format!("PRAGMA table_info({})", table);
     let rows: Vec<QueryResult> = db
         .query_all(Statement::from_string(db.get_database_backend(), sql))
@@ -335,8 +729,8 @@ async fn column_exists(db: &DatabaseConnection, table: &str, column: &str) -> Re
     Ok(false)
 }
 
-async fn ensure_column(
-    db: &DatabaseConnection,
+async fn ensure_column<C: ConnectionTrait>(
+    db: &C,
     table: &str,
     column: &str,
     column_def: &str,
@@ -349,3 +743,440 @@ async fn ensure_column(
         .await?;
     Ok(())
 }
+
+// The tests of tables share the database of a temporary app dir, they are run one by one
+// on the same runtime because the connections of database are bound to it
+#[cfg(test)]
+pub(crate) fn run_with_database<F: std::future::Future>(future: F) -> F::Output {
+    static RUNTIME: once_cell::sync::Lazy<tokio::runtime::Runtime> =
+        once_cell::sync::Lazy::new(|| tokio::runtime::Runtime::new().unwrap());
+    static LOCK: Mutex<bool> = Mutex::const_new(false);
+    RUNTIME.block_on(async {
+        let mut initialized = LOCK.lock().await;
+        if !*initialized {
+            let dir = tempfile::tempdir().unwrap().keep();
+            util::set_app_dir(dir.to_string_lossy().to_string());
+            init_tables().await.unwrap();
+            *initialized = true;
+        }
+        future.await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{
+        Variable, add_variable, get_secret_status, reveal_variable, unlock_secrets,
+    };
+
+    const HEAD_VERSION: i64 = 6;
+
+    fn snapshot_file(version: i64) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("testdata/schema/v{}.sql", version))
+    }
+
+    async fn new_database(dir: &Path) -> DatabaseConnection {
+        let file = dir.join(DB_FILENAME);
+        Database::connect(format!("sqlite://{}?mode=rwc", file.display()))
+            .await
+            .unwrap()
+    }
+
+    async fn execute(db: &DatabaseConnection, sql: &str) {
+        db.execute(Statement::from_string(
+            db.get_database_backend(),
+            sql.to_string(),
+        ))
+        .await
+        .unwrap();
+    }
+
+    async fn query_strings(db: &DatabaseConnection, sql: &str) -> Vec<String> {
+        let rows = db
+            .query_all(Statement::from_string(
+                db.get_database_backend(),
+                sql.to_string(),
+            ))
+            .await
+            .unwrap();
+        rows.iter()
+            .map(|row| row.try_get_by_index::<String>(0).unwrap())
+            .collect()
+    }
+
+    // The shadow tables of fts are created by the virtual table
+    async fn dump_schema(db: &DatabaseConnection) -> String {
+        let sql_list = query_strings(
+            db,
+            "SELECT sql FROM sqlite_master WHERE sql IS NOT NULL \
+            AND name NOT LIKE 'sqlite_%' AND name NOT LIKE 'search_index_%' \
+            ORDER BY CASE type WHEN 'table' THEN 0 ELSE 1 END, name",
+        )
+        .await;
+        format!("{};\n", sql_list.join(";\n\n"))
+    }
+
+    // The database of version, the version 0 is created by the app before migrations
+    async fn create_version(db: &DatabaseConnection, version: i64) {
+        let migrations = get_migrations();
+        if version == 0 {
+            for step in migrations[0].steps.iter() {
+                if let MigrationStep::Sql(sql) = step {
+                    execute(db, sql).await;
+                }
+            }
+            return;
+        }
+        let count = migrations
+            .iter()
+            .filter(|item| item.version <= version)
+            .count();
+        migrate(db, &migrations[..count]).await.unwrap();
+    }
+
+    // The schema of each version is kept by the snapshot, `UPDATE_GOLDEN=1` rewrites them.
+    // The applied migrations must not be changed, so the snapshots are not changed either.
+    #[tokio::test]
+    async fn schema_snapshots() {
+        let update = env::var("UPDATE_GOLDEN").is_ok_and(|value| value == "1");
+        for version in 0..=HEAD_VERSION {
+            let dir = tempfile::tempdir().unwrap();
+            let db = new_database(dir.path()).await;
+            create_version(&db, version).await;
+            let schema = dump_schema(&db).await;
+            let file = snapshot_file(version);
+            if update {
+                fs::create_dir_all(file.parent().unwrap()).unwrap();
+                fs::write(&file, &schema).unwrap();
+                continue;
+            }
+            let expected = fs::read_to_string(&file)
+                .unwrap_or_else(|err| panic!("read {}: {}", file.display(), err));
+            assert_eq!(expected, schema, "version {}", version);
+        }
+    }
+
+    // The database of each version is upgraded to the head, its data is migrated
+    #[tokio::test]
+    async fn upgrade_to_head() {
+        let head = fs::read_to_string(snapshot_file(HEAD_VERSION)).unwrap();
+        for version in 0..=HEAD_VERSION {
+            let dir = tempfile::tempdir().unwrap();
+            let db = new_database(dir.path()).await;
+            let snapshot = fs::read_to_string(snapshot_file(version)).unwrap();
+            for sql in snapshot.split(";\n\n") {
+                execute(&db, sql.trim().trim_end_matches(';')).await;
+            }
+            for applied in 1..=version {
+                execute(
+                    &db,
+                    &format!(
+                        "INSERT INTO schema_migrations (version) VALUES ({})",
+                        applied
+                    ),
+                )
+                .await;
+            }
+            execute(
+                &db,
+                "INSERT INTO api_collections (id, name) VALUES ('collection', 'Collection')",
+            )
+            .await;
+            if !column_exists(&db, "api_folders", "parent_id")
+                .await
+                .unwrap()
+            {
+                execute(
+                    &db,
+                    "INSERT INTO api_folders (id, collection, name, children) \
+                    VALUES ('folder', 'collection', 'Folder', 'setting')",
+                )
+                .await;
+                execute(
+                    &db,
+                    "INSERT INTO api_settings (id, collection, name, setting) \
+                    VALUES ('setting', 'collection', 'Users', '{\"uri\": \"http://localhost/zebra\"}')",
+                )
+                .await;
+            } else {
+                execute(
+                    &db,
+                    "INSERT INTO api_folders (id, collection, name, parent_id) \
+                    VALUES ('folder', 'collection', 'Folder', '')",
+                )
+                .await;
+                execute(
+                    &db,
+                    "INSERT INTO api_settings (id, collection, name, setting, parent_id) \
+                    VALUES ('setting', 'collection', 'Users', '{\"uri\": \"http://localhost/zebra\"}', 'folder')",
+                )
+                .await;
+            }
+
+            migrate(&db, &get_migrations()).await.unwrap();
+            assert_eq!(head, dump_schema(&db).await, "version {}", version);
+            assert_eq!(
+                (1..=HEAD_VERSION).collect::<Vec<_>>(),
+                list_applied_migration(&db).await.unwrap(),
+                "version {}",
+                version
+            );
+            assert_eq!(
+                vec!["folder".to_string()],
+                query_strings(
+                    &db,
+                    "SELECT parent_id FROM api_settings WHERE id = 'setting'"
+                )
+                .await,
+                "version {}",
+                version
+            );
+            assert_eq!(
+                vec!["setting".to_string()],
+                query_strings(
+                    &db,
+                    "SELECT id FROM search_index WHERE search_index MATCH 'zebra'"
+                )
+                .await,
+                "version {}",
+                version
+            );
+        }
+    }
+
+    // The failed migration is rolled back and its version is not recorded
+    #[tokio::test]
+    async fn rollback_failed_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = new_database(dir.path()).await;
+        migrate(&db, &get_migrations()).await.unwrap();
+        let schema = dump_schema(&db).await;
+
+        let mut migrations = get_migrations();
+        migrations.push(Migration {
+            version: HEAD_VERSION + 1,
+            name: "failed",
+            steps: vec![
+                MigrationStep::Sql("CREATE TABLE migration_test (id TEXT)".to_string()),
+                MigrationStep::AddColumn {
+                    table: get_table_name_proxy(),
+                    column: "migration_test",
+                    column_def: "migration_test TEXT DEFAULT ''",
+                },
+                MigrationStep::Sql("INSERT INTO not_exists VALUES (1)".to_string()),
+            ],
+        });
+        let err = migrate(&db, &migrations).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("apply migration 7(failed) fail: Execution Error"),
+            "{}",
+            err
+        );
+        assert_eq!(schema, dump_schema(&db).await);
+        assert_eq!(
+            (1..=HEAD_VERSION).collect::<Vec<_>>(),
+            list_applied_migration(&db).await.unwrap()
+        );
+
+        // It is applied again when it is fixed
+        migrations.last_mut().unwrap().steps.pop();
+        migrate(&db, &migrations).await.unwrap();
+        assert_eq!(
+            (1..=HEAD_VERSION + 1).collect::<Vec<_>>(),
+            list_applied_migration(&db).await.unwrap()
+        );
+        assert!(
+            column_exists(&db, &get_table_name_proxy(), "migration_test")
+                .await
+                .unwrap()
+        );
+    }
+
+    fn write_zip(file: &Path, files: &[(&str, &str)]) {
+        let mut w = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            w.start_file(*name, FileOptions::default()).unwrap();
+            w.write_all(data.as_bytes()).unwrap();
+        }
+        fs::write(file, w.finish().unwrap().into_inner()).unwrap();
+    }
+
+    fn read_error(file: &Path, files: &[(&str, &str)]) -> String {
+        write_zip(file, files);
+        read_backup_tables(&file.to_string_lossy(), None)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn validate_backup_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("backup.zip");
+
+        write_zip(
+            &file,
+            &[
+                ("api_collections.json", r#"[{"id": "c1", "name": "Pets"}]"#),
+                ("unknown.json", "[]"),
+            ],
+        );
+        let tables = read_backup_tables(&file.to_string_lossy(), None).unwrap();
+        assert_eq!(1, tables.len());
+        assert_eq!("api_collections", tables[0].0);
+        assert_eq!(1, tables[0].1.len());
+
+        assert_eq!(
+            "[importTables]row 2 of api_folders is invalid: missing field `collection`",
+            read_error(
+                &file,
+                &[(
+                    "api_folders.json",
+                    r#"[{"id": "f1", "collection": "c1"}, {"id": "f2"}]"#
+                )]
+            )
+        );
+        assert_eq!(
+            "[importTables]row 2 of variables has duplicate id: v1",
+            read_error(
+                &file,
+                &[(
+                    "variables.json",
+                    r#"[{"id": "v1", "category": "env", "collection": "c1"}, {"id": "v1", "category": "env", "collection": "c1"}]"#
+                )]
+            )
+        );
+        assert!(
+            read_error(&file, &[("proxies.json", "[{")])
+                .starts_with("[importTables]proxies.json is invalid: EOF while parsing")
+        );
+
+        fs::write(&file, crypto::encrypt(b"data", "secret").unwrap()).unwrap();
+        assert_eq!(
+            "[importTables]the backup is encrypted, the passphrase is required",
+            read_backup_tables(&file.to_string_lossy(), None)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    // The tables are unchanged if any row fails to be inserted
+    #[test]
+    fn import_tables_atomic() {
+        run_with_database(async {
+            let db = get_database().await;
+            execute(
+                &db,
+                "INSERT INTO api_collections (id, name) VALUES ('import-keep', 'Keep')",
+            )
+            .await;
+            let list_collections = || async {
+                let db = get_database().await;
+                query_strings(&db, "SELECT id FROM api_collections ORDER BY id").await
+            };
+            let before = list_collections().await;
+            assert!(before.contains(&"import-keep".to_string()));
+
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join("backup.zip");
+            let filename = file.to_string_lossy().to_string();
+            // The empty collection is rejected by the check of table
+            write_zip(
+                &file,
+                &[
+                    ("api_collections.json", r#"[{"id": "import-new"}]"#),
+                    (
+                        "api_folders.json",
+                        r#"[{"id": "import-folder", "collection": ""}]"#,
+                    ),
+                ],
+            );
+            assert!(import_tables(filename.clone(), None).await.is_err());
+            assert_eq!(before, list_collections().await);
+
+            write_zip(
+                &file,
+                &[
+                    ("api_collections.json", r#"[{"id": "import-new"}]"#),
+                    (
+                        "api_folders.json",
+                        r#"[{"id": "import-folder", "collection": "import-new"}]"#,
+                    ),
+                    ("variables.json", "[]"),
+                ],
+            );
+            let result = import_tables(filename, None).await.unwrap();
+            assert_eq!(
+                BTreeMap::from([
+                    ("api_collections".to_string(), 1),
+                    ("api_folders".to_string(), 1),
+                    ("variables".to_string(), 0),
+                ]),
+                result.tables
+            );
+            assert_eq!(vec!["import-new".to_string()], list_collections().await);
+        });
+    }
+
+    // The secrets left out of the export are kept, and the workspace key is exported with them
+    #[test]
+    fn import_keep_secrets() {
+        run_with_database(async {
+            unlock_secrets("master".to_string()).await.unwrap();
+            add_variable(Variable {
+                id: "import-secret".to_string(),
+                category: "env".to_string(),
+                collection: "import-secret-c".to_string(),
+                environment: None,
+                name: Some("token".to_string()),
+                value: Some("token-1".to_string()),
+                enabled: Some("1".to_string()),
+                secret: Some("1".to_string()),
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+            let dir = tempfile::tempdir().unwrap();
+
+            let file = dir.path().join("backup.zip");
+            let filename = file.to_string_lossy().to_string();
+            export_tables_to_file(&file, None, false).await.unwrap();
+            let tables = read_backup_tables(&filename, None).unwrap();
+            assert!(!tables.iter().any(|(name, _)| *name == "secret_keys"));
+            import_tables(filename, None).await.unwrap();
+            assert_eq!(
+                "token-1",
+                reveal_variable("import-secret".to_string()).await.unwrap()
+            );
+
+            let file = dir.path().join("backup-secrets.zip");
+            let filename = file.to_string_lossy().to_string();
+            export_tables_to_file(&file, None, true).await.unwrap();
+            let tables = read_backup_tables(&filename, None).unwrap();
+            let keys = tables
+                .iter()
+                .find(|(name, _)| *name == "secret_keys")
+                .map(|(_, data)| data.len());
+            assert_eq!(Some(1), keys);
+
+            // The key is restored into the database without it
+            let db = get_database().await;
+            execute(&db, "DELETE FROM secret_keys").await;
+            execute(
+                &db,
+                "UPDATE variables SET value = '' WHERE id = 'import-secret'",
+            )
+            .await;
+            let result = import_tables(filename, None).await.unwrap();
+            assert_eq!(Some(&1), result.tables.get("secret_keys"));
+            assert!(!get_secret_status().await.unwrap().unlocked);
+            unlock_secrets("master".to_string()).await.unwrap();
+            assert_eq!(
+                "token-1",
+                reveal_variable("import-secret".to_string()).await.unwrap()
+            );
+        });
+    }
+}
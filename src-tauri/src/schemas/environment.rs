@@ -3,10 +3,15 @@ use crate::{
     error::CyberAPIError,
 };
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
 use serde::{Deserialize, Serialize};
 
-use super::database::{ExportData, get_database};
+use super::database::{ExportData, IMPORT_BATCH_SIZE, get_database};
+
+// Keep in sync with `EnvironmentStatus` in src/commands/environment.ts
+pub const ENVIRONMENT_ENABLED: &str = "1";
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -60,9 +65,16 @@ pub fn get_environments_create_sql() -> String {
 }
 
 pub async fn add_environment(value: Environment) -> Result<Environment, DbErr> {
-    let model = value.into_active_model();
     let db = get_database().await;
-    let result = model.insert(&db).await?;
+    add_environment_with(&db, value).await
+}
+
+pub(crate) async fn add_environment_with<C: ConnectionTrait>(
+    db: &C,
+    value: Environment,
+) -> Result<Environment, DbErr> {
+    let model = value.into_active_model();
+    let result = model.insert(db).await?;
     Ok(result.into())
 }
 
@@ -75,9 +87,16 @@ pub async fn update_environment(value: Environment) -> Result<Environment, DbErr
 
 pub async fn list_environment(collection: String) -> Result<Vec<Environment>, DbErr> {
     let db = get_database().await;
+    list_environment_with(&db, collection).await
+}
+
+pub(crate) async fn list_environment_with<C: ConnectionTrait>(
+    db: &C,
+    collection: String,
+) -> Result<Vec<Environment>, DbErr> {
     let result = Environments::find()
         .filter(environments::Column::Collection.eq(collection))
-        .all(&db)
+        .all(db)
         .await?;
     Ok(result.into_iter().map(Environment::from).collect())
 }
@@ -95,29 +114,36 @@ pub fn get_table_name_environment() -> String {
     "environments".to_string()
 }
 
-pub async fn delete_all_environment() -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-    Environments::delete_many().exec(&db).await?;
+pub async fn delete_all_environment<C: ConnectionTrait>(db: &C) -> Result<(), CyberAPIError> {
+    Environments::delete_many().exec(db).await?;
     Ok(())
 }
 
-pub async fn export_environment() -> Result<ExportData, DbErr> {
+pub async fn export_environment(collections: Option<Vec<String>>) -> Result<ExportData, DbErr> {
     let db = get_database().await;
-    let data = Environments::find().into_json().all(&db).await?;
+    let mut query = Environments::find();
+    if let Some(collections) = collections {
+        query = query.filter(environments::Column::Collection.is_in(collections));
+    }
+    let data = query.into_json().all(&db).await?;
     Ok(ExportData {
         name: get_table_name_environment(),
         data,
     })
 }
 
-pub async fn import_environment(data: Vec<serde_json::Value>) -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
+pub async fn import_environment<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+) -> Result<usize, CyberAPIError> {
     let mut records = Vec::new();
     for ele in data {
         let model = environments::ActiveModel::from_json(ele)?;
         records.push(model);
     }
-    Environments::insert_many(records).exec(&db).await?;
-    Ok(())
+    let count = records.len();
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        Environments::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(count)
 }
@@ -0,0 +1,289 @@
+use crate::entities::{histories, prelude::*};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use super::database::get_database;
+
+const HISTORY_PINNED: &str = "1";
+const HISTORY_UNPINNED: &str = "0";
+const HISTORY_PAGE_SIZE: u64 = 50;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct History {
+    pub id: String,
+    pub collection: Option<String>,
+    pub api: Option<String>,
+    pub environment: Option<String>,
+    pub method: Option<String>,
+    pub url: Option<String>,
+    // The request sent by the app, the secrets filled by the backend are not saved
+    pub request: Option<String>,
+    pub status: Option<i32>,
+    pub headers: Option<String>,
+    // The response body is gzip compressed and base64 encoded
+    pub body: Option<String>,
+    pub body_size: Option<i64>,
+    pub body_truncated: Option<String>,
+    pub latency: Option<i64>,
+    pub stats: Option<String>,
+    pub error: Option<String>,
+    pub pinned: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<histories::Model> for History {
+    fn from(model: histories::Model) -> Self {
+        History {
+            id: model.id,
+            collection: model.collection,
+            api: model.api,
+            environment: model.environment,
+            method: model.method,
+            url: model.url,
+            request: model.request,
+            status: model.status,
+            headers: model.headers,
+            body: model.body,
+            body_size: model.body_size,
+            body_truncated: model.body_truncated,
+            latency: model.latency,
+            stats: model.stats,
+            error: model.error,
+            pinned: model.pinned,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+impl History {
+    fn into_active_model(self) -> histories::ActiveModel {
+        let created_at = self.created_at.or_else(|| Some(Utc::now().to_rfc3339()));
+        let updated_at = self.updated_at.or_else(|| Some(Utc::now().to_rfc3339()));
+        histories::ActiveModel {
+            id: Set(self.id),
+            collection: Set(self.collection),
+            api: Set(self.api),
+            environment: Set(self.environment),
+            method: Set(self.method),
+            url: Set(self.url),
+            request: Set(self.request),
+            status: Set(self.status),
+            headers: Set(self.headers),
+            body: Set(self.body),
+            body_size: Set(self.body_size),
+            body_truncated: Set(self.body_truncated),
+            latency: Set(self.latency),
+            stats: Set(self.stats),
+            error: Set(self.error),
+            pinned: Set(self.pinned.or_else(|| Some(HISTORY_UNPINNED.to_string()))),
+            created_at: Set(created_at),
+            updated_at: Set(updated_at),
+        }
+    }
+}
+
+// The filters of history, the empty values are ignored
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryQuery {
+    pub collection: Option<String>,
+    pub api: Option<String>,
+    // The part of url, e.g. `/users`
+    pub url: Option<String>,
+    pub method: Option<String>,
+    pub status_min: Option<i32>,
+    pub status_max: Option<i32>,
+    // The range of created time, they are rfc3339 strings
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub pinned: Option<bool>,
+    // The page starts from 0
+    pub page: u64,
+    pub page_size: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub total: u64,
+    // The request and response body are not returned
+    pub items: Vec<History>,
+}
+
+pub fn get_histories_create_sql() -> String {
+    "CREATE TABLE IF NOT EXISTS histories (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        collection TEXT DEFAULT '',
+        api TEXT DEFAULT '',
+        environment TEXT DEFAULT '',
+        method TEXT DEFAULT '',
+        url TEXT DEFAULT '',
+        request TEXT DEFAULT '',
+        status INTEGER DEFAULT 0,
+        headers TEXT DEFAULT '',
+        body TEXT DEFAULT '',
+        body_size INTEGER DEFAULT 0,
+        body_truncated TEXT DEFAULT '0',
+        latency INTEGER DEFAULT 0,
+        stats TEXT DEFAULT '',
+        error TEXT DEFAULT '',
+        pinned TEXT DEFAULT '0',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    )"
+    .to_string()
+}
+
+pub fn get_histories_index_sql() -> String {
+    "CREATE INDEX IF NOT EXISTS idx_histories_created_at ON histories (created_at)".to_string()
+}
+
+pub async fn add_history(history: History) -> Result<History, DbErr> {
+    let model = history.into_active_model();
+    let db = get_database().await;
+    let result = model.insert(&db).await?;
+    Ok(result.into())
+}
+
+pub async fn get_history(id: String) -> Result<Option<History>, DbErr> {
+    let db = get_database().await;
+    let result = Histories::find_by_id(id).one(&db).await?;
+    Ok(result.map(History::from))
+}
+
+pub async fn list_history(query: HistoryQuery) -> Result<HistoryPage, DbErr> {
+    let db = get_database().await;
+    let not_empty = |value: Option<String>| value.filter(|value| !value.is_empty());
+    let mut select = Histories::find();
+    if let Some(collection) = not_empty(query.collection) {
+        select = select.filter(histories::Column::Collection.eq(collection));
+    }
+    if let Some(api) = not_empty(query.api) {
+        select = select.filter(histories::Column::Api.eq(api));
+    }
+    if let Some(url) = not_empty(query.url) {
+        select = select.filter(histories::Column::Url.contains(url));
+    }
+    if let Some(method) = not_empty(query.method) {
+        select = select.filter(histories::Column::Method.eq(method.to_uppercase()));
+    }
+    if let Some(status) = query.status_min {
+        select = select.filter(histories::Column::Status.gte(status));
+    }
+    if let Some(status) = query.status_max {
+        select = select.filter(histories::Column::Status.lte(status));
+    }
+    if let Some(from) = not_empty(query.from) {
+        select = select.filter(histories::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = not_empty(query.to) {
+        select = select.filter(histories::Column::CreatedAt.lte(to));
+    }
+    if let Some(pinned) = query.pinned {
+        let value = if pinned {
+            HISTORY_PINNED
+        } else {
+            HISTORY_UNPINNED
+        };
+        select = select.filter(histories::Column::Pinned.eq(value));
+    }
+    let page_size = if query.page_size == 0 {
+        HISTORY_PAGE_SIZE
+    } else {
+        query.page_size
+    };
+    let paginator = select
+        .order_by_desc(histories::Column::CreatedAt)
+        .paginate(&db, page_size);
+    let total = paginator.num_items().await?;
+    let items = paginator
+        .fetch_page(query.page)
+        .await?
+        .into_iter()
+        .map(|model| History {
+            request: None,
+            body: None,
+            ..History::from(model)
+        })
+        .collect();
+    Ok(HistoryPage { total, items })
+}
+
+pub async fn pin_history(id: String, pinned: bool) -> Result<u64, DbErr> {
+    let db = get_database().await;
+    let value = if pinned {
+        HISTORY_PINNED
+    } else {
+        HISTORY_UNPINNED
+    };
+    let result = Histories::update_many()
+        .col_expr(histories::Column::Pinned, Expr::value(value))
+        .col_expr(
+            histories::Column::UpdatedAt,
+            Expr::value(Utc::now().to_rfc3339()),
+        )
+        .filter(histories::Column::Id.eq(id))
+        .exec(&db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+pub async fn delete_histories(ids: Vec<String>) -> Result<u64, DbErr> {
+    let db = get_database().await;
+    let result = Histories::delete_many()
+        .filter(histories::Column::Id.is_in(ids))
+        .exec(&db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// Delete the histories of collection, the pinned histories are kept
+pub async fn delete_history_by_collection(collection: String) -> Result<u64, DbErr> {
+    let db = get_database().await;
+    let result = Histories::delete_many()
+        .filter(histories::Column::Collection.eq(collection))
+        .filter(histories::Column::Pinned.ne(HISTORY_PINNED))
+        .exec(&db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// Delete the histories created before the time and the ones out of max count,
+// the pinned histories are kept
+pub async fn prune_history(max_count: u64, before: Option<String>) -> Result<u64, DbErr> {
+    let db = get_database().await;
+    let mut count = 0;
+    if let Some(before) = before {
+        let result = Histories::delete_many()
+            .filter(histories::Column::Pinned.ne(HISTORY_PINNED))
+            .filter(histories::Column::CreatedAt.lt(before))
+            .exec(&db)
+            .await?;
+        count += result.rows_affected;
+    }
+    if max_count != 0 {
+        let ids: Vec<String> = Histories::find()
+            .select_only()
+            .column(histories::Column::Id)
+            .filter(histories::Column::Pinned.ne(HISTORY_PINNED))
+            .order_by_desc(histories::Column::CreatedAt)
+            // The offset of sqlite is invalid without limit
+            .limit(i64::MAX as u64)
+            .offset(max_count)
+            .into_tuple()
+            .all(&db)
+            .await?;
+        if !ids.is_empty() {
+            count += delete_histories(ids).await?;
+        }
+    }
+    Ok(count)
+}
@@ -0,0 +1,903 @@
+use crate::entities::{
+    api_collections, api_folders, api_settings, environments, prelude::*, proxies, variables,
+};
+use crate::error::CyberAPIError;
+use chrono::DateTime;
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait,
+    IntoActiveModel, QueryFilter, TransactionTrait,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::api_collection::get_table_name_api_collection;
+use super::api_folder::get_table_name_api_folder;
+use super::api_setting::get_table_name_api_setting;
+use super::api_tree::{API_TREE_ROOT, repair_api_tree};
+use super::database::{IMPORT_BATCH_SIZE, TablesCount, get_database, read_backup_tables};
+use super::environment::get_table_name_environment;
+use super::proxy::get_table_name_proxy;
+use super::secret::{check_secret_key, get_table_name_secret_key, import_secret_key, lock_secrets};
+use super::variable::{fill_secret_values, get_table_name_variable};
+
+// The fields reference the id of other rows
+const MERGE_REFERENCE_FIELDS: [&str; 3] = ["collection", "parent_id", "environment"];
+
+// How to handle the row which id exists but the data is different
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MergePolicy {
+    // Replace the existing row if the incoming one is updated later
+    #[default]
+    UpdateIfNewer,
+    // Keep the existing row
+    Keep,
+    // Insert the incoming row with a new id
+    Duplicate,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeItem {
+    // The id of incoming row
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTableDiff {
+    pub added: Vec<MergeItem>,
+    pub updated: Vec<MergeItem>,
+    // The existing rows are kept, or the incoming rows are duplicated by the policy
+    pub conflicting: Vec<MergeItem>,
+    pub unchanged: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTablesResult {
+    pub policy: MergePolicy,
+    pub tables: BTreeMap<String, MergeTableDiff>,
+}
+
+// The name and rows of each table in backup
+type BackupTables = Vec<(String, Vec<Value>)>;
+
+struct MergeTable {
+    name: String,
+    diff: MergeTableDiff,
+    inserts: Vec<Value>,
+    updates: Vec<Value>,
+}
+
+fn get_id(row: &Value) -> String {
+    row.get("id")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn to_merge_item(row: &Value) -> MergeItem {
+    MergeItem {
+        id: get_id(row),
+        name: row
+            .get("name")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+// Convert the row to the same format as the model of table
+fn normalize_row<M: Serialize + DeserializeOwned>(row: Value) -> Result<Value, CyberAPIError> {
+    let model: M = serde_json::from_value(row)?;
+    Ok(serde_json::to_value(model)?)
+}
+
+async fn list_rows<E, C>(db: &C) -> Result<HashMap<String, Value>, CyberAPIError>
+where
+    E: EntityTrait,
+    E::Model: Serialize,
+    C: ConnectionTrait,
+{
+    let mut rows = HashMap::new();
+    for model in E::find().all(db).await? {
+        let row = serde_json::to_value(model)?;
+        rows.insert(get_id(&row), row);
+    }
+    Ok(rows)
+}
+
+async fn write_rows<A, C>(
+    db: &C,
+    inserts: Vec<Value>,
+    updates: Vec<Value>,
+) -> Result<(), CyberAPIError>
+where
+    A: ActiveModelTrait + ActiveModelBehavior + Send,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A> + DeserializeOwned,
+    C: ConnectionTrait,
+{
+    let mut records = Vec::new();
+    for row in inserts {
+        records.push(A::from_json(row)?);
+    }
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        <A::Entity as EntityTrait>::insert_many(chunk.to_vec())
+            .exec(db)
+            .await?;
+    }
+    for row in updates {
+        <A::Entity as EntityTrait>::update(A::from_json(row)?)
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+fn is_newer(incoming: &Value, existing: &Value) -> bool {
+    let get = |row: &Value| {
+        row.get("updated_at")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let (incoming, existing) = (get(incoming), get(existing));
+    match (
+        DateTime::parse_from_rfc3339(&incoming),
+        DateTime::parse_from_rfc3339(&existing),
+    ) {
+        (Ok(incoming), Ok(existing)) => incoming > existing,
+        _ => incoming > existing,
+    }
+}
+
+fn remap_references(row: &mut Value, ids: &HashMap<String, String>) {
+    for field in MERGE_REFERENCE_FIELDS {
+        let Some(new_id) = row
+            .get(field)
+            .and_then(|value| value.as_str())
+            .and_then(|value| ids.get(value))
+        else {
+            continue;
+        };
+        row[field] = Value::String(new_id.clone());
+    }
+}
+
+// The tree of folders is saved as children by the old versions,
+// convert it to the parent of items before comparing
+fn apply_legacy_children(tables: &mut [(String, Vec<Value>)]) {
+    let mut parents = HashMap::new();
+    for (name, data) in tables.iter() {
+        if *name != get_table_name_api_folder() {
+            continue;
+        }
+        for row in data {
+            let id = get_id(row);
+            let children = row
+                .get("children")
+                .and_then(|value| value.as_str())
+                .unwrap_or_default();
+            let children = children
+                .split(',')
+                .map(|item| item.trim())
+                .filter(|item| !item.is_empty() && *item != id);
+            for (index, child) in children.enumerate() {
+                parents
+                    .entry(child.to_string())
+                    .or_insert((id.clone(), index));
+            }
+        }
+    }
+    if parents.is_empty() {
+        return;
+    }
+    for (name, data) in tables.iter_mut() {
+        if *name != get_table_name_api_folder() && *name != get_table_name_api_setting() {
+            continue;
+        }
+        for row in data.iter_mut() {
+            let has_parent = row
+                .get("parent_id")
+                .and_then(|value| value.as_str())
+                .is_some_and(|value| !value.is_empty());
+            if has_parent {
+                continue;
+            }
+            if let Some((parent_id, index)) = parents.get(&get_id(row)) {
+                row["parent_id"] = Value::String(parent_id.clone());
+                row["sort_order"] = Value::from(*index);
+            }
+        }
+    }
+}
+
+// The old versions have no position of top level items, so the position is kept
+fn fill_tree_fields(row: &mut Value, existing: Option<&Value>) {
+    if row.get("parent_id").is_some_and(|value| value.is_null()) {
+        row["parent_id"] = Value::String(API_TREE_ROOT.to_string());
+    }
+    let sort_order = existing
+        .and_then(|item| item.get("sort_order"))
+        .filter(|_| row.get("sort_order").is_some_and(|value| value.is_null()));
+    if let Some(sort_order) = sort_order {
+        row["sort_order"] = sort_order.clone();
+    }
+}
+
+async fn list_table_rows<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+) -> Result<HashMap<String, Value>, CyberAPIError> {
+    match name {
+        n if n == get_table_name_api_collection() => list_rows::<ApiCollections, C>(db).await,
+        n if n == get_table_name_api_folder() => list_rows::<ApiFolders, C>(db).await,
+        n if n == get_table_name_api_setting() => list_rows::<ApiSettings, C>(db).await,
+        n if n == get_table_name_environment() => list_rows::<Environments, C>(db).await,
+        n if n == get_table_name_proxy() => list_rows::<Proxies, C>(db).await,
+        n if n == get_table_name_variable() => list_rows::<Variables, C>(db).await,
+        _ => Ok(HashMap::new()),
+    }
+}
+
+fn normalize_table_row(name: &str, row: Value) -> Result<Value, CyberAPIError> {
+    match name {
+        n if n == get_table_name_api_collection() => normalize_row::<api_collections::Model>(row),
+        n if n == get_table_name_api_folder() => normalize_row::<api_folders::Model>(row),
+        n if n == get_table_name_api_setting() => normalize_row::<api_settings::Model>(row),
+        n if n == get_table_name_environment() => normalize_row::<environments::Model>(row),
+        n if n == get_table_name_proxy() => normalize_row::<proxies::Model>(row),
+        n if n == get_table_name_variable() => normalize_row::<variables::Model>(row),
+        _ => Ok(row),
+    }
+}
+
+// Read the tables of backup, the workspace keys are returned separately
+// because they are not merged as rows
+fn read_merge_tables(
+    filename: &str,
+    passphrase: Option<&str>,
+) -> Result<(BackupTables, Vec<Value>), CyberAPIError> {
+    let (keys, mut tables): (Vec<_>, Vec<_>) = read_backup_tables(filename, passphrase)?
+        .into_iter()
+        .partition(|(name, _)| *name == get_table_name_secret_key());
+    apply_legacy_children(&mut tables);
+    let keys = keys.into_iter().flat_map(|(_, data)| data).collect();
+    Ok((tables, keys))
+}
+
+async fn plan_merge<C: ConnectionTrait>(
+    db: &C,
+    tables: BackupTables,
+    policy: MergePolicy,
+) -> Result<Vec<MergeTable>, CyberAPIError> {
+    let mut incoming = vec![];
+    for (name, mut data) in tables {
+        // The secrets left out of the backup are the same as the database
+        if name == get_table_name_variable() {
+            fill_secret_values(db, &mut data).await?;
+        }
+        let existing = list_table_rows(db, &name).await?;
+        let mut rows = vec![];
+        for row in data {
+            let mut row = normalize_table_row(&name, row)?;
+            let id = get_id(&row);
+            fill_tree_fields(&mut row, existing.get(&id));
+            rows.push(row);
+        }
+        incoming.push((name, rows, existing));
+    }
+
+    // The conflicting rows get new ids, and the rows reference them are changed too,
+    // so repeat until no more row is changed
+    let mut new_ids = HashMap::new();
+    if policy == MergePolicy::Duplicate {
+        loop {
+            let mut changed = false;
+            for (_, rows, existing) in incoming.iter() {
+                for row in rows {
+                    let id = get_id(row);
+                    if new_ids.contains_key(&id) {
+                        continue;
+                    }
+                    let mut row = row.clone();
+                    remap_references(&mut row, &new_ids);
+                    if existing.get(&id).is_some_and(|current| *current != row) {
+                        new_ids.insert(id, ulid::Ulid::new().to_string());
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    let mut result = vec![];
+    for (name, rows, existing) in incoming {
+        let mut table = MergeTable {
+            name,
+            diff: MergeTableDiff::default(),
+            inserts: vec![],
+            updates: vec![],
+        };
+        let mut ids = HashSet::new();
+        for mut row in rows {
+            let id = get_id(&row);
+            ids.insert(id.clone());
+            remap_references(&mut row, &new_ids);
+            let item = to_merge_item(&row);
+            if let Some(new_id) = new_ids.get(&id) {
+                row["id"] = Value::String(new_id.clone());
+                table.diff.conflicting.push(item);
+                table.inserts.push(row);
+                continue;
+            }
+            let Some(current) = existing.get(&id) else {
+                table.diff.added.push(item);
+                table.inserts.push(row);
+                continue;
+            };
+            if *current == row {
+                table.diff.unchanged += 1;
+            } else if policy == MergePolicy::UpdateIfNewer && is_newer(&row, current) {
+                table.diff.updated.push(item);
+                table.updates.push(row);
+            } else {
+                table.diff.conflicting.push(item);
+            }
+        }
+        result.push(table);
+    }
+    Ok(result)
+}
+
+fn to_merge_result(policy: MergePolicy, tables: &[MergeTable]) -> MergeTablesResult {
+    MergeTablesResult {
+        policy,
+        tables: tables
+            .iter()
+            .map(|table| (table.name.clone(), table.diff.clone()))
+            .collect(),
+    }
+}
+
+async fn write_table_rows<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+    inserts: Vec<Value>,
+    updates: Vec<Value>,
+) -> Result<(), CyberAPIError> {
+    match name {
+        n if n == get_table_name_api_collection() => {
+            write_rows::<api_collections::ActiveModel, _>(db, inserts, updates).await
+        }
+        n if n == get_table_name_api_folder() => {
+            write_rows::<api_folders::ActiveModel, _>(db, inserts, updates).await
+        }
+        n if n == get_table_name_api_setting() => {
+            write_rows::<api_settings::ActiveModel, _>(db, inserts, updates).await
+        }
+        n if n == get_table_name_environment() => {
+            write_rows::<environments::ActiveModel, _>(db, inserts, updates).await
+        }
+        n if n == get_table_name_proxy() => {
+            write_rows::<proxies::ActiveModel, _>(db, inserts, updates).await
+        }
+        n if n == get_table_name_variable() => {
+            write_rows::<variables::ActiveModel, _>(db, inserts, updates).await
+        }
+        _ => Ok(()),
+    }
+}
+
+// Compare the backup file with the database without any change
+pub async fn diff_tables(
+    filename: String,
+    passphrase: Option<String>,
+    policy: MergePolicy,
+) -> Result<MergeTablesResult, CyberAPIError> {
+    let (tables, _) = read_merge_tables(&filename, passphrase.as_deref())?;
+    let db = get_database().await;
+    let tables = plan_merge(&db, tables, policy).await?;
+    Ok(to_merge_result(policy, &tables))
+}
+
+// Merge the backup file into the database, the existing rows not in the file are kept,
+// and the workspace key of backup is only saved if the database has none.
+// It is refused if the secrets of backup are encrypted by another workspace key,
+// because they could not be decrypted after merged
+pub async fn merge_tables(
+    filename: String,
+    passphrase: Option<String>,
+    policy: MergePolicy,
+) -> Result<MergeTablesResult, CyberAPIError> {
+    let (tables, keys) = read_merge_tables(&filename, passphrase.as_deref())?;
+    let db = get_database().await;
+    let txn = db.begin().await?;
+    let values: Vec<&str> = tables
+        .iter()
+        .filter(|(name, _)| *name == get_table_name_variable())
+        .flat_map(|(_, data)| data)
+        .filter_map(|row| row.get("value").and_then(|value| value.as_str()))
+        .collect();
+    check_secret_key(&txn, &keys, &values).await?;
+    let tables = plan_merge(&txn, tables, policy).await?;
+    let result = to_merge_result(policy, &tables);
+    for table in tables {
+        write_table_rows(&txn, &table.name, table.inserts, table.updates).await?;
+    }
+    // The parent of merged items may not exist
+    repair_api_tree(&txn, vec![]).await?;
+    let key_count = import_secret_key(&txn, keys, false).await?;
+    txn.commit().await?;
+    if key_count != 0 {
+        lock_secrets();
+    }
+    Ok(result)
+}
+
+// Import the collections exported by export_collections as new data,
+// all the ids are changed so it never conflicts with the existing data
+pub async fn import_collections(
+    filename: String,
+    passphrase: Option<String>,
+) -> Result<TablesCount, CyberAPIError> {
+    let mut tables = read_backup_tables(&filename, passphrase.as_deref())?;
+    apply_legacy_children(&mut tables);
+    // The proxies and workspace keys do not belong to any collection
+    tables.retain(|(name, _)| {
+        *name != get_table_name_proxy() && *name != get_table_name_secret_key()
+    });
+
+    let mut new_ids = HashMap::new();
+    for (_, data) in tables.iter() {
+        for row in data {
+            new_ids.insert(get_id(row), ulid::Ulid::new().to_string());
+        }
+    }
+
+    let db = get_database().await;
+    let txn = db.begin().await?;
+    let mut result = TablesCount::default();
+    for (name, data) in tables {
+        let mut rows = vec![];
+        for row in data {
+            let mut row = normalize_table_row(&name, row)?;
+            remap_references(&mut row, &new_ids);
+            if let Some(new_id) = new_ids.get(&get_id(&row)) {
+                row["id"] = Value::String(new_id.clone());
+            }
+            rows.push(row);
+        }
+        result.tables.insert(name.clone(), rows.len());
+        write_table_rows(&txn, &name, rows, vec![]).await?;
+    }
+    repair_api_tree(&txn, vec![]).await?;
+    txn.commit().await?;
+    Ok(result)
+}
+
+// Replace all the data of the collection with the rows, the rows of other collections are kept
+pub(super) async fn replace_collection_tables(
+    collection: &str,
+    tables: Vec<(String, Vec<Value>)>,
+) -> Result<TablesCount, CyberAPIError> {
+    let db = get_database().await;
+    let txn = db.begin().await?;
+    ApiCollections::delete_by_id(collection).exec(&txn).await?;
+    ApiFolders::delete_many()
+        .filter(api_folders::Column::Collection.eq(collection))
+        .exec(&txn)
+        .await?;
+    ApiSettings::delete_many()
+        .filter(api_settings::Column::Collection.eq(collection))
+        .exec(&txn)
+        .await?;
+    Environments::delete_many()
+        .filter(environments::Column::Collection.eq(collection))
+        .exec(&txn)
+        .await?;
+    Variables::delete_many()
+        .filter(variables::Column::Collection.eq(collection))
+        .exec(&txn)
+        .await?;
+
+    let mut result = TablesCount::default();
+    for (name, data) in tables {
+        let mut rows = vec![];
+        for row in data {
+            rows.push(normalize_table_row(&name, row)?);
+        }
+        result.tables.insert(name.clone(), rows.len());
+        write_table_rows(&txn, &name, rows, vec![]).await?;
+    }
+    repair_api_tree(&txn, vec![]).await?;
+    txn.commit().await?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{export_collections, run_with_database};
+    use sea_orm::Statement;
+    use serde_json::json;
+    use std::io::{Cursor, Write};
+    use std::path::Path;
+
+    fn write_backup(file: &Path, tables: &[(&str, Value)]) {
+        let mut w = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in tables {
+            w.start_file(format!("{name}.json"), Default::default())
+                .unwrap();
+            w.write_all(data.to_string().as_bytes()).unwrap();
+        }
+        std::fs::write(file, w.finish().unwrap().into_inner()).unwrap();
+    }
+
+    async fn query_rows(sql: &str) -> Vec<(String, String, String)> {
+        let db = get_database().await;
+        let rows = db
+            .query_all(Statement::from_string(
+                db.get_database_backend(),
+                sql.to_string(),
+            ))
+            .await
+            .unwrap();
+        rows.iter()
+            .map(|row| {
+                (
+                    row.try_get_by_index(0).unwrap(),
+                    row.try_get_by_index(1).unwrap(),
+                    row.try_get_by_index(2).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn ids(items: &[MergeItem]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    fn folder(name: &str, updated_at: &str) -> Value {
+        json!({
+            "id": "merge-f1",
+            "collection": "merge-c",
+            "name": name,
+            "parent_id": "",
+            "sort_order": 0,
+            "created_at": "2023-01-01T00:00:00Z",
+            "updated_at": updated_at,
+        })
+    }
+
+    fn setting(id: &str, name: &str, sort_order: i32, updated_at: &str) -> Value {
+        json!({
+            "id": id,
+            "collection": "merge-c",
+            "name": name,
+            "category": "http",
+            "setting": "",
+            "parent_id": "merge-f1",
+            "sort_order": sort_order,
+            "created_at": "2023-01-01T00:00:00Z",
+            "updated_at": updated_at,
+        })
+    }
+
+    #[test]
+    fn merge_diff() {
+        run_with_database(async {
+            let db = get_database().await;
+            let existing = [
+                (
+                    get_table_name_api_collection(),
+                    json!({
+                        "id": "merge-c",
+                        "name": "Merge",
+                        "description": "",
+                        "created_at": "2023-01-01T00:00:00Z",
+                        "updated_at": "2023-01-01T00:00:00Z",
+                    }),
+                ),
+                (
+                    get_table_name_api_folder(),
+                    folder("Folder", "2023-01-01T00:00:00Z"),
+                ),
+                (
+                    get_table_name_api_setting(),
+                    setting("merge-s1", "Users", 0, "2023-01-02T00:00:00Z"),
+                ),
+                (
+                    get_table_name_api_setting(),
+                    setting("merge-s2", "Pets", 1, "2023-01-02T00:00:00Z"),
+                ),
+            ];
+            for (name, row) in existing.iter() {
+                write_table_rows(&db, name, vec![row.clone()], vec![])
+                    .await
+                    .unwrap();
+            }
+
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join("merge.zip");
+            write_backup(
+                &file,
+                &[
+                    ("api_collections", json!([existing[0].1])),
+                    (
+                        "api_folders",
+                        // The position of old versions is kept
+                        json!([{
+                            "id": "merge-f1",
+                            "collection": "merge-c",
+                            "name": "Folder 2",
+                            "created_at": "2023-01-01T00:00:00Z",
+                            "updated_at": "2024-01-01T00:00:00+08:00",
+                        }]),
+                    ),
+                    (
+                        "api_settings",
+                        json!([
+                            setting("merge-s1", "Old", 0, "2022-01-01T00:00:00Z"),
+                            existing[3].1,
+                            setting("merge-s3", "Orders", 2, "2022-01-01T00:00:00Z"),
+                        ]),
+                    ),
+                ],
+            );
+            let filename = file.to_string_lossy().to_string();
+
+            let result = diff_tables(filename.clone(), None, MergePolicy::UpdateIfNewer)
+                .await
+                .unwrap();
+            let collections = &result.tables["api_collections"];
+            assert_eq!(1, collections.unchanged);
+            let folders = &result.tables["api_folders"];
+            assert_eq!(vec!["merge-f1"], ids(&folders.updated));
+            assert_eq!("Folder 2", folders.updated[0].name);
+            let settings = &result.tables["api_settings"];
+            assert_eq!(vec!["merge-s3"], ids(&settings.added));
+            assert_eq!(vec!["merge-s1"], ids(&settings.conflicting));
+            assert!(settings.updated.is_empty());
+            assert_eq!(1, settings.unchanged);
+
+            let result = diff_tables(filename.clone(), None, MergePolicy::Keep)
+                .await
+                .unwrap();
+            let folders = &result.tables["api_folders"];
+            assert!(folders.updated.is_empty());
+            assert_eq!(vec!["merge-f1"], ids(&folders.conflicting));
+
+            // The rows reference the duplicated folder are duplicated too
+            let result = diff_tables(filename.clone(), None, MergePolicy::Duplicate)
+                .await
+                .unwrap();
+            assert_eq!(
+                vec!["merge-f1"],
+                ids(&result.tables["api_folders"].conflicting)
+            );
+            let settings = &result.tables["api_settings"];
+            assert_eq!(vec!["merge-s1", "merge-s2"], ids(&settings.conflicting));
+            assert_eq!(vec!["merge-s3"], ids(&settings.added));
+            assert_eq!(0, settings.unchanged);
+
+            // The dry run does not change anything
+            assert_eq!(
+                vec![("merge-f1".to_string(), "Folder".to_string(), "".to_string())],
+                query_rows(
+                    "SELECT id, name, parent_id FROM api_folders WHERE collection = 'merge-c'"
+                )
+                .await
+            );
+
+            merge_tables(filename.clone(), None, MergePolicy::UpdateIfNewer)
+                .await
+                .unwrap();
+            assert_eq!(
+                vec![
+                    ("merge-s1".to_string(), "Users".to_string(), "merge-f1".to_string()),
+                    ("merge-s2".to_string(), "Pets".to_string(), "merge-f1".to_string()),
+                    ("merge-s3".to_string(), "Orders".to_string(), "merge-f1".to_string()),
+                ],
+                query_rows(
+                    "SELECT id, name, parent_id FROM api_settings WHERE collection = 'merge-c' ORDER BY id"
+                )
+                .await
+            );
+            assert_eq!(
+                vec![(
+                    "merge-f1".to_string(),
+                    "Folder 2".to_string(),
+                    "".to_string()
+                )],
+                query_rows(
+                    "SELECT id, name, parent_id FROM api_folders WHERE collection = 'merge-c'"
+                )
+                .await
+            );
+        });
+    }
+
+    // The imported collection is a copy with new ids, the references are changed to them
+    #[test]
+    fn import_collections_remap() {
+        run_with_database(async {
+            let db = get_database().await;
+            let rows = [
+                (
+                    get_table_name_api_collection(),
+                    json!({"id": "remap-c", "name": "Remap"}),
+                ),
+                (
+                    get_table_name_api_folder(),
+                    json!({"id": "remap-f1", "collection": "remap-c", "name": "Folder", "parent_id": ""}),
+                ),
+                (
+                    get_table_name_api_setting(),
+                    json!({"id": "remap-s1", "collection": "remap-c", "name": "Users", "parent_id": "remap-f1"}),
+                ),
+                (
+                    get_table_name_environment(),
+                    json!({"id": "remap-e1", "collection": "remap-c", "name": "dev"}),
+                ),
+                (
+                    get_table_name_variable(),
+                    json!({
+                        "id": "remap-v1",
+                        "category": "env",
+                        "collection": "remap-c",
+                        "environment": "remap-e1",
+                        "name": "host",
+                        "value": "localhost",
+                    }),
+                ),
+            ];
+            for (name, row) in rows {
+                write_table_rows(
+                    &db,
+                    &name,
+                    vec![normalize_table_row(&name, row).unwrap()],
+                    vec![],
+                )
+                .await
+                .unwrap();
+            }
+
+            let dir = tempfile::tempdir().unwrap();
+            let filename = dir.path().join("remap.zip").to_string_lossy().to_string();
+            export_collections(vec!["remap-c".to_string()], filename.clone(), None)
+                .await
+                .unwrap();
+            for _ in 0..2 {
+                let result = import_collections(filename.clone(), None).await.unwrap();
+                assert_eq!(
+                    BTreeMap::from([
+                        ("api_collections".to_string(), 1),
+                        ("api_folders".to_string(), 1),
+                        ("api_settings".to_string(), 1),
+                        ("environments".to_string(), 1),
+                        ("variables".to_string(), 1),
+                    ]),
+                    result.tables
+                );
+            }
+
+            let collections = query_rows(
+                "SELECT id, name, '' FROM api_collections WHERE name = 'Remap' ORDER BY id",
+            )
+            .await;
+            assert_eq!(3, collections.len());
+            let copies: Vec<&str> = collections
+                .iter()
+                .map(|(id, _, _)| id.as_str())
+                .filter(|id| *id != "remap-c")
+                .collect();
+            assert_eq!(2, copies.len());
+            for collection in copies {
+                let folders = query_rows(&format!(
+                    "SELECT id, name, parent_id FROM api_folders WHERE collection = '{collection}'"
+                ))
+                .await;
+                assert_eq!(1, folders.len());
+                let folder = &folders[0].0;
+                assert_ne!("remap-f1", folder);
+                let settings = query_rows(&format!(
+                    "SELECT id, name, parent_id FROM api_settings WHERE collection = '{collection}'"
+                ))
+                .await;
+                assert_eq!(1, settings.len());
+                assert_ne!("remap-s1", settings[0].0);
+                assert_eq!(folder, &settings[0].2);
+
+                let environments = query_rows(&format!(
+                    "SELECT id, name, '' FROM environments WHERE collection = '{collection}'"
+                ))
+                .await;
+                assert_eq!(1, environments.len());
+                assert_ne!("remap-e1", environments[0].0);
+                let variables = query_rows(&format!(
+                    "SELECT id, value, environment FROM variables WHERE collection = '{collection}'"
+                ))
+                .await;
+                assert_eq!(1, variables.len());
+                assert_ne!("remap-v1", variables[0].0);
+                assert_eq!("localhost", variables[0].1);
+                assert_eq!(environments[0].0, variables[0].2);
+            }
+        });
+    }
+
+    // The secrets of backup encrypted by another workspace key are refused
+    #[test]
+    fn merge_secret_key() {
+        run_with_database(async {
+            use crate::crypto;
+            use crate::schemas::secret::encrypt_secret;
+            use crate::schemas::unlock_secrets;
+            use base64::{Engine as _, engine::general_purpose};
+
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join("secret.zip");
+            let filename = file.to_string_lossy().to_string();
+            // The backup key is another one, or the same one wrapped by another passphrase
+            let write = |value: String| {
+                let key = crypto::encrypt(&crypto::generate_key(), "other").unwrap();
+                write_backup(
+                    &file,
+                    &[
+                        (
+                            "secret_keys",
+                            json!([{"id": "workspace", "value": general_purpose::STANDARD.encode(key)}]),
+                        ),
+                        (
+                            "variables",
+                            json!([{
+                                "id": "merge-secret-v1",
+                                "category": "env",
+                                "collection": "merge-secret",
+                                "name": "token",
+                                "value": value,
+                                "secret": "1",
+                            }]),
+                        ),
+                    ],
+                );
+            };
+            let query = || {
+                query_rows(
+                    "SELECT id, name, collection FROM variables WHERE collection = 'merge-secret'",
+                )
+            };
+
+            unlock_secrets("master".to_string()).await.unwrap();
+            let data = crypto::encrypt_with_key(b"token", &crypto::generate_key()).unwrap();
+            write(format!("enc:v1:{}", general_purpose::STANDARD.encode(data)));
+            let err = merge_tables(filename.clone(), None, MergePolicy::UpdateIfNewer)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("another workspace key"));
+            lock_secrets();
+            let err = merge_tables(filename.clone(), None, MergePolicy::UpdateIfNewer)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("unlock the secrets"));
+            assert!(query().await.is_empty());
+
+            unlock_secrets("master".to_string()).await.unwrap();
+            write(encrypt_secret("token").unwrap());
+            merge_tables(filename.clone(), None, MergePolicy::UpdateIfNewer)
+                .await
+                .unwrap();
+            assert_eq!(1, query().await.len());
+            let workspace = query_rows("SELECT id, value, '' FROM secret_keys").await;
+            assert_eq!(1, workspace.len());
+        });
+    }
+}
@@ -1,34 +1,77 @@
 mod api_collection;
 mod api_folder;
 mod api_setting;
+mod api_tree;
 mod database;
 mod environment;
+mod history;
+mod merge;
 mod proxy;
+mod search;
+mod secret;
 mod variable;
 mod version;
+mod workspace;
 
-pub(crate) use database::resolve_db_file;
-pub use database::{export_tables, import_tables, init_tables};
+pub(crate) use api_folder::add_api_folder_with;
+pub(crate) use api_setting::{add_api_setting_with, update_api_setting_with};
+#[cfg(test)]
+pub(crate) use database::run_with_database;
+pub use database::{
+    TablesCount, export_collections, export_tables, export_tables_to_file, import_tables,
+    init_tables,
+};
+pub(crate) use database::{get_database, resolve_db_file};
+pub(crate) use environment::{add_environment_with, list_environment_with};
+pub(crate) use variable::{add_variable_with, list_variable_with, update_variable_with};
 
 pub use api_collection::{
     APICollection, add_api_collection, delete_api_collection, list_api_collection,
     update_api_collection,
 };
 pub use api_folder::{
-    APIFolder, APIFolderChildren, add_api_folder, delete_api_folder_by_collection,
-    delete_api_folders, list_api_folder, list_api_folder_all_children, update_api_folder,
+    APIFolder, add_api_folder, delete_api_folder_by_collection, delete_api_folders, get_api_folder,
+    list_api_folder, update_api_folder,
 };
 pub use api_setting::{
     APISetting, add_api_setting, delete_api_setting_by_collection, delete_api_settings,
-    list_api_setting, update_api_setting,
+    get_api_setting, list_api_setting, update_api_setting,
+};
+
+pub use api_tree::{
+    API_TREE_ROOT, APIFolderChildren, list_api_children, list_api_folder_all_children,
+    move_api_item, reorder_api_items,
 };
 
 pub use environment::{
-    Environment, add_environment, delete_environment, list_environment, update_environment,
+    ENVIRONMENT_ENABLED, Environment, add_environment, delete_environment, list_environment,
+    update_environment,
 };
 
+pub use history::{
+    History, HistoryPage, HistoryQuery, add_history, delete_histories,
+    delete_history_by_collection, get_history, list_history, pin_history, prune_history,
+};
+
+pub use merge::{MergePolicy, MergeTablesResult, diff_tables, import_collections, merge_tables};
+
 pub use proxy::{Proxy, add_proxy, delete_proxy, list_proxy, update_proxy};
 
-pub use variable::{Variable, add_variable, delete_variable, list_variable, update_variable};
+pub use search::{SearchHit, SearchQuery, search};
+
+pub use secret::{
+    SecretStatus, change_secret_passphrase, get_secret_status, lock_secrets, unlock_secrets,
+};
+
+pub use variable::{
+    VARIABLE_CATEGORY_CUSTOMIZE, VARIABLE_CATEGORY_ENVIRONMENT,
+    VARIABLE_CATEGORY_GLOBAL_REQ_HEADERS, VARIABLE_ENABLED, Variable, add_variable,
+    delete_variable, list_decrypted_variable, list_variable, reveal_variable, update_variable,
+};
 
 pub use version::{Version, add_version, get_latest_version};
+
+pub use workspace::{
+    WorkspaceExportResult, WorkspaceFormat, WorkspaceImportResult, export_workspace,
+    import_workspace,
+};
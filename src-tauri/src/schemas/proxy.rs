@@ -3,10 +3,13 @@ use crate::{
     error::CyberAPIError,
 };
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
 use serde::{Deserialize, Serialize};
 
-use super::database::{ExportData, get_database};
+use super::database::{ExportData, IMPORT_BATCH_SIZE, get_database};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -99,9 +102,8 @@ pub fn get_table_name_proxy() -> String {
     "proxies".to_string()
 }
 
-pub async fn delete_all_proxy() -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-    Proxies::delete_many().exec(&db).await?;
+pub async fn delete_all_proxy<C: ConnectionTrait>(db: &C) -> Result<(), CyberAPIError> {
+    Proxies::delete_many().exec(db).await?;
     Ok(())
 }
 
@@ -114,14 +116,18 @@ pub async fn export_proxy() -> Result<ExportData, DbErr> {
     })
 }
 
-pub async fn import_proxy(data: Vec<serde_json::Value>) -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
+pub async fn import_proxy<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+) -> Result<usize, CyberAPIError> {
     let mut records = Vec::new();
     for ele in data {
         let model = proxies::ActiveModel::from_json(ele)?;
         records.push(model);
     }
-    Proxies::insert_many(records).exec(&db).await?;
-    Ok(())
+    let count = records.len();
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        Proxies::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(count)
 }
@@ -0,0 +1,444 @@
+use sea_orm::{ConnectionTrait, DbErr, Statement};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::api_collection::list_api_collection;
+use super::api_folder::list_api_folder;
+use super::api_tree::{API_TREE_ROOT, list_api_children};
+use super::database::get_database;
+
+const SEARCH_KIND_COLLECTION: &str = "collection";
+const SEARCH_KIND_FOLDER: &str = "folder";
+const SEARCH_KIND_SETTING: &str = "setting";
+const SEARCH_LIMIT: u64 = 50;
+// The indexed tables and the kind of their rows
+const SEARCH_TABLES: [(&str, &str); 3] = [
+    ("api_collections", SEARCH_KIND_COLLECTION),
+    ("api_folders", SEARCH_KIND_FOLDER),
+    ("api_settings", SEARCH_KIND_SETTING),
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchQuery {
+    pub keyword: String,
+    // Search in all collections if it is empty
+    pub collection: Option<String>,
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    // collection, folder or setting
+    pub kind: String,
+    pub id: String,
+    pub collection: String,
+    pub collection_name: String,
+    // The names of folders from the top level to the parent
+    pub path: Vec<String>,
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    // The matched text, the keywords are wrapped with `[` and `]`
+    pub snippet: String,
+    // The smaller is the better
+    pub score: f64,
+}
+
+// Only the text columns are indexed, the others are used for the result
+pub fn get_search_index_create_sql() -> Vec<String> {
+    let mut sql_list = vec![
+        "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5 (
+            kind UNINDEXED,
+            id UNINDEXED,
+            collection UNINDEXED,
+            method UNINDEXED,
+            name,
+            url,
+            headers,
+            body,
+            tokenize = 'unicode61'
+        )"
+        .to_string(),
+    ];
+    // The index is kept in sync by triggers, so all the writes are covered
+    for (table, kind) in SEARCH_TABLES {
+        let insert = get_search_index_insert_sql(table, kind, "NEW");
+        let delete = format!("DELETE FROM search_index WHERE kind = '{kind}' AND id = OLD.id;");
+        sql_list.push(format!(
+            "CREATE TRIGGER IF NOT EXISTS {table}_search_insert AFTER INSERT ON {table} BEGIN {insert} END"
+        ));
+        sql_list.push(format!(
+            "CREATE TRIGGER IF NOT EXISTS {table}_search_update AFTER UPDATE ON {table} BEGIN {delete} {insert} END"
+        ));
+        sql_list.push(format!(
+            "CREATE TRIGGER IF NOT EXISTS {table}_search_delete AFTER DELETE ON {table} BEGIN {delete} END"
+        ));
+    }
+    sql_list
+}
+
+// The json field of setting, the invalid json is ignored
+fn setting_field(row: &str, expr: &str) -> String {
+    format!("CASE WHEN json_valid({row}.setting) THEN IFNULL({expr}, '') ELSE '' END")
+}
+
+// The values of key and value of setting's list field, e.g. headers
+fn setting_kv_field(row: &str, field: &str, separator: &str) -> String {
+    setting_field(
+        row,
+        &format!(
+            "(SELECT group_concat(json_extract(value, '$.key') || '{separator}' || json_extract(value, '$.value'), ' ') \
+            FROM json_each({row}.setting, '$.{field}') WHERE json_type(value) = 'object')"
+        ),
+    )
+}
+
+// The insert sql of search index, the row is `NEW` for trigger or the table name for rebuilding
+fn get_search_index_insert_sql(table: &str, kind: &str, row: &str) -> String {
+    let (method, url, headers, body) = match kind {
+        SEARCH_KIND_SETTING => (
+            setting_field(row, &format!("json_extract({row}.setting, '$.method')")),
+            format!(
+                "TRIM({} || ' ' || {})",
+                setting_field(row, &format!("json_extract({row}.setting, '$.uri')")),
+                setting_kv_field(row, "query", "=")
+            ),
+            setting_kv_field(row, "headers", ": "),
+            setting_field(row, &format!("json_extract({row}.setting, '$.body')")),
+        ),
+        SEARCH_KIND_COLLECTION => (
+            "''".to_string(),
+            "''".to_string(),
+            "''".to_string(),
+            format!("IFNULL({row}.description, '')"),
+        ),
+        _ => (
+            "''".to_string(),
+            "''".to_string(),
+            "''".to_string(),
+            "''".to_string(),
+        ),
+    };
+    let values = format!(
+        "'{kind}', {row}.id, {collection}, {method}, IFNULL({row}.name, ''), {url}, {headers}, {body}",
+        collection = if kind == SEARCH_KIND_COLLECTION {
+            format!("{row}.id")
+        } else {
+            format!("{row}.collection")
+        },
+    );
+    let columns = "kind, id, collection, method, name, url, headers, body";
+    if row == table {
+        format!("INSERT INTO search_index ({columns}) SELECT {values} FROM {table};")
+    } else {
+        format!("INSERT INTO search_index ({columns}) VALUES ({values});")
+    }
+}
+
+// Rebuild the search index from the data created before it
+pub fn get_search_index_rebuild_sql() -> Vec<String> {
+    let mut sql_list = vec!["DELETE FROM search_index".to_string()];
+    for (table, kind) in SEARCH_TABLES {
+        sql_list.push(get_search_index_insert_sql(table, kind, table));
+    }
+    sql_list
+}
+
+// Convert the keyword to fts query, each word is matched as prefix
+fn to_match_query(keyword: &str) -> String {
+    keyword
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// The names of folders and the parent folder of each item
+async fn list_parent(
+    collection: &str,
+) -> Result<(HashMap<String, String>, HashMap<String, String>), DbErr> {
+    let names = list_api_folder(collection.to_string())
+        .await?
+        .into_iter()
+        .map(|item| (item.id, item.name.unwrap_or_default()))
+        .collect();
+    let mut parents = HashMap::new();
+    for (parent_id, children) in list_api_children(collection.to_string()).await? {
+        if parent_id == API_TREE_ROOT {
+            continue;
+        }
+        for id in children {
+            parents.insert(id, parent_id.clone());
+        }
+    }
+    Ok((names, parents))
+}
+
+pub async fn search(query: SearchQuery) -> Result<Vec<SearchHit>, DbErr> {
+    let match_query = to_match_query(&query.keyword);
+    if match_query.is_empty() {
+        return Ok(vec![]);
+    }
+    let db = get_database().await;
+    let limit = if query.limit == 0 {
+        SEARCH_LIMIT
+    } else {
+        query.limit
+    };
+    let mut sql = "SELECT kind, id, collection, method, name, url, \
+        snippet(search_index, -1, '[', ']', '...', 12) AS snippet, \
+        bm25(search_index, 0, 0, 0, 0, 10.0, 5.0, 2.0, 1.0) AS score \
+        FROM search_index WHERE search_index MATCH ?"
+        .to_string();
+    let mut values = vec![match_query.into()];
+    if let Some(collection) = query.collection.filter(|value| !value.is_empty()) {
+        sql.push_str(" AND collection = ?");
+        values.push(collection.into());
+    }
+    sql.push_str(" ORDER BY score LIMIT ?");
+    values.push((limit as i64).into());
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            &sql,
+            values,
+        ))
+        .await?;
+    let mut hits = vec![];
+    for row in rows {
+        let text = |name: &str| -> Result<String, DbErr> {
+            let value: Option<String> = row.try_get("", name)?;
+            Ok(value.unwrap_or_default())
+        };
+        hits.push(SearchHit {
+            kind: text("kind")?,
+            id: text("id")?,
+            collection: text("collection")?,
+            method: text("method")?,
+            name: text("name")?,
+            url: text("url")?,
+            snippet: text("snippet")?,
+            score: row.try_get("", "score")?,
+            ..Default::default()
+        });
+    }
+
+    let collection_names: HashMap<String, String> = list_api_collection()
+        .await?
+        .into_iter()
+        .map(|item| (item.id, item.name.unwrap_or_default()))
+        .collect();
+    let mut folders = HashMap::new();
+    for hit in hits.iter_mut() {
+        hit.collection_name = collection_names
+            .get(&hit.collection)
+            .cloned()
+            .unwrap_or_default();
+        if hit.kind == SEARCH_KIND_COLLECTION {
+            continue;
+        }
+        if !folders.contains_key(&hit.collection) {
+            folders.insert(hit.collection.clone(), list_parent(&hit.collection).await?);
+        }
+        let (names, parents) = &folders[&hit.collection];
+        let mut path = vec![];
+        let mut visited = HashSet::new();
+        let mut current = &hit.id;
+        while let Some(parent) = parents.get(current) {
+            // Avoid the loop of broken data
+            if !visited.insert(parent) {
+                break;
+            }
+            path.push(names.get(parent).cloned().unwrap_or_default());
+            current = parent;
+        }
+        path.reverse();
+        hit.path = path;
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{
+        APICollection, APIFolder, APISetting, add_api_collection, add_api_folder, add_api_setting,
+        delete_api_collection, delete_api_settings, run_with_database, update_api_setting,
+    };
+    use serde_json::json;
+
+    fn new_folder(id: &str, parent_id: Option<&str>) -> APIFolder {
+        APIFolder {
+            id: id.to_string(),
+            collection: "search-collection".to_string(),
+            children: None,
+            name: Some(format!("{id}-name")),
+            parent_id: parent_id.map(|value| value.to_string()),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn new_setting(id: &str, name: &str, setting: String) -> APISetting {
+        APISetting {
+            id: id.to_string(),
+            collection: "search-collection".to_string(),
+            name: Some(name.to_string()),
+            category: Some("http".to_string()),
+            setting: Some(setting),
+            parent_id: Some("search-child".to_string()),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    async fn search_ids(keyword: &str) -> Vec<String> {
+        search(SearchQuery {
+            keyword: keyword.to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|item| item.id)
+        .collect()
+    }
+
+    #[test]
+    fn match_query() {
+        assert_eq!("\"GET\"* \"users\"*", to_match_query("GET /users"));
+        assert_eq!("\"user_id\"*", to_match_query("\"user_id\""));
+        assert_eq!("", to_match_query(" *:- "));
+    }
+
+    #[test]
+    fn search_index_sync() {
+        run_with_database(async {
+            add_api_collection(APICollection {
+                id: "search-collection".to_string(),
+                name: Some("Searchcollection".to_string()),
+                description: Some("zebradescription".to_string()),
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+            add_api_folder(new_folder("search-parent", None))
+                .await
+                .unwrap();
+            add_api_folder(new_folder("search-child", Some("search-parent")))
+                .await
+                .unwrap();
+            let setting = json!({
+                "method": "POST",
+                "uri": "http://localhost/quokkapath",
+                "query": [{"key": "wombatquery", "value": "1", "enabled": true}],
+                "headers": [{"key": "X-Koala", "value": "koalaheader", "enabled": true}],
+                "body": "{\"platypusbody\": 1}",
+            });
+            add_api_setting(new_setting(
+                "search-setting",
+                "Emu request",
+                setting.to_string(),
+            ))
+            .await
+            .unwrap();
+
+            let hits = search(SearchQuery {
+                keyword: "quokkapath".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+            assert_eq!(1, hits.len());
+            let hit = &hits[0];
+            assert_eq!(SEARCH_KIND_SETTING, hit.kind);
+            assert_eq!("search-setting", hit.id);
+            assert_eq!("Searchcollection", hit.collection_name);
+            assert_eq!(
+                vec![
+                    "search-parent-name".to_string(),
+                    "search-child-name".to_string()
+                ],
+                hit.path
+            );
+            assert_eq!("POST", hit.method);
+            assert_eq!("http://localhost/quokkapath wombatquery=1", hit.url);
+            assert_eq!("http://localhost/[quokkapath] wombatquery=1", hit.snippet);
+            for keyword in ["wombat", "koalaheader", "platypus", "emu"] {
+                assert_eq!(
+                    vec!["search-setting"],
+                    search_ids(keyword).await,
+                    "{keyword}"
+                );
+            }
+            assert_eq!(
+                vec!["search-collection"],
+                search_ids("zebradescription").await
+            );
+            assert_eq!(vec!["search-child"], search_ids("search child name").await);
+
+            // The name matches better than the body
+            add_api_setting(new_setting(
+                "search-setting-name",
+                "platypusbody",
+                "invalid json".to_string(),
+            ))
+            .await
+            .unwrap();
+            assert_eq!(
+                vec!["search-setting-name", "search-setting"],
+                search_ids("platypusbody").await
+            );
+            let hits = search(SearchQuery {
+                keyword: "platypusbody".to_string(),
+                collection: Some("search-other".to_string()),
+                limit: 1,
+            })
+            .await
+            .unwrap();
+            assert!(hits.is_empty());
+
+            // The index is updated by the triggers
+            let setting = json!({"method": "GET", "uri": "http://localhost/dingopath"});
+            update_api_setting(new_setting(
+                "search-setting",
+                "Emu request",
+                setting.to_string(),
+            ))
+            .await
+            .unwrap();
+            assert!(search_ids("quokkapath").await.is_empty());
+            assert_eq!(vec!["search-setting"], search_ids("dingopath").await);
+
+            delete_api_settings(vec!["search-setting".to_string()])
+                .await
+                .unwrap();
+            assert!(search_ids("dingopath").await.is_empty());
+
+            // The rebuilt index is the same as the synced one
+            let db = get_database().await;
+            for sql in get_search_index_rebuild_sql() {
+                db.execute(Statement::from_string(db.get_database_backend(), sql))
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(
+                vec!["search-setting-name"],
+                search_ids("platypusbody").await
+            );
+            assert!(search_ids("dingopath").await.is_empty());
+
+            delete_api_collection("search-collection".to_string())
+                .await
+                .unwrap();
+            assert!(search_ids("zebradescription").await.is_empty());
+        });
+    }
+}
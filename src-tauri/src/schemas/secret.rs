@@ -0,0 +1,294 @@
+use crate::crypto::{self, KEY_SIZE};
+use crate::error::CyberAPIError;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::Utc;
+use sea_orm::{ConnectionTrait, DbErr, Statement};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+use super::database::{ExportData, get_database};
+
+const SECRET_ERROR_CATEGORY: &str = "secret";
+// The workspace key is generated randomly and saved encrypted by the master passphrase
+const SECRET_KEY_ID: &str = "workspace";
+// The secret value is saved as enc:v1:<base64 of nonce and encrypted data>
+const SECRET_VALUE_PREFIX: &str = "enc:v1:";
+
+// The unlocked workspace key, it is only kept in memory
+static SECRET_KEY: RwLock<Option<[u8; KEY_SIZE]>> = RwLock::new(None);
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretStatus {
+    // The master passphrase has been set
+    pub initialized: bool,
+    pub unlocked: bool,
+}
+
+// The workspace key encrypted by the master passphrase, it is only exported with the secrets
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SecretKey {
+    pub id: String,
+    pub value: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(SECRET_ERROR_CATEGORY, message)
+}
+
+pub fn get_secret_keys_create_sql() -> String {
+    "CREATE TABLE IF NOT EXISTS secret_keys (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            value TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        )"
+    .to_string()
+}
+
+// The base64 of workspace key encrypted by the master passphrase
+async fn get_wrapped_key_value<C: ConnectionTrait>(db: &C) -> Result<Option<String>, DbErr> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT value FROM secret_keys WHERE id = ?",
+            [SECRET_KEY_ID.into()],
+        ))
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    Ok(Some(row.try_get("", "value")?))
+}
+
+async fn get_wrapped_key() -> Result<Option<Vec<u8>>, CyberAPIError> {
+    let db = get_database().await;
+    let Some(value) = get_wrapped_key_value(&db).await? else {
+        return Ok(None);
+    };
+    let data = general_purpose::STANDARD
+        .decode(value)
+        .map_err(|err| new_error(err.to_string()))?;
+    Ok(Some(data))
+}
+
+async fn save_wrapped_key(key: &[u8; KEY_SIZE], passphrase: &str) -> Result<(), CyberAPIError> {
+    let value = general_purpose::STANDARD.encode(crypto::encrypt(key, passphrase)?);
+    let now = Utc::now().to_rfc3339();
+    let db = get_database().await;
+    db.execute(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO secret_keys (id, value, created_at, updated_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        [
+            SECRET_KEY_ID.into(),
+            value.into(),
+            now.clone().into(),
+            now.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn unwrap_key(passphrase: &str) -> Result<Option<[u8; KEY_SIZE]>, CyberAPIError> {
+    let Some(data) = get_wrapped_key().await? else {
+        return Ok(None);
+    };
+    let key = crypto::decrypt(&data, passphrase)
+        .map_err(|_| new_error("the master passphrase is wrong".to_string()))?;
+    let key: [u8; KEY_SIZE] = key
+        .try_into()
+        .map_err(|_| new_error("the workspace key is corrupted".to_string()))?;
+    Ok(Some(key))
+}
+
+fn get_key() -> Result<[u8; KEY_SIZE], CyberAPIError> {
+    SECRET_KEY.read().unwrap().ok_or_else(|| {
+        new_error(
+            "the secrets are locked, please unlock them with the master passphrase".to_string(),
+        )
+    })
+}
+
+pub async fn get_secret_status() -> Result<SecretStatus, CyberAPIError> {
+    Ok(SecretStatus {
+        initialized: get_wrapped_key().await?.is_some(),
+        unlocked: is_secrets_unlocked(),
+    })
+}
+
+// Unlock the secrets with the master passphrase,
+// the workspace key is created by the passphrase for the first time
+pub async fn unlock_secrets(passphrase: String) -> Result<SecretStatus, CyberAPIError> {
+    if passphrase.is_empty() {
+        return Err(new_error("the master passphrase is required".to_string()));
+    }
+    let key = match unwrap_key(&passphrase).await? {
+        Some(key) => key,
+        None => {
+            let key = crypto::generate_key();
+            save_wrapped_key(&key, &passphrase).await?;
+            key
+        }
+    };
+    *SECRET_KEY.write().unwrap() = Some(key);
+    Ok(SecretStatus {
+        initialized: true,
+        unlocked: true,
+    })
+}
+
+pub fn lock_secrets() {
+    *SECRET_KEY.write().unwrap() = None;
+}
+
+pub(super) fn is_secrets_unlocked() -> bool {
+    SECRET_KEY.read().unwrap().is_some()
+}
+
+// Only the workspace key is encrypted again, the secret values are not changed
+pub async fn change_secret_passphrase(
+    passphrase: String,
+    new_passphrase: String,
+) -> Result<(), CyberAPIError> {
+    if new_passphrase.is_empty() {
+        return Err(new_error("the master passphrase is required".to_string()));
+    }
+    let Some(key) = unwrap_key(&passphrase).await? else {
+        return Err(new_error("the master passphrase is not set".to_string()));
+    };
+    save_wrapped_key(&key, &new_passphrase).await
+}
+
+fn is_encrypted_secret(value: &str) -> bool {
+    value.starts_with(SECRET_VALUE_PREFIX)
+}
+
+pub(super) fn encrypt_secret(value: &str) -> Result<String, CyberAPIError> {
+    if value.is_empty() || is_encrypted_secret(value) {
+        return Ok(value.to_string());
+    }
+    let data = crypto::encrypt_with_key(value.as_bytes(), &get_key()?)?;
+    Ok(format!(
+        "{SECRET_VALUE_PREFIX}{}",
+        general_purpose::STANDARD.encode(data)
+    ))
+}
+
+// The value which is not encrypted is returned as it is
+pub(super) fn decrypt_secret(value: &str) -> Result<String, CyberAPIError> {
+    let Some(value) = value.strip_prefix(SECRET_VALUE_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let data = general_purpose::STANDARD
+        .decode(value)
+        .map_err(|err| new_error(err.to_string()))?;
+    let data = crypto::decrypt_with_key(&data, &get_key()?)?;
+    String::from_utf8(data).map_err(|err| new_error(err.to_string()))
+}
+
+pub fn get_table_name_secret_key() -> String {
+    "secret_keys".to_string()
+}
+
+pub async fn export_secret_key() -> Result<ExportData, DbErr> {
+    let db = get_database().await;
+    let rows = db
+        .query_all(Statement::from_string(
+            db.get_database_backend(),
+            "SELECT id, value, created_at, updated_at FROM secret_keys".to_string(),
+        ))
+        .await?;
+    let mut data = vec![];
+    for row in rows {
+        let key = SecretKey {
+            id: row.try_get("", "id")?,
+            value: row.try_get("", "value")?,
+            created_at: row.try_get("", "created_at")?,
+            updated_at: row.try_get("", "updated_at")?,
+        };
+        data.push(serde_json::to_value(key).map_err(|err| DbErr::Json(err.to_string()))?);
+    }
+    Ok(ExportData {
+        name: get_table_name_secret_key(),
+        data,
+    })
+}
+
+// The secret values of backup can only be merged if they are encrypted by the workspace key,
+// the same key may be wrapped by another passphrase, so the values are decrypted to check it
+pub(super) async fn check_secret_key<C: ConnectionTrait>(
+    db: &C,
+    keys: &[serde_json::Value],
+    values: &[&str],
+) -> Result<(), CyberAPIError> {
+    let incoming = keys
+        .iter()
+        .filter_map(|item| serde_json::from_value::<SecretKey>(item.clone()).ok())
+        .find(|key| key.id == SECRET_KEY_ID);
+    let values: Vec<&str> = values
+        .iter()
+        .copied()
+        .filter(|value| is_encrypted_secret(value))
+        .collect();
+    let (Some(incoming), false) = (incoming, values.is_empty()) else {
+        return Ok(());
+    };
+    // The workspace key of backup is saved if the database has none
+    let Some(current) = get_wrapped_key_value(db).await? else {
+        return Ok(());
+    };
+    if current == incoming.value {
+        return Ok(());
+    }
+    if !is_secrets_unlocked() {
+        return Err(new_error(
+            "the workspace key of backup is different, please unlock the secrets to check whether they can be merged"
+                .to_string(),
+        ));
+    }
+    if values.iter().any(|value| decrypt_secret(value).is_err()) {
+        return Err(new_error(
+            "the secrets of backup are encrypted by another workspace key, they can not be merged"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Save the workspace keys of backup, the existing key is replaced only if replace is true,
+// the secrets should be locked after it is committed because the key may be changed
+pub async fn import_secret_key<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+    replace: bool,
+) -> Result<usize, CyberAPIError> {
+    let sql = if replace {
+        "INSERT INTO secret_keys (id, value, created_at, updated_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at"
+    } else {
+        "INSERT INTO secret_keys (id, value, created_at, updated_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(id) DO NOTHING"
+    };
+    let mut count = 0;
+    for item in data {
+        let key: SecretKey = serde_json::from_value(item)?;
+        let result = db
+            .execute(Statement::from_sql_and_values(
+                db.get_database_backend(),
+                sql,
+                [
+                    key.id.into(),
+                    key.value.into(),
+                    key.created_at.unwrap_or_default().into(),
+                    key.updated_at.unwrap_or_default().into(),
+                ],
+            ))
+            .await?;
+        count += result.rows_affected() as usize;
+    }
+    Ok(count)
+}
@@ -3,10 +3,26 @@ use crate::{
     error::CyberAPIError,
 };
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::database::{ExportData, IMPORT_BATCH_SIZE, get_database};
+use super::secret::{decrypt_secret, encrypt_secret, is_secrets_unlocked};
+
+const VARIABLE_ERROR_CATEGORY: &str = "variable";
 
-use super::database::{ExportData, get_database};
+// Keep in sync with `VariableCategory` and `VariableStatus` in src/commands/variable.ts
+pub const VARIABLE_CATEGORY_ENVIRONMENT: &str = "env";
+pub const VARIABLE_CATEGORY_CUSTOMIZE: &str = "customize";
+pub const VARIABLE_CATEGORY_GLOBAL_REQ_HEADERS: &str = "globalReqHeaders";
+pub const VARIABLE_ENABLED: &str = "1";
+pub const VARIABLE_SECRET: &str = "1";
+// The secret value is listed as the mask, and it is not changed if the mask is updated
+pub const VARIABLE_SECRET_MASK: &str = "******";
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +34,8 @@ pub struct Variable {
     pub name: Option<String>,
     pub value: Option<String>,
     pub enabled: Option<String>,
+    // The value is encrypted if it is secret (1: secret)
+    pub secret: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -32,12 +50,22 @@ impl From<variables::Model> for Variable {
             name: model.name,
             value: model.value,
             enabled: model.enabled,
+            secret: model.secret,
             created_at: model.created_at,
             updated_at: model.updated_at,
         }
     }
 }
 impl Variable {
+    pub fn is_secret(&self) -> bool {
+        self.secret.as_deref() == Some(VARIABLE_SECRET)
+    }
+    fn mask(mut self) -> Self {
+        if self.is_secret() && self.value.as_ref().is_some_and(|value| !value.is_empty()) {
+            self.value = Some(VARIABLE_SECRET_MASK.to_string());
+        }
+        self
+    }
     fn into_active_model(self) -> variables::ActiveModel {
         let created_at = self.created_at.or_else(|| Some(Utc::now().to_rfc3339()));
         let updated_at = self.updated_at.or_else(|| Some(Utc::now().to_rfc3339()));
@@ -49,6 +77,7 @@ impl Variable {
             name: Set(self.name),
             value: Set(self.value),
             enabled: Set(self.enabled),
+            secret: Set(self.secret),
             created_at: Set(created_at),
             updated_at: Set(updated_at),
         }
@@ -70,30 +99,127 @@ pub fn get_variables_create_sql() -> String {
     .to_string()
 }
 
-pub async fn add_variable(value: Variable) -> Result<Variable, DbErr> {
-    let model = value.into_active_model();
+pub async fn add_variable(value: Variable) -> Result<Variable, CyberAPIError> {
     let db = get_database().await;
-    let result = model.insert(&db).await?;
-    Ok(result.into())
+    add_variable_with(&db, value).await
 }
 
-pub async fn update_variable(value: Variable) -> Result<Variable, DbErr> {
+pub(crate) async fn add_variable_with<C: ConnectionTrait>(
+    db: &C,
+    mut value: Variable,
+) -> Result<Variable, CyberAPIError> {
+    if value.is_secret() {
+        value.value = Some(encrypt_secret(&value.value.unwrap_or_default())?);
+    }
     let model = value.into_active_model();
+    let result = model.insert(db).await?;
+    Ok(Variable::from(result).mask())
+}
+
+pub async fn update_variable(value: Variable) -> Result<Variable, CyberAPIError> {
     let db = get_database().await;
-    let result = model.update(&db).await?;
-    Ok(result.into())
+    update_variable_with(&db, value).await
+}
+
+pub(crate) async fn update_variable_with<C: ConnectionTrait>(
+    db: &C,
+    mut value: Variable,
+) -> Result<Variable, CyberAPIError> {
+    let mut keep_value = false;
+    if value.value.as_deref() == Some(VARIABLE_SECRET_MASK) {
+        // The value is not changed, it is decrypted or encrypted if the secret flag is changed
+        let existing = Variables::find_by_id(value.id.clone())
+            .one(db)
+            .await?
+            .map(Variable::from);
+        if let Some(existing) = existing {
+            keep_value = existing.is_secret() == value.is_secret();
+            value.value = match existing.value {
+                Some(current) if existing.is_secret() && !keep_value => {
+                    Some(decrypt_secret(&current)?)
+                }
+                current => current,
+            };
+        }
+    }
+    if value.is_secret() && !keep_value {
+        value.value = Some(encrypt_secret(&value.value.unwrap_or_default())?);
+    }
+    let mut model = value.into_active_model();
+    if keep_value {
+        model.value = NotSet;
+    }
+    let result = model.update(db).await?;
+    Ok(Variable::from(result).mask())
 }
 
+// The secret values are masked, use reveal_variable to get the value
 pub async fn list_variable(collection: String, category: String) -> Result<Vec<Variable>, DbErr> {
     let db = get_database().await;
+    list_variable_with(&db, collection, category).await
+}
+
+async fn find_variable<C: ConnectionTrait>(
+    db: &C,
+    collection: String,
+    category: String,
+) -> Result<Vec<Variable>, DbErr> {
     let result = Variables::find()
         .filter(variables::Column::Collection.eq(collection))
         .filter(variables::Column::Category.eq(category))
-        .all(&db)
+        .all(db)
         .await?;
     Ok(result.into_iter().map(Variable::from).collect())
 }
 
+pub(crate) async fn list_variable_with<C: ConnectionTrait>(
+    db: &C,
+    collection: String,
+    category: String,
+) -> Result<Vec<Variable>, DbErr> {
+    let result = find_variable(db, collection, category).await?;
+    Ok(result.into_iter().map(Variable::mask).collect())
+}
+
+// The secret values are decrypted, it should only be used for sending requests.
+// The secrets are left out while they are locked, so the requests not using them can be sent
+pub async fn list_decrypted_variable(
+    collection: String,
+    category: String,
+) -> Result<Vec<Variable>, CyberAPIError> {
+    let db = get_database().await;
+    let unlocked = is_secrets_unlocked();
+    let mut variables = vec![];
+    for mut variable in find_variable(&db, collection, category).await? {
+        if variable.is_secret() && !unlocked {
+            continue;
+        }
+        // The disabled secrets are not used, so they are not decrypted
+        let enabled = variable.enabled.as_deref() == Some(VARIABLE_ENABLED);
+        if variable.is_secret() && enabled {
+            variable.value = Some(decrypt_secret(&variable.value.unwrap_or_default())?);
+        }
+        variables.push(variable);
+    }
+    Ok(variables)
+}
+
+pub async fn reveal_variable(id: String) -> Result<String, CyberAPIError> {
+    let db = get_database().await;
+    let Some(model) = Variables::find_by_id(id.clone()).one(&db).await? else {
+        return Err(CyberAPIError::new(
+            VARIABLE_ERROR_CATEGORY,
+            format!("variable {id} is not found"),
+        ));
+    };
+    let variable = Variable::from(model);
+    let value = variable.value.clone().unwrap_or_default();
+    if !variable.is_secret() {
+        return Ok(value);
+    }
+    decrypt_secret(&value)
+}
+
 pub async fn delete_variable(ids: Vec<String>) -> Result<u64, DbErr> {
     let db = get_database().await;
     let result = Variables::delete_many()
@@ -107,29 +233,261 @@ pub fn get_table_name_variable() -> String {
     "variables".to_string()
 }
 
-pub async fn delete_all_variable() -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-    Variables::delete_many().exec(&db).await?;
+pub async fn delete_all_variable<C: ConnectionTrait>(db: &C) -> Result<(), CyberAPIError> {
+    Variables::delete_many().exec(db).await?;
     Ok(())
 }
 
-pub async fn export_variable() -> Result<ExportData, DbErr> {
+// The secret values are left out unless include_secrets is true, they are still encrypted
+pub async fn export_variable(
+    collections: Option<Vec<String>>,
+    include_secrets: bool,
+) -> Result<ExportData, DbErr> {
     let db = get_database().await;
-    let data = Variables::find().into_json().all(&db).await?;
+    let mut query = Variables::find();
+    if let Some(collections) = collections {
+        query = query.filter(variables::Column::Collection.is_in(collections));
+    }
+    let mut data = query.into_json().all(&db).await?;
+    if !include_secrets {
+        for row in data.iter_mut() {
+            if row.get("secret").and_then(|value| value.as_str()) == Some(VARIABLE_SECRET) {
+                row["value"] = serde_json::Value::String("".to_string());
+            }
+        }
+    }
     Ok(ExportData {
         name: get_table_name_variable(),
         data,
     })
 }
 
-pub async fn import_variable(data: Vec<serde_json::Value>) -> Result<(), CyberAPIError> {
-    let db = get_database().await;
-
+pub async fn import_variable<C: ConnectionTrait>(
+    db: &C,
+    data: Vec<serde_json::Value>,
+) -> Result<usize, CyberAPIError> {
     let mut records = Vec::new();
     for ele in data {
         let model = variables::ActiveModel::from_json(ele)?;
         records.push(model);
     }
-    Variables::insert_many(records).exec(&db).await?;
+    let count = records.len();
+    for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+        Variables::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(count)
+}
+
+// Fill the empty values of secret variables with the encrypted values in database,
+// so the secrets are kept when the export without secrets is imported
+pub(crate) async fn fill_secret_values<C: ConnectionTrait>(
+    db: &C,
+    data: &mut [serde_json::Value],
+) -> Result<(), DbErr> {
+    let is_empty_secret = |row: &serde_json::Value| {
+        let get = |field: &str| row.get(field).and_then(|value| value.as_str());
+        get("secret") == Some(VARIABLE_SECRET) && get("value").unwrap_or_default().is_empty()
+    };
+    if !data.iter().any(is_empty_secret) {
+        return Ok(());
+    }
+    let values: HashMap<String, String> = Variables::find()
+        .filter(variables::Column::Secret.eq(VARIABLE_SECRET))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|item| Some((item.id, item.value?)))
+        .collect();
+    for row in data.iter_mut().filter(|row| is_empty_secret(row)) {
+        let value = row
+            .get("id")
+            .and_then(|value| value.as_str())
+            .and_then(|id| values.get(id));
+        if let Some(value) = value {
+            row["value"] = serde_json::Value::String(value.clone());
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{lock_secrets, run_with_database, unlock_secrets};
+
+    const MASTER_PASSPHRASE: &str = "master";
+
+    fn new_variable(id: &str, value: &str, secret: bool) -> Variable {
+        Variable {
+            id: id.to_string(),
+            category: VARIABLE_CATEGORY_ENVIRONMENT.to_string(),
+            collection: "variable-secret".to_string(),
+            environment: Some("".to_string()),
+            name: Some(id.to_string()),
+            value: Some(value.to_string()),
+            enabled: Some(VARIABLE_ENABLED.to_string()),
+            secret: Some(if secret { VARIABLE_SECRET } else { "" }.to_string()),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    async fn get_value(id: &str) -> String {
+        let db = get_database().await;
+        Variables::find_by_id(id)
+            .one(&db)
+            .await
+            .unwrap()
+            .and_then(|item| item.value)
+            .unwrap_or_default()
+    }
+
+    async fn list_values(decrypted: bool) -> Vec<(String, String)> {
+        let collection = "variable-secret".to_string();
+        let category = VARIABLE_CATEGORY_ENVIRONMENT.to_string();
+        let result = if decrypted {
+            list_decrypted_variable(collection, category).await.unwrap()
+        } else {
+            list_variable(collection, category).await.unwrap()
+        };
+        // The variables of other tests are in the same collection
+        let mut values: Vec<(String, String)> = result
+            .into_iter()
+            .filter(|item| item.id.starts_with("secret-v"))
+            .map(|item| (item.id, item.value.unwrap_or_default()))
+            .collect();
+        values.sort();
+        values
+    }
+
+    fn values(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(id, value)| (id.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn secret_variable() {
+        run_with_database(async {
+            lock_secrets();
+            let err = add_variable(new_variable("secret-v1", "token-1", true))
+                .await
+                .unwrap_err();
+            assert_eq!(
+                "[secret]the secrets are locked, please unlock them with the master passphrase",
+                err.to_string()
+            );
+
+            unlock_secrets(MASTER_PASSPHRASE.to_string()).await.unwrap();
+            let result = add_variable(new_variable("secret-v1", "token-1", true))
+                .await
+                .unwrap();
+            assert_eq!(Some(VARIABLE_SECRET_MASK.to_string()), result.value);
+            add_variable(new_variable("secret-v2", "localhost", false))
+                .await
+                .unwrap();
+            // The empty secret is not masked
+            add_variable(new_variable("secret-v3", "", true))
+                .await
+                .unwrap();
+
+            let encrypted = get_value("secret-v1").await;
+            assert!(encrypted.starts_with("enc:v1:"));
+            assert!(!encrypted.contains("token-1"));
+            assert_eq!(
+                "token-1",
+                reveal_variable("secret-v1".to_string()).await.unwrap()
+            );
+            assert_eq!(
+                values(&[
+                    ("secret-v1", VARIABLE_SECRET_MASK),
+                    ("secret-v2", "localhost"),
+                    ("secret-v3", ""),
+                ]),
+                list_values(false).await
+            );
+            assert_eq!(
+                values(&[
+                    ("secret-v1", "token-1"),
+                    ("secret-v2", "localhost"),
+                    ("secret-v3", ""),
+                ]),
+                list_values(true).await
+            );
+
+            // The secrets are left out of the decrypted values if they are locked
+            lock_secrets();
+            assert_eq!(
+                values(&[
+                    ("secret-v1", VARIABLE_SECRET_MASK),
+                    ("secret-v2", "localhost"),
+                    ("secret-v3", ""),
+                ]),
+                list_values(false).await
+            );
+            assert_eq!(
+                values(&[("secret-v2", "localhost")]),
+                list_values(true).await
+            );
+            assert!(reveal_variable("secret-v1".to_string()).await.is_err());
+            assert_eq!(
+                "localhost",
+                reveal_variable("secret-v2".to_string()).await.unwrap()
+            );
+            unlock_secrets(MASTER_PASSPHRASE.to_string()).await.unwrap();
+            assert_eq!(
+                "[secret]the master passphrase is wrong",
+                unlock_secrets("wrong".to_string())
+                    .await
+                    .unwrap_err()
+                    .to_string()
+            );
+        });
+    }
+
+    #[test]
+    fn update_secret_variable() {
+        run_with_database(async {
+            unlock_secrets(MASTER_PASSPHRASE.to_string()).await.unwrap();
+            add_variable(new_variable("secret-update", "token-1", true))
+                .await
+                .unwrap();
+            let encrypted = get_value("secret-update").await;
+
+            // The mask keeps the encrypted value
+            let mut variable = new_variable("secret-update", VARIABLE_SECRET_MASK, true);
+            variable.name = Some("token".to_string());
+            let result = update_variable(variable).await.unwrap();
+            assert_eq!(Some(VARIABLE_SECRET_MASK.to_string()), result.value);
+            assert_eq!(Some("token".to_string()), result.name);
+            assert_eq!(encrypted, get_value("secret-update").await);
+
+            // The value is decrypted if it is not secret any more
+            update_variable(new_variable("secret-update", VARIABLE_SECRET_MASK, false))
+                .await
+                .unwrap();
+            assert_eq!("token-1", get_value("secret-update").await);
+
+            // And it is encrypted again if it is secret
+            update_variable(new_variable("secret-update", VARIABLE_SECRET_MASK, true))
+                .await
+                .unwrap();
+            let value = get_value("secret-update").await;
+            assert!(value.starts_with("enc:v1:"));
+            assert_ne!(encrypted, value);
+            assert_eq!(
+                "token-1",
+                reveal_variable("secret-update".to_string()).await.unwrap()
+            );
+
+            update_variable(new_variable("secret-update", "token-2", true))
+                .await
+                .unwrap();
+            assert_eq!(
+                "token-2",
+                reveal_variable("secret-update".to_string()).await.unwrap()
+            );
+        });
+    }
+}
@@ -0,0 +1,899 @@
+use crate::error::CyberAPIError;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::api_collection::{export_api_collection, get_table_name_api_collection};
+use super::api_folder::{export_api_folder, get_table_name_api_folder};
+use super::api_setting::{export_api_setting, get_table_name_api_setting};
+use super::api_tree::API_TREE_ROOT;
+use super::environment::{export_environment, get_table_name_environment};
+use super::merge::replace_collection_tables;
+use super::secret::encrypt_secret;
+use super::variable::{VARIABLE_SECRET, export_variable, get_table_name_variable};
+
+const WORKSPACE_ERROR_CATEGORY: &str = "workspace";
+const COLLECTION_FILE: &str = "collection";
+const VARIABLES_FILE: &str = "variables";
+const FOLDER_FILE: &str = "_folder";
+const ENVIRONMENTS_DIR: &str = "environments";
+const REQUESTS_DIR: &str = "requests";
+const NAME_MAX_LENGTH: usize = 64;
+// The fields are not saved to files, they are derived from the directory or changed frequently
+const WORKSPACE_IGNORED_FIELDS: [&str; 4] = ["collection", "parent_id", "created_at", "updated_at"];
+// The values of these headers are secrets unless they reference variables, e.g. `Bearer {{token}}`
+const SECRET_KEYWORDS: [&str; 10] = [
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "apikey",
+    "api-key",
+    "api_key",
+    "authorization",
+    "cookie",
+    "credential",
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum WorkspaceFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+const WORKSPACE_FORMATS: [WorkspaceFormat; 2] = [WorkspaceFormat::Json, WorkspaceFormat::Yaml];
+
+impl WorkspaceFormat {
+    fn ext(&self) -> &'static str {
+        match self {
+            WorkspaceFormat::Json => "json",
+            WorkspaceFormat::Yaml => "yaml",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceExportResult {
+    // The relative paths of files
+    pub files: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceImportResult {
+    pub collection: String,
+    // The count of imported rows of each table
+    pub tables: BTreeMap<String, usize>,
+}
+
+fn new_error(message: String) -> CyberAPIError {
+    CyberAPIError::new(WORKSPACE_ERROR_CATEGORY, message)
+}
+
+fn get_str<'a>(row: &'a Value, field: &str) -> &'a str {
+    row.get(field)
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+}
+
+fn get_sort_order(row: &Value) -> i64 {
+    row.get("sort_order")
+        .and_then(|value| value.as_i64())
+        .unwrap_or_default()
+}
+
+// The keys of objects are sorted, so the files are the same for the same data
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        _ => value,
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYWORDS.iter().any(|keyword| key.contains(keyword))
+}
+
+fn is_secret_variable(row: &Value) -> bool {
+    get_str(row, "secret") == VARIABLE_SECRET
+}
+
+fn is_secret(key: &str, value: &str, all: bool) -> bool {
+    !value.is_empty() && !value.contains("{{") && (all || is_secret_key(key))
+}
+
+// Split the uri into the part before query, the query string and the fragment with `#`
+fn split_uri_query(uri: &str) -> Option<(&str, &str, &str)> {
+    let (base, rest) = uri.split_once('?')?;
+    let index = rest.find('#').unwrap_or(rest.len());
+    Some((base, &rest[..index], &rest[index..]))
+}
+
+// Change the values of query string by the key and value, the other parts of uri are kept,
+// e.g. `{{host}}/users?token=abc#top`
+fn replace_uri_query(uri: &str, replace: impl Fn(&str, &str) -> Option<String>) -> String {
+    let Some((base, query, fragment)) = split_uri_query(uri) else {
+        return uri.to_string();
+    };
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| {
+            pair.split_once('=')
+                .and_then(|(key, value)| Some(format!("{key}={}", replace(key, value)?)))
+                .unwrap_or_else(|| pair.to_string())
+        })
+        .collect();
+    format!("{base}?{}{fragment}", query.join("&"))
+}
+
+// The secret values of headers, query and auth of request are removed,
+// the query string of uri is checked too, and all the values of auth are secrets
+fn strip_setting_secrets(setting: &mut Value) {
+    for (field, all) in [("headers", false), ("query", false), ("auth", true)] {
+        let Some(items) = setting
+            .get_mut(field)
+            .and_then(|value| value.as_array_mut())
+        else {
+            continue;
+        };
+        for item in items.iter_mut() {
+            if is_secret(get_str(item, "key"), get_str(item, "value"), all) {
+                item["value"] = Value::String("".to_string());
+            }
+        }
+    }
+    if let Some(uri) = setting.get("uri").and_then(|value| value.as_str()) {
+        let uri = replace_uri_query(uri, |key, value| {
+            is_secret(key, value, false).then(String::new)
+        });
+        setting["uri"] = Value::String(uri);
+    }
+}
+
+// Fill the removed secrets with the values of the same keys in database
+fn restore_setting_secrets(setting: &mut Value, existing: &Value) {
+    for field in ["headers", "query", "auth"] {
+        let Some(current) = existing.get(field).and_then(|value| value.as_array()) else {
+            continue;
+        };
+        let Some(items) = setting
+            .get_mut(field)
+            .and_then(|value| value.as_array_mut())
+        else {
+            continue;
+        };
+        for item in items.iter_mut() {
+            if !get_str(item, "value").is_empty() {
+                continue;
+            }
+            let key = get_str(item, "key").to_string();
+            if let Some(value) = current
+                .iter()
+                .find(|value| get_str(value, "key") == key)
+                .and_then(|value| value.get("value"))
+            {
+                item["value"] = value.clone();
+            }
+        }
+    }
+    let current: HashMap<&str, &str> = split_uri_query(get_str(existing, "uri"))
+        .map(|(_, query, _)| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(uri) = setting.get("uri").and_then(|value| value.as_str()) {
+        let uri = replace_uri_query(uri, |key, value| {
+            current
+                .get(key)
+                .filter(|_| value.is_empty())
+                .map(|value| value.to_string())
+        });
+        setting["uri"] = Value::String(uri);
+    }
+}
+
+// Convert the name to the file name, the characters not allowed in path are replaced
+fn to_file_name(name: &str) -> String {
+    let mut result = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            result.push(c);
+        } else if !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+    let result: String = result
+        .trim_matches(|c| c == '-' || c == '.')
+        .chars()
+        .take(NAME_MAX_LENGTH)
+        .collect();
+    if result.is_empty() {
+        "untitled".to_string()
+    } else {
+        result
+    }
+}
+
+// The names are compared ignoring case for the case-insensitive file systems,
+// the id is appended if the name is used
+fn unique_file_name(used: &mut HashSet<String>, name: &str, id: &str) -> String {
+    let mut name = to_file_name(name);
+    if used.contains(&name.to_lowercase()) {
+        let suffix: String = id
+            .chars()
+            .rev()
+            .take(6)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        name = format!("{name}-{}", suffix.to_lowercase());
+    }
+    used.insert(name.to_lowercase());
+    name
+}
+
+fn to_file_row(mut row: Value, extra_ignored: &[&str]) -> Value {
+    if let Some(map) = row.as_object_mut() {
+        for field in WORKSPACE_IGNORED_FIELDS.iter().chain(extra_ignored) {
+            map.remove(*field);
+        }
+    }
+    row
+}
+
+struct WorkspaceWriter {
+    root: PathBuf,
+    format: WorkspaceFormat,
+    files: Vec<String>,
+}
+
+impl WorkspaceWriter {
+    // Write the file only if it is changed, the path is relative and without extension
+    fn write(&mut self, path: &Path, value: Value) -> Result<(), CyberAPIError> {
+        let value = sort_keys(value);
+        let content = match self.format {
+            WorkspaceFormat::Json => serde_json::to_string_pretty(&value)? + "\n",
+            WorkspaceFormat::Yaml => serde_yaml::to_string(&value)?,
+        };
+        // The name may contain dot, so the extension is appended instead of set
+        let mut relative = path.as_os_str().to_owned();
+        relative.push(format!(".{}", self.format.ext()));
+        let relative = PathBuf::from(relative);
+        let file = self.root.join(&relative);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::read_to_string(&file).ok().as_deref() != Some(content.as_str()) {
+            fs::write(&file, content)?;
+        }
+        self.files
+            .push(relative.to_string_lossy().replace('\\', "/"));
+        Ok(())
+    }
+
+    fn is_workspace_file(path: &Path) -> bool {
+        let ext = path.extension().and_then(|ext| ext.to_str());
+        WORKSPACE_FORMATS
+            .iter()
+            .any(|format| Some(format.ext()) == ext)
+    }
+
+    // Remove the files of deleted items and the files of the other format,
+    // the other files in the directory are kept
+    fn remove_stale(&self, dir: &Path, removed: &mut Vec<String>) -> Result<(), CyberAPIError> {
+        let path = self.root.join(dir);
+        if !path.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(&path)? {
+            let entry_path = entry?.path();
+            let relative = dir.join(entry_path.file_name().unwrap_or_default());
+            if entry_path.is_dir() {
+                self.remove_stale(&relative, removed)?;
+                // Only the empty directory is removed
+                let _ = fs::remove_dir(&entry_path);
+                continue;
+            }
+            let name = relative.to_string_lossy().replace('\\', "/");
+            if Self::is_workspace_file(&entry_path) && !self.files.contains(&name) {
+                fs::remove_file(&entry_path)?;
+                removed.push(name);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_requests(
+    writer: &mut WorkspaceWriter,
+    dir: &Path,
+    parent_id: &str,
+    children: &HashMap<String, Vec<(bool, Value)>>,
+) -> Result<(), CyberAPIError> {
+    let Some(items) = children.get(parent_id) else {
+        return Ok(());
+    };
+    let mut used = HashSet::from([FOLDER_FILE.to_lowercase()]);
+    for (is_folder, row) in items {
+        let id = get_str(row, "id").to_string();
+        let name = unique_file_name(&mut used, get_str(row, "name"), &id);
+        if *is_folder {
+            let folder_dir = dir.join(&name);
+            writer.write(&folder_dir.join(FOLDER_FILE), to_file_row(row.clone(), &[]))?;
+            write_requests(writer, &folder_dir, &id, children)?;
+            continue;
+        }
+        let mut row = to_file_row(row.clone(), &[]);
+        // The setting is saved as object to make the diff readable
+        if let Ok(mut setting) = serde_json::from_str::<Value>(get_str(&row, "setting")) {
+            strip_setting_secrets(&mut setting);
+            row["setting"] = setting;
+        }
+        writer.write(&dir.join(&name), row)?;
+    }
+    Ok(())
+}
+
+fn sort_by_created(rows: &mut [Value]) {
+    rows.sort_by(|a, b| {
+        (get_str(a, "created_at"), get_str(a, "id"))
+            .cmp(&(get_str(b, "created_at"), get_str(b, "id")))
+    });
+}
+
+// Export the collection to the directory as files, the secrets are not exported
+pub async fn export_workspace(
+    collection: String,
+    dir: String,
+    format: WorkspaceFormat,
+) -> Result<WorkspaceExportResult, CyberAPIError> {
+    let collections = Some(vec![collection.clone()]);
+    let Some(collection_row) = export_api_collection(collections.clone())
+        .await?
+        .data
+        .into_iter()
+        .next()
+    else {
+        return Err(new_error(format!("collection {collection} is not found")));
+    };
+    let folders = export_api_folder(collections.clone()).await?.data;
+    let settings = export_api_setting(collections.clone()).await?.data;
+    let mut environments = export_environment(collections.clone()).await?.data;
+    let mut variables = export_variable(collections, false).await?.data;
+    sort_by_created(&mut environments);
+    sort_by_created(&mut variables);
+
+    let mut writer = WorkspaceWriter {
+        root: PathBuf::from(dir),
+        format,
+        files: vec![],
+    };
+    writer.write(Path::new(COLLECTION_FILE), to_file_row(collection_row, &[]))?;
+
+    let mut environment_variables: HashMap<String, Vec<Value>> = HashMap::new();
+    let mut collection_variables = vec![];
+    // The values of secret variables are removed by export_variable
+    for variable in variables {
+        let environment = get_str(&variable, "environment").to_string();
+        if environment.is_empty() {
+            collection_variables.push(to_file_row(variable, &[]));
+        } else {
+            environment_variables
+                .entry(environment)
+                .or_default()
+                .push(to_file_row(variable, &["environment"]));
+        }
+    }
+    writer.write(
+        Path::new(VARIABLES_FILE),
+        Value::Array(collection_variables),
+    )?;
+
+    let mut used = HashSet::new();
+    for environment in environments {
+        let id = get_str(&environment, "id").to_string();
+        let name = unique_file_name(&mut used, get_str(&environment, "name"), &id);
+        // The enabled environment is the choice of each user
+        let mut row = to_file_row(environment, &["enabled"]);
+        row["variables"] = Value::Array(environment_variables.remove(&id).unwrap_or_default());
+        writer.write(&Path::new(ENVIRONMENTS_DIR).join(name), row)?;
+    }
+
+    let mut children: HashMap<String, Vec<(bool, Value)>> = HashMap::new();
+    for (is_folder, rows) in [(true, folders), (false, settings)] {
+        for row in rows {
+            let parent_id = get_str(&row, "parent_id").to_string();
+            children
+                .entry(parent_id)
+                .or_default()
+                .push((is_folder, row));
+        }
+    }
+    for items in children.values_mut() {
+        items.sort_by(|a, b| {
+            (get_sort_order(&a.1), get_str(&a.1, "id"))
+                .cmp(&(get_sort_order(&b.1), get_str(&b.1, "id")))
+        });
+    }
+    write_requests(
+        &mut writer,
+        Path::new(REQUESTS_DIR),
+        API_TREE_ROOT,
+        &children,
+    )?;
+
+    let mut removed = vec![];
+    for format in WORKSPACE_FORMATS.iter().filter(|item| **item != format) {
+        for name in [COLLECTION_FILE, VARIABLES_FILE] {
+            let name = format!("{name}.{}", format.ext());
+            let file = writer.root.join(&name);
+            if file.is_file() {
+                fs::remove_file(file)?;
+                removed.push(name);
+            }
+        }
+    }
+    writer.remove_stale(Path::new(ENVIRONMENTS_DIR), &mut removed)?;
+    writer.remove_stale(Path::new(REQUESTS_DIR), &mut removed)?;
+    Ok(WorkspaceExportResult {
+        files: writer.files,
+        removed,
+    })
+}
+
+struct WorkspaceReader {
+    format: WorkspaceFormat,
+}
+
+impl WorkspaceReader {
+    fn read(&self, file: &Path) -> Result<Value, CyberAPIError> {
+        let content = fs::read_to_string(file)?;
+        let value = match self.format {
+            WorkspaceFormat::Json => serde_json::from_str(&content)?,
+            WorkspaceFormat::Yaml => serde_yaml::from_str(&content)?,
+        };
+        Ok(value)
+    }
+
+    // The files of directory sorted by name, only the files of workspace format are returned
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>, CyberAPIError> {
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir()
+                || path.extension().and_then(|ext| ext.to_str()) == Some(self.format.ext())
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn read_requests(
+        &self,
+        dir: &Path,
+        parent_id: &str,
+        folders: &mut Vec<Value>,
+        settings: &mut Vec<Value>,
+    ) -> Result<(), CyberAPIError> {
+        let folder_file = format!("{FOLDER_FILE}.{}", self.format.ext());
+        for path in self.list(dir)? {
+            if path.is_dir() {
+                let file = path.join(&folder_file);
+                if !file.is_file() {
+                    continue;
+                }
+                let mut folder = self.read(&file)?;
+                folder["parent_id"] = Value::String(parent_id.to_string());
+                let id = get_str(&folder, "id").to_string();
+                folders.push(folder);
+                self.read_requests(&path, &id, folders, settings)?;
+                continue;
+            }
+            if path.file_name().and_then(|name| name.to_str()) == Some(folder_file.as_str()) {
+                continue;
+            }
+            let mut setting = self.read(&path)?;
+            setting["parent_id"] = Value::String(parent_id.to_string());
+            settings.push(setting);
+        }
+        Ok(())
+    }
+}
+
+fn to_row_map(rows: Vec<Value>) -> HashMap<String, Value> {
+    rows.into_iter()
+        .map(|row| (get_str(&row, "id").to_string(), row))
+        .collect()
+}
+
+// The setting is compared as json because the keys of file are sorted
+fn is_same_field(field: &str, value: &Value, current: &Value) -> bool {
+    let parse = |value: &Value| {
+        value
+            .as_str()
+            .and_then(|value| serde_json::from_str::<Value>(value).ok())
+    };
+    if field != "setting" {
+        return value == current;
+    }
+    match (parse(value), parse(current)) {
+        (Some(value), Some(current)) => value == current,
+        _ => value == current,
+    }
+}
+
+fn is_row_changed(row: &Value, existing: &Value) -> bool {
+    let (Some(row), Some(existing)) = (row.as_object(), existing.as_object()) else {
+        return row != existing;
+    };
+    row.keys()
+        .chain(existing.keys())
+        .filter(|field| *field != "created_at" && *field != "updated_at")
+        .any(|field| {
+            let value = row.get(field).unwrap_or(&Value::Null);
+            let current = existing.get(field).unwrap_or(&Value::Null);
+            !is_same_field(field, value, current)
+        })
+}
+
+// Set the fields not saved in files, they are from database if the row exists,
+// and the updated time is changed only if the row is different from the database
+fn fill_row(row: &mut Value, collection: Option<&str>, existing: Option<&Value>, now: &str) {
+    if let Some(collection) = collection {
+        row["collection"] = Value::String(collection.to_string());
+    }
+    let changed = existing.is_none_or(|item| is_row_changed(row, item));
+    for field in ["created_at", "updated_at"] {
+        let current = existing.and_then(|item| item.get(field)).cloned();
+        row[field] = match current {
+            Some(current) if field == "created_at" || !changed => current,
+            _ => Value::String(now.to_string()),
+        };
+    }
+}
+
+// Import the collection from the directory, the data of collection in database is replaced,
+// and the secrets which are not exported are kept
+pub async fn import_workspace(dir: String) -> Result<WorkspaceImportResult, CyberAPIError> {
+    let root = PathBuf::from(dir);
+    let Some(format) = WORKSPACE_FORMATS.into_iter().find(|format| {
+        root.join(format!("{COLLECTION_FILE}.{}", format.ext()))
+            .is_file()
+    }) else {
+        return Err(new_error(format!(
+            "{COLLECTION_FILE} file is not found in {}",
+            root.display()
+        )));
+    };
+    let reader = WorkspaceReader { format };
+    let ext = format.ext();
+    let mut collection_row = reader.read(&root.join(format!("{COLLECTION_FILE}.{ext}")))?;
+    let collection = get_str(&collection_row, "id").to_string();
+    if collection.is_empty() {
+        return Err(new_error("the id of collection is empty".to_string()));
+    }
+
+    let collections = Some(vec![collection.clone()]);
+    let existing_collection = export_api_collection(collections.clone()).await?.data;
+    let existing_folders = to_row_map(export_api_folder(collections.clone()).await?.data);
+    let existing_settings = to_row_map(export_api_setting(collections.clone()).await?.data);
+    let existing_environments = to_row_map(export_environment(collections.clone()).await?.data);
+    let existing_variables = to_row_map(export_variable(collections, true).await?.data);
+    let now = Utc::now().to_rfc3339();
+
+    fill_row(&mut collection_row, None, existing_collection.first(), &now);
+
+    let variables_file = root.join(format!("{VARIABLES_FILE}.{ext}"));
+    let mut variables: Vec<Value> = if variables_file.is_file() {
+        serde_json::from_value(reader.read(&variables_file)?)?
+    } else {
+        vec![]
+    };
+    let mut environments = vec![];
+    for path in reader.list(&root.join(ENVIRONMENTS_DIR))? {
+        if path.is_dir() {
+            continue;
+        }
+        let mut environment = reader.read(&path)?;
+        let id = get_str(&environment, "id").to_string();
+        let items = environment
+            .as_object_mut()
+            .and_then(|map| map.remove("variables"))
+            .unwrap_or(Value::Array(vec![]));
+        for mut variable in serde_json::from_value::<Vec<Value>>(items)? {
+            variable["environment"] = Value::String(id.clone());
+            variables.push(variable);
+        }
+        let existing = existing_environments.get(&id);
+        environment["enabled"] = existing
+            .and_then(|item| item.get("enabled"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        fill_row(&mut environment, Some(&collection), existing, &now);
+        environments.push(environment);
+    }
+    for variable in variables.iter_mut() {
+        let existing = existing_variables.get(get_str(variable, "id"));
+        let value = get_str(variable, "value").to_string();
+        if value.is_empty() && is_secret_variable(variable) {
+            // The encrypted value is kept only if it is still secret
+            let current = existing
+                .filter(|item| is_secret_variable(item))
+                .and_then(|item| item.get("value"));
+            if let Some(current) = current {
+                variable["value"] = current.clone();
+            }
+        } else if is_secret_variable(variable) {
+            variable["value"] = Value::String(encrypt_secret(&value)?);
+        }
+        fill_row(variable, Some(&collection), existing, &now);
+    }
+
+    let mut folders = vec![];
+    let mut settings = vec![];
+    reader.read_requests(
+        &root.join(REQUESTS_DIR),
+        API_TREE_ROOT,
+        &mut folders,
+        &mut settings,
+    )?;
+    for folder in folders.iter_mut() {
+        let existing = existing_folders.get(get_str(folder, "id"));
+        fill_row(folder, Some(&collection), existing, &now);
+    }
+    for setting in settings.iter_mut() {
+        let existing = existing_settings.get(get_str(setting, "id"));
+        if let Some(value) = setting.get_mut("setting").filter(|value| value.is_object()) {
+            let current = existing
+                .and_then(|item| serde_json::from_str::<Value>(get_str(item, "setting")).ok());
+            if let Some(current) = current {
+                restore_setting_secrets(value, &current);
+            }
+            *value = Value::String(serde_json::to_string(value)?);
+        }
+        fill_row(setting, Some(&collection), existing, &now);
+    }
+
+    let tables = replace_collection_tables(
+        &collection,
+        vec![
+            (get_table_name_api_collection(), vec![collection_row]),
+            (get_table_name_api_folder(), folders),
+            (get_table_name_api_setting(), settings),
+            (get_table_name_environment(), environments),
+            (get_table_name_variable(), variables),
+        ],
+    )
+    .await?;
+    Ok(WorkspaceImportResult {
+        collection,
+        tables: tables.tables,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::run_with_database;
+    use serde_json::json;
+
+    const CREATED_AT: &str = "2023-01-01T00:00:00Z";
+
+    fn with_time(mut row: Value) -> Value {
+        row["created_at"] = Value::String(CREATED_AT.to_string());
+        row["updated_at"] = Value::String(CREATED_AT.to_string());
+        row
+    }
+
+    fn read_json(dir: &Path, file: &str) -> Value {
+        serde_json::from_str(&fs::read_to_string(dir.join(file)).unwrap()).unwrap()
+    }
+
+    async fn list_rows(name: &str) -> HashMap<String, Value> {
+        let collections = Some(vec!["ws-c".to_string()]);
+        let data = match name {
+            "api_folders" => export_api_folder(collections).await.unwrap().data,
+            "api_settings" => export_api_setting(collections).await.unwrap().data,
+            _ => export_variable(collections, true).await.unwrap().data,
+        };
+        to_row_map(data)
+    }
+
+    #[test]
+    fn export_and_import_workspace() {
+        run_with_database(async {
+            let setting = json!({
+                "method": "GET",
+                "uri": "http://localhost/users?access_token=abc&page=1&key={{key}}",
+                "headers": [
+                    {"key": "Authorization", "value": "Bearer abc", "enabled": true},
+                    {"key": "X-Token", "value": "{{token}}", "enabled": true},
+                    {"key": "Accept", "value": "application/json", "enabled": true},
+                ],
+                "query": [{"key": "api_key", "value": "123", "enabled": true}],
+                "auth": [{"key": "admin", "value": "123456", "enabled": true}],
+            });
+            let tables = vec![
+                (
+                    get_table_name_api_collection(),
+                    vec![with_time(json!({"id": "ws-c", "name": "Workspace"}))],
+                ),
+                (
+                    get_table_name_api_folder(),
+                    vec![with_time(json!({
+                        "id": "ws-f1",
+                        "collection": "ws-c",
+                        "name": "Users",
+                        "parent_id": "",
+                        "sort_order": 0,
+                    }))],
+                ),
+                (
+                    get_table_name_api_setting(),
+                    vec![with_time(json!({
+                        "id": "ws-s1",
+                        "collection": "ws-c",
+                        "name": "List users",
+                        "category": "http",
+                        "setting": setting.to_string(),
+                        "parent_id": "ws-f1",
+                        "sort_order": 0,
+                    }))],
+                ),
+                (
+                    get_table_name_environment(),
+                    vec![with_time(
+                        json!({"id": "ws-e1", "collection": "ws-c", "name": "dev", "enabled": "1"}),
+                    )],
+                ),
+                (
+                    get_table_name_variable(),
+                    vec![
+                        with_time(json!({
+                            "id": "ws-v1",
+                            "category": "customize",
+                            "collection": "ws-c",
+                            "environment": "",
+                            "name": "token",
+                            "value": "abc",
+                            "secret": "",
+                        })),
+                        with_time(json!({
+                            "id": "ws-v2",
+                            "category": "env",
+                            "collection": "ws-c",
+                            "environment": "ws-e1",
+                            "name": "host",
+                            "value": "enc:v1:abc",
+                            "secret": VARIABLE_SECRET,
+                        })),
+                    ],
+                ),
+            ];
+            replace_collection_tables("ws-c", tables).await.unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            let dir_name = dir.path().to_string_lossy().to_string();
+            let result =
+                export_workspace("ws-c".to_string(), dir_name.clone(), WorkspaceFormat::Json)
+                    .await
+                    .unwrap();
+            assert_eq!(
+                vec![
+                    "collection.json",
+                    "variables.json",
+                    "environments/dev.json",
+                    "requests/Users/_folder.json",
+                    "requests/Users/List-users.json",
+                ],
+                result.files
+            );
+
+            // Only the variables flagged secret are removed
+            let variables = read_json(dir.path(), "variables.json");
+            assert_eq!("abc", variables[0]["value"]);
+            let environment = read_json(dir.path(), "environments/dev.json");
+            assert_eq!("host", environment["variables"][0]["name"]);
+            assert_eq!("", environment["variables"][0]["value"]);
+            let request = read_json(dir.path(), "requests/Users/List-users.json");
+            let headers = &request["setting"]["headers"];
+            assert_eq!("", headers[0]["value"]);
+            assert_eq!("{{token}}", headers[1]["value"]);
+            assert_eq!("application/json", headers[2]["value"]);
+            assert_eq!("", request["setting"]["query"][0]["value"]);
+            assert_eq!(
+                "http://localhost/users?access_token=&page=1&key={{key}}",
+                request["setting"]["uri"]
+            );
+            assert_eq!("", request["setting"]["auth"][0]["value"]);
+
+            // The rows are not changed by importing the exported files
+            import_workspace(dir_name.clone()).await.unwrap();
+            let settings = list_rows("api_settings").await;
+            assert_eq!(CREATED_AT, settings["ws-s1"]["updated_at"]);
+            let current: Value =
+                serde_json::from_str(get_str(&settings["ws-s1"], "setting")).unwrap();
+            assert_eq!(setting, current);
+            let variables = list_rows("variables").await;
+            assert_eq!("enc:v1:abc", variables["ws-v2"]["value"]);
+            assert_eq!(CREATED_AT, variables["ws-v2"]["updated_at"]);
+            assert_eq!(CREATED_AT, variables["ws-v1"]["updated_at"]);
+            assert_eq!(
+                CREATED_AT,
+                list_rows("api_folders").await["ws-f1"]["updated_at"]
+            );
+
+            // Only the updated time of changed row is changed
+            let file = dir.path().join("requests/Users/_folder.json");
+            let mut folder = read_json(dir.path(), "requests/Users/_folder.json");
+            folder["name"] = Value::String("Members".to_string());
+            fs::write(&file, folder.to_string()).unwrap();
+            import_workspace(dir_name).await.unwrap();
+            let folder = &list_rows("api_folders").await["ws-f1"];
+            assert_eq!("Members", folder["name"]);
+            assert_eq!(CREATED_AT, folder["created_at"]);
+            assert_ne!(CREATED_AT, folder["updated_at"]);
+            assert_eq!(
+                CREATED_AT,
+                list_rows("api_settings").await["ws-s1"]["updated_at"]
+            );
+        });
+    }
+
+    #[test]
+    fn strip_and_restore_uri_secrets() {
+        let existing = json!({
+            "uri": "{{host}}/users?token=abc&page=1&flag&password=#top",
+            "query": [
+                {"key": "Secret", "value": "123", "enabled": true},
+                {"key": "size", "value": "10", "enabled": true},
+            ],
+        });
+        let mut setting = existing.clone();
+        strip_setting_secrets(&mut setting);
+        assert_eq!(
+            json!({
+                "uri": "{{host}}/users?token=&page=1&flag&password=#top",
+                "query": [
+                    {"key": "Secret", "value": "", "enabled": true},
+                    {"key": "size", "value": "10", "enabled": true},
+                ],
+            }),
+            setting
+        );
+        restore_setting_secrets(&mut setting, &existing);
+        assert_eq!(existing, setting);
+
+        // The uri without query is not changed
+        let mut setting = json!({"uri": "http://localhost/users#token=abc"});
+        strip_setting_secrets(&mut setting);
+        assert_eq!("http://localhost/users#token=abc", setting["uri"]);
+    }
+}
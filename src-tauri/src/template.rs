@@ -0,0 +1,571 @@
+use crate::error::CyberAPIError;
+use crate::extractor;
+use crate::http_request::get_latest_response;
+use crate::json_path;
+use crate::schemas::{
+    ENVIRONMENT_ENABLED, VARIABLE_CATEGORY_CUSTOMIZE, VARIABLE_CATEGORY_ENVIRONMENT,
+    VARIABLE_ENABLED, list_decrypted_variable, list_environment,
+};
+use base64::{Engine as _, engine::general_purpose};
+use md5::{Digest, Md5};
+use rand::Rng;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::warn;
+
+const TEMPLATE_ERROR_CATEGORY: &str = "template";
+
+const SAMPLE_NAMES: [&str; 30] = [
+    "Ava",
+    "Liam",
+    "Noah",
+    "Mia",
+    "Ethan",
+    "Zoe",
+    "Lucas",
+    "Ivy",
+    "Mason",
+    "Luna",
+    "Olivia",
+    "Elijah",
+    "Charlotte",
+    "Amelia",
+    "Harper",
+    "Ella",
+    "James",
+    "Benjamin",
+    "Henry",
+    "Sophia",
+    "Isabella",
+    "Jack",
+    "Leo",
+    "Grace",
+    "Aria",
+    "Chloe",
+    "Emma",
+    "Scarlett",
+    "Samuel",
+    "Wyatt",
+];
+
+// The expression inside of `{{...}}`
+#[derive(Debug, PartialEq)]
+enum Expression {
+    // `{{name}}`, resolved from the variables
+    Variable(String),
+    // `{{fn2.fn1(param1, param2)}}`, functions are applied from right to left
+    Functions {
+        fn_list: Vec<String>,
+        params: Vec<String>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str, Expression),
+}
+
+enum Value {
+    Params(Vec<String>),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    fn params(&self) -> Vec<String> {
+        match self {
+            Value::Params(params) => params.clone(),
+            Value::Text(text) => text.split(',').map(trim_param).collect(),
+            Value::Bytes(buf) => vec![String::from_utf8_lossy(buf).to_string()],
+        }
+    }
+    fn into_text(self) -> String {
+        match self {
+            Value::Params(params) => params.join(","),
+            Value::Text(text) => text,
+            Value::Bytes(buf) => String::from_utf8_lossy(&buf).to_string(),
+        }
+    }
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Value::Bytes(buf) => buf,
+            value => value.into_text().into_bytes(),
+        }
+    }
+}
+
+fn trim_param(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches('\'')
+        .trim_matches('"')
+        .to_string()
+}
+
+fn parse_expression(expr: &str) -> Expression {
+    let expr = expr.trim();
+    match (expr.find('('), expr.rfind(')')) {
+        (Some(start), Some(end)) if start < end => {
+            let name = format!("{}{}", &expr[..start], &expr[end + 1..]);
+            let fn_list = name
+                .split('.')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect();
+            let params = expr[start + 1..end].split(',').map(trim_param).collect();
+            Expression::Functions { fn_list, params }
+        }
+        _ => Expression::Variable(expr.to_string()),
+    }
+}
+
+// Split the value into plain text and `{{...}}` placeholders
+fn parse(value: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}").map(|end| start + 2 + end) else {
+            break;
+        };
+        let expr = &rest[start + 2..end];
+        if expr.trim().is_empty() {
+            segments.push(Segment::Text(&rest[..end + 2]));
+        } else {
+            if start > 0 {
+                segments.push(Segment::Text(&rest[..start]));
+            }
+            segments.push(Segment::Placeholder(
+                &rest[start..end + 2],
+                parse_expression(expr),
+            ));
+        }
+        rest = &rest[end + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+fn random_string(length: usize) -> String {
+    let chars = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| chars[rng.gen_range(0..chars.len())] as char)
+        .collect()
+}
+
+fn generate_random_value(params: Vec<String>) -> String {
+    let category = params.first().cloned().unwrap_or_default();
+    let option = params.get(1).filter(|value| !value.is_empty());
+    match category.to_lowercase().as_str() {
+        "email" => {
+            let domain = option
+                .map(|value| value.trim_start_matches('@'))
+                .unwrap_or("example.com");
+            format!(
+                "{}{}@{}",
+                chrono::Utc::now().timestamp(),
+                random_string(8),
+                domain
+            )
+        }
+        "name" => {
+            let name = SAMPLE_NAMES[rand::thread_rng().gen_range(0..SAMPLE_NAMES.len())];
+            match option {
+                Some(prefix) => format!("{}-{}", prefix, name),
+                None => name.to_string(),
+            }
+        }
+        value => {
+            // `{{random(20)}}` returns a random string of 20 characters
+            if let Ok(length) = value.parse::<usize>() {
+                return random_string(length);
+            }
+            option.cloned().unwrap_or_else(|| random_string(10))
+        }
+    }
+}
+
+// Only document, desktop and download are allowed, download is the default
+fn resolve_file(params: Vec<String>) -> Result<PathBuf, CyberAPIError> {
+    let file = params.first().cloned().unwrap_or_default();
+    if file.is_empty() {
+        return Err(CyberAPIError::new(
+            TEMPLATE_ERROR_CATEGORY,
+            "file of read file function is empty".to_string(),
+        ));
+    }
+    let file = Path::new(&file);
+    if file.is_absolute() {
+        return Ok(file.to_path_buf());
+    }
+    let dir = params.get(1).map(|value| value.to_lowercase());
+    let base_dir = match dir.as_deref() {
+        Some("document") => dirs::document_dir(),
+        Some("desktop") => dirs::desktop_dir(),
+        _ => dirs::download_dir(),
+    };
+    match base_dir {
+        Some(dir) => Ok(dir.join(file)),
+        None => Err(CyberAPIError::new(
+            TEMPLATE_ERROR_CATEGORY,
+            "base directory of read file function is not found".to_string(),
+        )),
+    }
+}
+
+fn get_response_value(params: Vec<String>) -> Result<String, CyberAPIError> {
+    if params.len() != 2 {
+        return Err(CyberAPIError::new(
+            TEMPLATE_ERROR_CATEGORY,
+            "params of get from response is invalid".to_string(),
+        ));
+    }
+    let Some(resp) = get_latest_response(&params[0]) else {
+        return Err(CyberAPIError::new(
+            TEMPLATE_ERROR_CATEGORY,
+            format!("response of {} is not found", params[0]),
+        ));
+    };
+    let json = resp.json_body()?;
+    Ok(json_path::find_first(&json, &params[1])
+        .map(json_path::to_string)
+        .unwrap_or_default())
+}
+
+#[derive(Debug, Default)]
+pub struct TemplateContext {
+    pub collection: String,
+    // The environment used by `env()`, None if there is no enabled environment
+    pub environment: Option<String>,
+    // Variables bound by the row of data file, they have the highest priority
+    data_variables: HashMap<String, String>,
+    // Variables are updated by the extractors of response
+    environment_variables: RwLock<HashMap<String, String>>,
+    global_variables: RwLock<HashMap<String, String>>,
+}
+
+impl TemplateContext {
+    // Load the variables of collection, the enabled environment is used if environment is None
+    pub async fn load(
+        collection: &str,
+        environment: Option<String>,
+    ) -> Result<TemplateContext, CyberAPIError> {
+        let environment = match environment.filter(|value| !value.is_empty()) {
+            Some(value) => Some(value),
+            None => list_environment(collection.to_string())
+                .await?
+                .into_iter()
+                .find(|item| item.enabled.as_deref() == Some(ENVIRONMENT_ENABLED))
+                .map(|item| item.id),
+        };
+        let mut global_variables = HashMap::new();
+        let mut environment_variables = HashMap::new();
+        for category in [VARIABLE_CATEGORY_ENVIRONMENT, VARIABLE_CATEGORY_CUSTOMIZE] {
+            for item in
+                list_decrypted_variable(collection.to_string(), category.to_string()).await?
+            {
+                if item.enabled.as_deref() != Some(VARIABLE_ENABLED) {
+                    continue;
+                }
+                let Some(name) = item.name.filter(|value| !value.is_empty()) else {
+                    continue;
+                };
+                let value = item.value.unwrap_or_default();
+                match item.environment.filter(|value| !value.is_empty()) {
+                    None => {
+                        global_variables.entry(name).or_insert(value);
+                    }
+                    Some(env) if Some(&env) == environment.as_ref() => {
+                        environment_variables.entry(name).or_insert(value);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(TemplateContext {
+            collection: collection.to_string(),
+            environment,
+            environment_variables: RwLock::new(environment_variables),
+            global_variables: RwLock::new(global_variables),
+            ..Default::default()
+        })
+    }
+
+    // Copy the context with the variables of a data row
+    pub fn with_data(&self, data: HashMap<String, String>) -> TemplateContext {
+        let copy = |variables: &RwLock<HashMap<String, String>>| {
+            RwLock::new(
+                variables
+                    .read()
+                    .map(|value| value.clone())
+                    .unwrap_or_default(),
+            )
+        };
+        TemplateContext {
+            collection: self.collection.clone(),
+            environment: self.environment.clone(),
+            data_variables: data,
+            environment_variables: copy(&self.environment_variables),
+            global_variables: copy(&self.global_variables),
+        }
+    }
+
+    // The priority is data variables > runtime variables > environment variables > global variables
+    pub fn get_variable(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.data_variables.get(name) {
+            return Some(value.clone());
+        }
+        if let Some(value) = extractor::get_runtime_variable(&self.collection, name) {
+            return Some(value);
+        }
+        for variables in [&self.environment_variables, &self.global_variables] {
+            if let Some(value) = variables.read().ok()?.get(name) {
+                return Some(value.clone());
+            }
+        }
+        None
+    }
+
+    pub fn set_variable(&self, is_environment: bool, name: &str, value: &str) {
+        let variables = if is_environment {
+            &self.environment_variables
+        } else {
+            &self.global_variables
+        };
+        if let Ok(mut variables) = variables.write() {
+            variables.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    fn call(&self, name: &str, value: Value) -> Result<Value, CyberAPIError> {
+        let result = match name {
+            "readTextFile" | "rtf" => {
+                let file = resolve_file(value.params())?;
+                Value::Text(fs::read_to_string(file)?)
+            }
+            "readFile" | "rf" => {
+                let file = resolve_file(value.params())?;
+                Value::Bytes(fs::read(file)?)
+            }
+            "base64" | "b64" => Value::Text(general_purpose::STANDARD.encode(value.into_bytes())),
+            "openFile" | "of" => {
+                return Err(CyberAPIError::new(
+                    TEMPLATE_ERROR_CATEGORY,
+                    "openFile is only supported in the app window".to_string(),
+                ));
+            }
+            "get" | "g" => Value::Text(get_response_value(value.params())?),
+            "timestamp" | "ts" => Value::Text(chrono::Utc::now().timestamp().to_string()),
+            "md5" => Value::Text(format!("{:x}", Md5::digest(value.into_bytes()))),
+            "sha256" => Value::Text(format!("{:x}", Sha256::digest(value.into_bytes()))),
+            "env" => {
+                let name = value.into_text();
+                match self.get_variable(&name) {
+                    Some(found) => Value::Text(found),
+                    None => {
+                        warn!("variable {} is not found", name);
+                        Value::Text(name)
+                    }
+                }
+            }
+            "random" => Value::Text(generate_random_value(value.params())),
+            _ => {
+                warn!("template function {} is not supported", name);
+                value
+            }
+        };
+        Ok(result)
+    }
+
+    fn evaluate(&self, raw: &str, expr: Expression) -> Result<String, CyberAPIError> {
+        match expr {
+            Expression::Variable(name) => {
+                Ok(self.get_variable(&name).unwrap_or_else(|| raw.to_string()))
+            }
+            Expression::Functions { fn_list, params } => {
+                let mut value = Value::Params(params);
+                for name in fn_list.iter().rev() {
+                    value = self.call(name, value)?;
+                }
+                Ok(value.into_text())
+            }
+        }
+    }
+
+    // Replace all `{{...}}` placeholders of the value
+    pub fn render(&self, value: &str) -> Result<String, CyberAPIError> {
+        let mut result = String::with_capacity(value.len());
+        for segment in parse(value) {
+            match segment {
+                Segment::Text(text) => result.push_str(text),
+                Segment::Placeholder(raw, expr) => result.push_str(&self.evaluate(raw, expr)?),
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn new_context(collection: &str) -> TemplateContext {
+        let ctx = TemplateContext {
+            collection: collection.to_string(),
+            ..Default::default()
+        };
+        ctx.set_variable(false, "host", "http://global");
+        ctx.set_variable(false, "name", "global");
+        ctx.set_variable(true, "host", "http://env");
+        ctx
+    }
+
+    #[test]
+    fn parse_segments() {
+        assert_eq!(
+            parse("a{{ name }}b{{}}c"),
+            vec![
+                Segment::Text("a"),
+                Segment::Placeholder("{{ name }}", Expression::Variable("name".to_string())),
+                Segment::Text("b{{}}"),
+                Segment::Text("c"),
+            ]
+        );
+        assert_eq!(
+            parse_expression("b64.md5('a', \"b\")"),
+            Expression::Functions {
+                fn_list: vec!["b64".to_string(), "md5".to_string()],
+                params: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+        assert_eq!(parse("{{unclosed"), vec![Segment::Text("{{unclosed")]);
+    }
+
+    #[test]
+    fn render_variables() {
+        let ctx = new_context("template-variables");
+        // The environment variables override the global variables
+        assert_eq!(ctx.render("{{host}}/users").unwrap(), "http://env/users");
+        assert_eq!(ctx.render("{{name}}").unwrap(), "global");
+        // The unknown variables are kept as they are
+        assert_eq!(
+            ctx.render("{{missing}}-{{ env(missing) }}").unwrap(),
+            "{{missing}}-missing"
+        );
+        assert_eq!(ctx.render("{{env(host)}}").unwrap(), "http://env");
+    }
+
+    #[test]
+    fn render_with_data() {
+        let ctx = new_context("template-data");
+        let data = HashMap::from([("host".to_string(), "http://data".to_string())]);
+        let row = ctx.with_data(data);
+        assert_eq!(
+            row.render("{{host}} {{name}}").unwrap(),
+            "http://data global"
+        );
+        // The variables set by the copy do not change the original context
+        row.set_variable(true, "token", "abc");
+        assert_eq!(row.render("{{token}}").unwrap(), "abc");
+        assert_eq!(ctx.render("{{token}}").unwrap(), "{{token}}");
+        assert_eq!(ctx.render("{{host}}").unwrap(), "http://env");
+    }
+
+    #[test]
+    fn render_functions() {
+        let ctx = new_context("template-functions");
+        assert_eq!(ctx.render("{{base64(abc)}}").unwrap(), "YWJj");
+        assert_eq!(ctx.render("{{b64(abc)}}").unwrap(), "YWJj");
+        assert_eq!(
+            ctx.render("{{md5(abc)}}").unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            ctx.render("{{sha256(abc)}}").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let timestamp: i64 = ctx.render("{{ts()}}").unwrap().parse().unwrap();
+        assert!((chrono::Utc::now().timestamp() - timestamp).abs() <= 1);
+        assert_eq!(
+            ctx.render("{{timestamp()}}").unwrap().len(),
+            timestamp.to_string().len()
+        );
+        // The unsupported function returns the params
+        assert_eq!(ctx.render("{{unknown(a, b)}}").unwrap(), "a,b");
+        assert!(ctx.render("{{openFile(a.txt)}}").is_err());
+    }
+
+    #[test]
+    fn render_nested_functions() {
+        let ctx = new_context("template-nested");
+        // The functions are applied from right to left
+        assert_eq!(
+            ctx.render("{{base64.md5(abc)}}").unwrap(),
+            general_purpose::STANDARD.encode("900150983cd24fb0d6963f7d28e17f72")
+        );
+        assert_eq!(
+            ctx.render("{{md5.env(name)}}").unwrap(),
+            format!("{:x}", Md5::digest("global"))
+        );
+    }
+
+    #[test]
+    fn render_random() {
+        let ctx = new_context("template-random");
+        assert_eq!(ctx.render("{{random(20)}}").unwrap().len(), 20);
+        assert_eq!(ctx.render("{{random()}}").unwrap().len(), 10);
+        assert!(
+            ctx.render("{{random(email, test.com)}}")
+                .unwrap()
+                .ends_with("@test.com")
+        );
+        let name = ctx.render("{{random(name, user)}}").unwrap();
+        assert!(name.starts_with("user-"));
+        assert!(SAMPLE_NAMES.contains(&name.trim_start_matches("user-")));
+    }
+
+    #[test]
+    fn render_files() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"hello").unwrap();
+        let path = file.path().to_string_lossy().to_string();
+        let ctx = new_context("template-files");
+        assert_eq!(
+            ctx.render(&format!("{{{{rtf({path})}}}}")).unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            ctx.render(&format!("{{{{readTextFile({path})}}}}"))
+                .unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            ctx.render(&format!("{{{{base64.readFile({path})}}}}"))
+                .unwrap(),
+            "aGVsbG8="
+        );
+        assert!(ctx.render("{{readFile()}}").is_err());
+    }
+
+    #[test]
+    fn render_get_without_response() {
+        let ctx = new_context("template-get");
+        let err = ctx.render("{{get(no-such-request, data.id)}}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[template]response of no-such-request is not found"
+        );
+        let err = ctx.render("{{g(no-such-request)}}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[template]params of get from response is invalid"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+CREATE TABLE api_collections (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        name TEXT DEFAULT '',
+        description TEXT DEFAULT '',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE api_folders (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            collection TEXT NOT NULL check (collection != ''),
+            children TEXT DEFAULT '',
+            name TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        );
+
+CREATE TABLE api_settings (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        collection TEXT NOT NULL check (collection != ''),
+        name TEXT DEFAULT '',
+        category TEXT DEFAULT '',
+        setting TEXT DEFAULT '',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE environments (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            collection TEXT NOT NULL check (collection != ''),
+            name TEXT DEFAULT '',
+            enabled TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        );
+
+CREATE TABLE proxies (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        proxy TEXT DEFAULT '',
+        list TEXT DEFAULT '',
+        mode TEXT DEFAULT '',
+        enabled TEXT DEFAULT '1',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE schema_migrations (
+        version INTEGER PRIMARY KEY NOT NULL,
+        name TEXT DEFAULT '',
+        applied_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE variables (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            category TEXT NOT NULL check (category != ''),
+            collection TEXT NOT NULL check (collection != ''),
+            environment TEXT DEFAULT '',
+            name TEXT DEFAULT '',
+            value TEXT DEFAULT '',
+            enabled TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        );
+
+CREATE TABLE versions (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        version TEXT NOT NULL check (version != ''),
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
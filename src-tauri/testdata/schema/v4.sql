@@ -0,0 +1,123 @@
+CREATE TABLE api_collections (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        name TEXT DEFAULT '',
+        description TEXT DEFAULT '',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE api_folders (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            collection TEXT NOT NULL check (collection != ''),
+            children TEXT DEFAULT '',
+            name TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        );
+
+CREATE TABLE api_settings (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        collection TEXT NOT NULL check (collection != ''),
+        name TEXT DEFAULT '',
+        category TEXT DEFAULT '',
+        setting TEXT DEFAULT '',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE environments (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            collection TEXT NOT NULL check (collection != ''),
+            name TEXT DEFAULT '',
+            enabled TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        );
+
+CREATE TABLE histories (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        collection TEXT DEFAULT '',
+        api TEXT DEFAULT '',
+        environment TEXT DEFAULT '',
+        method TEXT DEFAULT '',
+        url TEXT DEFAULT '',
+        request TEXT DEFAULT '',
+        status INTEGER DEFAULT 0,
+        headers TEXT DEFAULT '',
+        body TEXT DEFAULT '',
+        body_size INTEGER DEFAULT 0,
+        body_truncated TEXT DEFAULT '0',
+        latency INTEGER DEFAULT 0,
+        stats TEXT DEFAULT '',
+        error TEXT DEFAULT '',
+        pinned TEXT DEFAULT '0',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE proxies (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        proxy TEXT DEFAULT '',
+        list TEXT DEFAULT '',
+        mode TEXT DEFAULT '',
+        enabled TEXT DEFAULT '1',
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TABLE schema_migrations (
+        version INTEGER PRIMARY KEY NOT NULL,
+        name TEXT DEFAULT '',
+        applied_at TEXT DEFAULT ''
+    );
+
+CREATE VIRTUAL TABLE search_index USING fts5 (
+            kind UNINDEXED,
+            id UNINDEXED,
+            collection UNINDEXED,
+            method UNINDEXED,
+            name,
+            url,
+            headers,
+            body,
+            tokenize = 'unicode61'
+        );
+
+CREATE TABLE variables (
+            id TEXT PRIMARY KEY NOT NULL check (id != ''),
+            category TEXT NOT NULL check (category != ''),
+            collection TEXT NOT NULL check (collection != ''),
+            environment TEXT DEFAULT '',
+            name TEXT DEFAULT '',
+            value TEXT DEFAULT '',
+            enabled TEXT DEFAULT '',
+            created_at TEXT DEFAULT '',
+            updated_at TEXT DEFAULT ''
+        );
+
+CREATE TABLE versions (
+        id TEXT PRIMARY KEY NOT NULL check (id != ''),
+        version TEXT NOT NULL check (version != ''),
+        created_at TEXT DEFAULT '',
+        updated_at TEXT DEFAULT ''
+    );
+
+CREATE TRIGGER api_collections_search_delete AFTER DELETE ON api_collections BEGIN DELETE FROM search_index WHERE kind = 'collection' AND id = OLD.id; END;
+
+CREATE TRIGGER api_collections_search_insert AFTER INSERT ON api_collections BEGIN INSERT INTO search_index (kind, id, collection, method, name, url, headers, body) VALUES ('collection', NEW.id, NEW.id, '', IFNULL(NEW.name, ''), '', '', IFNULL(NEW.description, '')); END;
+
+CREATE TRIGGER api_collections_search_update AFTER UPDATE ON api_collections BEGIN DELETE FROM search_index WHERE kind = 'collection' AND id = OLD.id; INSERT INTO search_index (kind, id, collection, method, name, url, headers, body) VALUES ('collection', NEW.id, NEW.id, '', IFNULL(NEW.name, ''), '', '', IFNULL(NEW.description, '')); END;
+
+CREATE TRIGGER api_folders_search_delete AFTER DELETE ON api_folders BEGIN DELETE FROM search_index WHERE kind = 'folder' AND id = OLD.id; END;
+
+CREATE TRIGGER api_folders_search_insert AFTER INSERT ON api_folders BEGIN INSERT INTO search_index (kind, id, collection, method, name, url, headers, body) VALUES ('folder', NEW.id, NEW.collection, '', IFNULL(NEW.name, ''), '', '', ''); END;
+
+CREATE TRIGGER api_folders_search_update AFTER UPDATE ON api_folders BEGIN DELETE FROM search_index WHERE kind = 'folder' AND id = OLD.id; INSERT INTO search_index (kind, id, collection, method, name, url, headers, body) VALUES ('folder', NEW.id, NEW.collection, '', IFNULL(NEW.name, ''), '', '', ''); END;
+
+CREATE TRIGGER api_settings_search_delete AFTER DELETE ON api_settings BEGIN DELETE FROM search_index WHERE kind = 'setting' AND id = OLD.id; END;
+
+CREATE TRIGGER api_settings_search_insert AFTER INSERT ON api_settings BEGIN INSERT INTO search_index (kind, id, collection, method, name, url, headers, body) VALUES ('setting', NEW.id, NEW.collection, CASE WHEN json_valid(NEW.setting) THEN IFNULL(json_extract(NEW.setting, '$.method'), '') ELSE '' END, IFNULL(NEW.name, ''), TRIM(CASE WHEN json_valid(NEW.setting) THEN IFNULL(json_extract(NEW.setting, '$.uri'), '') ELSE '' END || ' ' || CASE WHEN json_valid(NEW.setting) THEN IFNULL((SELECT group_concat(json_extract(value, '$.key') || '=' || json_extract(value, '$.value'), ' ') FROM json_each(NEW.setting, '$.query') WHERE json_type(value) = 'object'), '') ELSE '' END), CASE WHEN json_valid(NEW.setting) THEN IFNULL((SELECT group_concat(json_extract(value, '$.key') || ': ' || json_extract(value, '$.value'), ' ') FROM json_each(NEW.setting, '$.headers') WHERE json_type(value) = 'object'), '') ELSE '' END, CASE WHEN json_valid(NEW.setting) THEN IFNULL(json_extract(NEW.setting, '$.body'), '') ELSE '' END); END;
+
+CREATE TRIGGER api_settings_search_update AFTER UPDATE ON api_settings BEGIN DELETE FROM search_index WHERE kind = 'setting' AND id = OLD.id; INSERT INTO search_index (kind, id, collection, method, name, url, headers, body) VALUES ('setting', NEW.id, NEW.collection, CASE WHEN json_valid(NEW.setting) THEN IFNULL(json_extract(NEW.setting, '$.method'), '') ELSE '' END, IFNULL(NEW.name, ''), TRIM(CASE WHEN json_valid(NEW.setting) THEN IFNULL(json_extract(NEW.setting, '$.uri'), '') ELSE '' END || ' ' || CASE WHEN json_valid(NEW.setting) THEN IFNULL((SELECT group_concat(json_extract(value, '$.key') || '=' || json_extract(value, '$.value'), ' ') FROM json_each(NEW.setting, '$.query') WHERE json_type(value) = 'object'), '') ELSE '' END), CASE WHEN json_valid(NEW.setting) THEN IFNULL((SELECT group_concat(json_extract(value, '$.key') || ': ' || json_extract(value, '$.value'), ' ') FROM json_each(NEW.setting, '$.headers') WHERE json_type(value) = 'object'), '') ELSE '' END, CASE WHEN json_valid(NEW.setting) THEN IFNULL(json_extract(NEW.setting, '$.body'), '') ELSE '' END); END;
+
+CREATE INDEX idx_histories_created_at ON histories (created_at);
@@ -1,9 +1,10 @@
 import dayjs from 'dayjs'
+import { compact } from 'lodash-es'
 import { ulid } from 'ulid'
 
 import { isWebMode } from '../helpers/util'
 import { fakeAdd, fakeDeleteItems, fakeList, fakeUpdate } from './fake'
-import { cmdAddAPIFolder, cmdDeleteAPIFolder, cmdListAPIFolder, cmdUpdateAPIFolder, run } from './invoke'
+import { cmdAddAPIFolder, cmdDeleteAPIFolder, cmdListAPIFolder, cmdMoveItem, cmdReorder, cmdUpdateAPIFolder, run } from './invoke'
 
 const store = 'apiFolders'
 
@@ -11,9 +12,13 @@ export interface APIFolder {
   [key: string]: unknown
   id: string
   collection: string
+  // The ids of children in order, it is generated by the backend and use moveAPIItem to change it
   children: string
   // Name
   name: string
+  // Parent folder, it is empty for the top level
+  parentId?: string
+  sortOrder?: number
   // Created at
   createdAt: string
   // Updated at
@@ -61,6 +66,49 @@ export async function updateAPIFolder(folder: APIFolder) {
   })
 }
 
+// Move the folder or setting into the folder before the item, the empty parent is the top level
+export async function moveAPIItem(params: { id: string; parentId: string; before?: string }) {
+  const { id, parentId, before } = params
+  if (isWebMode()) {
+    // The children of folders are saved in web mode
+    const folders = await fakeList<APIFolder>(store)
+    for (const folder of folders) {
+      const children = compact(folder.children.split(','))
+      const index = children.indexOf(id)
+      if (index === -1 && folder.id !== parentId) {
+        continue
+      }
+      if (index !== -1) {
+        children.splice(index, 1)
+      }
+      if (folder.id === parentId) {
+        const beforeIndex = before ? children.indexOf(before) : -1
+        if (beforeIndex === -1) {
+          children.push(id)
+        } else {
+          children.splice(beforeIndex, 0, id)
+        }
+      }
+      folder.children = children.join(',')
+      await fakeUpdate(store, folder)
+    }
+    return
+  }
+  await run(cmdMoveItem, {
+    id,
+    parentId,
+    before,
+  })
+}
+
+// Set the order of children of the folder, the empty parent is the top level
+export async function reorderAPIItems(params: { collection: string; parentId: string; ids: string[] }) {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdReorder, params)
+}
+
 export async function deleteAPIFolder(id: string): Promise<{
   folders: string[]
   settings: string[]
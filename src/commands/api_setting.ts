@@ -17,6 +17,9 @@ export interface APISetting {
   category: string
   // Config
   setting: string
+  // Parent folder, it is empty for the top level
+  parentId?: string
+  sortOrder?: number
   // Created at
   createdAt: string
   // Updated at
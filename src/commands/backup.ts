@@ -0,0 +1,30 @@
+import { isWebMode } from '../helpers/util'
+import { TablesCount } from './database'
+import { cmdListBackups, cmdRestoreBackup, run } from './invoke'
+
+export interface BackupFile {
+  name: string
+  path: string
+  size: number
+  // startup, import or scheduled
+  reason: string
+  createdAt: string
+}
+
+// List the automatic backups, the latest is the first
+export async function listBackups(): Promise<BackupFile[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<BackupFile[]>(cmdListBackups)
+}
+
+// Restore the automatic backup, the current data is backed up first
+export async function restoreBackup(name: string): Promise<TablesCount | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<TablesCount>(cmdRestoreBackup, {
+    name,
+  })
+}
@@ -0,0 +1,118 @@
+import { listen } from '@tauri-apps/api/event'
+import { isWebMode } from '../helpers/util'
+import { APISetting } from './api_setting'
+import { RequestTimeout } from './http_request'
+import { KVParam } from './interface'
+import {
+  cmdClearCapturedExchange,
+  cmdGetCaptureCACert,
+  cmdListCapturedExchange,
+  cmdListCaptureProxy,
+  cmdSaveCapturedExchanges,
+  cmdStartCaptureProxy,
+  cmdStopCaptureProxy,
+  run,
+} from './invoke'
+
+const captureExchangeEvent = 'capture-exchange'
+
+export interface CaptureOptions {
+  [key: string]: unknown
+  // The listen address, it is 127.0.0.1 if it is empty
+  host?: string
+  // A random port is used if it is 0
+  port?: number
+  // Decrypt https with the certificates signed by the local CA
+  decryptHttps?: boolean
+  timeout?: RequestTimeout
+}
+
+export interface CaptureProxyInfo {
+  port: number
+  address: string
+  decryptHttps: boolean
+  startedAt: string
+}
+
+export interface CapturedExchange {
+  id: string
+  port: number
+  time: string
+  method: string
+  // Only host and port of the CONNECT tunnel
+  url: string
+  requestHeaders: KVParam[]
+  // Base64 encoded
+  requestBody: string
+  status?: number
+  responseHeaders: Record<string, string[]>
+  // Base64 encoded
+  responseBody: string
+  latency: number
+  error?: string
+}
+
+export async function startCaptureProxy(options: CaptureOptions): Promise<CaptureProxyInfo | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<CaptureProxyInfo>(cmdStartCaptureProxy, {
+    options,
+  })
+}
+
+export async function stopCaptureProxy(port: number): Promise<boolean> {
+  if (isWebMode()) {
+    return false
+  }
+  return await run<boolean>(cmdStopCaptureProxy, {
+    port,
+  })
+}
+
+export async function listCaptureProxy(): Promise<CaptureProxyInfo[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<CaptureProxyInfo[]>(cmdListCaptureProxy)
+}
+
+// Listen the exchanges recorded by the capture proxies, it returns the function to stop listening
+export async function onCapturedExchange(fn: (exchange: CapturedExchange) => void) {
+  return await listen<CapturedExchange>(captureExchangeEvent, (event) => {
+    fn(event.payload)
+  })
+}
+
+export async function listCapturedExchange(): Promise<CapturedExchange[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<CapturedExchange[]>(cmdListCapturedExchange)
+}
+
+export async function clearCapturedExchange() {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdClearCapturedExchange)
+}
+
+// Save the exchanges as requests at the end of folder
+export async function saveCapturedExchanges(ids: string[], folder: string): Promise<APISetting[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<APISetting[]>(cmdSaveCapturedExchanges, {
+    ids,
+    folder,
+  })
+}
+
+// PEM of the local CA, it should be trusted by the client to decrypt https
+export async function getCaptureCACert(): Promise<string> {
+  if (isWebMode()) {
+    return ''
+  }
+  return await run<string>(cmdGetCaptureCACert)
+}
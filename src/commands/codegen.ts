@@ -0,0 +1,31 @@
+import { isWebMode } from '../helpers/util'
+import type { HTTPRequest } from './http_request'
+import { cmdGenerateCodeSnippet, run } from './invoke'
+
+export enum CodeLanguage {
+  Curl = 'curl',
+  HTTPie = 'httpie',
+  Wget = 'wget',
+  Python = 'python',
+  JavaScript = 'javascript',
+  Go = 'go',
+  Rust = 'rust',
+}
+
+// Generate the code snippet of request, the templates are rendered by the environment
+export async function generateCodeSnippet(params: {
+  collection: string
+  req: HTTPRequest
+  language: CodeLanguage
+  environment?: string
+}): Promise<string | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<string>(cmdGenerateCodeSnippet, {
+    collection: params.collection,
+    req: params.req,
+    language: params.language,
+    environment: params.environment,
+  })
+}
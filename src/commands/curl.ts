@@ -0,0 +1,22 @@
+import { isWebMode } from '../helpers/util'
+import { APISetting } from './api_setting'
+import { cmdImportCurl, run } from './invoke'
+
+export interface CurlImportResult {
+  setting: APISetting
+  // The proxy of `-x`, it is not saved and only suggested by the warnings
+  proxy?: string
+  // The options of curl which are not supported, e.g. `--cert`
+  warnings: string[]
+}
+
+// Import the curl command as a request of collection
+export async function importCurl(collection: string, content: string): Promise<CurlImportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<CurlImportResult>(cmdImportCurl, {
+    collection,
+    content,
+  })
+}
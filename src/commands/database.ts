@@ -4,7 +4,19 @@ import dayjs from 'dayjs'
 import { ulid } from 'ulid'
 
 import { isWebMode } from '../helpers/util'
-import { cmdAddVersion, cmdExportTables, cmdGetLatestVersion, cmdImportTables, cmdInitTables, cmdSetDbPath, run } from './invoke'
+import {
+  cmdAddVersion,
+  cmdDiffTables,
+  cmdExportCollections,
+  cmdExportTables,
+  cmdGetLatestVersion,
+  cmdImportCollections,
+  cmdImportTables,
+  cmdInitTables,
+  cmdMergeTables,
+  cmdSetDbPath,
+  run,
+} from './invoke'
 import { loadAppSetting } from './settings'
 
 export interface Version {
@@ -66,13 +78,86 @@ export async function handleDatabaseCompatible() {
   }
 }
 
-export async function exportTables(): Promise<string> {
-  return await run(cmdExportTables)
+// The backup is encrypted if the passphrase is not empty
+// The secret values are left out unless includeSecrets is true
+export async function exportTables(passphrase?: string, includeSecrets?: boolean): Promise<string> {
+  return await run(cmdExportTables, {
+    passphrase,
+    includeSecrets,
+  })
+}
+
+export interface TablesCount {
+  // The count of imported or exported rows of each table
+  tables: Record<string, number>
+}
+
+// The import is atomic, the original data is kept if it fails
+export async function importTables(file: string, passphrase?: string) {
+  return await run<TablesCount>(cmdImportTables, {
+    file,
+    passphrase,
+  })
+}
+
+// Export the collections with their folders, settings, environments and variables to the file
+export async function exportCollections(collections: string[], file: string, passphrase?: string) {
+  return await run<TablesCount>(cmdExportCollections, {
+    collections,
+    file,
+    passphrase,
+  })
+}
+
+// Import the collections as new data, the ids are changed to avoid conflicts
+export async function importCollections(file: string, passphrase?: string) {
+  return await run<TablesCount>(cmdImportCollections, {
+    file,
+    passphrase,
+  })
+}
+
+export enum MergePolicy {
+  // Replace the existing data if the incoming one is updated later
+  UpdateIfNewer = 'updateIfNewer',
+  // Keep the existing data
+  Keep = 'keep',
+  // Add the incoming data with new id
+  Duplicate = 'duplicate',
+}
+
+export interface MergeItem {
+  id: string
+  name: string
+}
+
+export interface MergeTableDiff {
+  added: MergeItem[]
+  updated: MergeItem[]
+  conflicting: MergeItem[]
+  unchanged: number
+}
+
+export interface MergeTablesResult {
+  policy: MergePolicy
+  tables: Record<string, MergeTableDiff>
+}
+
+// Compare the backup file with the current data, nothing is changed
+export async function diffTables(file: string, policy: MergePolicy, passphrase?: string) {
+  return await run<MergeTablesResult>(cmdDiffTables, {
+    file,
+    passphrase,
+    policy,
+  })
 }
 
-export async function importTables(file: string) {
-  return await run(cmdImportTables, {
+// Merge the backup file into the current data, the data not in the file is kept
+export async function mergeTables(file: string, policy: MergePolicy, passphrase?: string) {
+  return await run<MergeTablesResult>(cmdMergeTables, {
     file,
+    passphrase,
+    policy,
   })
 }
 
@@ -8,7 +8,7 @@ import { get, toString, trim } from 'lodash-es'
 import { i18nCommon } from '../i18n'
 import { EnvironmentStatus, listEnvironment } from './environment'
 import { getLatestResponse, getResponseBody } from './http_response'
-import { listVariable, VariableCategory, VariableStatus } from './variable'
+import { listRuntimeVariable, listVariable, VariableCategory, VariableSecret, VariableStatus } from './variable'
 
 interface FnHandler {
   collection: string
@@ -241,6 +241,12 @@ export async function doFnHandler(handler: FnHandler): Promise<string> {
       case Fn.env:
         {
           const name = toString(p)
+          // Runtime variables have the highest priority
+          const runtimeVariables = await listRuntimeVariable(collection)
+          if (name in runtimeVariables) {
+            p = runtimeVariables[name]
+            break
+          }
           const environments = await listEnvironment(collection)
           const activeEnvironmentId = environments.find((item) => item.enabled === EnvironmentStatus.Enabled)?.id || ''
           const [environmentVariables, customizeVariables] = await Promise.all([
@@ -254,6 +260,10 @@ export async function doFnHandler(handler: FnHandler): Promise<string> {
           if (!found && activeEnvironmentId) {
             found = variables.find((item) => item.enabled === VariableStatus.Enabled && item.name === name && !item.environment)
           }
+          // The secret value is masked, the whole placeholder is left for the backend
+          if (found?.secret === VariableSecret.Secret) {
+            return handler.text
+          }
           if (found) {
             p = found.value
           }
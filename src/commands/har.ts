@@ -0,0 +1,37 @@
+import { isWebMode } from '../helpers/util'
+import { cmdExportHAR, cmdImportHAR, run } from './invoke'
+
+// How the imported requests are grouped into folders
+export enum HARGroup {
+  Host = 'host',
+  Page = 'page',
+}
+
+export interface HARImportResult {
+  folders: number
+  settings: number
+  cookies: number
+}
+
+export async function importHAR(collection: string, file: string, group: HARGroup): Promise<HARImportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<HARImportResult>(cmdImportHAR, {
+    collection,
+    file,
+    group,
+  })
+}
+
+// Export the requests of folder, it returns the count of entries
+export async function exportHAR(folder: string, file: string, environment?: string): Promise<number> {
+  if (isWebMode()) {
+    return 0
+  }
+  return await run<number>(cmdExportHAR, {
+    folder,
+    environment,
+    file,
+  })
+}
@@ -0,0 +1,116 @@
+import { isWebMode } from '../helpers/util'
+import { Timeout } from '../stores/setting'
+import { HTTPRequest } from './http_request'
+import { HTTPResponse } from './http_response'
+import {
+  cmdClearHistory,
+  cmdDeleteHistories,
+  cmdGetHistory,
+  cmdListHistory,
+  cmdPinHistory,
+  cmdRerunHistory,
+  run,
+} from './invoke'
+
+export interface History {
+  id: string
+  collection?: string
+  api?: string
+  environment?: string
+  method?: string
+  // The url with query which is sent
+  url?: string
+  status?: number
+  headers?: string
+  bodySize?: number
+  bodyTruncated?: string
+  latency?: number
+  stats?: string
+  error?: string
+  pinned?: string
+  createdAt?: string
+  updatedAt?: string
+}
+
+export interface HistoryQuery {
+  collection?: string
+  api?: string
+  url?: string
+  method?: string
+  statusMin?: number
+  statusMax?: number
+  // The range of created time, e.g. dayjs().toISOString()
+  from?: string
+  to?: string
+  pinned?: boolean
+  // The page starts from 0
+  page?: number
+  pageSize?: number
+}
+
+export interface HistoryPage {
+  total: number
+  items: History[]
+}
+
+export interface HistoryDetail {
+  history: History
+  request: HTTPRequest
+  // It is empty if the request failed
+  response?: HTTPResponse
+}
+
+export async function listHistory(query: HistoryQuery): Promise<HistoryPage> {
+  if (isWebMode()) {
+    return {
+      total: 0,
+      items: [],
+    }
+  }
+  return await run<HistoryPage>(cmdListHistory, {
+    query,
+  })
+}
+
+export async function getHistory(id: string): Promise<HistoryDetail> {
+  return await run<HistoryDetail>(cmdGetHistory, {
+    id,
+  })
+}
+
+export async function pinHistory(id: string, pinned: boolean) {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdPinHistory, {
+    id,
+    pinned,
+  })
+}
+
+export async function deleteHistories(ids: string[]) {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdDeleteHistories, {
+    ids,
+  })
+}
+
+// Clear the histories of collection, the pinned ones are kept
+export async function clearHistory(collection: string): Promise<number> {
+  if (isWebMode()) {
+    return 0
+  }
+  return await run<number>(cmdClearHistory, {
+    collection,
+  })
+}
+
+// Send the request of history again, the templates are not rendered again
+export async function rerunHistory(id: string, timeout: Timeout): Promise<HTTPResponse> {
+  return await run<HTTPResponse>(cmdRerunHistory, {
+    id,
+    timeout,
+  })
+}
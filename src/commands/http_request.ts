@@ -8,6 +8,7 @@ import { ulid } from 'ulid'
 
 import mime from 'mime'
 import { delay, formatError, isWebMode } from '../helpers/util'
+import { CodeLanguage, generateCodeSnippet } from './codegen'
 import { Cookie } from './cookies'
 import { doFnHandler, parseFunctions } from './fn'
 import { HTTPResponse, addLatestResponse } from './http_response'
@@ -48,6 +49,11 @@ export interface HTTPRequest {
   headers: KVParam[]
   query: KVParam[]
   auth: KVParam[]
+  assertions?: unknown[]
+  // Extract values from the response into variables
+  extractors?: unknown[]
+  // Example response served by the mock server
+  mock?: unknown
 }
 
 function convertKVListToURLValues(kvList: KVParam[]) {
@@ -65,6 +71,15 @@ function convertKVListToURLValues(kvList: KVParam[]) {
 }
 
 export async function convertRequestToCURL(collection: string, req: HTTPRequest, cookies: Cookie[]) {
+  // The backend resolves the request like it is sent, and escapes it for shell
+  const snippet = await generateCodeSnippet({
+    collection,
+    req,
+    language: CodeLanguage.Curl,
+  })
+  if (snippet !== null) {
+    return snippet
+  }
   await convertKVParams(collection, req.query)
   await convertKVParams(collection, req.headers)
   const queryList = convertKVListToURLValues(req.query)
@@ -287,6 +302,10 @@ export async function doHTTPRequest(options: {
     contentType,
     headers: req.headers,
     query: req.query,
+    // The basic authorization is built by the backend, so the secrets of auth can be filled
+    auth: req.auth,
+    assertions: req.assertions || [],
+    extractors: req.extractors || [],
   }
   await convertKVParams(collection, params.query)
   await convertKVParams(collection, params.headers)
@@ -332,16 +351,6 @@ export async function doHTTPRequest(options: {
     enabled: true,
   })
 
-  const auth = req.auth.filter((item) => item.enabled)
-  if (auth.length) {
-    const value = encode(`${auth[0].key}:${auth[0].value}`)
-    params.headers.push({
-      key: 'Authorization',
-      value: `Basic ${value}`,
-      enabled: true,
-    })
-  }
-
   const requestTimeout = {
     connect: 10,
     write: 120,
@@ -365,6 +374,7 @@ export async function doHTTPRequest(options: {
     resp = await run<HTTPResponse>(cmdDoHTTPRequest, {
       req: params,
       api: id,
+      collection,
       timeout: requestTimeout,
     })
   } catch (err) {
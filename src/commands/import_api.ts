@@ -1,12 +1,15 @@
 import { Promise } from 'bluebird'
 import dayjs from 'dayjs'
-import { forEach, get, has, uniq } from 'lodash-es'
+import { compact, forEach, get, has, uniq } from 'lodash-es'
 import { ulid } from 'ulid'
 import { SettingType } from '../stores/api_setting'
-import { APIFolder, createAPIFolder, newDefaultAPIFolder } from './api_folder'
+import { APIFolder, createAPIFolder, moveAPIItem, newDefaultAPIFolder } from './api_folder'
 import { APISetting, createAPISetting, newDefaultAPISetting } from './api_setting'
+import { importCurl } from './curl'
 import { ContentType, HTTPRequest } from './http_request'
 import { KVParam } from './interface'
+import { importOpenAPI, isOpenAPI } from './openapi'
+import { importPostman } from './postman'
 import { createVariable, newDefaultVariable, Variable, VariableCategory } from './variable'
 // eslint-disable-next-line @typescript-eslint/ban-ts-comment
 // @ts-ignore
@@ -331,6 +334,14 @@ export async function importAPI(params: { category: ImportCategory; collection:
     folders: [],
   }
   if (params.fileData.startsWith('curl')) {
+    // The backend supports more options of curl
+    const imported = await importCurl(collection, params.fileData)
+    if (imported) {
+      imported.warnings.forEach((item) => {
+        console.warn(item)
+      })
+      return [imported.setting.id]
+    }
     const req = parseCurl(params.fileData)
     const id = ulid()
 
@@ -345,6 +356,16 @@ export async function importAPI(params: { category: ImportCategory; collection:
     })
     return [id]
   }
+  // OpenAPI and Swagger are imported by the backend, which also supports yaml
+  if (isOpenAPI(params.fileData)) {
+    const imported = await importOpenAPI({
+      collection,
+      content: params.fileData,
+    })
+    if (imported) {
+      return imported.top
+    }
+  }
   const json = JSON.parse(params.fileData)
   const environments: Variable[] = []
   if (has(json, 'swagger')) {
@@ -355,6 +376,20 @@ export async function importAPI(params: { category: ImportCategory; collection:
     category = ImportCategory.Insomnia
   }
 
+  // Postman collections and environments are imported by the backend
+  if (category === ImportCategory.PostMan || has(json, '_postman_variable_scope')) {
+    const imported = await importPostman({
+      collection,
+      content: params.fileData,
+    })
+    if (imported) {
+      imported.unsupported.forEach((item) => {
+        console.warn(`${item} is not supported`)
+      })
+      return imported.top
+    }
+  }
+
   switch (category) {
     case ImportCategory.Swagger: {
       convertSwaggerSetting({
@@ -467,6 +502,15 @@ export async function importAPI(params: { category: ImportCategory; collection:
     }
     await createAPISetting(item)
   })
+  // The children of folders are saved as the parent of items
+  await Promise.each(result.folders, async (folder) => {
+    await Promise.each(compact(folder.children?.split(',')), async (id) => {
+      await moveAPIItem({
+        id,
+        parentId: folder.id,
+      })
+    })
+  })
   await Promise.each(environments, async (item) => {
     if (!item.name && !item.value) {
       return
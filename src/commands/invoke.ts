@@ -6,6 +6,22 @@ export const cmdInitTables = 'init_tables'
 export const cmdSetDbPath = 'set_db_path'
 export const cmdExportTables = 'export_tables'
 export const cmdImportTables = 'import_tables'
+export const cmdExportCollections = 'export_collections'
+export const cmdImportCollections = 'import_collections'
+export const cmdExportWorkspace = 'export_workspace'
+export const cmdImportWorkspace = 'import_workspace'
+export const cmdListBackups = 'list_backups'
+export const cmdRestoreBackup = 'restore_backup'
+export const cmdDiffTables = 'diff_tables'
+export const cmdMergeTables = 'merge_tables'
+export const cmdImportHAR = 'import_har'
+export const cmdExportHAR = 'export_har'
+export const cmdImportOpenAPI = 'import_openapi'
+export const cmdExportOpenAPI = 'export_openapi'
+export const cmdImportPostman = 'import_postman'
+export const cmdExportPostman = 'export_postman'
+export const cmdGenerateCodeSnippet = 'generate_code_snippet'
+export const cmdImportCurl = 'import_curl'
 
 export const cmdAddAPISetting = 'add_api_setting'
 export const cmdUpdateAPISetting = 'update_api_setting'
@@ -16,6 +32,8 @@ export const cmdAddAPIFolder = 'add_api_folder'
 export const cmdListAPIFolder = 'list_api_folder'
 export const cmdUpdateAPIFolder = 'update_api_folder'
 export const cmdDeleteAPIFolder = 'delete_api_folder'
+export const cmdMoveItem = 'move_item'
+export const cmdReorder = 'reorder'
 
 export const cmdAddAPICollection = 'add_api_collection'
 export const cmdUpdateAPICollection = 'update_api_collection'
@@ -23,6 +41,28 @@ export const cmdListAPICollection = 'list_api_collection'
 export const cmdDeleteAPICollection = 'delete_api_collection'
 
 export const cmdDoHTTPRequest = 'do_http_request'
+export const cmdListHistory = 'list_history'
+export const cmdGetHistory = 'get_history'
+export const cmdPinHistory = 'pin_history'
+export const cmdDeleteHistories = 'delete_histories'
+export const cmdClearHistory = 'clear_history'
+export const cmdRerunHistory = 'rerun_history'
+export const cmdSearch = 'search'
+export const cmdRenderTemplate = 'render_template'
+export const cmdRunSuite = 'run_suite'
+export const cmdRunLoadTest = 'run_load_test'
+export const cmdStopLoadTest = 'stop_load_test'
+export const cmdStartMockServer = 'start_mock_server'
+export const cmdStopMockServer = 'stop_mock_server'
+export const cmdListMockServer = 'list_mock_server'
+export const cmdListMockLog = 'list_mock_log'
+export const cmdStartCaptureProxy = 'start_capture_proxy'
+export const cmdStopCaptureProxy = 'stop_capture_proxy'
+export const cmdListCaptureProxy = 'list_capture_proxy'
+export const cmdListCapturedExchange = 'list_captured_exchange'
+export const cmdClearCapturedExchange = 'clear_captured_exchange'
+export const cmdSaveCapturedExchanges = 'save_captured_exchanges'
+export const cmdGetCaptureCACert = 'get_capture_ca_cert'
 
 export const cmdListCookie = 'list_cookie'
 export const cmdDeleteCookie = 'delete_cookie'
@@ -37,6 +77,13 @@ export const cmdAddVariable = 'add_variable'
 export const cmdUpdateVariable = 'update_variable'
 export const cmdDeleteVariable = 'delete_variable'
 export const cmdListVariable = 'list_variable'
+export const cmdRevealVariable = 'reveal_variable'
+export const cmdGetSecretStatus = 'get_secret_status'
+export const cmdUnlockSecrets = 'unlock_secrets'
+export const cmdLockSecrets = 'lock_secrets'
+export const cmdChangeSecretPassphrase = 'change_secret_passphrase'
+export const cmdListRuntimeVariable = 'list_runtime_variable'
+export const cmdClearRuntimeVariable = 'clear_runtime_variable'
 
 export const cmdAddProxy = 'add_proxy'
 export const cmdUpdateProxy = 'update_proxy'
@@ -0,0 +1,57 @@
+import { isWebMode } from '../helpers/util'
+import { cmdListMockLog, cmdListMockServer, cmdStartMockServer, cmdStopMockServer, run } from './invoke'
+
+export interface MockServerInfo {
+  port: number
+  address: string
+  collection: string
+  environment?: string
+  startedAt: string
+}
+
+export interface MockLog {
+  time: string
+  method: string
+  path: string
+  status: number
+  // Id of the matched api setting, it is empty if no route is matched
+  setting?: string
+  latency: number
+}
+
+// A random port is used if port is 0
+export async function startMockServer(collection: string, port: number, environment?: string): Promise<MockServerInfo | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<MockServerInfo>(cmdStartMockServer, {
+    collection,
+    port,
+    environment,
+  })
+}
+
+export async function stopMockServer(port: number): Promise<boolean> {
+  if (isWebMode()) {
+    return false
+  }
+  return await run<boolean>(cmdStopMockServer, {
+    port,
+  })
+}
+
+export async function listMockServer(): Promise<MockServerInfo[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<MockServerInfo[]>(cmdListMockServer)
+}
+
+export async function listMockLog(port: number): Promise<MockLog[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<MockLog[]>(cmdListMockLog, {
+    port,
+  })
+}
@@ -0,0 +1,64 @@
+import { isWebMode } from '../helpers/util'
+import { cmdExportOpenAPI, cmdImportOpenAPI, run } from './invoke'
+
+export enum OpenAPIFormat {
+  JSON = 'json',
+  YAML = 'yaml',
+}
+
+export interface OpenAPIImportOptions {
+  // Update the requests with the same method and url instead of creating new ones
+  update?: boolean
+}
+
+export interface OpenAPIImportResult {
+  created: number
+  updated: number
+  folders: number
+  environments: number
+  top: string[]
+}
+
+// Whether the content is an OpenAPI 3 or Swagger 2 document of json or yaml
+export function isOpenAPI(content: string): boolean {
+  if (/^\s*["']?(openapi|swagger)["']?\s*:/m.test(content)) {
+    return true
+  }
+  return /^\s*\{[\s\S]*"(openapi|swagger)"\s*:/.test(content)
+}
+
+// Import the document, the external refs are resolved relative to the file
+export async function importOpenAPI(params: {
+  collection: string
+  content?: string
+  file?: string
+  options?: OpenAPIImportOptions
+}): Promise<OpenAPIImportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<OpenAPIImportResult>(cmdImportOpenAPI, {
+    collection: params.collection,
+    content: params.content || '',
+    file: params.file || '',
+    options: params.options || {},
+  })
+}
+
+// Export the collection as an OpenAPI 3.1 document, it returns the count of operations
+export async function exportOpenAPI(params: {
+  collection: string
+  file: string
+  format: OpenAPIFormat
+  environment?: string
+}): Promise<number> {
+  if (isWebMode()) {
+    return 0
+  }
+  return await run<number>(cmdExportOpenAPI, {
+    collection: params.collection,
+    environment: params.environment,
+    format: params.format,
+    file: params.file,
+  })
+}
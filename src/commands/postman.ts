@@ -0,0 +1,46 @@
+import { isWebMode } from '../helpers/util'
+import { cmdExportPostman, cmdImportPostman, run } from './invoke'
+
+export interface PostmanImportResult {
+  folders: number
+  settings: number
+  variables: number
+  environments: number
+  top: string[]
+  // The parts of Postman which are not supported, e.g. scripts
+  unsupported: string[]
+}
+
+export interface PostmanExportResult {
+  settings: number
+  environments: number
+  // The parts of the app which can not be exported, e.g. assertions
+  unsupported: string[]
+}
+
+// Import the Postman collection or environment of v2.1
+export async function importPostman(params: {
+  collection: string
+  content?: string
+  file?: string
+}): Promise<PostmanImportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<PostmanImportResult>(cmdImportPostman, {
+    collection: params.collection,
+    content: params.content || '',
+    file: params.file || '',
+  })
+}
+
+// Export the collection, the environments are exported beside the file
+export async function exportPostman(collection: string, file: string): Promise<PostmanExportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<PostmanExportResult>(cmdExportPostman, {
+    collection,
+    file,
+  })
+}
@@ -0,0 +1,183 @@
+import { listen } from '@tauri-apps/api/event'
+import { ulid } from 'ulid'
+import { isWebMode } from '../helpers/util'
+import { RequestTimeout } from './http_request'
+import { cmdRunLoadTest, cmdRunSuite, cmdStopLoadTest, run } from './invoke'
+
+const runnerProgressEvent = 'runner-progress'
+
+export enum RunTargetCategory {
+  Collection = 'collection',
+  Folder = 'folder',
+  Setting = 'setting',
+}
+
+export interface RunTarget {
+  category: RunTargetCategory
+  id: string
+}
+
+export interface RunOptions {
+  [key: string]: unknown
+  environment?: string
+  // Row count of the data file by default, otherwise 1
+  iterations?: number
+  // CSV or JSON file, each row binds the variables of an iteration
+  dataFile?: string
+  // Delay between requests (ms)
+  delay?: number
+  stopOnFailure?: boolean
+  concurrency?: number
+  timeout?: RequestTimeout
+}
+
+export interface RunResult {
+  id: string
+  iteration: number
+  name: string
+  method: string
+  uri: string
+  status: number
+  latency: number
+  passed: boolean
+  error?: string
+  assertions: {
+    name: string
+    passed: boolean
+    message: string
+  }[]
+}
+
+export interface RunProgress {
+  id: string
+  completed: number
+  total: number
+  result: RunResult
+}
+
+export interface RunReport {
+  id: string
+  collection: string
+  environment?: string
+  startedAt: string
+  duration: number
+  iterations: number
+  total: number
+  passed: number
+  failed: number
+  results: RunResult[]
+}
+
+export async function runSuite(
+  target: RunTarget,
+  options: RunOptions,
+  onProgress: (progress: RunProgress) => void,
+): Promise<RunReport | null> {
+  if (isWebMode()) {
+    return null
+  }
+  const id = ulid()
+  const unlisten = await listen<RunProgress>(runnerProgressEvent, (event) => {
+    if (event.payload.id === id) {
+      onProgress(event.payload)
+    }
+  })
+  try {
+    return await run<RunReport>(cmdRunSuite, {
+      id,
+      target,
+      options,
+    })
+  } finally {
+    unlisten()
+  }
+}
+
+const loadTestProgressEvent = 'load-test-progress'
+
+export interface LoadTestOptions {
+  [key: string]: unknown
+  environment?: string
+  concurrency?: number
+  // Total count of requests, it is ignored if duration is set
+  count?: number
+  // Duration (s)
+  duration?: number
+  // Target requests per second, 0 means no limit
+  rps?: number
+  timeout?: RequestTimeout
+}
+
+// Latency (ms)
+export interface LatencySummary {
+  min: number
+  max: number
+  mean: number
+  p50: number
+  p90: number
+  p99: number
+}
+
+export interface LoadTestPoint {
+  second: number
+  count: number
+  errors: number
+  latency: LatencySummary
+}
+
+export interface LoadTestProgress {
+  id: string
+  completed: number
+  point: LoadTestPoint
+}
+
+export interface LoadTestReport {
+  id: string
+  setting: string
+  method: string
+  uri: string
+  startedAt: string
+  duration: number
+  total: number
+  success: number
+  failed: number
+  throughput: number
+  latency: LatencySummary
+  status: Record<string, number>
+  errors: Record<string, number>
+  series: LoadTestPoint[]
+}
+
+export async function runLoadTest(
+  id: string,
+  setting: string,
+  options: LoadTestOptions,
+  onProgress: (progress: LoadTestProgress) => void,
+): Promise<LoadTestReport | null> {
+  if (isWebMode()) {
+    return null
+  }
+  const unlisten = await listen<LoadTestProgress>(loadTestProgressEvent, (event) => {
+    if (event.payload.id === id) {
+      onProgress(event.payload)
+    }
+  })
+  try {
+    return await run<LoadTestReport>(cmdRunLoadTest, {
+      id,
+      setting,
+      options,
+    })
+  } finally {
+    unlisten()
+  }
+}
+
+export async function stopLoadTest(id: string): Promise<boolean> {
+  if (isWebMode()) {
+    return false
+  }
+  return await run<boolean>(cmdStopLoadTest, {
+    id,
+  })
+}
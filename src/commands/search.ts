@@ -0,0 +1,40 @@
+import { isWebMode } from '../helpers/util'
+import { cmdSearch, run } from './invoke'
+
+export enum SearchKind {
+  Collection = 'collection',
+  Folder = 'folder',
+  Setting = 'setting',
+}
+
+export interface SearchQuery {
+  keyword: string
+  // Search in all collections if it is empty
+  collection?: string
+  limit?: number
+}
+
+export interface SearchHit {
+  kind: SearchKind
+  id: string
+  collection: string
+  collectionName: string
+  // The names of folders from the top level to the parent
+  path: string[]
+  name: string
+  method: string
+  url: string
+  // The matched text, the keywords are wrapped with `[` and `]`
+  snippet: string
+  // The smaller is the better
+  score: number
+}
+
+export async function search(query: SearchQuery): Promise<SearchHit[]> {
+  if (isWebMode()) {
+    return []
+  }
+  return await run<SearchHit[]>(cmdSearch, {
+    query,
+  })
+}
@@ -2,7 +2,20 @@ import dayjs from 'dayjs'
 import { ulid } from 'ulid'
 import { isWebMode } from '../helpers/util'
 import { fakeAdd, fakeList, fakeUpdate, fakeUpdateStore } from './fake'
-import { cmdAddVariable, cmdDeleteVariable, cmdListVariable, cmdUpdateVariable, run } from './invoke'
+import {
+  cmdAddVariable,
+  cmdChangeSecretPassphrase,
+  cmdClearRuntimeVariable,
+  cmdDeleteVariable,
+  cmdGetSecretStatus,
+  cmdListRuntimeVariable,
+  cmdListVariable,
+  cmdLockSecrets,
+  cmdRevealVariable,
+  cmdUnlockSecrets,
+  cmdUpdateVariable,
+  run,
+} from './invoke'
 
 const store = 'variables'
 
@@ -11,6 +24,13 @@ export enum VariableStatus {
   Disabled = '0',
 }
 
+export enum VariableSecret {
+  Secret = '1',
+}
+
+// The secret value is listed as the mask, and it is not changed if the mask is updated
+export const secretValueMask = '******'
+
 export enum VariableCategory {
   // Environment variables
   Environment = 'env',
@@ -32,6 +52,8 @@ export interface Variable {
   value: string
   // Enabled (0: disabled, 1: enabled)
   enabled: string
+  // The value is encrypted if it is secret (1: secret)
+  secret?: string
   // Created at
   createdAt: string
   // Updated at
@@ -94,3 +116,76 @@ export async function deleteVariable(ids: string[]) {
     ids,
   })
 }
+
+// Runtime variables are extracted from responses and only kept in memory
+export async function listRuntimeVariable(collection: string): Promise<Record<string, string>> {
+  if (isWebMode()) {
+    return {}
+  }
+  const result = await run<Record<string, string>>(cmdListRuntimeVariable, {
+    collection,
+  })
+  return result || {}
+}
+
+export async function clearRuntimeVariable(collection: string) {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdClearRuntimeVariable, {
+    collection,
+  })
+}
+
+// Get the decrypted value of the secret variable, the secrets should be unlocked
+export async function revealVariable(id: string): Promise<string> {
+  if (isWebMode()) {
+    return ''
+  }
+  return await run<string>(cmdRevealVariable, {
+    id,
+  })
+}
+
+export interface SecretStatus {
+  // The master passphrase has been set
+  initialized: boolean
+  unlocked: boolean
+}
+
+export async function getSecretStatus(): Promise<SecretStatus> {
+  if (isWebMode()) {
+    return {
+      initialized: false,
+      unlocked: false,
+    }
+  }
+  return await run<SecretStatus>(cmdGetSecretStatus)
+}
+
+// Unlock the secret variables, the master passphrase is set for the first time
+export async function unlockSecrets(passphrase: string): Promise<SecretStatus | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<SecretStatus>(cmdUnlockSecrets, {
+    passphrase,
+  })
+}
+
+export async function lockSecrets() {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdLockSecrets)
+}
+
+export async function changeSecretPassphrase(passphrase: string, newPassphrase: string) {
+  if (isWebMode()) {
+    return
+  }
+  await run(cmdChangeSecretPassphrase, {
+    passphrase,
+    newPassphrase,
+  })
+}
@@ -0,0 +1,44 @@
+import { isWebMode } from '../helpers/util'
+import { cmdExportWorkspace, cmdImportWorkspace, run } from './invoke'
+
+export enum WorkspaceFormat {
+  Json = 'json',
+  Yaml = 'yaml',
+}
+
+export interface WorkspaceExportResult {
+  // The relative paths of files
+  files: string[]
+  removed: string[]
+}
+
+export interface WorkspaceImportResult {
+  collection: string
+  tables: Record<string, number>
+}
+
+// Write the collection to the directory as files, the secrets are not written
+export async function exportWorkspace(params: {
+  collection: string
+  dir: string
+  format?: WorkspaceFormat
+}): Promise<WorkspaceExportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<WorkspaceExportResult>(cmdExportWorkspace, {
+    collection: params.collection,
+    dir: params.dir,
+    format: params.format || WorkspaceFormat.Json,
+  })
+}
+
+// Replace the collection with the files of the directory
+export async function importWorkspace(dir: string): Promise<WorkspaceImportResult | null> {
+  if (isWebMode()) {
+    return null
+  }
+  return await run<WorkspaceImportResult>(cmdImportWorkspace, {
+    dir,
+  })
+}
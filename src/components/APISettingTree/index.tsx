@@ -6,6 +6,7 @@ import s from './index.module.css'
 import { useRoute } from 'vue-router'
 import { newDefaultAPIFolder } from '../../commands/api_folder'
 import { newDefaultAPISetting } from '../../commands/api_setting'
+import { importCurl } from '../../commands/curl'
 // eslint-disable-next-line @typescript-eslint/ban-ts-comment
 // @ts-ignore
 import { addFolderKey, addHTTPSettingFromCURLKey, addHTTPSettingKey } from '../../constants/provide'
@@ -120,6 +121,22 @@ export default defineComponent({
         onConfirm: async (data) => {
           try {
             const curl = (data.curl as string).trim()
+            // The backend supports more options of curl, and lists the unsupported ones
+            const imported = await importCurl(collection, curl)
+            if (imported) {
+              await apiSettingStore.fetch(collection)
+              if (folder) {
+                await apiFolderStore.addChild({
+                  id: folder,
+                  children: [imported.setting.id],
+                })
+              }
+              apiSettingStore.select(imported.setting.id)
+              imported.warnings.forEach((item) => {
+                message.warning(item)
+              })
+              return
+            }
             let req
             try {
               req = parseCurl(curl)
@@ -1,7 +1,7 @@
 import dayjs from 'dayjs'
-import { compact, uniq } from 'lodash-es'
+import { uniq } from 'lodash-es'
 import { defineStore } from 'pinia'
-import { APIFolder, createAPIFolder, deleteAPIFolder, listAPIFolder, updateAPIFolder } from '../commands/api_folder'
+import { APIFolder, createAPIFolder, deleteAPIFolder, listAPIFolder, moveAPIItem, updateAPIFolder } from '../commands/api_folder'
 import { useAPISettingStore } from './api_setting'
 
 export const useAPIFolderStore = defineStore('apiFolders', {
@@ -68,49 +68,19 @@ export const useAPIFolderStore = defineStore('apiFolders', {
         return
       }
       this.updating = true
-      const { id, children } = params
-      const { apiFolders } = this
-      // If the folder does not exist
-      const currentFolder = apiFolders.find((item) => item.id === id)
-      const omitChild = (children: string, child: string) => {
-        const arr = children.split(',').filter((item) => item !== child)
-
-        return uniq(compact(arr)).join(',')
-      }
-      const addChild = (children: string, child: string, beforeItem: string) => {
-        const arr = compact(children.split(','))
-        const index = arr.indexOf(beforeItem)
-        if (index === -1) {
-          arr.push(child)
-        } else {
-          arr.splice(index, 0, child)
-        }
-        return uniq(compact(arr)).join(',')
-      }
-
-      const updateData: Map<string, APIFolder> = new Map()
-
-      for (let i = 0; i < children.length; i++) {
-        const child = children[i]
-        // Check whether this child already has a folder
-        apiFolders.forEach((folder) => {
-          if (folder.children?.includes(child)) {
-            folder.children = omitChild(folder.children, child)
-            updateData.set(folder.id, folder)
-          }
-        })
-        if (currentFolder) {
-          currentFolder.children = addChild(currentFolder.children, child, params.before || '')
-          updateData.set(currentFolder.id, currentFolder)
-        }
-      }
-      const list: APIFolder[] = []
-      updateData.forEach((value) => {
-        list.push(value)
-      })
+      const { id, children, before } = params
       try {
-        for (let i = 0; i < list.length; i++) {
-          await updateAPIFolder(list[i])
+        for (let i = 0; i < children.length; i++) {
+          await moveAPIItem({
+            id: children[i],
+            parentId: id,
+            before,
+          })
+        }
+        // The children of folders are changed by moving
+        const collection = this.apiFolders[0]?.collection
+        if (collection) {
+          this.apiFolders = await listAPIFolder(collection)
         }
       } finally {
         this.updating = false
@@ -4,6 +4,7 @@ import { defineStore } from 'pinia'
 import { cloneDeep } from 'lodash-es'
 import { APISetting, createAPISetting, deleteAPISettings, listAPISetting, updateAPISetting } from '../commands/api_setting'
 import { HTTPRequest } from '../commands/http_request'
+import { VariableSecret } from '../commands/variable'
 import { isWebMode, setAppTitle } from '../helpers/util'
 import { ENVRegexp, useEnvironmentStore, useEnvironmentVariableStore } from './environment'
 import { useGlobalReqHeaderStore } from './global_req_header'
@@ -67,6 +68,10 @@ export const useAPISettingStore = defineStore('apiSettings', {
           req.headers = []
         }
         variables.forEach((item) => {
+          // The global headers of secrets are added by the backend
+          if (item.secret === VariableSecret.Secret) {
+            return
+          }
           const hasEnabledHeader = req.headers.some((header) => header.key === item.name && header.enabled)
           if (!hasEnabledHeader) {
             req.headers.push({
@@ -2,7 +2,16 @@ import dayjs from 'dayjs'
 import { sortBy } from 'lodash-es'
 import { defineStore } from 'pinia'
 import { createEnvironment, deleteEnvironment, Environment, EnvironmentStatus, listEnvironment, updateEnvironment } from '../commands/environment'
-import { createVariable, deleteVariable, listVariable, updateVariable, Variable, VariableCategory, VariableStatus } from '../commands/variable'
+import {
+  createVariable,
+  deleteVariable,
+  listVariable,
+  updateVariable,
+  Variable,
+  VariableCategory,
+  VariableSecret,
+  VariableStatus,
+} from '../commands/variable'
 
 export const ENVRegexp = /\{\{([\S\s]+)\}\}/
 
@@ -180,13 +189,16 @@ export const useEnvironmentVariableStore = defineStore('environmentVariables', {
       const byEnv = this.variables.find((item) => {
         return item.enabled === VariableStatus.Enabled && item.name === name && item.environment === environmentId
       })
-      if (byEnv) {
-        return byEnv.value
+      const found =
+        byEnv ||
+        this.variables.find((item) => {
+          return item.enabled === VariableStatus.Enabled && item.name === name && !item.environment
+        })
+      // The secret value is masked, its placeholder is left for the backend to fill
+      if (found?.secret === VariableSecret.Secret) {
+        return
       }
-      const global = this.variables.find((item) => {
-        return item.enabled === VariableStatus.Enabled && item.name === name && !item.environment
-      })
-      return global?.value
+      return found?.value
     },
   },
 })
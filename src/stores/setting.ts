@@ -35,6 +35,29 @@ interface AppSetting {
     query: LocationQuery
   }
   timeout: Timeout
+  // The retention of request history, 0 means no limit
+  historyRetention?: {
+    maxCount: number
+    maxDays: number
+  }
+  // The automatic backups, 0 hours means no scheduled backup
+  backup?: {
+    dir: string
+    onStartup: boolean
+    beforeImport: boolean
+    intervalHours: number
+    keepLast: number
+    keepDaily: number
+    keepWeekly: number
+  }
+  // The workspace directory of each collection, the collection is synced with the files
+  workspaces?: Record<
+    string,
+    {
+      dir: string
+      format: 'json' | 'yaml'
+    }
+  >
 }
 
 export enum ResizeType {
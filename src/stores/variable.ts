@@ -1,7 +1,16 @@
 import dayjs from 'dayjs'
 import { sortBy } from 'lodash-es'
 import { defineStore } from 'pinia'
-import { createVariable, deleteVariable, listVariable, updateVariable, Variable, VariableCategory, VariableStatus } from '../commands/variable'
+import {
+  createVariable,
+  deleteVariable,
+  listVariable,
+  updateVariable,
+  Variable,
+  VariableCategory,
+  VariableSecret,
+  VariableStatus,
+} from '../commands/variable'
 
 export const useCustomizeStore = newVariableStore('customizeVariables', VariableCategory.Customize)
 
@@ -21,6 +30,10 @@ export function newVariableStore(name: string, category: VariableCategory) {
         const value = this.variables.find((item) => {
           return item.enabled === VariableStatus.Enabled && item.name === name
         })
+        // The secret value is masked, it is filled by the backend
+        if (value?.secret === VariableSecret.Secret) {
+          return
+        }
         return value?.value
       },
       async add(value: Variable) {